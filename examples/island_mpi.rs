@@ -0,0 +1,31 @@
+// Island model TSP over MPI: every rank evolves an island of berlin52 and
+// sends its best individuals to the next one every 20 generations, the root
+// reports the best tour of them all. Run it with
+// `mpirun -n 4 cargo run --example island_mpi`, started alone it evolves a
+// single island. The same run as `ga-run --backend mpi-islands` with these
+// arguments.
+use clap::Parser;
+use genetic_algorithm::backend::{self, BackendKind};
+use genetic_algorithm::cli::RunCli;
+
+fn main() {
+    let cli = RunCli::parse_from([
+        "island_mpi",
+        "--instance",
+        "builtin:berlin52",
+        "--pop-size",
+        "300",
+        "--iterations",
+        "100",
+        "--migration-interval",
+        "20",
+        "--seed",
+        "7",
+    ]);
+    backend::run(&cli.run, BackendKind::MpiIslands);
+}
+
+#[test]
+fn runs() {
+    main()
+}
@@ -0,0 +1,112 @@
+// 0/1 knapsack registered as a named problem and created through the registry,
+// the same path the binary uses for `--problem <name>`.
+use genetic_algorithm::organism::Organism;
+use genetic_algorithm::registry;
+use genetic_algorithm::runner::{self, PopulationSolver, RunParameters};
+use rand::Rng;
+use std::sync::Arc;
+
+struct Items {
+    weights: Vec<u32>,
+    values: Vec<u32>,
+    capacity: u32,
+}
+
+#[derive(Clone)]
+struct Knapsack {
+    items: Arc<Items>,
+    taken: Vec<bool>,
+}
+
+impl std::fmt::Debug for Knapsack {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let taken = (0..self.taken.len())
+            .filter(|i| self.taken[*i])
+            .collect::<Vec<usize>>();
+        write!(f, "Knapsack {:?}", taken)
+    }
+}
+
+impl Organism for Knapsack {
+    // Counted in whole units
+    type Fitness = i64;
+
+    // The GA minimizes, so the packed value is negated
+    fn fitness(&self) -> i64 {
+        let (weight, value) = self
+            .taken
+            .iter()
+            .enumerate()
+            .filter(|(_, taken)| **taken)
+            .fold((0, 0), |(weight, value), (i, _)| {
+                (weight + self.items.weights[i], value + self.items.values[i])
+            });
+
+        if weight > self.items.capacity {
+            return (weight - self.items.capacity) as i64;
+        }
+
+        -(value as i64)
+    }
+
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let index = rng.gen_range(0..self.taken.len());
+        self.taken[index] = !self.taken[index];
+    }
+
+    fn cross_over<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self {
+        let taken = self
+            .taken
+            .iter()
+            .zip(other.taken.iter())
+            .map(|(a, b)| if rng.gen_bool(0.5) { *a } else { *b })
+            .collect();
+
+        Knapsack {
+            items: self.items.clone(),
+            taken,
+        }
+    }
+}
+
+fn main() {
+    let mut rng = rand::thread_rng();
+    let weights = (0..50).map(|_| rng.gen_range(1..20)).collect::<Vec<u32>>();
+    let values = (0..50).map(|_| rng.gen_range(1..50)).collect::<Vec<u32>>();
+    let capacity = weights.iter().sum::<u32>() / 3;
+    let items = Arc::new(Items {
+        weights,
+        values,
+        capacity,
+    });
+
+    // Every item is taken with the odds that fill the knapsack on average
+    registry::register_problem("knapsack", move |parameters| {
+        let mut rng = rand::thread_rng();
+        let population = (0..parameters.population_size)
+            .map(|_| Knapsack {
+                items: items.clone(),
+                taken: (0..items.weights.len())
+                    .map(|_| rng.gen_bool(1.0 / 3.0))
+                    .collect(),
+            })
+            .collect::<Vec<Knapsack>>();
+        Box::new(PopulationSolver::new(population, *parameters))
+    });
+
+    let parameters = RunParameters {
+        iterations: 100,
+        population_size: 300,
+        elite: 10,
+        mutation_rate: 0.3,
+        crossover_rate: 0.9,
+    };
+
+    let mut solver = registry::create_problem("knapsack", &parameters).unwrap();
+    runner::run(solver.as_mut(), parameters.iterations);
+}
+
+#[test]
+fn runs() {
+    main()
+}
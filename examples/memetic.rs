@@ -0,0 +1,32 @@
+// Memetic TSP on eil101: every offspring shorter than both of its parents is
+// polished with 2-opt before it is evaluated, at most 50 of them a generation.
+// The same run as `ga-run --memetic improved-offspring --polish-budget 50`,
+// through the local backend the binary picks without MPI or islands. The run
+// is seeded so it prints the same every time.
+use clap::Parser;
+use genetic_algorithm::backend::{self, LocalBackend};
+use genetic_algorithm::cli::RunCli;
+
+fn main() {
+    let cli = RunCli::parse_from([
+        "memetic",
+        "--instance",
+        "builtin:eil101",
+        "--pop-size",
+        "200",
+        "--iterations",
+        "60",
+        "--memetic",
+        "improved-offspring",
+        "--polish-budget",
+        "50",
+        "--seed",
+        "7",
+    ]);
+    assert!(backend::execute(&cli.run, &LocalBackend));
+}
+
+#[test]
+fn runs() {
+    main()
+}
@@ -0,0 +1,82 @@
+// Continuous optimization of the Rastrigin function with the GeneticAlgorithm
+// builder, stepping it by hand to print every 20th generation. The run is
+// seeded so it prints the same every time.
+use genetic_algorithm::{GeneticAlgorithm, Organism};
+use rand::Rng;
+use std::f64::consts::PI;
+
+const DIMENSIONS: usize = 10;
+const BOUND: f64 = 5.12;
+
+#[derive(Clone, Debug)]
+struct Point {
+    coordinates: Vec<f64>,
+}
+
+impl Organism for Point {
+    // Summed in double precision, the GA ranks the result as an f32
+    type Fitness = f64;
+
+    fn fitness(&self) -> f64 {
+        10.0 * self.coordinates.len() as f64
+            + self
+                .coordinates
+                .iter()
+                .map(|x| x * x - 10.0 * (2.0 * PI * x).cos())
+                .sum::<f64>()
+    }
+
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let index = rng.gen_range(0..self.coordinates.len());
+        self.coordinates[index] =
+            (self.coordinates[index] + rng.gen_range(-0.5..0.5)).clamp(-BOUND, BOUND);
+    }
+
+    // Blend crossover, each coordinate is a random mix of both parents
+    fn cross_over<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self {
+        let coordinates = self
+            .coordinates
+            .iter()
+            .zip(other.coordinates.iter())
+            .map(|(a, b)| {
+                let alpha = rng.gen_range(0.0..1.0);
+                alpha * a + (1.0 - alpha) * b
+            })
+            .collect();
+
+        Point { coordinates }
+    }
+}
+
+fn main() {
+    let mut ga = GeneticAlgorithm::builder()
+        .population_size(400)
+        .elite(10)
+        .mutation_rate(0.2)
+        .crossover_rate(0.9)
+        .seed(42)
+        .build(|rng| Point {
+            coordinates: (0..DIMENSIONS)
+                .map(|_| rng.gen_range(-BOUND..BOUND))
+                .collect(),
+        })
+        .expect("Valid parameters");
+
+    for i in 0..200 {
+        let summary = ga.step();
+        if i % 20 == 0 {
+            println!(
+                "Generation {}, best: {}, mean: {:.3}",
+                i, summary.best, summary.mean
+            );
+        }
+    }
+
+    let (fitness, best) = ga.best().unwrap();
+    println!("Best one: {} -> {:?}", fitness, best);
+}
+
+#[test]
+fn runs() {
+    main()
+}
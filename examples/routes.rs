@@ -0,0 +1,159 @@
+// Delivery routes as a composite fitness over the plain TSP organism: the
+// tour is cut into one route per vehicle, and its length, the load over the
+// capacity of every vehicle and the gap between the longest and shortest
+// route are weighted terms, as is the time every route takes over the duration
+// limit counting the service at its cities. Weights can be given as arguments,
+// e.g. `cargo run --example routes -- balance=2 overload=0`, and the cities,
+// service times and limit can come from an instance file with
+// `instance=path.tsp`, see `loader::parse`. The run is seeded so it prints the
+// same every time.
+use genetic_algorithm::tsp::duration::RouteDuration;
+use genetic_algorithm::tsp::matrix::WeightMatrix;
+use genetic_algorithm::tsp::{loader, TspProblem, TspSolution, TSP};
+use genetic_algorithm::{Composite, CompositeFitness, GeneticAlgorithm, Organism};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+
+const CITIES: usize = 40;
+const VEHICLES: usize = 4;
+// Without an instance file
+const SERVICE_TIMES: std::ops::Range<f32> = 2.0..8.0;
+const DURATION_LIMIT: f32 = 240.0;
+const DURATION_PENALTY: f32 = 5.0;
+
+// Cities of every route, the tour cut in as even parts as it allows
+fn routes(solution: &TspSolution) -> impl Iterator<Item = &[usize]> {
+    solution.path.chunks(solution.path.len().div_ceil(VEHICLES))
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let (arguments, instance): (Vec<String>, Vec<String>) = std::env::args()
+        .skip(1)
+        .partition(|argument| !argument.starts_with("instance="));
+    let (graph_weights, duration) = match instance.last() {
+        Some(argument) => {
+            let path = std::path::Path::new(&argument["instance=".len()..]);
+            let instance = loader::load(path).expect("A valid instance file");
+            let duration = instance.duration.map_or_else(
+                || RouteDuration {
+                    service: vec![0.0; instance.graph_weights.len()],
+                    limit: None,
+                    penalty: None,
+                },
+                |duration| (*duration).clone(),
+            );
+            (instance.graph_weights, duration)
+        }
+        None => {
+            let coordinates = (0..CITIES)
+                .map(|_| (rng.gen_range(0.0..100.0), rng.gen_range(0.0..100.0)))
+                .collect::<Vec<(f32, f32)>>();
+            let graph_weights = coordinates
+                .iter()
+                .map(|(x1, y1)| {
+                    coordinates
+                        .iter()
+                        .map(|(x2, y2)| ((x1 - x2).powi(2) + (y1 - y2).powi(2)).sqrt())
+                        .collect::<Vec<f32>>()
+                })
+                .collect::<Vec<Vec<f32>>>();
+            let duration = RouteDuration {
+                service: (0..CITIES).map(|_| rng.gen_range(SERVICE_TIMES)).collect(),
+                limit: Some(DURATION_LIMIT),
+                penalty: Some(DURATION_PENALTY),
+            };
+            (graph_weights, duration)
+        }
+    };
+    let graph_weights = Arc::new(WeightMatrix::from_rows(&graph_weights));
+    println!("Route duration: {}", duration);
+    let demands = (0..graph_weights.len())
+        .map(|_| rng.gen_range(1.0..10.0))
+        .collect::<Vec<f32>>();
+    let capacity = demands.iter().sum::<f32>() / VEHICLES as f32;
+
+    let weights = graph_weights.clone();
+    let overtime = move |tsp: &TSP| {
+        routes(tsp.get_solution())
+            .map(|route| duration.cost(weights.as_ref(), route, false))
+            .sum::<f32>()
+    };
+    let weights = graph_weights.clone();
+    let route_length = move |route: &[usize]| {
+        route
+            .windows(2)
+            .map(|pair| weights[pair[0]][pair[1]])
+            .sum::<f32>()
+    };
+    let mut builder = CompositeFitness::builder()
+        .term("length", 1.0, |tsp: &TSP| tsp.fitness())
+        .term("overload", 10.0, move |tsp: &TSP| {
+            routes(tsp.get_solution())
+                .map(|route| {
+                    let load = route.iter().map(|city| demands[*city]).sum::<f32>();
+                    (load - capacity).max(0.0)
+                })
+                .sum()
+        })
+        .term("balance", 1.0, move |tsp: &TSP| {
+            let lengths = routes(tsp.get_solution())
+                .map(&route_length)
+                .collect::<Vec<f32>>();
+            lengths.iter().copied().fold(f32::MIN, f32::max)
+                - lengths.iter().copied().fold(f32::MAX, f32::min)
+        })
+        .term("duration", 1.0, overtime);
+    for argument in arguments {
+        let (name, weight) = argument
+            .split_once('=')
+            .and_then(|(name, weight)| Some((name, weight.parse().ok()?)))
+            .expect("Weights are given as name=weight");
+        builder = builder.weight(name, weight);
+    }
+    let fitness = builder.build().expect("Valid weights");
+    println!("Weights: {:?}", fitness);
+
+    let problem = Arc::new(TspProblem::new(graph_weights.clone()));
+    let mut ga = GeneticAlgorithm::builder()
+        .population_size(400)
+        .elite(10)
+        .mutation_rate(0.3)
+        .crossover_rate(0.9)
+        .seed(42)
+        .build(|rng| Composite::new(TSP::new_shuffled(&problem, rng), fitness.clone()))
+        .expect("Valid parameters");
+
+    for i in 0..300 {
+        // Of the population the step evaluates
+        let objectives = fitness.summaries(ga.population());
+        let summary = ga.step();
+        if i % 30 == 0 {
+            println!(
+                "Generation {}, best: {}, mean: {:.3}",
+                i, summary.best, summary.mean
+            );
+            for objective in objectives.iter() {
+                println!(
+                    "  {:<8} x {:<4} best: {:.3}, mean: {:.3}",
+                    objective.name,
+                    objective.weight,
+                    objective.summary.best,
+                    objective.summary.mean()
+                );
+            }
+        }
+    }
+
+    let (total, best) = ga.best().unwrap();
+    println!("Best one: {} -> {:?}", total, best);
+    for (name, cost) in fitness.names().iter().zip(best.costs()) {
+        println!("  {}: {:.3}", name, cost);
+    }
+}
+
+#[test]
+fn runs() {
+    main()
+}
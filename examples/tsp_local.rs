@@ -0,0 +1,56 @@
+// Single process TSP on a small random euclidean instance, no MPI involved.
+// Exercises the diverse initialization and the generic PopulationSolver.
+use genetic_algorithm::initialization::{diverse_population, DiversityOptions};
+use genetic_algorithm::runner::{self, PopulationSolver, RunParameters};
+use genetic_algorithm::tsp::matrix::WeightMatrix;
+use genetic_algorithm::tsp::{TspProblem, TSP};
+use rand::Rng;
+use std::sync::Arc;
+
+const CITIES: usize = 30;
+
+fn main() {
+    let mut rng = rand::thread_rng();
+    let coordinates = (0..CITIES)
+        .map(|_| (rng.gen_range(0.0..100.0), rng.gen_range(0.0..100.0)))
+        .collect::<Vec<(f32, f32)>>();
+
+    let graph_weights = coordinates
+        .iter()
+        .map(|(x1, y1)| {
+            coordinates
+                .iter()
+                .map(|(x2, y2)| ((x1 - x2).powi(2) + (y1 - y2).powi(2)).sqrt())
+                .collect::<Vec<f32>>()
+        })
+        .collect::<Vec<Vec<f32>>>();
+    let problem = Arc::new(TspProblem::new(Arc::new(WeightMatrix::from_rows(
+        &graph_weights,
+    ))));
+
+    let parameters = RunParameters {
+        iterations: 100,
+        population_size: 500,
+        elite: 10,
+        mutation_rate: 0.1,
+        crossover_rate: 0.9,
+    };
+
+    let seeds = (0..CITIES)
+        .map(|start| TSP::new_nearest_neighbor(&problem, start))
+        .collect::<Vec<TSP>>();
+    let population = diverse_population(
+        parameters.population_size,
+        DiversityOptions::default(),
+        seeds,
+        || TSP::new_with_random_path(&problem),
+    );
+
+    let mut solver = PopulationSolver::new(population, parameters);
+    runner::run(&mut solver, parameters.iterations);
+}
+
+#[test]
+fn runs() {
+    main()
+}
@@ -0,0 +1,112 @@
+use crate::backend::{ExecutionBackend, LocalBackend};
+use crate::cli::RunArgs;
+use crate::local_search::MemeticPolicy;
+use crate::tsp::TspInstance;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+// What `ablate` switches off, one at a time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ablation {
+    Elitism,
+    Mutation,
+    Crossover,
+    LocalSearch,
+}
+
+impl Ablation {
+    pub const ALL: [Ablation; 4] = [
+        Ablation::Elitism,
+        Ablation::Mutation,
+        Ablation::Crossover,
+        Ablation::LocalSearch,
+    ];
+
+    // Whether `run` uses it at all, there is nothing to switch off otherwise
+    pub fn used_by(self, run: &RunArgs) -> bool {
+        match self {
+            Ablation::Elitism => run.elite > 0,
+            Ablation::Mutation => run.mutation_rate > 0.0,
+            Ablation::Crossover => run.crossover_rate > 0.0,
+            Ablation::LocalSearch => run.memetic != MemeticPolicy::Off,
+        }
+    }
+
+    pub fn apply(self, run: &mut RunArgs) {
+        match self {
+            Ablation::Elitism => run.elite = 0,
+            Ablation::Mutation => run.mutation_rate = 0.0,
+            Ablation::Crossover => run.crossover_rate = 0.0,
+            Ablation::LocalSearch => run.memetic = MemeticPolicy::Off,
+        }
+    }
+}
+
+impl fmt::Display for Ablation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Ablation::Elitism => "elitism",
+            Ablation::Mutation => "mutation",
+            Ablation::Crossover => "crossover",
+            Ablation::LocalSearch => "local-search",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for Ablation {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ablation::ALL
+            .into_iter()
+            .find(|ablation| ablation.to_string() == name)
+            .ok_or_else(|| format!("unknown ablation: {}", name))
+    }
+}
+
+// The best fitness of every run of one setup, `ablation` is None for the
+// setup as configured
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    pub ablation: Option<Ablation>,
+    pub bests: Vec<f32>,
+}
+
+impl Outcome {
+    pub fn mean_best(&self) -> f32 {
+        self.bests.iter().sum::<f32>() / self.bests.len().max(1) as f32
+    }
+}
+
+// The configured setup, then one setup per ablation it can be given, each
+// solved `repeats` times locally. Run n of every setup starts from the same
+// seed, so setups differ by what was switched off rather than by luck.
+pub fn run(run: &RunArgs, instance: &TspInstance, repeats: usize) -> Vec<Outcome> {
+    let run = run.repeatable();
+    let seed = run.seed.unwrap_or_else(|| rand::thread_rng().gen());
+
+    let setups = std::iter::once(None).chain(
+        Ablation::ALL
+            .into_iter()
+            .filter(|ablation| ablation.used_by(&run))
+            .map(Some),
+    );
+    setups
+        .map(|ablation| {
+            let mut run = run.clone();
+            if let Some(ablation) = ablation {
+                ablation.apply(&mut run);
+            }
+            let bests = (0..repeats as u64)
+                .filter_map(|repeat| {
+                    run.seed = Some(seed.wrapping_add(repeat));
+                    LocalBackend.solve(&run, instance)
+                })
+                .map(|summary| summary.best)
+                .collect();
+            Outcome { ablation, bests }
+        })
+        .collect()
+}
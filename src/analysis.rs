@@ -0,0 +1,132 @@
+use crate::organism::{Distance, Organism};
+use crate::parallel::prelude::*;
+use rand::Rng;
+
+// Landscape statistics of a problem under its mutation operator
+#[derive(Clone, Copy, Debug)]
+pub struct LandscapeReport {
+    pub walks: usize,
+    pub steps: usize,
+    // Mean lag 1 autocorrelation of the fitness along the random walks
+    pub autocorrelation: f64,
+    // Steps after which fitness values become uncorrelated, -1 / ln|rho(1)|
+    pub correlation_length: f64,
+    // Correlation between fitness and distance to the best sampled individual
+    pub fitness_distance_correlation: f64,
+}
+
+// Fitness along a walk where every step applies one mutation
+pub fn random_walk<T, R>(start: &T, steps: usize, rng: &mut R) -> Vec<f32>
+where
+    T: Organism + Clone,
+    R: Rng + ?Sized,
+{
+    let mut current = start.clone();
+    let mut fitness = Vec::with_capacity(steps + 1);
+    fitness.push(current.score());
+
+    for _ in 0..steps {
+        current.mutate(rng);
+        fitness.push(current.score());
+    }
+
+    fitness
+}
+
+pub fn autocorrelation(series: &[f32], lag: usize) -> f64 {
+    if series.len() <= lag {
+        return f64::NAN;
+    }
+
+    let mean = series.iter().map(|value| *value as f64).sum::<f64>() / series.len() as f64;
+    let variance = series
+        .iter()
+        .map(|value| (*value as f64 - mean).powi(2))
+        .sum::<f64>();
+    if variance == 0.0 {
+        return 1.0;
+    }
+
+    let covariance = series
+        .iter()
+        .zip(series.iter().skip(lag))
+        .map(|(a, b)| (*a as f64 - mean) * (*b as f64 - mean))
+        .sum::<f64>();
+
+    covariance / variance
+}
+
+pub fn correlation_length(autocorrelation: f64) -> f64 {
+    -1.0 / autocorrelation.abs().ln()
+}
+
+pub fn fitness_distance_correlation<T>(samples: &[T]) -> f64
+where
+    T: Organism + Distance + Sync,
+{
+    let fitness = samples
+        .par_iter()
+        .map(|sample| sample.score() as f64)
+        .collect::<Vec<f64>>();
+
+    let best = match (0..samples.len()).min_by(|a, b| fitness[*a].total_cmp(&fitness[*b])) {
+        Some(best) => best,
+        None => return f64::NAN,
+    };
+
+    let distance = samples
+        .par_iter()
+        .map(|sample| sample.distance(&samples[best]) as f64)
+        .collect::<Vec<f64>>();
+
+    pearson(&fitness, &distance)
+}
+
+pub fn probe<T, F>(generate: F, walks: usize, steps: usize, samples: usize) -> LandscapeReport
+where
+    T: Organism + Distance + Clone + Send + Sync,
+    F: Fn() -> T + Sync,
+{
+    let autocorrelations = (0..walks)
+        .into_par_iter()
+        .map(|_| {
+            let walk = random_walk(&generate(), steps, &mut rand::thread_rng());
+            autocorrelation(&walk, 1)
+        })
+        .filter(|value| value.is_finite())
+        .collect::<Vec<f64>>();
+    let autocorrelation =
+        autocorrelations.iter().sum::<f64>() / autocorrelations.len().max(1) as f64;
+
+    let samples = (0..samples)
+        .into_par_iter()
+        .map(|_| generate())
+        .collect::<Vec<T>>();
+
+    LandscapeReport {
+        walks,
+        steps,
+        autocorrelation,
+        correlation_length: correlation_length(autocorrelation),
+        fitness_distance_correlation: fitness_distance_correlation(&samples),
+    }
+}
+
+fn pearson(xs: &[f64], ys: &[f64]) -> f64 {
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+
+    let (covariance, variance_x, variance_y) = xs.iter().zip(ys.iter()).fold(
+        (0.0, 0.0, 0.0),
+        |(covariance, variance_x, variance_y), (x, y)| {
+            (
+                covariance + (x - mean_x) * (y - mean_y),
+                variance_x + (x - mean_x).powi(2),
+                variance_y + (y - mean_y).powi(2),
+            )
+        },
+    );
+
+    covariance / (variance_x * variance_y).sqrt()
+}
@@ -0,0 +1,266 @@
+use crate::batch::{self, BatchEntry, BatchResult};
+use crate::cli::RunArgs;
+use crate::distributed::ROOT_PROCESS;
+#[cfg(feature = "mpi")]
+use crate::distributed::{MpiBackend, Role};
+use crate::driver::{self, evaluate_chunk, solutions_of};
+use crate::logging::{self, LevelControl, LogConfig};
+use crate::scheduler::RunSummary;
+use crate::self_test;
+use crate::tsp::TspInstance;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+
+// Exit codes of a run that can't go on, 2 is taken by an invalid command line
+// or config file, found before any backend starts. A rank failing under MPI
+// aborts every rank with its code, instead of leaving the others waiting on
+// it until the job is killed.
+// `--check` or the checks before the run refused the configuration
+pub const EXIT_INVALID: i32 = 1;
+// The instance, or the list of a `--batch`, can't be loaded on some rank
+pub const EXIT_NO_INSTANCE: i32 = 3;
+// A rank panicked, the code Rust exits with on a panic
+pub const EXIT_PANIC: i32 = 101;
+
+// Where and how the GA runs, the rest of a run is the same whatever the
+// backend: every process loads the instance, logs and checks the
+// configuration, then the backend solves.
+pub trait ExecutionBackend {
+    // Rank of this process among those running the backend, the root reports
+    fn rank(&self) -> i32 {
+        ROOT_PROCESS
+    }
+
+    fn processes(&self) -> i32 {
+        1
+    }
+
+    // Every process logs with the configuration the root was started with
+    fn share_log_config(&self, config: LogConfig) -> LogConfig {
+        config
+    }
+
+    // Runs on every process of the backend, only the root returns a summary
+    fn solve(&self, run: &RunArgs, instance: &TspInstance) -> Option<RunSummary>;
+
+    // Every instance of a `--batch`, only the root returns how they went. One
+    // after another with all the processes by default.
+    fn solve_batch(&self, run: &RunArgs, batch: &[BatchEntry]) -> Option<Vec<BatchResult>> {
+        let results = batch::solve_in_turn(run, batch, self.rank(), |run, instance| {
+            self.solve(run, instance)
+        });
+        (self.rank() == ROOT_PROCESS).then_some(results)
+    }
+
+    // Ends every process of the backend with `code`, wherever the others are
+    // waiting
+    fn abort(&self, code: i32) -> ! {
+        std::process::exit(code)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BackendKind {
+    Local,
+    Islands,
+    #[cfg(feature = "mpi")]
+    Mpi,
+    // Every MPI rank evolves an island
+    #[cfg(feature = "mpi")]
+    MpiIslands,
+}
+
+impl BackendKind {
+    pub const ALL: &'static [BackendKind] = &[
+        BackendKind::Local,
+        BackendKind::Islands,
+        #[cfg(feature = "mpi")]
+        BackendKind::Mpi,
+        #[cfg(feature = "mpi")]
+        BackendKind::MpiIslands,
+    ];
+
+    // What runs in this process only, islands when there is more than one
+    // unless `--backend` chooses
+    pub fn in_process(run: &RunArgs) -> BackendKind {
+        match run.backend {
+            Some(BackendKind::Local) => BackendKind::Local,
+            Some(BackendKind::Islands) => BackendKind::Islands,
+            _ if run.islands > 1 => BackendKind::Islands,
+            _ => BackendKind::Local,
+        }
+    }
+
+    // Whether the run breeds islands, in this process or one on every rank
+    pub fn runs_islands(&self) -> bool {
+        match self {
+            BackendKind::Islands => true,
+            #[cfg(feature = "mpi")]
+            BackendKind::MpiIslands => true,
+            _ => false,
+        }
+    }
+
+    // `--backend`, or MPI when the binary has it and no islands were asked for
+    pub fn for_run(run: &RunArgs) -> BackendKind {
+        if let Some(kind) = run.backend {
+            return kind;
+        }
+        #[cfg(feature = "mpi")]
+        if run.islands <= 1 {
+            return BackendKind::Mpi;
+        }
+        BackendKind::in_process(run)
+    }
+
+    pub fn create(&self) -> Box<dyn ExecutionBackend> {
+        match self {
+            BackendKind::Local => Box::new(LocalBackend),
+            BackendKind::Islands => Box::new(IslandsBackend),
+            #[cfg(feature = "mpi")]
+            BackendKind::Mpi => Box::new(MpiBackend::initialize(Role::Evaluation)),
+            #[cfg(feature = "mpi")]
+            BackendKind::MpiIslands => Box::new(MpiBackend::initialize(Role::Islands)),
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            BackendKind::Local => "local",
+            BackendKind::Islands => "islands",
+            #[cfg(feature = "mpi")]
+            BackendKind::Mpi => "mpi",
+            #[cfg(feature = "mpi")]
+            BackendKind::MpiIslands => "mpi-islands",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        BackendKind::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.to_string() == name)
+            .ok_or_else(|| match name {
+                "mpi" => "this binary was built without MPI".to_string(),
+                _ => format!("unknown backend: {}", name),
+            })
+    }
+}
+
+// The whole GA in this process, evaluation included
+pub struct LocalBackend;
+
+impl ExecutionBackend for LocalBackend {
+    fn solve(&self, run: &RunArgs, instance: &TspInstance) -> Option<RunSummary> {
+        let mut level_control = run.log_level_file.clone().map(LevelControl::new);
+        Some(driver::evolve(run, instance, 1, |population, map| {
+            if let Some(control) = level_control.as_mut() {
+                control.update();
+            }
+            let (evaluated_population, summary) = evaluate_chunk(map, solutions_of(population));
+            (evaluated_population, summary, None, Vec::new())
+        }))
+    }
+}
+
+// `--islands` populations on threads of this process
+pub struct IslandsBackend;
+
+impl ExecutionBackend for IslandsBackend {
+    fn solve(&self, run: &RunArgs, instance: &TspInstance) -> Option<RunSummary> {
+        Some(driver::evolve_islands(run, instance))
+    }
+}
+
+// Creates the backend, runs and exits with an error when the configuration
+// is invalid. The backend is gone by then, MPI is finalized. A panic on any
+// process aborts the backend once the panic was reported.
+pub fn run(run: &RunArgs, kind: BackendKind) {
+    let backend = kind.create();
+    let valid = panic::catch_unwind(AssertUnwindSafe(|| execute(run, backend.as_ref())))
+        .unwrap_or_else(|_| backend.abort(EXIT_PANIC));
+    drop(backend);
+    if !valid {
+        std::process::exit(EXIT_INVALID);
+    }
+}
+
+// False when `--check` found the configuration invalid
+pub fn execute(run: &RunArgs, backend: &dyn ExecutionBackend) -> bool {
+    let rank = backend.rank();
+    let root = rank == ROOT_PROCESS;
+
+    let log_config = backend.share_log_config(run.log_config());
+    if let Err(error) = logging::init(rank, &log_config) {
+        eprintln!("Rank {} failed to set up logging: {}", rank, error);
+        std::process::exit(1);
+    }
+
+    if let Some(path) = &run.batch {
+        return batch::execute(run, backend, path);
+    }
+    // Every rank loads the instance, the checks and the island model need it
+    // everywhere. Evaluation workers still take the map the root broadcasts.
+    let instance = driver::load_instance(run, &run.instance).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        backend.abort(EXIT_NO_INSTANCE)
+    });
+    let run = &driver::with_portfolio(run, &instance).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        backend.abort(EXIT_INVALID)
+    });
+
+    if run.check {
+        return driver::check(run, &instance, backend.processes(), root);
+    }
+    if let Some(test) = run.self_test {
+        return !root || self_test::run(run, &instance, test);
+    }
+    // Parameters the GA can't breed with are refused before anything runs,
+    // as `--check` would
+    let problems = driver::check_configuration(run, &instance);
+    if !problems.is_empty() {
+        if root {
+            for problem in problems.iter() {
+                eprintln!("error: {}", problem);
+            }
+        }
+        return false;
+    }
+
+    // Only the TSP goes through the backend, the clustered TSP and registered
+    // problems run on the root
+    if run.problem == driver::CLUSTERED_PROBLEM {
+        if root {
+            driver::run_clustered(run, &instance);
+        }
+        return true;
+    }
+    if run.problem != "tsp" {
+        if root {
+            driver::run_registered_problem(run);
+        }
+        return true;
+    }
+
+    driver::check_memory(
+        rank,
+        backend.processes(),
+        run.pop_size,
+        instance.graph_weights.len(),
+    );
+    if let Some(run_summary) = backend.solve(run, &instance) {
+        driver::write_summary(run, &run_summary);
+        driver::write_history(run, &run_summary);
+        driver::write_explanation(run, &instance, &run_summary);
+    }
+    true
+}
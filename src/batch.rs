@@ -0,0 +1,577 @@
+use crate::backend::{ExecutionBackend, LocalBackend, EXIT_NO_INSTANCE};
+use crate::cli::RunArgs;
+use crate::comm::{self, Payload};
+use crate::distributed::{self, ROOT_PROCESS};
+use crate::driver;
+use crate::scheduler::RunSummary;
+#[cfg(feature = "compression")]
+use crate::transport::Compressing;
+use crate::transport::{Group, Transport};
+use crate::tsp::{builtin, TspInstance, TspSolution};
+use crate::warm_start::WarmStart;
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// One instance of a `--batch` file, named after its file for its results
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchEntry {
+    pub index: usize,
+    pub name: String,
+    // As `--instance` takes it
+    pub instance: String,
+}
+
+// How an instance of the batch went, the rest is in its own directory
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchResult {
+    pub index: usize,
+    pub name: String,
+    pub instance: String,
+    pub cities: usize,
+    // Rank that solved it
+    pub rank: i32,
+    pub best: Option<f32>,
+    pub mean: Option<f64>,
+    pub generations_completed: usize,
+    pub elapsed_seconds: f64,
+    pub error: Option<String>,
+    // Best tour found, for `--warm-start`
+    pub tour: Option<TspSolution>,
+}
+
+// Between the manager of a batch, the root, and the other ranks
+#[derive(Clone, Serialize, Deserialize)]
+enum BatchMessage {
+    // A rank waiting for something to do
+    Ready,
+    // A leader done with its instance, it waits as well
+    Finished(BatchResult),
+    // A leader asking between two generations whether it got helpers
+    Poll,
+    // Solve this instance of the batch, with the ranks that come to help,
+    // starting from these tours of similar instances
+    Lead(usize, Vec<TspSolution>),
+    // Evaluate for this leader until it says to stop
+    Help(i32),
+    // Ranks from now on evaluating for the leader, the answer to a poll
+    Helpers(Vec<i32>),
+    Stop,
+}
+
+impl Payload for BatchMessage {
+    fn kind(&self) -> &'static str {
+        match self {
+            BatchMessage::Ready => "Ready",
+            BatchMessage::Finished(_) => "Finished",
+            BatchMessage::Poll => "Poll",
+            BatchMessage::Lead(..) => "Lead",
+            BatchMessage::Help(_) => "Help",
+            BatchMessage::Helpers(_) => "Helpers",
+            BatchMessage::Stop => "Stop",
+        }
+    }
+}
+
+// Instances of a batch stop once they converge: after `--stagnation`
+// generations without a better best individual, or this many when it is
+// not given, whatever `--iterations` says
+pub const BATCH_STAGNATION: usize = 50;
+
+// One instance per line, as `--instance` takes it. Blank lines and lines
+// starting with # are skipped. Two instances with the same name get `-2`,
+// `-3`... appended, so they don't share a result directory.
+pub fn read_batch(path: &Path) -> io::Result<Vec<BatchEntry>> {
+    let content = fs::read_to_string(path)?;
+    let mut names = HashSet::new();
+
+    let mut entries = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let stem = match line.strip_prefix(builtin::PREFIX) {
+            Some(name) => name.to_string(),
+            None => Path::new(line).file_stem().map_or_else(
+                || line.to_string(),
+                |stem| stem.to_string_lossy().into_owned(),
+            ),
+        };
+        let mut name = stem.clone();
+        let mut copy = 1;
+        while !names.insert(name.clone()) {
+            copy += 1;
+            name = format!("{}-{}", stem, copy);
+        }
+        entries.push(BatchEntry {
+            index: entries.len(),
+            name,
+            instance: line.to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+// `--batch`: every instance solved as its own run, its summary and the
+// statistics of its generations written to `<batch out>/<name>/`, then the
+// root writes `batch.csv` with a line per instance. False when `--check`
+// found the configuration invalid for one of them.
+pub fn execute(run: &RunArgs, backend: &dyn ExecutionBackend, path: &Path) -> bool {
+    let root = backend.rank() == ROOT_PROCESS;
+    let batch = read_batch(path).unwrap_or_else(|error| {
+        eprintln!("{}: {}", path.display(), error);
+        backend.abort(EXIT_NO_INSTANCE)
+    });
+
+    if run.check {
+        let mut valid = true;
+        for entry in batch.iter() {
+            let instance = driver::load_instance(run, &entry.instance).unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                backend.abort(EXIT_NO_INSTANCE)
+            });
+            let problems = driver::check_configuration(run, &instance);
+            if root {
+                for problem in problems.iter() {
+                    eprintln!("error: {}: {}", entry.name, problem);
+                }
+            }
+            valid &= problems.is_empty();
+        }
+        if root && valid {
+            println!("Configuration is valid for the {} instances", batch.len());
+        }
+        return valid;
+    }
+
+    if root {
+        info!(
+            "Solving {} instances of {} on {} processes",
+            batch.len(),
+            path.display(),
+            backend.processes()
+        );
+    }
+    let started = Instant::now();
+    if let Some(results) = backend.solve_batch(run, &batch) {
+        let solved = results.iter().filter(|result| result.error.is_none());
+        info!(
+            "Solved {} of {} instances in {:.3} seconds",
+            solved.count(),
+            results.len(),
+            started.elapsed().as_secs_f64()
+        );
+        write_index(run, &results);
+    }
+    true
+}
+
+// `batch.csv` in `--batch-out`
+pub fn write_index(run: &RunArgs, results: &[BatchResult]) {
+    let directory = output_directory(run);
+    let index = directory.join("batch.csv");
+    let written = fs::create_dir_all(&directory).and_then(|_| write_results(&index, results));
+    if let Err(error) = written {
+        error!("Failed to write {}: {}", index.display(), error);
+    }
+}
+
+// The instances one after another with `solve`, in this process
+pub fn solve_in_turn<F>(
+    run: &RunArgs,
+    batch: &[BatchEntry],
+    rank: i32,
+    solve: F,
+) -> Vec<BatchResult>
+where
+    F: Fn(&RunArgs, &TspInstance) -> Option<RunSummary>,
+{
+    let mut warm_start = warm_start(run);
+    batch
+        .iter()
+        .map(|entry| {
+            let instance = driver::load_instance(run, &entry.instance);
+            let tours = match (&warm_start, &instance) {
+                (Some(warm_start), Ok(instance)) => warm_start.tours_for(instance),
+                _ => Vec::new(),
+            };
+            let result = solve_entry(run, entry, instance.clone(), tours, rank, &solve);
+            if let (Some(warm_start), Ok(instance), Some(tour)) =
+                (&mut warm_start, instance, &result.tour)
+            {
+                warm_start.add(instance, tour.clone());
+            }
+            result
+        })
+        .collect()
+}
+
+// What `--warm-start` keeps of the solved instances, when it is given
+fn warm_start(run: &RunArgs) -> Option<WarmStart> {
+    run.warm_start
+        .map(|similarity| WarmStart::new(similarity.create(), run.warm_start_threshold))
+}
+
+// The root manages the batch and every other rank solves it. Each instance
+// has a leader running its GA, the largest instances are handed out first.
+// An instance stops on its own as it converges and its ranks go back to the
+// manager: to lead the next instance, or once none is left to help a leader
+// still running, whichever has the fewest helpers. A single process solves
+// the instances in turn. Only the root returns the results.
+pub fn solve<T: Transport>(
+    transport: &T,
+    run: &RunArgs,
+    batch: &[BatchEntry],
+) -> Option<Vec<BatchResult>> {
+    #[cfg(feature = "compression")]
+    if run.compress {
+        return solve_on(&Compressing::new(transport), run, batch);
+    }
+    solve_on(transport, run, batch)
+}
+
+fn solve_on<T: Transport>(
+    transport: &T,
+    run: &RunArgs,
+    batch: &[BatchEntry],
+) -> Option<Vec<BatchResult>> {
+    if transport.size() == 1 {
+        return Some(solve_in_turn(run, batch, ROOT_PROCESS, |run, instance| {
+            LocalBackend.solve(run, instance)
+        }));
+    }
+    if transport.rank() == ROOT_PROCESS {
+        Some(manage(transport, run, batch))
+    } else {
+        serve(transport, run, batch);
+        None
+    }
+}
+
+// What the manager knows of the ranks solving the batch
+struct Allocation {
+    // Instances nobody leads yet, the next one first
+    pending: VecDeque<usize>,
+    // Every leader and how many ranks help it
+    leaders: BTreeMap<i32, usize>,
+    // Ranks ready to help as soon as a leader polls
+    waiting: Vec<i32>,
+    // The instances, loaded once, and the tours solved so far, only with
+    // `--warm-start`
+    instances: Vec<Option<TspInstance>>,
+    warm_start: Option<WarmStart>,
+}
+
+impl Allocation {
+    // Gives `rank`, ready, its next instance, or has it wait for a leader
+    fn assign<T: Transport>(&mut self, transport: &T, rank: i32) {
+        match self.pending.pop_front() {
+            Some(index) => {
+                let tours = match (&self.warm_start, &self.instances[index]) {
+                    (Some(warm_start), Some(instance)) => warm_start.tours_for(instance),
+                    _ => Vec::new(),
+                };
+                send(transport, rank, &BatchMessage::Lead(index, tours));
+                self.leaders.insert(rank, 0);
+            }
+            None => self.waiting.push(rank),
+        }
+    }
+
+    // The best tour of a finished instance, for the next ones to start from
+    fn solved(&mut self, result: &BatchResult) {
+        if let (Some(warm_start), Some(instance), Some(tour)) = (
+            &mut self.warm_start,
+            self.instances[result.index].take(),
+            &result.tour,
+        ) {
+            warm_start.add(instance, tour.clone());
+        }
+    }
+
+    // A waiting rank for `leader` when no other leader has fewer helpers
+    fn helpers_for<T: Transport>(&mut self, transport: &T, leader: i32) -> Vec<i32> {
+        let fewest = self.leaders.values().min().copied().unwrap_or(0);
+        if self.leaders.get(&leader) != Some(&fewest) {
+            return Vec::new();
+        }
+        let Some(helper) = self.waiting.pop() else {
+            return Vec::new();
+        };
+        send(transport, helper, &BatchMessage::Help(leader));
+        *self.leaders.get_mut(&leader).unwrap() += 1;
+        vec![helper]
+    }
+}
+
+fn manage<T: Transport>(transport: &T, run: &RunArgs, batch: &[BatchEntry]) -> Vec<BatchResult> {
+    let workers = transport.size() as usize - 1;
+    let instances = batch
+        .iter()
+        .map(|entry| driver::load_instance(run, &entry.instance).ok())
+        .collect::<Vec<_>>();
+    let cities = instances
+        .iter()
+        .map(|instance| {
+            instance
+                .as_ref()
+                .map_or(0, |instance| instance.graph_weights.len())
+        })
+        .collect::<Vec<_>>();
+    let mut order = (0..batch.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&index| std::cmp::Reverse(cities[index]));
+
+    let mut allocation = Allocation {
+        pending: order.into(),
+        leaders: BTreeMap::new(),
+        waiting: Vec::new(),
+        warm_start: warm_start(run),
+        instances: if run.warm_start.is_some() {
+            instances
+        } else {
+            Vec::new()
+        },
+    };
+    let mut results = Vec::with_capacity(batch.len());
+    // Every rank is stopped at the end only, a rank that left early would
+    // look like one that failed
+    while results.len() < batch.len() || allocation.waiting.len() < workers {
+        let (source, message) = comm::receive_any(transport);
+        match message {
+            Ok(BatchMessage::Ready) => allocation.assign(transport, source),
+            Ok(BatchMessage::Finished(result)) => {
+                info!(
+                    "Rank {} finished {} with {} helpers, {} instances left",
+                    source,
+                    result.name,
+                    allocation.leaders.remove(&source).unwrap_or(0),
+                    batch.len() - results.len() - 1
+                );
+                allocation.solved(&result);
+                results.push(result);
+                allocation.assign(transport, source);
+            }
+            Ok(BatchMessage::Poll) => {
+                let helpers = allocation.helpers_for(transport, source);
+                send(transport, source, &BatchMessage::Helpers(helpers));
+            }
+            Ok(other) => panic!("Rank {} sent {} to the manager", source, other.kind()),
+            Err(error) => panic!("The batch manager failed to receive, {}", error),
+        }
+    }
+    for rank in allocation.waiting {
+        send(transport, rank, &BatchMessage::Stop);
+    }
+
+    results.sort_by_key(|result| result.index);
+    results
+}
+
+// A rank solving the batch: leading or helping as the manager says, until it
+// says to stop
+fn serve<T: Transport>(transport: &T, run: &RunArgs, batch: &[BatchEntry]) {
+    let rank = transport.rank();
+    send(transport, ROOT_PROCESS, &BatchMessage::Ready);
+    loop {
+        match comm::receive(transport, ROOT_PROCESS) {
+            Ok(BatchMessage::Lead(index, tours)) => {
+                let entry = &batch[index];
+                let instance = driver::load_instance(run, &entry.instance);
+                let result = solve_entry(run, entry, instance, tours, rank, |run, instance| {
+                    Some(lead(transport, run, instance))
+                });
+                send(transport, ROOT_PROCESS, &BatchMessage::Finished(result));
+            }
+            Ok(BatchMessage::Help(leader)) => {
+                let group = Group::new(transport, vec![leader, rank]);
+                let chunks = distributed::work(&group, run.worker_stats, !run.open_path);
+                debug!(
+                    "Rank {} evaluated {} chunks for rank {}",
+                    rank, chunks, leader
+                );
+                send(transport, ROOT_PROCESS, &BatchMessage::Ready);
+            }
+            Ok(BatchMessage::Stop) => break,
+            Ok(other) => panic!("Rank {} received {} from the manager", rank, other.kind()),
+            Err(error) => panic!("Rank {} failed to hear from the manager, {}", rank, error),
+        }
+    }
+}
+
+// The GA of one instance on this rank, evaluated with the ranks the manager
+// sends to help. They join between two generations and are sent the map
+// first, when the instance is done they go back to the manager.
+fn lead<T: Transport>(transport: &T, run: &RunArgs, instance: &TspInstance) -> RunSummary {
+    let rank = transport.rank();
+    let group = Group::new(transport, vec![rank]);
+    let mut health = distributed::WorkerHealth::new(run.worker_timeout.map(Duration::from_secs));
+    let run_summary = driver::evolve(run, instance, 1, |population, map| {
+        send(transport, ROOT_PROCESS, &BatchMessage::Poll);
+        match comm::receive(transport, ROOT_PROCESS) {
+            Ok(BatchMessage::Helpers(helpers)) => {
+                for helper in helpers {
+                    let newcomer = Group::new(transport, vec![rank, helper]);
+                    distributed::broadcast_map(
+                        &newcomer,
+                        &map.graph_weights,
+                        map.scenarios.as_deref(),
+                        map.duration.as_deref(),
+                    );
+                    group.join(helper);
+                }
+            }
+            Ok(other) => panic!("Rank {} polled the manager and got {}", rank, other.kind()),
+            Err(error) => panic!("Rank {} failed to poll the manager, {}", rank, error),
+        }
+        distributed::evaluate_population(
+            &group,
+            population,
+            map,
+            run.chunks_per_worker,
+            run.chunk_scheduling,
+            &mut health,
+        )
+    });
+    distributed::terminate_workers(&group);
+    run_summary
+}
+
+fn send<T: Transport>(transport: &T, destination: i32, message: &BatchMessage) {
+    comm::send(transport, destination, message).unwrap_or_else(|error| {
+        panic!(
+            "Failed to send {} to rank {}: {}",
+            message.kind(),
+            destination,
+            error
+        )
+    });
+}
+
+// `--batch-out`, under the tag of the run
+fn output_directory(run: &RunArgs) -> PathBuf {
+    run.tagged(&run.batch_out)
+}
+
+// The run of one instance, with the outputs of `run` moved to its directory,
+// its first population starting from `tours`
+fn solve_entry<F>(
+    run: &RunArgs,
+    entry: &BatchEntry,
+    instance: Result<TspInstance, String>,
+    tours: Vec<TspSolution>,
+    rank: i32,
+    solve: F,
+) -> BatchResult
+where
+    F: Fn(&RunArgs, &TspInstance) -> Option<RunSummary>,
+{
+    let mut result = BatchResult {
+        index: entry.index,
+        name: entry.name.clone(),
+        instance: entry.instance.clone(),
+        cities: 0,
+        rank,
+        best: None,
+        mean: None,
+        generations_completed: 0,
+        elapsed_seconds: 0.0,
+        error: None,
+        tour: None,
+    };
+    let instance = match instance {
+        Ok(instance) => instance,
+        Err(error) => {
+            error!("Skipping {}: {}", entry.name, error);
+            result.error = Some(error);
+            return result;
+        }
+    };
+    result.cities = instance.graph_weights.len();
+    let run = &match driver::with_portfolio(run, &instance) {
+        Ok(run) => run,
+        Err(error) => {
+            error!("Skipping {}: {}", entry.name, error);
+            result.error = Some(error);
+            return result;
+        }
+    };
+    let problems = driver::check_configuration(run, &instance);
+    if !problems.is_empty() {
+        let error = problems.join(", ");
+        error!("Skipping {}: {}", entry.name, error);
+        result.error = Some(error);
+        return result;
+    }
+
+    let directory = output_directory(run).join(&entry.name);
+    if let Err(error) = fs::create_dir_all(&directory) {
+        error!(
+            "Skipping {}: {}: {}",
+            entry.name,
+            directory.display(),
+            error
+        );
+        result.error = Some(error.to_string());
+        return result;
+    }
+    let mut entry_run = run.clone();
+    entry_run.instance = entry.instance.clone();
+    entry_run.stagnation = run.stagnation.or(Some(BATCH_STAGNATION));
+    entry_run.summary_file = Some(directory.join("summary.json"));
+    entry_run.stats_out = Some(directory.join("history.csv"));
+    entry_run.explain = run.explain.as_ref().map(|_| directory.join("legs.csv"));
+    entry_run.edge_stats = run.edge_stats.as_ref().map(|_| directory.join("edges"));
+    entry_run.warm_start_tours = tours;
+
+    info!("Solving {} ({} cities)", entry.name, result.cities);
+    if !entry_run.warm_start_tours.is_empty() {
+        info!(
+            "Warm starting {} from {} tours of similar instances",
+            entry.name,
+            entry_run.warm_start_tours.len()
+        );
+    }
+    if let Some(summary) = solve(&entry_run, &instance) {
+        driver::write_summary(&entry_run, &summary);
+        driver::write_history(&entry_run, &summary);
+        driver::write_explanation(&entry_run, &instance, &summary);
+        result.best = Some(summary.best);
+        result.mean = summary.mean;
+        result.generations_completed = summary.generations_completed;
+        result.elapsed_seconds = summary.elapsed_seconds;
+        result.tour = summary.best_tour;
+    }
+    result
+}
+
+fn write_results(path: &Path, results: &[BatchResult]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(
+        writer,
+        "name,instance,cities,rank,best,mean,generations,elapsed_seconds,error"
+    )?;
+    for result in results.iter() {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{}",
+            result.name,
+            result.instance,
+            result.cities,
+            result.rank,
+            result
+                .best
+                .map_or_else(String::new, |best| best.to_string()),
+            result
+                .mean
+                .map_or_else(String::new, |mean| mean.to_string()),
+            result.generations_completed,
+            result.elapsed_seconds,
+            result.error.as_deref().unwrap_or("").replace(',', ";")
+        )?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,9 @@
+use genetic_algorithm::cli::{self, BenchCli};
+use genetic_algorithm::{driver, tools};
+
+// Landscape probing, operator recommendation, tuning and journal inspection
+fn main() {
+    let cli = cli::parse::<BenchCli>();
+    driver::setup(&cli.run);
+    tools::run_command(&cli.command, &cli.run);
+}
@@ -0,0 +1,10 @@
+use genetic_algorithm::backend::{self, BackendKind};
+use genetic_algorithm::cli::{self, RunCli};
+use genetic_algorithm::driver;
+
+// Solves in this process only, MPI is never initialized
+fn main() {
+    let cli = cli::parse::<RunCli>();
+    driver::setup(&cli.run);
+    backend::run(&cli.run, BackendKind::in_process(&cli.run));
+}
@@ -0,0 +1,10 @@
+use genetic_algorithm::backend::{self, BackendKind};
+use genetic_algorithm::cli::{self, RunCli};
+use genetic_algorithm::driver;
+
+// Started under mpirun, rank 0 coordinates and every other rank evaluates
+fn main() {
+    let cli = cli::parse::<RunCli>();
+    driver::setup(&cli.run);
+    backend::run(&cli.run, BackendKind::Mpi);
+}
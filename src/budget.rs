@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+
+// Evaluations the islands of a run may spend together, and the reports
+// without improvement after which an island hands over what it has left, 0
+// never
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetPolicy {
+    pub evaluations: u64,
+    pub stagnation: usize,
+}
+
+// Periodic report an island sends to the coordinator, evaluations are cumulative
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct BudgetReport {
+    pub island: usize,
+    pub evaluations: u64,
+    pub best: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BudgetDecision {
+    // Total evaluations the island may consume since the start of the run
+    Continue { allowance: u64 },
+    Terminate,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct IslandState {
+    allowance: u64,
+    consumed: u64,
+    best: f32,
+    stale_reports: usize,
+    active: bool,
+}
+
+// Splits a global evaluation budget between islands. Islands that exhaust their
+// share or stop improving for `stagnation_limit` reports are terminated and
+// what they didn't use is handed to the islands that are still improving.
+pub struct BudgetCoordinator {
+    stagnation_limit: usize,
+    islands: Vec<IslandState>,
+}
+
+impl BudgetCoordinator {
+    pub fn new(total_evaluations: u64, islands: usize, stagnation_limit: usize) -> Self {
+        let mut coordinator = BudgetCoordinator {
+            stagnation_limit,
+            islands: vec![
+                IslandState {
+                    allowance: 0,
+                    consumed: 0,
+                    best: f32::INFINITY,
+                    stale_reports: 0,
+                    active: true,
+                };
+                islands
+            ],
+        };
+        coordinator.distribute(total_evaluations, false);
+        coordinator
+    }
+
+    pub fn report(&mut self, report: BudgetReport) -> BudgetDecision {
+        let limit = self.stagnation_limit;
+        let island = &mut self.islands[report.island];
+
+        if !island.active {
+            return BudgetDecision::Terminate;
+        }
+
+        island.consumed = report.evaluations;
+        if report.best < island.best {
+            island.best = report.best;
+            island.stale_reports = 0;
+        } else {
+            island.stale_reports += 1;
+        }
+
+        let exhausted = island.consumed >= island.allowance;
+        let stagnant = limit > 0 && island.stale_reports >= limit;
+        if !exhausted && !stagnant {
+            return BudgetDecision::Continue {
+                allowance: island.allowance,
+            };
+        }
+
+        island.active = false;
+        let freed = island.allowance.saturating_sub(island.consumed);
+        island.allowance = island.consumed;
+        self.distribute(freed, true);
+
+        BudgetDecision::Terminate
+    }
+
+    pub fn allowance(&self, island: usize) -> u64 {
+        self.islands[island].allowance
+    }
+
+    pub fn is_active(&self, island: usize) -> bool {
+        self.islands[island].active
+    }
+
+    pub fn consumed(&self) -> u64 {
+        self.islands.iter().map(|island| island.consumed).sum()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.islands.iter().all(|island| !island.active)
+    }
+
+    pub fn islands(&self) -> usize {
+        self.islands.len()
+    }
+
+    // Equal split, the remainder goes to the lowest indices so it is deterministic
+    fn distribute(&mut self, evaluations: u64, prefer_improving: bool) {
+        let improving = self
+            .islands
+            .iter()
+            .enumerate()
+            .filter(|(_, island)| island.active && island.stale_reports == 0)
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+
+        let receivers = if prefer_improving && !improving.is_empty() {
+            improving
+        } else {
+            self.islands
+                .iter()
+                .enumerate()
+                .filter(|(_, island)| island.active)
+                .map(|(i, _)| i)
+                .collect()
+        };
+
+        if receivers.is_empty() {
+            return;
+        }
+
+        let share = evaluations / receivers.len() as u64;
+        let remainder = evaluations % receivers.len() as u64;
+        for (position, i) in receivers.into_iter().enumerate() {
+            let extra = if (position as u64) < remainder { 1 } else { 0 };
+            self.islands[i].allowance += share + extra;
+        }
+    }
+}
+
+// The share of the budget an island spends, as the coordinator last decided.
+// Every island starts from the equal split the coordinator makes, so they
+// need no decision before the first report.
+#[derive(Clone, Copy, Debug)]
+pub struct IslandBudget {
+    island: usize,
+    allowance: u64,
+    evaluations: u64,
+    terminated: bool,
+}
+
+impl IslandBudget {
+    pub fn new(policy: BudgetPolicy, islands: usize, island: usize) -> Self {
+        let coordinator = BudgetCoordinator::new(policy.evaluations, islands, policy.stagnation);
+        IslandBudget {
+            island,
+            allowance: coordinator.allowance(island),
+            evaluations: 0,
+            terminated: false,
+        }
+    }
+
+    // Counts `evaluations` when the allowance still covers them, an island
+    // that can't afford its next generation stops breeding
+    pub fn spend(&mut self, evaluations: u64) -> bool {
+        if self.terminated || self.evaluations + evaluations > self.allowance {
+            return false;
+        }
+        self.evaluations += evaluations;
+        true
+    }
+
+    pub fn report(&self, best: f32) -> BudgetReport {
+        BudgetReport {
+            island: self.island,
+            evaluations: self.evaluations,
+            best,
+        }
+    }
+
+    pub fn apply(&mut self, decision: BudgetDecision) {
+        match decision {
+            BudgetDecision::Continue { allowance } => self.allowance = allowance,
+            BudgetDecision::Terminate => self.terminated = true,
+        }
+    }
+
+    pub fn evaluations(&self) -> u64 {
+        self.evaluations
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+}
@@ -0,0 +1,138 @@
+use crate::cli::RunArgs;
+use crate::statistics::RunHistory;
+use crate::tsp::{TspInstance, TspSolution};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"GAC3";
+
+// What a run must keep to be resumed, the population is the one about to be
+// evaluated as generation `generation`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub parameters: CheckpointParameters,
+    // Seed of the whole run, every generation draws from a generator derived
+    // from it and the generation, so a resumed run draws what the
+    // uninterrupted one would have
+    pub seed: u64,
+    pub generation: usize,
+    pub population: Vec<TspSolution>,
+    pub history: RunHistory,
+}
+
+// A checkpoint is only resumed with the settings it was written with
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointParameters {
+    pub instance_checksum: u32,
+    pub processes: i32,
+    pub population_size: usize,
+    pub elite: usize,
+    pub mutation_rate: f32,
+    pub crossover_rate: f32,
+    pub selection: String,
+    pub crossover: String,
+    pub mutation: String,
+    pub closed_tour: bool,
+}
+
+impl CheckpointParameters {
+    pub fn of(run: &RunArgs, instance: &TspInstance, processes: i32) -> Self {
+        CheckpointParameters {
+            instance_checksum: instance.checksum(),
+            processes,
+            population_size: run.pop_size,
+            elite: run.elite,
+            mutation_rate: run.mutation_rate,
+            crossover_rate: run.crossover_rate,
+            selection: run.selection.to_string(),
+            crossover: run.crossover.to_string(),
+            mutation: run.mutation.to_string(),
+            closed_tour: !run.open_path,
+        }
+    }
+
+    // Every setting that differs from `current`, empty when it can be resumed
+    pub fn differences(&self, current: &CheckpointParameters) -> Vec<String> {
+        let mut differences = Vec::new();
+        let mut compare = |name: &str, saved: String, now: String| {
+            if saved != now {
+                differences.push(format!("{} was {}, now {}", name, saved, now));
+            }
+        };
+
+        compare(
+            "instance checksum",
+            format!("{:08x}", self.instance_checksum),
+            format!("{:08x}", current.instance_checksum),
+        );
+        compare(
+            "processes",
+            self.processes.to_string(),
+            current.processes.to_string(),
+        );
+        compare(
+            "population size",
+            self.population_size.to_string(),
+            current.population_size.to_string(),
+        );
+        compare("elite", self.elite.to_string(), current.elite.to_string());
+        compare(
+            "mutation rate",
+            self.mutation_rate.to_string(),
+            current.mutation_rate.to_string(),
+        );
+        compare(
+            "crossover rate",
+            self.crossover_rate.to_string(),
+            current.crossover_rate.to_string(),
+        );
+        compare(
+            "selection",
+            self.selection.clone(),
+            current.selection.clone(),
+        );
+        compare(
+            "crossover",
+            self.crossover.clone(),
+            current.crossover.clone(),
+        );
+        compare("mutation", self.mutation.clone(), current.mutation.clone());
+        compare(
+            "closed tour",
+            self.closed_tour.to_string(),
+            current.closed_tour.to_string(),
+        );
+        differences
+    }
+}
+
+impl Checkpoint {
+    // Written next to `path` and renamed over it, a job killed while writing
+    // leaves the previous checkpoint intact
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let partial = path.with_extension("partial");
+        let mut output = BufWriter::new(File::create(&partial)?);
+        output.write_all(MAGIC)?;
+        bincode::serialize_into(&mut output, self).map_err(io::Error::other)?;
+        output.flush()?;
+        output.get_ref().sync_all()?;
+        drop(output);
+        fs::rename(&partial, path)
+    }
+
+    pub fn read(path: &Path) -> io::Result<Checkpoint> {
+        let mut input = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a checkpoint",
+            ));
+        }
+        bincode::deserialize_from(input).map_err(io::Error::other)
+    }
+}
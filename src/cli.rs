@@ -0,0 +1,1300 @@
+use crate::backend::BackendKind;
+use crate::budget::BudgetPolicy;
+use crate::config::{self, ConfigEntry, KeySpec, ValueKind};
+use crate::distributed::ChunkScheduling;
+use crate::driver::{CROSSOVER_RATE, ELITE, ITERATIONS, MUTATION_RATE, POPULATION_SIZE};
+use crate::initialization::InitStrategy;
+use crate::islands::{MigrantAcceptance, MigrantSelection, MigrationMode, MigrationPolicy};
+use crate::local_search::{LocalSearch, LocalSearchKind, MemeticPolicy};
+use crate::logging::LogConfig;
+use crate::operator_schedule::{OperatorPhase, OperatorSchedule};
+use crate::permutation::{CrossoverOperator, MutationOperator, MutationWeights};
+use crate::registry::{self, ComponentKind};
+use crate::robustness::NoiseModel;
+use crate::runner::RunParameters;
+use crate::selection::{Selection, SelectionKind};
+use crate::self_test::SelfTest;
+use crate::termination::{
+    MaxGenerations, Stagnation, StopRule, TargetFitness, Termination, TimeLimit,
+};
+use crate::topology::{Topology, TopologyKind};
+use crate::tsp::scenarios::ScenarioAggregate;
+use crate::tsp::{builtin, TspSolution};
+use crate::warm_start::SimilarityKind;
+use clap::parser::ValueSource;
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use log::LevelFilter;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(about = "Distributed genetic algorithm solver")]
+pub struct Cli {
+    #[command(flatten)]
+    pub run: RunArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+// `ga-run` and `ga-worker` only solve, they take no subcommand
+#[derive(Parser)]
+#[command(about = "Genetic algorithm solver")]
+pub struct RunCli {
+    #[command(flatten)]
+    pub run: RunArgs,
+}
+
+// `ga-bench` always runs one of the tools
+#[derive(Parser)]
+#[command(about = "Landscape analysis, tuning and inspection tools")]
+pub struct BenchCli {
+    #[command(flatten)]
+    pub run: RunArgs,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+impl AsMut<RunArgs> for Cli {
+    fn as_mut(&mut self) -> &mut RunArgs {
+        &mut self.run
+    }
+}
+
+impl AsMut<RunArgs> for RunCli {
+    fn as_mut(&mut self) -> &mut RunArgs {
+        &mut self.run
+    }
+}
+
+impl AsMut<RunArgs> for BenchCli {
+    fn as_mut(&mut self) -> &mut RunArgs {
+        &mut self.run
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// List the compiled-in problems, operators, selectors and distance providers
+    List,
+    /// Probe the fitness landscape of the instance with random walks
+    Probe {
+        /// Number of independent random walks
+        #[arg(long, default_value_t = 100)]
+        walks: usize,
+        /// Mutations applied along each walk
+        #[arg(long, default_value_t = 1000)]
+        steps: usize,
+        /// Random individuals sampled for the fitness distance correlation
+        #[arg(long, default_value_t = 1000)]
+        samples: usize,
+    },
+    /// Try every crossover and mutation pair on short runs and suggest the best one
+    Recommend {
+        /// Generations of each trial run
+        #[arg(long, default_value_t = 30)]
+        generations: usize,
+        /// Population size of each trial run
+        #[arg(long, default_value_t = 200)]
+        population: usize,
+        /// Trial runs per operator pair
+        #[arg(long, default_value_t = 3)]
+        repeats: usize,
+    },
+    /// Tune operators and rates with successive halving or Hyperband
+    Tune {
+        /// Largest number of generations any configuration is run for
+        #[arg(long, default_value_t = 81)]
+        max_generations: usize,
+        /// Fraction 1/eta of the configurations kept at every rung
+        #[arg(long, default_value_t = 3)]
+        eta: usize,
+        /// Population size of every tuning run
+        #[arg(long, default_value_t = 200)]
+        population: usize,
+        /// Run plain successive halving over this many configurations instead of Hyperband
+        #[arg(long)]
+        configurations: Option<usize>,
+        /// Portfolio file the best configuration is added to, keyed by the
+        /// size and clustering of the instance, created when missing
+        #[arg(long)]
+        add_to_portfolio: Option<PathBuf>,
+    },
+    /// Solve the configured setup with elitism, mutation, crossover and local
+    /// search switched off in turn, and report what each one contributes
+    Ablate {
+        /// Runs of every setup, the n-th of each from the same seed
+        #[arg(long, default_value_t = 5)]
+        repeats: usize,
+    },
+    /// Drive a local run interactively, one generation at a time
+    Repl {
+        /// Population size of the session
+        #[arg(long, default_value_t = 200)]
+        population: usize,
+    },
+    /// Run the distributed protocol with virtual ranks on threads, over an
+    /// in memory network with optional latency, message loss and a failing rank
+    Simulate {
+        /// Virtual ranks, including the root
+        #[arg(long, default_value_t = 4)]
+        ranks: usize,
+        /// Delay of every message in milliseconds
+        #[arg(long, default_value_t = 0)]
+        latency_ms: u64,
+        /// Uniform random extra delay of up to this many milliseconds
+        #[arg(long, default_value_t = 0)]
+        jitter_ms: u64,
+        /// Probability that a point to point message is lost
+        #[arg(long, default_value_t = 0.0)]
+        drop_rate: f64,
+        /// Rank that fails, see --fail-after
+        #[arg(long, requires = "fail_after")]
+        fail_rank: Option<i32>,
+        /// Messages the failing rank sends before it stops
+        #[arg(long, requires = "fail_rank")]
+        fail_after: Option<usize>,
+        /// Rank whose messages are delayed, see --slow-ms
+        #[arg(long, requires = "slow_ms")]
+        slow_rank: Option<i32>,
+        /// Extra delay in milliseconds of every message the slow rank sends
+        #[arg(long, requires = "slow_rank")]
+        slow_ms: Option<u64>,
+        /// Seconds a rank waits for a message before declaring it lost
+        #[arg(long, default_value_t = 30)]
+        stall_timeout: u64,
+        /// Seed of the latency jitter and message loss
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Have every rank evolve an island, as the mpi-islands backend does,
+        /// instead of evaluating for the root
+        #[arg(long)]
+        island_model: bool,
+    },
+    /// Trace how an individual recorded in a journal was bred, generation by generation
+    Trace {
+        /// Journal written with --journal
+        journal: PathBuf,
+        /// Generation the individual belongs to
+        #[arg(long)]
+        generation: u32,
+        /// Rank of the individual in that generation, 0 is the best
+        #[arg(long, default_value_t = 0)]
+        rank: u32,
+    },
+    /// Show the recorded runs of the instance and the best configuration found so far
+    #[cfg(feature = "database")]
+    History {
+        /// Portfolio file the best configuration of every recorded instance
+        /// that can still be read is added to, created when missing
+        #[arg(long)]
+        add_to_portfolio: Option<PathBuf>,
+    },
+}
+
+#[derive(Args, Clone)]
+pub struct RunArgs {
+    /// TOML file providing defaults for any of these options, flags given on
+    /// the command line take precedence
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Name of the problem to solve, see `list`
+    #[arg(long, default_value = "tsp")]
+    pub problem: String,
+
+    /// TSP instance to solve, a TSPLIB file or `builtin:<name>` for a bundled one
+    #[arg(long, visible_alias = "input", default_value = "builtin:wi29")]
+    pub instance: String,
+
+    /// Minimize the path through every city instead of the tour returning to
+    /// the first one
+    #[arg(long)]
+    pub open_path: bool,
+
+    /// Another instance of the same cities, with other edge weights the
+    /// tours must do well under too, may be repeated. The fitness aggregates
+    /// the lengths in the instance and in every scenario.
+    #[arg(long)]
+    pub scenario: Vec<String>,
+
+    /// Comma separated weights of the instance and then of every --scenario
+    /// in the mean, all the same when not given
+    #[arg(long, value_delimiter = ',')]
+    pub scenario_weights: Vec<f32>,
+
+    /// How the lengths in the scenarios make the fitness: their weighted mean,
+    /// or max for the worst of them
+    #[arg(long, default_value = "mean")]
+    pub scenario_aggregate: ScenarioAggregate,
+
+    /// File of edges of the instance to reweigh or forbid, one per line:
+    /// `<city> <city> <cost>`, `<city> > <city> <cost>` for one way only, and
+    /// `forbidden` as the cost to close the edge
+    #[arg(long)]
+    pub edge_overrides: Option<PathBuf>,
+
+    /// File of what every tour must respect, one per line: `start <city>`,
+    /// `end <city>` and `<city> before <city>`. Without it an instance file
+    /// uses the `.constraints` file next to it, if there is one
+    #[arg(long)]
+    pub constraints: Option<PathBuf>,
+
+    /// File of the groups of cities `--problem clustered-tsp` visits one
+    /// after another, the cities of a group on a line. Without it an instance
+    /// file uses the `.groups` file next to it
+    #[arg(long)]
+    pub groups: Option<PathBuf>,
+
+    /// Generations to run
+    #[arg(long, default_value_t = ITERATIONS)]
+    pub iterations: usize,
+
+    /// Individuals in the population, split between the islands if there are
+    /// several
+    #[arg(long, default_value_t = POPULATION_SIZE)]
+    pub pop_size: usize,
+
+    /// Best individuals copied unchanged into the next generation
+    #[arg(long, default_value_t = ELITE)]
+    pub elite: usize,
+
+    /// Probability that a child is mutated
+    #[arg(long, default_value_t = MUTATION_RATE)]
+    pub mutation_rate: f32,
+
+    /// Probability that a pair of parents is crossed over
+    #[arg(long, default_value_t = CROSSOVER_RATE)]
+    pub crossover_rate: f32,
+
+    /// How parents are chosen: adjacent pairs every individual with the next
+    /// fittest one, or tournament, roulette or rank
+    #[arg(long, default_value = "tournament")]
+    pub selection: SelectionKind,
+
+    /// Individuals drawn for every tournament of --selection tournament
+    #[arg(long, default_value_t = 3)]
+    pub tournament_size: usize,
+
+    /// Crossover operator for TSP tours, see `list`
+    #[arg(long, default_value = "order")]
+    pub crossover: CrossoverOperator,
+
+    /// Mutation operator for TSP tours, see `list`
+    #[arg(long, default_value = "swap")]
+    pub mutation: MutationOperator,
+
+    /// Relative weights of the operators the composite mutation draws from,
+    /// those left out are never used
+    #[arg(long, default_value = "swap=1,inversion=1,insertion=1,3-swap=1")]
+    pub mutation_weights: MutationWeights,
+
+    /// Portfolio of configurations learned on past runs, see `tune` and
+    /// `history`: the operators and rates of the entry whose instance is
+    /// closest in size and clustering, when one is close enough, replace
+    /// those not given on the command line or in --config
+    #[arg(long)]
+    pub portfolio: Option<PathBuf>,
+
+    // Keys of the options the command line or --config gave, a portfolio
+    // leaves them as they are
+    #[arg(skip)]
+    pub given: Vec<String>,
+
+    /// Seed of every random draw of the GA, so a run can be repeated; random
+    /// and logged when not given
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Where the GA runs: local, islands or mpi. Defaults to mpi when the
+    /// binary has it, islands with --islands above 1, local otherwise
+    #[arg(long)]
+    pub backend: Option<BackendKind>,
+
+    /// Stamp every MPI message with a checksum of the instance and stop when
+    /// a rank receives the stamp of another one; always on in debug builds
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Have every worker report how much it evaluated and how long it took,
+    /// sent along with the evaluated populations
+    #[arg(long)]
+    pub worker_stats: bool,
+
+    /// Chunks every MPI worker evaluates per generation; above 1 the next
+    /// chunk is sent while the worker still evaluates the current one
+    #[arg(long, default_value_t = 1)]
+    pub chunks_per_worker: usize,
+
+    /// How chunks are handed out to the MPI workers: static, every worker
+    /// the same share, or dynamic, the next chunk to whichever worker
+    /// returns one, so faster nodes take more of the work
+    #[arg(long, default_value = "static")]
+    pub chunk_scheduling: ChunkScheduling,
+
+    /// Seconds the root waits on an MPI worker holding chunks before it
+    /// hands them to the others and stops sending to that worker; waits
+    /// forever if not given. Catches ranks that hang or fall silent, a rank
+    /// that crashes usually takes the whole MPI job down with it
+    #[arg(long)]
+    pub worker_timeout: Option<u64>,
+
+    /// Independent populations evolved on threads of this process, exchanging
+    /// migrants over a ring; the population is split between them
+    #[arg(long, default_value_t = 1)]
+    pub islands: usize,
+
+    /// Generations between two migrations of the island model, 0 never migrates
+    #[arg(long, default_value_t = 10)]
+    pub migration_interval: usize,
+
+    /// Fittest individuals every island sends at each migration
+    #[arg(long, default_value_t = 5)]
+    pub migrants: usize,
+
+    /// Individuals an island sends: best, random, or diverse for the best one
+    /// then each farthest from those already chosen
+    #[arg(long, default_value = "best")]
+    pub migrant_selection: MigrantSelection,
+
+    /// Whom the migrants replace: the worst residents, the most similar ones
+    /// when fitter, or random ones with a probability favoring fitter migrants
+    #[arg(long, default_value = "worst")]
+    pub migrant_acceptance: MigrantAcceptance,
+
+    /// migrate to move the migrants themselves, pollinate to cross each with a
+    /// resident of the receiving island and move in the offspring instead
+    #[arg(long, default_value = "migrate")]
+    pub migration_mode: MigrationMode,
+
+    /// Whom every island sends its migrants to: ring for the next island,
+    /// fully-connected for all others, random for a ring in a new random
+    /// order at every migration, torus for its four neighbors on a grid
+    #[arg(long, default_value = "ring")]
+    pub topology: TopologyKind,
+
+    /// Also send every island the best individual of all islands at each
+    /// migration
+    #[arg(long)]
+    pub broadcast_best: bool,
+
+    /// How the islands build their first population, given to the islands in
+    /// turn: random, nearest-neighbor or greedy, comma separated
+    #[arg(long, value_delimiter = ',', default_value = "random")]
+    pub island_init: Vec<InitStrategy>,
+
+    /// Seed of the first population of the first island, every next island
+    /// gets the next seed; drawn from --seed when not given
+    #[arg(long)]
+    pub island_seed: Option<u64>,
+
+    /// Evaluations all the islands may spend together, split evenly at the
+    /// start; at every migration an island that used its share or stopped
+    /// improving stops breeding and what it left goes to the others
+    #[arg(long)]
+    pub evaluation_budget: Option<u64>,
+
+    /// Migrations without improvement after which an island under
+    /// --evaluation-budget stops breeding, 0 never
+    #[arg(long, default_value_t = 3)]
+    pub budget_stagnation: usize,
+
+    /// Individuals polished with 2-opt before evaluation: off,
+    /// improved-offspring for only the offspring shorter than both parents,
+    /// all-offspring, or elites for the elite carried over instead
+    #[arg(long, default_value = "off")]
+    pub memetic: MemeticPolicy,
+
+    /// Most individuals polished per generation, unlimited when not set
+    #[arg(long)]
+    pub polish_budget: Option<usize>,
+
+    /// 2-opt sweeps over every polished individual at most, fewer once one
+    /// finds nothing to improve
+    #[arg(long, default_value_t = 3)]
+    pub polish_passes: usize,
+
+    /// Neighborhoods polishing an individual, comma separated and searched
+    /// in turn within every pass: 2-opt, or-opt or 3-opt
+    #[arg(long, value_delimiter = ',', default_value = "2-opt")]
+    pub local_search: Vec<LocalSearchKind>,
+
+    /// Every this many generations, learn an edge model from the best tours
+    /// and sample offspring from it, an estimation of distribution step in
+    /// the GA; never when not set
+    #[arg(long)]
+    pub eda_interval: Option<usize>,
+
+    /// Best tours the edge model of --eda-interval learns from
+    #[arg(long, default_value_t = 50)]
+    pub eda_elite: usize,
+
+    /// Offspring sampled from the edge model at every --eda-interval
+    #[arg(long, default_value_t = 10)]
+    pub eda_samples: usize,
+
+    // Operator mixes changing over the run, only set from the `phases` array
+    // of a config file
+    #[arg(skip)]
+    pub phases: OperatorSchedule,
+
+    /// Directory receiving per generation edge frequency CSV files (and SVG
+    /// heatmaps when the instance has coordinates)
+    #[arg(long)]
+    pub edge_stats: Option<PathBuf>,
+
+    /// Seed the initial population with the best of this many random tours,
+    /// evaluated in bounded slices so it may exceed what fits in memory
+    #[arg(long)]
+    pub seed_samples: Option<usize>,
+
+    /// Fraction of the initial population built by heuristics, nearest
+    /// neighbor tours from random start cities for half of it and greedy edge
+    /// tours for the other half, the rest random; without it one nearest
+    /// neighbor tour from every city
+    #[arg(long)]
+    pub heuristic_fraction: Option<f32>,
+
+    /// Random tours generated and evaluated at once with --seed-samples
+    #[arg(long, default_value_t = 100_000)]
+    pub seed_slice: usize,
+
+    /// Append only binary journal recording selection, seeds and the operators
+    /// that produced every individual, read it back with `trace`
+    #[arg(long)]
+    pub journal: Option<PathBuf>,
+
+    /// Run evaluation and breeding on the main thread only, without a thread
+    /// pool, so a generation can be stepped through in a debugger
+    #[arg(long)]
+    pub sequential: bool,
+
+    /// Validate the instance and parameters, print the effective configuration
+    /// and exit without running
+    #[arg(long)]
+    pub check: bool,
+
+    /// Check this machine instead of solving: `determinism` solves locally
+    /// with 1, 2 and every thread from the same seed and fails unless every
+    /// generation is the same
+    #[arg(long)]
+    pub self_test: Option<SelfTest>,
+
+    /// Wall clock seconds the run may take, under SLURM or PBS it defaults to
+    /// slightly less than what is left of the job
+    #[arg(long)]
+    pub time_limit: Option<u64>,
+
+    /// Stop once the best individual is at or below this fitness
+    #[arg(long)]
+    pub target_fitness: Option<f32>,
+
+    /// Stop after this many generations in a row without a better best
+    /// individual
+    #[arg(long)]
+    pub stagnation: Option<usize>,
+
+    /// Stopping rule composed of criteria, for example
+    /// `any(time-limit=600, all(min-generations=100, stagnation=50))`. It
+    /// stops the run alongside `--iterations` and the other criteria.
+    #[arg(long)]
+    pub stop: Option<StopRule>,
+
+    /// JSON file receiving the end of run summary, defaults to
+    /// `summary-<job id>.json` (`summary-<tag>-<job id>.json` with --tag)
+    /// when running under SLURM or PBS
+    #[arg(long)]
+    pub summary_file: Option<PathBuf>,
+
+    /// File receiving the statistics of every generation, JSON when it ends
+    /// in .json and CSV otherwise
+    #[arg(long)]
+    pub stats_out: Option<PathBuf>,
+
+    /// File receiving every leg of the best tour with its cost, the distance
+    /// and time so far and the slack left before the route duration limit,
+    /// JSON when it ends in .json, CSV otherwise, and a table on stdout for -
+    #[arg(long)]
+    pub explain: Option<PathBuf>,
+
+    /// Once the run is done, measure the best tours again under this many
+    /// random perturbations of the edge costs and report their mean, 95th
+    /// percentile and worst length
+    #[arg(long)]
+    pub robustness: Option<usize>,
+
+    /// How --robustness perturbs every edge cost: by a uniform, gaussian or
+    /// log-normal factor around 1
+    #[arg(long, default_value = "uniform")]
+    pub robustness_noise: NoiseModel,
+
+    /// Spread of the --robustness factors, the half width of the uniform one
+    /// and the standard deviation of the others
+    #[arg(long, default_value_t = 0.1)]
+    pub robustness_scale: f32,
+
+    /// Distinct best tours the --robustness report compares
+    #[arg(long, default_value_t = 5)]
+    pub robustness_tours: usize,
+
+    /// File listing instances to solve as independent runs, one per line,
+    /// each stopping once it converges; the root of an MPI run hands them out
+    /// to the other ranks as they free up
+    #[arg(long)]
+    pub batch: Option<PathBuf>,
+
+    /// Directory receiving a directory of results per instance of --batch,
+    /// and batch.csv summing them up
+    #[arg(long, default_value = "batch-results")]
+    pub batch_out: PathBuf,
+
+    /// Start every instance of --batch from the best tours of the instances
+    /// already solved that are similar by this measure: shared-cities, or
+    /// identical; cities are matched by their coordinates
+    #[arg(long)]
+    pub warm_start: Option<SimilarityKind>,
+
+    /// Least similarity, from 0 to 1, of a solved instance for --warm-start
+    /// to use its tours
+    #[arg(long, default_value_t = 0.5)]
+    pub warm_start_threshold: f64,
+
+    // Tours of similar instances the first population starts from, only set
+    // by a batch with --warm-start
+    #[arg(skip)]
+    pub warm_start_tours: Vec<TspSolution>,
+
+    /// Name of the experiment the run belongs to, written in the log lines,
+    /// the statistics, the summary and the database, and a subdirectory of
+    /// --edge-stats and --log-dir
+    #[arg(long)]
+    pub tag: Option<String>,
+
+    /// File the whole GA state is saved to every --checkpoint-every
+    /// generations, continue from it with --resume
+    #[arg(long)]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Generations between two checkpoints
+    #[arg(long, default_value_t = 10)]
+    pub checkpoint_every: usize,
+
+    /// Continue the run saved in this checkpoint, with the same instance,
+    /// parameters and number of processes
+    #[arg(long)]
+    pub resume: Option<PathBuf>,
+
+    /// Most verbose log level written: off, error, warn, info, debug or trace
+    #[arg(long, default_value = "info")]
+    pub log_level: LevelFilter,
+
+    /// File read every generation of a local or MPI run; writing a log level
+    /// to it changes the level on every rank without restarting the run
+    #[arg(long)]
+    pub log_level_file: Option<PathBuf>,
+
+    /// Directory receiving one `rank-<n>.log` file per MPI rank, logs go to
+    /// stderr when it is not set
+    #[arg(long)]
+    pub log_dir: Option<PathBuf>,
+
+    /// Size in bytes after which a rank log file is rotated
+    #[arg(long, default_value_t = 10 * 1024 * 1024)]
+    pub log_max_bytes: u64,
+
+    /// Rotated log files kept per rank
+    #[arg(long, default_value_t = 5)]
+    pub log_max_files: usize,
+
+    /// SQLite database recording the configuration and results of every run
+    #[cfg(feature = "database")]
+    #[arg(long)]
+    pub database: Option<PathBuf>,
+
+    /// Shared library exporting a problem through the plugin ABI, may be repeated
+    #[cfg(feature = "plugins")]
+    #[arg(long)]
+    pub plugin: Vec<PathBuf>,
+
+    /// Rhai script defining a problem fitness function, may be repeated
+    #[cfg(feature = "scripting")]
+    #[arg(long)]
+    pub script: Vec<PathBuf>,
+
+    /// Compress the larger messages between ranks with LZ4, which pays off
+    /// for big populations on a slow interconnect
+    #[cfg(feature = "compression")]
+    #[arg(long)]
+    pub compress: bool,
+}
+
+impl RunArgs {
+    // Takes a validated config file entry, see `config_schema`
+    fn apply(&mut self, entry: &ConfigEntry) {
+        match entry.key.as_str() {
+            "problem" => self.problem = entry.as_str().to_string(),
+            "instance" => self.instance = entry.as_str().to_string(),
+            "open_path" => self.open_path = entry.as_bool(),
+            "scenario" => {
+                self.scenario = entry.as_strings().into_iter().map(String::from).collect()
+            }
+            "scenario_weights" => {
+                self.scenario_weights = entry
+                    .as_floats()
+                    .into_iter()
+                    .map(|weight| weight as f32)
+                    .collect()
+            }
+            "scenario_aggregate" => self.scenario_aggregate = entry.as_str().parse().unwrap(),
+            "edge_overrides" => self.edge_overrides = Some(entry.as_path()),
+            "constraints" => self.constraints = Some(entry.as_path()),
+            "groups" => self.groups = Some(entry.as_path()),
+            "iterations" => self.iterations = entry.as_integer() as usize,
+            "pop_size" => self.pop_size = entry.as_integer() as usize,
+            "elite" => self.elite = entry.as_integer() as usize,
+            "mutation_rate" => self.mutation_rate = entry.as_float() as f32,
+            "crossover_rate" => self.crossover_rate = entry.as_float() as f32,
+            "selection" => self.selection = entry.as_str().parse().unwrap(),
+            "tournament_size" => self.tournament_size = entry.as_integer() as usize,
+            "crossover" => self.crossover = entry.as_str().parse().unwrap(),
+            "mutation" => self.mutation = entry.as_str().parse().unwrap(),
+            "mutation_weights" => self.mutation_weights = weights_or_exit(entry),
+            "portfolio" => self.portfolio = Some(entry.as_path()),
+            "seed" => self.seed = Some(entry.as_integer() as u64),
+            "backend" => self.backend = Some(entry.as_str().parse().unwrap()),
+            "strict" => self.strict = entry.as_bool(),
+            "worker_stats" => self.worker_stats = entry.as_bool(),
+            "chunks_per_worker" => self.chunks_per_worker = entry.as_integer() as usize,
+            "chunk_scheduling" => self.chunk_scheduling = entry.as_str().parse().unwrap(),
+            "worker_timeout" => self.worker_timeout = Some(entry.as_integer() as u64),
+            "islands" => self.islands = entry.as_integer() as usize,
+            "migration_interval" => self.migration_interval = entry.as_integer() as usize,
+            "migrants" => self.migrants = entry.as_integer() as usize,
+            "migrant_selection" => self.migrant_selection = entry.as_str().parse().unwrap(),
+            "migrant_acceptance" => self.migrant_acceptance = entry.as_str().parse().unwrap(),
+            "migration_mode" => self.migration_mode = entry.as_str().parse().unwrap(),
+            "topology" => self.topology = entry.as_str().parse().unwrap(),
+            "broadcast_best" => self.broadcast_best = entry.as_bool(),
+            "island_init" => {
+                self.island_init = entry
+                    .as_strings()
+                    .into_iter()
+                    .map(|strategy| strategy.parse().unwrap())
+                    .collect()
+            }
+            "island_seed" => self.island_seed = Some(entry.as_integer() as u64),
+            "evaluation_budget" => self.evaluation_budget = Some(entry.as_integer() as u64),
+            "budget_stagnation" => self.budget_stagnation = entry.as_integer() as usize,
+            "memetic" => self.memetic = entry.as_str().parse().unwrap(),
+            "polish_budget" => self.polish_budget = Some(entry.as_integer() as usize),
+            "polish_passes" => self.polish_passes = entry.as_integer() as usize,
+            "local_search" => {
+                self.local_search = entry
+                    .as_strings()
+                    .into_iter()
+                    .map(|kind| kind.parse().unwrap())
+                    .collect()
+            }
+            "eda_interval" => self.eda_interval = Some(entry.as_integer() as usize),
+            "eda_elite" => self.eda_elite = entry.as_integer() as usize,
+            "eda_samples" => self.eda_samples = entry.as_integer() as usize,
+            "phases" => {
+                let phases = entry
+                    .as_tables()
+                    .iter()
+                    .map(|keys| phase_of(keys))
+                    .collect();
+                self.phases = OperatorSchedule::new(phases).unwrap_or_else(|error| {
+                    eprintln!("error: line {}: {}", entry.line, error);
+                    std::process::exit(2);
+                });
+            }
+            "edge_stats" => self.edge_stats = Some(entry.as_path()),
+            "seed_samples" => self.seed_samples = Some(entry.as_integer() as usize),
+            "seed_slice" => self.seed_slice = entry.as_integer() as usize,
+            "heuristic_fraction" => self.heuristic_fraction = Some(entry.as_float() as f32),
+            "journal" => self.journal = Some(entry.as_path()),
+            "sequential" => self.sequential = entry.as_bool(),
+            "time_limit" => self.time_limit = Some(entry.as_integer() as u64),
+            "target_fitness" => self.target_fitness = Some(entry.as_float() as f32),
+            "stagnation" => self.stagnation = Some(entry.as_integer() as usize),
+            "stop" => self.stop = Some(entry.as_str().parse().unwrap()),
+            "summary_file" => self.summary_file = Some(entry.as_path()),
+            "stats_out" => self.stats_out = Some(entry.as_path()),
+            "explain" => self.explain = Some(entry.as_path()),
+            "robustness" => self.robustness = Some(entry.as_integer() as usize),
+            "robustness_noise" => self.robustness_noise = entry.as_str().parse().unwrap(),
+            "robustness_scale" => self.robustness_scale = entry.as_float() as f32,
+            "robustness_tours" => self.robustness_tours = entry.as_integer() as usize,
+            "batch" => self.batch = Some(entry.as_path()),
+            "batch_out" => self.batch_out = entry.as_path(),
+            "warm_start" => self.warm_start = Some(entry.as_str().parse().unwrap()),
+            "warm_start_threshold" => self.warm_start_threshold = entry.as_float(),
+            "tag" => self.tag = Some(entry.as_str().to_string()),
+            "checkpoint" => self.checkpoint = Some(entry.as_path()),
+            "checkpoint_every" => self.checkpoint_every = entry.as_integer() as usize,
+            "resume" => self.resume = Some(entry.as_path()),
+            "log_level" => self.log_level = entry.as_str().parse().unwrap(),
+            "log_level_file" => self.log_level_file = Some(entry.as_path()),
+            "log_dir" => self.log_dir = Some(entry.as_path()),
+            "log_max_bytes" => self.log_max_bytes = entry.as_integer() as u64,
+            "log_max_files" => self.log_max_files = entry.as_integer() as usize,
+            #[cfg(feature = "database")]
+            "database" => self.database = Some(entry.as_path()),
+            #[cfg(feature = "plugins")]
+            "plugin" => self.plugin = entry.as_paths(),
+            #[cfg(feature = "scripting")]
+            "script" => self.script = entry.as_paths(),
+            #[cfg(feature = "compression")]
+            "compress" => self.compress = entry.as_bool(),
+            _ => unreachable!("{} is not in the config schema", entry.key),
+        }
+    }
+
+    pub fn run_parameters(&self) -> RunParameters {
+        RunParameters {
+            iterations: self.iterations,
+            population_size: self.pop_size,
+            elite: self.elite,
+            mutation_rate: self.mutation_rate,
+            crossover_rate: self.crossover_rate,
+        }
+    }
+
+    // `--iterations` and whichever other criteria were asked for, the time
+    // limit is the one the run was given, if any
+    pub fn termination(&self, time_limit: Option<Duration>) -> Vec<Box<dyn Termination>> {
+        let mut criteria: Vec<Box<dyn Termination>> =
+            vec![Box::new(MaxGenerations(self.iterations))];
+        if let Some(limit) = time_limit {
+            criteria.push(Box::new(TimeLimit(limit)));
+        }
+        if let Some(target) = self.target_fitness {
+            criteria.push(Box::new(TargetFitness(target)));
+        }
+        if let Some(generations) = self.stagnation {
+            criteria.push(Box::new(Stagnation::new(generations)));
+        }
+        if let Some(rule) = &self.stop {
+            criteria.push(rule.create());
+        }
+        criteria
+    }
+
+    // The neighborhoods of `--local-search` one after another
+    pub fn local_search_operator(&self) -> Box<dyn LocalSearch> {
+        Box::new(
+            self.local_search
+                .iter()
+                .map(LocalSearchKind::create)
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn selection_operator(&self) -> Arc<dyn Selection> {
+        self.selection.create(self.tournament_size)
+    }
+
+    // `seed` draws the orders of the random topology
+    pub fn migration_topology(&self, seed: u64) -> Arc<dyn Topology> {
+        self.topology.create(seed)
+    }
+
+    pub fn migration_policy(&self) -> MigrationPolicy {
+        MigrationPolicy {
+            interval: self.migration_interval,
+            migrants: self.migrants,
+            selection: self.migrant_selection,
+            acceptance: self.migrant_acceptance,
+            mode: self.migration_mode,
+            broadcast_best: self.broadcast_best,
+        }
+    }
+
+    pub fn budget_policy(&self) -> Option<BudgetPolicy> {
+        self.evaluation_budget.map(|evaluations| BudgetPolicy {
+            evaluations,
+            stagnation: self.budget_stagnation,
+        })
+    }
+
+    pub fn log_config(&self) -> LogConfig {
+        LogConfig {
+            level: self.log_level,
+            directory: self
+                .log_dir
+                .as_deref()
+                .map(|directory| self.tagged(directory)),
+            max_bytes: self.log_max_bytes,
+            max_files: self.log_max_files,
+            tag: self.tag.clone(),
+        }
+    }
+
+    // The same run without whatever depends on the clock or writes files, so
+    // it can be solved again and again in this process
+    pub fn repeatable(&self) -> RunArgs {
+        let mut run = self.clone();
+        run.time_limit = None;
+        run.checkpoint = None;
+        run.resume = None;
+        run.journal = None;
+        run.edge_stats = None;
+        run.log_level_file = None;
+        #[cfg(feature = "database")]
+        {
+            run.database = None;
+        }
+        run
+    }
+
+    // Where a tagged run writes in `directory`, runs of different
+    // experiments sharing it don't overwrite each other
+    pub fn tagged(&self, directory: &Path) -> PathBuf {
+        match &self.tag {
+            Some(tag) => directory.join(tag),
+            None => directory.to_path_buf(),
+        }
+    }
+}
+
+fn weights_or_exit(entry: &ConfigEntry) -> MutationWeights {
+    let weights = entry.as_weights();
+    MutationWeights::from_pairs(
+        weights
+            .iter()
+            .map(|(name, weight)| (name.as_str(), *weight)),
+    )
+    .unwrap_or_else(|error| {
+        eprintln!("error: line {}: {}", entry.line, error);
+        std::process::exit(2);
+    })
+}
+
+// Takes the validated keys of one table of `phases`, see `phase_schema`
+fn phase_of(keys: &[ConfigEntry]) -> OperatorPhase {
+    let mut phase = OperatorPhase::default();
+    for entry in keys.iter() {
+        match entry.key.as_str() {
+            "until" => phase.until = entry.as_float(),
+            "crossover" => phase.crossover = Some(entry.as_str().parse().unwrap()),
+            "mutation" => phase.mutation = Some(entry.as_str().parse().unwrap()),
+            "mutation_weights" => phase.mutation_weights = Some(weights_or_exit(entry)),
+            "crossover_rate" => phase.crossover_rate = Some(entry.as_float() as f32),
+            "mutation_rate" => phase.mutation_rate = Some(entry.as_float() as f32),
+            _ => unreachable!("{} is not in the phase schema", entry.key),
+        }
+    }
+    phase
+}
+
+// Keys of a phase, `until` is the fraction of the generations it ends at
+fn phase_schema(
+    crossovers: Vec<String>,
+    mutations: Vec<String>,
+    basic_mutations: Vec<String>,
+) -> Vec<KeySpec> {
+    let fraction = || ValueKind::Float { min: 0.0, max: 1.0 };
+    vec![
+        KeySpec::new("until", fraction()),
+        KeySpec::new("crossover", ValueKind::Choice(crossovers)),
+        KeySpec::new("mutation", ValueKind::Choice(mutations)),
+        KeySpec::new("mutation_weights", ValueKind::Weights(basic_mutations)),
+        KeySpec::new("crossover_rate", fraction()),
+        KeySpec::new("mutation_rate", fraction()),
+    ]
+}
+
+// Keys accepted in a config file, named after the matching flags
+pub fn config_schema() -> Vec<KeySpec> {
+    let crossovers = CrossoverOperator::ALL
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<String>>();
+    let mutations = MutationOperator::ALL
+        .iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<String>>();
+    let basic_mutations = MutationOperator::BASIC
+        .iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<String>>();
+    let backends = BackendKind::ALL.iter().map(|b| b.to_string()).collect();
+    let selections = MigrantSelection::ALL
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let acceptances = MigrantAcceptance::ALL
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    let modes = MigrationMode::ALL.iter().map(|m| m.to_string()).collect();
+    let topologies = TopologyKind::ALL.iter().map(|t| t.to_string()).collect();
+    let schedulings = ChunkScheduling::ALL.iter().map(|s| s.to_string()).collect();
+    let similarities = SimilarityKind::ALL.iter().map(|s| s.to_string()).collect();
+    let selection_kinds = SelectionKind::ALL.iter().map(|s| s.to_string()).collect();
+    let strategies = InitStrategy::ALL.iter().map(|s| s.to_string()).collect();
+    let policies = MemeticPolicy::ALL.iter().map(|p| p.to_string()).collect();
+    let searches = LocalSearchKind::ALL.iter().map(|s| s.to_string()).collect();
+    let noise_models = NoiseModel::ALL.iter().map(|m| m.to_string()).collect();
+    let aggregates = ScenarioAggregate::ALL
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    let levels = ["off", "error", "warn", "info", "debug", "trace"]
+        .iter()
+        .map(|level| level.to_string())
+        .collect();
+
+    let phases = phase_schema(
+        crossovers.clone(),
+        mutations.clone(),
+        basic_mutations.clone(),
+    );
+
+    #[allow(unused_mut)]
+    let mut schema = vec![
+        KeySpec::new("problem", ValueKind::String),
+        KeySpec::new("instance", ValueKind::String),
+        KeySpec::new("open_path", ValueKind::Bool),
+        KeySpec::new("scenario", ValueKind::PathList),
+        KeySpec::new(
+            "scenario_weights",
+            ValueKind::Numbers {
+                min: 0.0,
+                max: f64::MAX,
+            },
+        ),
+        KeySpec::new("scenario_aggregate", ValueKind::Choice(aggregates)),
+        KeySpec::new("edge_overrides", ValueKind::Path),
+        KeySpec::new("constraints", ValueKind::Path),
+        KeySpec::new("groups", ValueKind::Path),
+        KeySpec::new(
+            "iterations",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "pop_size",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "elite",
+            ValueKind::Integer {
+                min: 0,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new("mutation_rate", ValueKind::Float { min: 0.0, max: 1.0 }),
+        KeySpec::new("crossover_rate", ValueKind::Float { min: 0.0, max: 1.0 }),
+        KeySpec::new("selection", ValueKind::Choice(selection_kinds)),
+        KeySpec::new(
+            "tournament_size",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new("crossover", ValueKind::Choice(crossovers)),
+        KeySpec::new("mutation", ValueKind::Choice(mutations)),
+        KeySpec::new("mutation_weights", ValueKind::Weights(basic_mutations)),
+        KeySpec::new("phases", ValueKind::Tables(phases)),
+        KeySpec::new("portfolio", ValueKind::Path),
+        KeySpec::new(
+            "seed",
+            ValueKind::Integer {
+                min: 0,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new("backend", ValueKind::Choice(backends)),
+        KeySpec::new("strict", ValueKind::Bool),
+        KeySpec::new("worker_stats", ValueKind::Bool),
+        KeySpec::new(
+            "chunks_per_worker",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new("chunk_scheduling", ValueKind::Choice(schedulings)),
+        KeySpec::new(
+            "worker_timeout",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "islands",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "migration_interval",
+            ValueKind::Integer {
+                min: 0,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "migrants",
+            ValueKind::Integer {
+                min: 0,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new("migrant_selection", ValueKind::Choice(selections)),
+        KeySpec::new("migrant_acceptance", ValueKind::Choice(acceptances)),
+        KeySpec::new("migration_mode", ValueKind::Choice(modes)),
+        KeySpec::new("topology", ValueKind::Choice(topologies)),
+        KeySpec::new("broadcast_best", ValueKind::Bool),
+        KeySpec::new("island_init", ValueKind::Choices(strategies)),
+        KeySpec::new(
+            "island_seed",
+            ValueKind::Integer {
+                min: 0,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "evaluation_budget",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "budget_stagnation",
+            ValueKind::Integer {
+                min: 0,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new("memetic", ValueKind::Choice(policies)),
+        KeySpec::new(
+            "polish_budget",
+            ValueKind::Integer {
+                min: 0,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "polish_passes",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new("local_search", ValueKind::Choices(searches)),
+        KeySpec::new(
+            "eda_interval",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "eda_elite",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "eda_samples",
+            ValueKind::Integer {
+                min: 0,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new("edge_stats", ValueKind::Path),
+        KeySpec::new(
+            "seed_samples",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "seed_slice",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "heuristic_fraction",
+            ValueKind::Float { min: 0.0, max: 1.0 },
+        ),
+        KeySpec::new("journal", ValueKind::Path),
+        KeySpec::new("sequential", ValueKind::Bool),
+        KeySpec::new(
+            "time_limit",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "target_fitness",
+            ValueKind::Float {
+                min: f64::MIN,
+                max: f64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "stagnation",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "stop",
+            ValueKind::Parsed("a stopping rule", |rule| {
+                rule.parse::<StopRule>().map(|_| ())
+            }),
+        ),
+        KeySpec::new("summary_file", ValueKind::Path),
+        KeySpec::new("stats_out", ValueKind::Path),
+        KeySpec::new("explain", ValueKind::Path),
+        KeySpec::new(
+            "robustness",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new("robustness_noise", ValueKind::Choice(noise_models)),
+        KeySpec::new(
+            "robustness_scale",
+            ValueKind::Float {
+                min: 0.0,
+                max: f64::MAX,
+            },
+        ),
+        KeySpec::new(
+            "robustness_tours",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new("batch", ValueKind::Path),
+        KeySpec::new("batch_out", ValueKind::Path),
+        KeySpec::new("warm_start", ValueKind::Choice(similarities)),
+        KeySpec::new(
+            "warm_start_threshold",
+            ValueKind::Float { min: 0.0, max: 1.0 },
+        ),
+        KeySpec::new("tag", ValueKind::String),
+        KeySpec::new("checkpoint", ValueKind::Path),
+        KeySpec::new(
+            "checkpoint_every",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new("resume", ValueKind::Path),
+        KeySpec::new("log_level", ValueKind::Choice(levels)),
+        KeySpec::new("log_level_file", ValueKind::Path),
+        KeySpec::new("log_dir", ValueKind::Path),
+        KeySpec::new(
+            "log_max_bytes",
+            ValueKind::Integer {
+                min: 1,
+                max: i64::MAX,
+            },
+        ),
+        KeySpec::new("log_max_files", ValueKind::Integer { min: 0, max: 1000 }),
+    ];
+
+    #[cfg(feature = "database")]
+    schema.push(KeySpec::new("database", ValueKind::Path));
+    #[cfg(feature = "plugins")]
+    schema.push(KeySpec::new("plugin", ValueKind::PathList));
+    #[cfg(feature = "scripting")]
+    schema.push(KeySpec::new("script", ValueKind::PathList));
+    #[cfg(feature = "compression")]
+    schema.push(KeySpec::new("compress", ValueKind::Bool));
+
+    schema
+}
+
+// Parses the command line and fills what it leaves unset from `--config`
+pub fn parse<C>() -> C
+where
+    C: CommandFactory + FromArgMatches + AsMut<RunArgs>,
+{
+    let matches = C::command().get_matches();
+    let mut cli = C::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+    cli.as_mut().given = matches
+        .ids()
+        .filter(|id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine))
+        .map(|id| id.to_string())
+        .collect();
+
+    if let Some(path) = cli.as_mut().config.clone() {
+        match config::load(&path, &config_schema()) {
+            Ok(entries) => {
+                for entry in entries.iter() {
+                    if matches.value_source(&entry.key) != Some(ValueSource::CommandLine) {
+                        cli.as_mut().apply(entry);
+                        cli.as_mut().given.push(entry.key.clone());
+                    }
+                }
+            }
+            Err(errors) => {
+                for error in errors.iter() {
+                    eprintln!("error: {}", error);
+                }
+                std::process::exit(2);
+            }
+        }
+    }
+
+    cli
+}
+
+pub fn print_components() {
+    let kinds = [
+        ComponentKind::Problem,
+        ComponentKind::Crossover,
+        ComponentKind::Mutation,
+        ComponentKind::Selection,
+        ComponentKind::Distance,
+    ];
+
+    for kind in kinds {
+        println!("Available {}:", kind);
+        for component in registry::components_of(kind) {
+            println!("  {:<12} {}", component.name, component.description);
+            for key in component.config_keys.iter() {
+                match &key.default {
+                    Some(default) => println!(
+                        "      {:<26} {} (default: {})",
+                        key.name, key.description, default
+                    ),
+                    None => println!("      {:<26} {}", key.name, key.description),
+                }
+            }
+        }
+        println!();
+    }
+
+    println!("Bundled instances:");
+    for name in builtin::names() {
+        println!("  {}{}", builtin::PREFIX, name);
+    }
+}
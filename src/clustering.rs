@@ -0,0 +1,126 @@
+use crate::organism::Distance;
+use crate::parallel::prelude::*;
+
+#[derive(Clone, Debug)]
+pub struct Cluster {
+    // Index into the clustered population of the representative individual
+    pub medoid: usize,
+    pub members: Vec<usize>,
+    // Mean distance of the members to the medoid
+    pub spread: f32,
+}
+
+const MAX_ITERATIONS: usize = 20;
+
+// k-medoids over an evenly spaced sample of at most `sample_size` individuals,
+// then every individual is assigned to its closest medoid. Medoids start from
+// the first individual and are spread by farthest point, so pass the population
+// sorted by fitness to seed the first cluster with the best one.
+pub fn k_medoids<T>(population: &[T], k: usize, sample_size: usize) -> Vec<Cluster>
+where
+    T: Distance + Sync,
+{
+    if population.is_empty() || k == 0 {
+        return Vec::new();
+    }
+
+    let stride = population.len().div_ceil(sample_size.max(1));
+    let sample = (0..population.len())
+        .step_by(stride)
+        .collect::<Vec<usize>>();
+    let k = k.min(sample.len());
+
+    // Farthest point initialization
+    let mut medoids = vec![sample[0]];
+    while medoids.len() < k {
+        let farthest = sample
+            .par_iter()
+            .map(|i| (nearest(population, &medoids, *i).1, *i))
+            .max_by(|a, b| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1)))
+            .unwrap();
+        if farthest.0 <= 0.0 {
+            break;
+        }
+        medoids.push(farthest.1);
+    }
+
+    for _ in 0..MAX_ITERATIONS {
+        let groups = assign(population, &medoids, &sample);
+
+        // The new medoid is the member closest to all others of its group
+        let updated = groups
+            .par_iter()
+            .zip(medoids.par_iter())
+            .map(|(members, medoid)| {
+                members
+                    .iter()
+                    .map(|candidate| {
+                        let cost = members
+                            .iter()
+                            .map(|other| population[*candidate].distance(&population[*other]))
+                            .sum::<f32>();
+                        (cost, *candidate)
+                    })
+                    .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+                    .map(|(_, candidate)| candidate)
+                    .unwrap_or(*medoid)
+            })
+            .collect::<Vec<usize>>();
+
+        if updated == medoids {
+            break;
+        }
+        medoids = updated;
+    }
+
+    let everyone = (0..population.len()).collect::<Vec<usize>>();
+    assign(population, &medoids, &everyone)
+        .into_iter()
+        .zip(medoids)
+        .map(|(members, medoid)| {
+            let spread = members
+                .iter()
+                .map(|member| population[*member].distance(&population[medoid]))
+                .sum::<f32>()
+                / members.len().max(1) as f32;
+            Cluster {
+                medoid,
+                members,
+                spread,
+            }
+        })
+        .collect()
+}
+
+fn nearest<T>(population: &[T], medoids: &[usize], individual: usize) -> (usize, f32)
+where
+    T: Distance + Sync,
+{
+    medoids
+        .iter()
+        .enumerate()
+        .map(|(cluster, medoid)| {
+            (
+                cluster,
+                population[individual].distance(&population[*medoid]),
+            )
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+        .unwrap()
+}
+
+fn assign<T>(population: &[T], medoids: &[usize], individuals: &[usize]) -> Vec<Vec<usize>>
+where
+    T: Distance + Sync,
+{
+    let closest = individuals
+        .par_iter()
+        .map(|i| nearest(population, medoids, *i).0)
+        .collect::<Vec<usize>>();
+
+    let mut groups = vec![Vec::new(); medoids.len()];
+    for (individual, cluster) in individuals.iter().zip(closest) {
+        groups[cluster].push(*individual);
+    }
+    groups
+}
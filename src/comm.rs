@@ -0,0 +1,242 @@
+use crate::transport::Transport;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+// Every message between ranks goes out framed: the payload length and an
+// FNV-1a checksum of the payload, both little endian, then the payload. A
+// frame that was cut short, grew or got corrupted on the way is an error for
+// the receiver instead of garbage handed to the deserializer.
+pub const HEADER_BYTES: usize = 12;
+
+// Larger lengths come from a corrupted header, not a real message, and are
+// refused before anything gets allocated for them
+pub const MAX_PAYLOAD_BYTES: u64 = 1 << 36;
+
+// Set in the length of a frame whose payload is LZ4 compressed, transports
+// that compress say so, see `Transport::compresses`. Receivers decompress
+// whatever they were told.
+const COMPRESSED: u64 = 1 << 63;
+
+// Smaller payloads go out as they are, compressing them saves next to nothing
+pub const COMPRESS_MIN_BYTES: usize = 1024;
+
+// What goes through the framing, named for the traffic statistics
+pub trait Payload: Serialize + DeserializeOwned {
+    fn kind(&self) -> &'static str;
+}
+
+pub fn checksum(bytes: impl IntoIterator<Item = u8>) -> u32 {
+    bytes.into_iter().fold(0x811c9dc5, |hash: u32, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+pub fn frame(payload: &[u8]) -> Vec<u8> {
+    frame_with(payload, 0)
+}
+
+fn frame_with(payload: &[u8], flags: u64) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(HEADER_BYTES + payload.len());
+    frame.extend((payload.len() as u64 | flags).to_le_bytes());
+    frame.extend(checksum(payload.iter().copied()).to_le_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+// `message` framed for `transport`, compressed if it compresses and the
+// payload is worth it
+#[cfg_attr(not(feature = "compression"), allow(unused_variables))]
+fn encode<T: Transport, P: Payload>(transport: &T, message: &P) -> Result<Vec<u8>, String> {
+    let payload = bincode::serialize(message).map_err(|error| error.to_string())?;
+    #[cfg(feature = "compression")]
+    if transport.compresses() && payload.len() >= COMPRESS_MIN_BYTES {
+        return Ok(frame_with(
+            &lz4_flex::compress_prepend_size(&payload),
+            COMPRESSED,
+        ));
+    }
+    Ok(frame(&payload))
+}
+
+// The message of a whole frame
+fn decode<P: Payload>(frame: &[u8]) -> Result<P, String> {
+    let (payload, compressed) = unframe(frame)?;
+    deserialize(payload, compressed)
+}
+
+fn deserialize<P: Payload>(payload: &[u8], compressed: bool) -> Result<P, String> {
+    if !compressed {
+        return bincode::deserialize(payload).map_err(|error| error.to_string());
+    }
+    #[cfg(feature = "compression")]
+    {
+        let payload = lz4_flex::decompress_size_prepended(payload)
+            .map_err(|error| format!("compressed payload: {}", error))?;
+        bincode::deserialize(&payload).map_err(|error| error.to_string())
+    }
+    #[cfg(not(feature = "compression"))]
+    Err("compressed payload, built without the compression feature".to_string())
+}
+
+// Length and checksum of the payload that follows `header`, and whether it
+// is compressed
+pub fn read_header(header: &[u8]) -> Result<(usize, u32, bool), String> {
+    if header.len() < HEADER_BYTES {
+        return Err(format!(
+            "frame of {} bytes is shorter than its header",
+            header.len()
+        ));
+    }
+    let length = u64::from_le_bytes(header[..8].try_into().unwrap());
+    let compressed = length & COMPRESSED != 0;
+    let length = length & !COMPRESSED;
+    if length > MAX_PAYLOAD_BYTES {
+        return Err(format!("frame announces a payload of {} bytes", length));
+    }
+    let checksum = u32::from_le_bytes(header[8..HEADER_BYTES].try_into().unwrap());
+    Ok((length as usize, checksum, compressed))
+}
+
+pub fn verify(payload: &[u8], expected: u32) -> Result<(), String> {
+    let actual = checksum(payload.iter().copied());
+    if actual != expected {
+        return Err(format!(
+            "payload checksum is {:08x}, the frame says {:08x}",
+            actual, expected
+        ));
+    }
+    Ok(())
+}
+
+// The payload of a whole frame, and whether it is compressed
+pub fn unframe(frame: &[u8]) -> Result<(&[u8], bool), String> {
+    let (length, checksum, compressed) = read_header(frame)?;
+    let payload = &frame[HEADER_BYTES..];
+    if payload.len() != length {
+        return Err(format!(
+            "frame announces {} payload bytes but carries {}",
+            length,
+            payload.len()
+        ));
+    }
+    verify(payload, checksum)?;
+    Ok((payload, compressed))
+}
+
+pub fn send<T: Transport, P: Payload>(
+    transport: &T,
+    destination: i32,
+    message: &P,
+) -> Result<(), String> {
+    let frame = encode(transport, message)?;
+
+    let started = Instant::now();
+    transport.send(destination, &frame);
+    if let Some(traffic) = transport.traffic() {
+        traffic.record_sent(message.kind(), frame.len(), started.elapsed());
+    }
+    Ok(())
+}
+
+// As `send`, returning before `destination` received it, see
+// `Transport::send_immediate`
+pub fn send_immediate<T: Transport, P: Payload>(
+    transport: &T,
+    destination: i32,
+    message: &P,
+) -> Result<(), String> {
+    let frame = encode(transport, message)?;
+    let bytes = frame.len();
+
+    let started = Instant::now();
+    transport.send_immediate(destination, frame);
+    if let Some(traffic) = transport.traffic() {
+        traffic.record_sent(message.kind(), bytes, started.elapsed());
+    }
+    Ok(())
+}
+
+// Frames that fail to check out or deserialize are accounted as `Invalid`
+pub fn receive<T: Transport, P: Payload>(transport: &T, source: i32) -> Result<P, String> {
+    let started = Instant::now();
+    let frame = transport.receive(source);
+    let message = decode::<P>(&frame);
+
+    if let Some(traffic) = transport.traffic() {
+        let kind = message.as_ref().map_or("Invalid", Payload::kind);
+        traffic.record_received(kind, frame.len(), started.elapsed());
+    }
+    message.map_err(|error| format!("message from rank {}: {}", source, error))
+}
+
+// The first message to arrive from any rank, with the rank that sent it
+pub fn receive_any<T: Transport, P: Payload>(transport: &T) -> (i32, Result<P, String>) {
+    let started = Instant::now();
+    let (source, frame) = transport.receive_any();
+    decode_from(transport, source, &frame, started)
+}
+
+// As `receive_any`, None when nothing arrived within `timeout`
+pub fn receive_any_timeout<T: Transport, P: Payload>(
+    transport: &T,
+    timeout: Duration,
+) -> Option<(i32, Result<P, String>)> {
+    let started = Instant::now();
+    let (source, frame) = transport.receive_any_timeout(timeout)?;
+    Some(decode_from(transport, source, &frame, started))
+}
+
+fn decode_from<T: Transport, P: Payload>(
+    transport: &T,
+    source: i32,
+    frame: &[u8],
+    started: Instant,
+) -> (i32, Result<P, String>) {
+    let message = decode::<P>(frame);
+
+    if let Some(traffic) = transport.traffic() {
+        let kind = message.as_ref().map_or("Invalid", Payload::kind);
+        traffic.record_received(kind, frame.len(), started.elapsed());
+    }
+    let message = message.map_err(|error| format!("message from rank {}: {}", source, error));
+    (source, message)
+}
+
+// `root` broadcasts `message`, every other rank gets it back in there. The
+// collective completes on every rank whatever fails: a root that can't encode
+// the message broadcasts an empty frame, which the others refuse.
+pub fn broadcast<T: Transport, P: Payload>(
+    transport: &T,
+    root: i32,
+    message: &mut P,
+) -> Result<(), String> {
+    let is_root = transport.rank() == root;
+    let started = Instant::now();
+
+    let (frame, encoded) = if is_root {
+        match encode(transport, message) {
+            Ok(frame) => (frame, Ok(())),
+            Err(error) => (Vec::new(), Err(error)),
+        }
+    } else {
+        (Vec::new(), Ok(()))
+    };
+    let frame = transport.broadcast(root, frame);
+    let received = if is_root {
+        encoded
+    } else {
+        decode(&frame).map(|received| *message = received)
+    };
+
+    if let Some(traffic) = transport.traffic() {
+        match (is_root, &received) {
+            (true, _) => traffic.record_sent(message.kind(), frame.len(), started.elapsed()),
+            (false, Ok(())) => {
+                traffic.record_received(message.kind(), frame.len(), started.elapsed())
+            }
+            (false, Err(_)) => traffic.record_received("Invalid", frame.len(), started.elapsed()),
+        }
+    }
+    received.map_err(|error| format!("broadcast from rank {}: {}", root, error))
+}
@@ -0,0 +1,436 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use toml::{Spanned, Value};
+
+// What a key of a config file accepts
+#[derive(Clone, Debug)]
+pub enum ValueKind {
+    String,
+    Path,
+    PathList,
+    // Array of numbers, each within the range
+    Numbers { min: f64, max: f64 },
+    Bool,
+    Integer { min: i64, max: i64 },
+    Float { min: f64, max: f64 },
+    Choice(Vec<String>),
+    // Non empty array of the choices, repeats allowed
+    Choices(Vec<String>),
+    // Inline table of non negative numbers keyed by some of the choices
+    Weights(Vec<String>),
+    // Array of tables, each checked against its own schema
+    Tables(Vec<KeySpec>),
+    // A string the function parses, named by the description
+    Parsed(&'static str, fn(&str) -> Result<(), String>),
+}
+
+impl ValueKind {
+    fn describe(&self) -> String {
+        match self {
+            ValueKind::String => "a string".to_string(),
+            ValueKind::Path => "a path".to_string(),
+            ValueKind::PathList => "a list of paths".to_string(),
+            ValueKind::Numbers { .. } => "a list of numbers".to_string(),
+            ValueKind::Bool => "true or false".to_string(),
+            ValueKind::Integer { .. } => "an integer".to_string(),
+            ValueKind::Float { .. } => "a number".to_string(),
+            ValueKind::Choice(choices) => format!("one of {}", choices.join(", ")),
+            ValueKind::Choices(choices) => format!("a list of {}", choices.join(", ")),
+            ValueKind::Weights(choices) => {
+                format!("a table of weights for {}", choices.join(", "))
+            }
+            ValueKind::Tables(_) => "an array of tables".to_string(),
+            ValueKind::Parsed(description, _) => description.to_string(),
+        }
+    }
+
+    // None when the value is acceptable, the reason otherwise
+    fn check(&self, value: &Value) -> Option<String> {
+        let mismatch = || {
+            Some(format!(
+                "expected {}, found {}",
+                self.describe(),
+                describe_value(value)
+            ))
+        };
+
+        match (self, value) {
+            (ValueKind::String, Value::String(_)) | (ValueKind::Path, Value::String(_)) => None,
+            (ValueKind::Parsed(_, parse), Value::String(text)) => parse(text).err(),
+            (ValueKind::Bool, Value::Boolean(_)) => None,
+            (ValueKind::PathList, Value::Array(paths)) => {
+                if paths.iter().all(Value::is_str) {
+                    None
+                } else {
+                    mismatch()
+                }
+            }
+            (ValueKind::Numbers { min, max }, Value::Array(numbers)) => {
+                let number = ValueKind::Float {
+                    min: *min,
+                    max: *max,
+                };
+                numbers.iter().find_map(|value| match value {
+                    Value::Float(_) | Value::Integer(_) => number.check(value),
+                    _ => mismatch(),
+                })
+            }
+            (ValueKind::Integer { min, max }, Value::Integer(integer)) => {
+                if integer < min || integer > max {
+                    Some(format!("{} is out of range [{}, {}]", integer, min, max))
+                } else {
+                    None
+                }
+            }
+            (ValueKind::Float { min, max }, Value::Float(_) | Value::Integer(_)) => {
+                let number = value
+                    .as_float()
+                    .unwrap_or_else(|| value.as_integer().unwrap() as f64);
+                if !(number >= *min && number <= *max) {
+                    Some(format!("{} is out of range [{}, {}]", number, min, max))
+                } else {
+                    None
+                }
+            }
+            (ValueKind::Choice(choices), Value::String(choice)) => {
+                if choices.iter().any(|known| known == choice) {
+                    None
+                } else {
+                    let mut message =
+                        format!("unknown value \"{}\", expected {}", choice, self.describe());
+                    if let Some(suggestion) = suggest(choice, choices.iter().map(String::as_str)) {
+                        message.push_str(&format!(": did you mean \"{}\"?", suggestion));
+                    }
+                    Some(message)
+                }
+            }
+            (ValueKind::Choices(choices), Value::Array(values)) => {
+                if values.is_empty() {
+                    return mismatch();
+                }
+                let choice = ValueKind::Choice(choices.clone());
+                values.iter().find_map(|value| match value {
+                    Value::String(_) => choice.check(value),
+                    _ => mismatch(),
+                })
+            }
+            (ValueKind::Weights(choices), Value::Table(table)) => {
+                for (name, weight) in table.iter() {
+                    if !choices.iter().any(|known| known == name) {
+                        let mut message = format!("unknown weight \"{}\"", name);
+                        if let Some(suggestion) = suggest(name, choices.iter().map(String::as_str))
+                        {
+                            message.push_str(&format!(": did you mean \"{}\"?", suggestion));
+                        }
+                        return Some(message);
+                    }
+                    let weight = weight
+                        .as_float()
+                        .or_else(|| weight.as_integer().map(|integer| integer as f64));
+                    if !weight.is_some_and(|weight| weight >= 0.0 && weight.is_finite()) {
+                        return Some(format!("weight of {} must be a non negative number", name));
+                    }
+                }
+                None
+            }
+            (ValueKind::Tables(schema), Value::Array(tables)) => {
+                for (i, table) in tables.iter().enumerate() {
+                    let Some(table) = table.as_table() else {
+                        return mismatch();
+                    };
+                    for (key, value) in table.iter() {
+                        let Some(spec) = schema.iter().find(|spec| spec.name == key) else {
+                            let mut message = format!("table {}: unknown key \"{}\"", i + 1, key);
+                            if let Some(suggestion) =
+                                suggest(key, schema.iter().map(|spec| spec.name))
+                            {
+                                message.push_str(&format!(": did you mean \"{}\"?", suggestion));
+                            }
+                            return Some(message);
+                        };
+                        if let Some(problem) = spec.kind.check(value) {
+                            return Some(format!(
+                                "table {}: invalid value for \"{}\": {}",
+                                i + 1,
+                                key,
+                                problem
+                            ));
+                        }
+                    }
+                }
+                None
+            }
+            _ => mismatch(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KeySpec {
+    pub name: &'static str,
+    pub kind: ValueKind,
+}
+
+impl KeySpec {
+    pub fn new(name: &'static str, kind: ValueKind) -> Self {
+        KeySpec { name, kind }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.path.display(),
+            self.line,
+            self.column,
+            self.message
+        )
+    }
+}
+
+// A key that passed validation, its value matches the kind of its spec
+#[derive(Clone, Debug)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+}
+
+impl ConfigEntry {
+    pub fn as_str(&self) -> &str {
+        self.value.as_str().unwrap_or_default()
+    }
+
+    pub fn as_path(&self) -> PathBuf {
+        PathBuf::from(self.as_str())
+    }
+
+    pub fn as_paths(&self) -> Vec<PathBuf> {
+        self.value
+            .as_array()
+            .map(|paths| {
+                paths
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(PathBuf::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn as_strings(&self) -> Vec<&str> {
+        self.value
+            .as_array()
+            .map(|values| values.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn as_bool(&self) -> bool {
+        self.value.as_bool().unwrap_or_default()
+    }
+
+    pub fn as_integer(&self) -> i64 {
+        self.value.as_integer().unwrap_or_default()
+    }
+
+    pub fn as_weights(&self) -> Vec<(String, f64)> {
+        self.value
+            .as_table()
+            .map(|table| {
+                table
+                    .iter()
+                    .map(|(name, weight)| {
+                        let weight = weight
+                            .as_float()
+                            .or_else(|| weight.as_integer().map(|integer| integer as f64))
+                            .unwrap_or_default();
+                        (name.clone(), weight)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // Every table of the array as entries, all on the line of the array key
+    pub fn as_tables(&self) -> Vec<Vec<ConfigEntry>> {
+        self.value
+            .as_array()
+            .map(|tables| {
+                tables
+                    .iter()
+                    .filter_map(Value::as_table)
+                    .map(|table| {
+                        table
+                            .iter()
+                            .map(|(key, value)| ConfigEntry {
+                                key: key.clone(),
+                                value: value.clone(),
+                                line: self.line,
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn as_float(&self) -> f64 {
+        self.value
+            .as_float()
+            .or_else(|| self.value.as_integer().map(|integer| integer as f64))
+            .unwrap_or_default()
+    }
+
+    pub fn as_floats(&self) -> Vec<f64> {
+        self.value
+            .as_array()
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| {
+                        value
+                            .as_float()
+                            .or_else(|| value.as_integer().map(|integer| integer as f64))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+// Reads a flat TOML file and checks every key against the schema, all the
+// problems found are returned at once
+pub fn load(path: &Path, schema: &[KeySpec]) -> Result<Vec<ConfigEntry>, Vec<ConfigError>> {
+    let source = std::fs::read_to_string(path).map_err(|error| {
+        vec![ConfigError {
+            path: path.to_path_buf(),
+            line: 0,
+            column: 0,
+            message: error.to_string(),
+        }]
+    })?;
+    parse(path, &source, schema)
+}
+
+pub fn parse(
+    path: &Path,
+    source: &str,
+    schema: &[KeySpec],
+) -> Result<Vec<ConfigEntry>, Vec<ConfigError>> {
+    let error_at = |offset: usize, message: String| {
+        let (line, column) = position(source, offset);
+        ConfigError {
+            path: path.to_path_buf(),
+            line,
+            column,
+            message,
+        }
+    };
+
+    let table =
+        toml::from_str::<BTreeMap<Spanned<String>, Spanned<Value>>>(source).map_err(|error| {
+            let offset = error.span().map(|span| span.start).unwrap_or(0);
+            let message = error.message().trim().replace('\n', ", ");
+            vec![error_at(offset, message)]
+        })?;
+
+    // Report in file order
+    let mut keys = table.into_iter().collect::<Vec<_>>();
+    keys.sort_by_key(|(key, _)| key.span().start);
+
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    for (key, value) in keys {
+        let Some(spec) = schema.iter().find(|spec| spec.name == key.get_ref()) else {
+            let mut message = format!("unknown key \"{}\"", key.get_ref());
+            if let Some(suggestion) = suggest(key.get_ref(), schema.iter().map(|spec| spec.name)) {
+                message.push_str(&format!(": did you mean \"{}\"?", suggestion));
+            }
+            errors.push(error_at(key.span().start, message));
+            continue;
+        };
+
+        match spec.kind.check(value.get_ref()) {
+            Some(problem) => errors.push(error_at(
+                value.span().start,
+                format!("invalid value for \"{}\": {}", spec.name, problem),
+            )),
+            None => entries.push(ConfigEntry {
+                key: key.get_ref().clone(),
+                line: position(source, key.span().start).0,
+                value: value.into_inner(),
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(entries)
+    } else {
+        Err(errors)
+    }
+}
+
+// One based line and column of a byte offset
+fn position(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, column)
+}
+
+fn describe_value(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "a string",
+        Value::Integer(_) => "an integer",
+        Value::Float(_) => "a float",
+        Value::Boolean(_) => "a boolean",
+        Value::Datetime(_) => "a date",
+        Value::Array(_) => "an array",
+        Value::Table(_) => "a table",
+    }
+}
+
+// Closest known name, ignoring case and `_`/`-` so `mutationrate` finds
+// `mutation_rate`, or None when nothing is close enough to be a typo
+pub fn suggest<'a>(name: &str, known: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|c| *c != '_' && *c != '-')
+            .flat_map(char::to_lowercase)
+            .collect::<Vec<char>>()
+    };
+    let target = normalize(name);
+
+    known
+        .map(|candidate| (edit_distance(&target, &normalize(candidate)), candidate))
+        .filter(|(distance, candidate)| *distance <= (candidate.len() / 3).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(first: &[char], second: &[char]) -> usize {
+    let mut previous = (0..=second.len()).collect::<Vec<usize>>();
+    let mut current = vec![0; second.len() + 1];
+
+    for (i, a) in first.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b) in second.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[second.len()]
+}
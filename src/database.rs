@@ -0,0 +1,185 @@
+use crate::statistics::FitnessSummary;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    recorded_at INTEGER NOT NULL,
+    problem TEXT NOT NULL,
+    instance TEXT NOT NULL,
+    crossover TEXT NOT NULL,
+    mutation TEXT NOT NULL,
+    mutation_rate REAL NOT NULL,
+    crossover_rate REAL NOT NULL,
+    population_size INTEGER NOT NULL,
+    elite INTEGER NOT NULL,
+    generations INTEGER NOT NULL,
+    seed INTEGER,
+    best REAL NOT NULL,
+    mean REAL,
+    std_dev REAL,
+    invalid INTEGER NOT NULL,
+    tag TEXT
+)";
+
+const COLUMNS: &str = "recorded_at, problem, instance, crossover, mutation, mutation_rate, \
+    crossover_rate, population_size, elite, generations, seed, best, mean, std_dev, invalid, tag";
+
+// One finished run, the configuration is kept in plain columns so the table
+// can also be queried with the sqlite3 shell
+#[derive(Clone, Debug)]
+pub struct RunRecord {
+    pub recorded_at: u64,
+    pub problem: String,
+    pub instance: String,
+    pub crossover: String,
+    pub mutation: String,
+    pub mutation_rate: f32,
+    pub crossover_rate: f32,
+    pub population_size: usize,
+    pub elite: usize,
+    pub generations: usize,
+    pub seed: Option<u64>,
+    pub best: f32,
+    pub mean: Option<f64>,
+    pub std_dev: Option<f64>,
+    pub invalid: usize,
+    // `--tag` of the run, to group the runs of an experiment
+    pub tag: Option<String>,
+}
+
+impl RunRecord {
+    // Fills the metrics from the summary of the last generation, the rest is
+    // left for the caller to set
+    pub fn from_summary(problem: &str, instance: &str, summary: &FitnessSummary) -> Self {
+        RunRecord {
+            recorded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or(0),
+            problem: problem.to_string(),
+            instance: instance.to_string(),
+            crossover: String::new(),
+            mutation: String::new(),
+            mutation_rate: 0.0,
+            crossover_rate: 0.0,
+            population_size: 0,
+            elite: 0,
+            generations: 0,
+            seed: None,
+            best: summary.best,
+            mean: (summary.valid() > 0).then(|| summary.mean()),
+            std_dev: (summary.valid() > 0).then(|| summary.std_dev()),
+            invalid: summary.invalid,
+            tag: None,
+        }
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(RunRecord {
+            recorded_at: row.get::<_, i64>(0)? as u64,
+            problem: row.get(1)?,
+            instance: row.get(2)?,
+            crossover: row.get(3)?,
+            mutation: row.get(4)?,
+            mutation_rate: row.get::<_, f64>(5)? as f32,
+            crossover_rate: row.get::<_, f64>(6)? as f32,
+            population_size: row.get::<_, i64>(7)? as usize,
+            elite: row.get::<_, i64>(8)? as usize,
+            generations: row.get::<_, i64>(9)? as usize,
+            seed: row.get::<_, Option<i64>>(10)?.map(|seed| seed as u64),
+            best: row.get::<_, f64>(11)? as f32,
+            mean: row.get(12)?,
+            std_dev: row.get(13)?,
+            invalid: row.get::<_, i64>(14)? as usize,
+            tag: row.get(15)?,
+        })
+    }
+}
+
+pub struct ResultDatabase {
+    connection: Connection,
+}
+
+impl ResultDatabase {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute(SCHEMA, [])?;
+        add_tag_column(&connection)?;
+        Ok(ResultDatabase { connection })
+    }
+
+    pub fn record(&self, run: &RunRecord) -> rusqlite::Result<()> {
+        self.connection.execute(
+            &format!(
+                "INSERT INTO runs ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+                COLUMNS
+            ),
+            params![
+                run.recorded_at as i64,
+                run.problem,
+                run.instance,
+                run.crossover,
+                run.mutation,
+                run.mutation_rate as f64,
+                run.crossover_rate as f64,
+                run.population_size as i64,
+                run.elite as i64,
+                run.generations as i64,
+                run.seed.map(|seed| seed as i64),
+                run.best as f64,
+                run.mean,
+                run.std_dev,
+                run.invalid as i64,
+                run.tag,
+            ],
+        )?;
+        Ok(())
+    }
+
+    // Run with the lowest best fitness ever recorded for the instance
+    pub fn best_for_instance(&self, instance: &str) -> rusqlite::Result<Option<RunRecord>> {
+        self.connection
+            .query_row(
+                &format!(
+                    "SELECT {} FROM runs WHERE instance = ?1 ORDER BY best ASC, id ASC LIMIT 1",
+                    COLUMNS
+                ),
+                params![instance],
+                RunRecord::from_row,
+            )
+            .optional()
+    }
+
+    // Every run of the instance, oldest first
+    pub fn runs_for_instance(&self, instance: &str) -> rusqlite::Result<Vec<RunRecord>> {
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {} FROM runs WHERE instance = ?1 ORDER BY id ASC",
+            COLUMNS
+        ))?;
+        let runs = statement.query_map(params![instance], RunRecord::from_row)?;
+        runs.collect()
+    }
+
+    pub fn instances(&self) -> rusqlite::Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT DISTINCT instance FROM runs ORDER BY instance ASC")?;
+        let instances = statement.query_map([], |row| row.get(0))?;
+        instances.collect()
+    }
+}
+
+// Databases created before runs were tagged get the column, their runs have
+// no tag
+fn add_tag_column(connection: &Connection) -> rusqlite::Result<()> {
+    let mut statement = connection.prepare("SELECT name FROM pragma_table_info('runs')")?;
+    let columns = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    if !columns.iter().any(|column| column == "tag") {
+        connection.execute("ALTER TABLE runs ADD COLUMN tag TEXT", [])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,945 @@
+use crate::backend::{ExecutionBackend, LocalBackend};
+use crate::cli::RunArgs;
+use crate::comm::{self, Payload};
+use crate::distributed_islands;
+use crate::driver::{self, EvaluatedPopulation};
+use crate::logging::{self, LevelControl, LogConfig};
+use crate::memory::{self, Bytes};
+use crate::scheduler::RunSummary;
+use crate::statistics::FitnessSummary;
+#[cfg(feature = "compression")]
+use crate::transport::Compressing;
+use crate::transport::{Checked, MessageTraffic, TrafficStats, Transport};
+use crate::tsp::duration::RouteDuration;
+use crate::tsp::matrix::WeightMatrix;
+use crate::tsp::packed::{self, PackedTour};
+use crate::tsp::scenarios::Scenarios;
+use crate::tsp::{TspInstance, TspProblem, TSP};
+use log::{debug, error, info, warn, LevelFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Message {
+    Terminate,
+    // Sequence number of the chunk within the generation, the chunk, and
+    // whether another one for the same worker follows it. Only the tours go,
+    // every worker has the map since the broadcast.
+    Population(u32, Vec<PackedTour>, bool),
+    // With the scenarios the tours are measured in and the route duration
+    // they are held to as well, if any
+    MapCreation(WeightMatrix, Option<Scenarios>, Option<RouteDuration>),
+    // Log from now on with this level, sent between two generations
+    SetLogLevel(LevelFilter),
+    // Sequence number of the chunk evaluated, the chunk, the resident set of
+    // the worker when it is known, and its statistics when they are collected
+    EvaluatedPopulation(
+        u32,
+        Vec<(f32, PackedTour)>,
+        FitnessSummary,
+        Option<u64>,
+        Option<WorkerStats>,
+    ),
+}
+
+// What a worker did for one or more generations, it rides along with the
+// evaluated population so collecting it costs no extra message
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct WorkerStats {
+    pub evaluated: usize,
+    // Spent evaluating, the rest of the time the worker waited on the root
+    pub seconds: f64,
+}
+
+impl WorkerStats {
+    pub fn merge(&mut self, other: &WorkerStats) {
+        self.evaluated += other.evaluated;
+        self.seconds += other.seconds;
+    }
+}
+
+impl Payload for Message {
+    fn kind(&self) -> &'static str {
+        match self {
+            Message::Terminate => "Terminate",
+            Message::Population(..) => "Population",
+            Message::MapCreation(..) => "MapCreation",
+            Message::SetLogLevel(_) => "SetLogLevel",
+            Message::EvaluatedPopulation(..) => "EvaluatedPopulation",
+        }
+    }
+}
+
+pub const ROOT_PROCESS: i32 = 0;
+
+#[cfg(feature = "mpi")]
+pub use self::mpi_backend::MpiBackend;
+
+#[cfg(feature = "mpi")]
+mod mpi_backend {
+    use super::{share_log_config, solve, Role};
+    use crate::backend::ExecutionBackend;
+    use crate::batch::{self, BatchEntry, BatchResult};
+    use crate::cli::RunArgs;
+    use crate::logging::LogConfig;
+    use crate::scheduler::RunSummary;
+    use crate::transport::{Metered, MpiTransport, Transport};
+    use crate::tsp::TspInstance;
+    use mpi::environment::Universe;
+    use mpi::traits::Communicator;
+
+    // The root runs the GA and farms out evaluation, every other rank is a
+    // worker, or every rank evolves an island
+    pub struct MpiBackend {
+        // Declared first, the communicator goes before MPI is finalized
+        transport: Metered<MpiTransport>,
+        role: Role,
+        universe: Universe,
+    }
+
+    impl MpiBackend {
+        pub fn initialize(role: Role) -> Self {
+            let universe = mpi::initialize().unwrap();
+            MpiBackend {
+                transport: Metered::new(MpiTransport::new(universe.world())),
+                role,
+                universe,
+            }
+        }
+    }
+
+    impl ExecutionBackend for MpiBackend {
+        fn rank(&self) -> i32 {
+            self.transport.rank()
+        }
+
+        fn processes(&self) -> i32 {
+            self.transport.size()
+        }
+
+        fn share_log_config(&self, config: LogConfig) -> LogConfig {
+            share_log_config(&self.transport, config)
+        }
+
+        fn solve(&self, run: &RunArgs, instance: &TspInstance) -> Option<RunSummary> {
+            solve(&self.transport, run, instance, self.role)
+        }
+
+        // Instance by instance, the root hands them out as ranks free up
+        fn solve_batch(&self, run: &RunArgs, batch: &[BatchEntry]) -> Option<Vec<BatchResult>> {
+            batch::solve(&self.transport, run, batch)
+        }
+
+        // MPI_Abort, mpirun ends the job with `code` and kills the ranks
+        // still waiting on this one. Logs written to a file are flushed
+        // first, MPI never returns to drop anything.
+        fn abort(&self, code: i32) -> ! {
+            log::logger().flush();
+            self.universe.world().abort(code)
+        }
+    }
+}
+
+// What the ranks of a distributed run do
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    // The root runs the GA, the other ranks evaluate for it
+    Evaluation,
+    // Every rank evolves its own island, see `distributed_islands`
+    Islands,
+}
+
+// Every rank plays its part of `role`, over `transport` stamped with the
+// instance checksum in debug builds or with `--strict`, and compressing
+// with `--compress`
+pub fn solve<T: Transport>(
+    transport: &T,
+    run: &RunArgs,
+    instance: &TspInstance,
+    role: Role,
+) -> Option<RunSummary> {
+    #[cfg(feature = "compression")]
+    if run.compress {
+        return solve_stamped(&Compressing::new(transport), run, instance, role);
+    }
+    solve_stamped(transport, run, instance, role)
+}
+
+fn solve_stamped<T: Transport>(
+    transport: &T,
+    run: &RunArgs,
+    instance: &TspInstance,
+    role: Role,
+) -> Option<RunSummary> {
+    if run.strict || cfg!(debug_assertions) {
+        let checksum = instance.checksum();
+        info!(
+            "Rank {} stamps its messages with instance checksum {:08x}",
+            transport.rank(),
+            checksum
+        );
+        solve_on(&Checked::new(transport, checksum), run, instance, role)
+    } else {
+        solve_on(transport, run, instance, role)
+    }
+}
+
+fn solve_on<T: Transport>(
+    transport: &T,
+    run: &RunArgs,
+    instance: &TspInstance,
+    role: Role,
+) -> Option<RunSummary> {
+    if role == Role::Islands {
+        distributed_islands::evolve(transport, run, instance)
+    } else if transport.size() == 1 {
+        // `mpirun -n 1` or no mpirun at all: nobody to evaluate for the root,
+        // which runs the whole GA by itself without a message
+        info!("Single MPI process, solving locally");
+        LocalBackend.solve(run, instance)
+    } else if transport.rank() == ROOT_PROCESS {
+        Some(coordinate(transport, run, instance))
+    } else {
+        work(transport, run.worker_stats, !run.open_path);
+        None
+    }
+}
+
+// The root side of a whole run
+pub fn coordinate<T: Transport>(
+    transport: &T,
+    run: &RunArgs,
+    instance: &TspInstance,
+) -> RunSummary {
+    broadcast_map(
+        transport,
+        &instance.matrix(),
+        instance.scenarios.as_deref(),
+        instance.duration.as_deref(),
+    );
+
+    let mut level_control = run.log_level_file.clone().map(LevelControl::new);
+    let mut health = WorkerHealth::new(run.worker_timeout.map(Duration::from_secs));
+    let mut run_summary = driver::evolve(run, instance, transport.size(), |population, map| {
+        if let Some(level) = level_control.as_mut().and_then(LevelControl::update) {
+            (1..transport.size())
+                .for_each(|i| send_message(transport, i, &Message::SetLogLevel(level)));
+        }
+        evaluate_population(
+            transport,
+            population,
+            map,
+            run.chunks_per_worker,
+            run.chunk_scheduling,
+            &mut health,
+        )
+    });
+
+    let lost = health.lost_ranks();
+    if !lost.is_empty() {
+        warn!("Ranks {:?} were lost during the run", lost);
+    }
+    terminate_workers(transport);
+
+    if let Some(traffic) = transport.traffic() {
+        let traffic = traffic.stats();
+        print_traffic(&traffic);
+        run_summary.traffic = Some(traffic);
+    }
+    run_summary
+}
+
+pub fn broadcast_map<T: Transport>(
+    transport: &T,
+    graph_weights: &WeightMatrix,
+    scenarios: Option<&Scenarios>,
+    duration: Option<&RouteDuration>,
+) {
+    info!("Root process is broadcasting the map");
+    let mut message =
+        Message::MapCreation(graph_weights.clone(), scenarios.cloned(), duration.cloned());
+    comm::broadcast(transport, ROOT_PROCESS, &mut message)
+        .unwrap_or_else(|error| panic!("Failed to broadcast the map: {}", error));
+}
+
+pub fn terminate_workers<T: Transport>(transport: &T) {
+    (1..transport.size()).for_each(|i| send_message(transport, i, &Message::Terminate));
+}
+
+fn send_message<T: Transport>(transport: &T, destination: i32, message: &Message) {
+    comm::send(transport, destination, message).unwrap_or_else(|error| {
+        panic!(
+            "Failed to send {} to rank {}: {}",
+            message.kind(),
+            destination,
+            error
+        )
+    });
+}
+
+fn send_immediate<T: Transport>(transport: &T, destination: i32, message: &Message) {
+    comm::send_immediate(transport, destination, message).unwrap_or_else(|error| {
+        panic!(
+            "Failed to send {} to rank {}: {}",
+            message.kind(),
+            destination,
+            error
+        )
+    });
+}
+
+fn print_traffic(traffic: &TrafficStats) {
+    let print = |direction: &str, kind: &str, traffic: &MessageTraffic| {
+        println!(
+            "  {:<8} {:<20} messages: {:<8} bytes: {:<12} blocked: {:.3} s",
+            direction,
+            kind,
+            traffic.count,
+            Bytes(traffic.bytes).to_string(),
+            traffic.seconds
+        )
+    };
+
+    println!("Root traffic:");
+    for (kind, sent) in traffic.sent.iter() {
+        print("sent", kind, sent);
+    }
+    print("sent", "total", &traffic.total_sent());
+    for (kind, received) in traffic.received.iter() {
+        print("received", kind, received);
+    }
+    print("received", "total", &traffic.total_received());
+}
+
+// The worker side: receive the map, then evaluate every population chunk the
+// root sends until it says to stop. Returns the number of chunks evaluated.
+// Every process is started with the same arguments, so the root expects
+// statistics exactly when `collect_stats` is set, and evaluates tours as
+// `closed_tour` says as well.
+//
+// When the root says another chunk follows, it is received on this thread
+// while another one evaluates the current chunk, the transport never leaves
+// this thread. That one chunk ahead is all the worker queues.
+pub fn work<T: Transport>(transport: &T, collect_stats: bool, closed_tour: bool) -> usize {
+    let rank = transport.rank();
+    let mut evaluated = 0;
+
+    let mut message = Message::Terminate;
+    if let Err(error) = comm::broadcast(transport, ROOT_PROCESS, &mut message) {
+        panic!("Process {} failed to receive the map: {}", rank, error);
+    }
+
+    if let Message::MapCreation(map, scenarios, duration) = message {
+        let map = TspProblem {
+            closed_tour,
+            scenarios: scenarios.map(Arc::new),
+            duration: duration.map(Arc::new),
+            ..TspProblem::new(Arc::new(map))
+        };
+        info!("Process {} received the map", rank);
+        let mut queue = VecDeque::new();
+        loop {
+            // Receive the population from the root process or a termination signal
+            let (sequence, population, follows) = match queue.pop_front() {
+                Some(chunk) => chunk,
+                None => match receive_chunk(transport) {
+                    Incoming::Chunk(sequence, population, follows) => {
+                        (sequence, population, follows)
+                    }
+                    Incoming::Dropped => continue,
+                    Incoming::Terminate => break,
+                },
+            };
+
+            let ((evaluated_population, summary, seconds), next) = thread::scope(|scope| {
+                let evaluation = scope.spawn(|| {
+                    let started = Instant::now();
+                    let population = population.into_iter().map(PackedTour::unpack).collect();
+                    let (evaluated_population, summary) = driver::evaluate_chunk(&map, population);
+                    (
+                        evaluated_population,
+                        summary,
+                        started.elapsed().as_secs_f64(),
+                    )
+                });
+                let next = follows.then(|| receive_chunk(transport));
+                (evaluation.join().unwrap(), next)
+            });
+            let stats = collect_stats.then_some(WorkerStats {
+                evaluated: evaluated_population.len(),
+                seconds,
+            });
+
+            // Send the evaluated population to the root process, without
+            // waiting for it to be received before the next chunk
+            let message = Message::EvaluatedPopulation(
+                sequence,
+                packed::pack_evaluated(&evaluated_population),
+                summary,
+                memory::resident_set(),
+                stats,
+            );
+            send_immediate(transport, ROOT_PROCESS, &message);
+            evaluated += 1;
+
+            match next {
+                Some(Incoming::Chunk(sequence, population, follows)) => {
+                    queue.push_back((sequence, population, follows))
+                }
+                Some(Incoming::Terminate) => break,
+                Some(Incoming::Dropped) | None => {}
+            }
+        }
+    }
+    transport.complete_sends();
+    info!("Process {} is done", rank);
+    evaluated
+}
+
+enum Incoming {
+    Chunk(u32, Vec<PackedTour>, bool),
+    Dropped,
+    Terminate,
+}
+
+// What the root sent next, a message that fails to check out or that a worker
+// has no use for is logged and dropped. Level changes are applied on the way.
+fn receive_chunk<T: Transport>(transport: &T) -> Incoming {
+    loop {
+        match comm::receive(transport, ROOT_PROCESS) {
+            Ok(Message::Population(sequence, population, follows)) => {
+                return Incoming::Chunk(sequence, population, follows)
+            }
+            Ok(Message::SetLogLevel(level)) => {
+                logging::set_level(level);
+                info!("Process {} logs at level {}", transport.rank(), level);
+            }
+            Ok(Message::Terminate) => return Incoming::Terminate,
+            Ok(other) => {
+                error!("Process {} dropped a {}", transport.rank(), other.kind());
+                return Incoming::Dropped;
+            }
+            Err(error) => {
+                error!("Process {} dropped a {}", transport.rank(), error);
+                return Incoming::Dropped;
+            }
+        }
+    }
+}
+
+impl Payload for LogConfig {
+    fn kind(&self) -> &'static str {
+        "LogConfig"
+    }
+}
+
+// Every rank logs with the configuration the root was started with
+pub fn share_log_config<T: Transport>(transport: &T, mut config: LogConfig) -> LogConfig {
+    comm::broadcast(transport, ROOT_PROCESS, &mut config)
+        .unwrap_or_else(|error| panic!("Failed to share the log configuration: {}", error));
+    config
+}
+
+// How the chunks of a generation are handed out to the workers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkScheduling {
+    // Worker w evaluates chunks w - 1, w - 1 + workers, ... whatever its pace
+    Static,
+    // Every worker gets the next chunk nobody has as it returns one, so
+    // faster nodes evaluate more of the generation
+    Dynamic,
+}
+
+impl ChunkScheduling {
+    pub const ALL: [ChunkScheduling; 2] = [ChunkScheduling::Static, ChunkScheduling::Dynamic];
+}
+
+impl fmt::Display for ChunkScheduling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ChunkScheduling::Static => "static",
+            ChunkScheduling::Dynamic => "dynamic",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for ChunkScheduling {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        ChunkScheduling::ALL
+            .into_iter()
+            .find(|scheduling| scheduling.to_string() == name)
+            .ok_or_else(|| format!("unknown chunk scheduling: {}", name))
+    }
+}
+
+// Times a chunk goes to another worker before the root evaluates it itself,
+// a chunk that takes down every worker it reaches would take them all
+const CHUNK_RETRIES: usize = 2;
+
+// Workers the root gave up on after they held chunks silently for longer than
+// the timeout, never sent a chunk again for the rest of the run
+pub struct WorkerHealth {
+    timeout: Option<Duration>,
+    lost: Vec<bool>,
+}
+
+impl WorkerHealth {
+    pub fn new(timeout: Option<Duration>) -> Self {
+        WorkerHealth {
+            timeout,
+            lost: Vec::new(),
+        }
+    }
+
+    pub fn lost_ranks(&self) -> Vec<i32> {
+        (0..self.lost.len())
+            .filter(|worker| self.lost[*worker])
+            .map(|worker| worker as i32 + 1)
+            .collect()
+    }
+}
+
+// Which chunk of the generation every worker gets next. A chunk tells its
+// worker whether another one follows, which it then receives while
+// evaluating: that one is held back for it whoever asks first.
+struct Dispatch {
+    scheduling: ChunkScheduling,
+    // The chunks of every worker with static scheduling, the chunks nobody
+    // has in the first one with dynamic scheduling
+    queues: Vec<VecDeque<usize>>,
+    // Workers told that another chunk follows
+    promised: Vec<bool>,
+    // Workers that didn't get their first chunk yet, one is held back for
+    // each of them as well
+    unserved: Vec<bool>,
+    // Worker every chunk is with until it comes back
+    owners: Vec<Option<usize>>,
+}
+
+impl Dispatch {
+    // Lost workers get nothing
+    fn new(scheduling: ChunkScheduling, lost: &[bool], chunks: usize) -> Self {
+        let workers = lost.len();
+        let mut dispatch = Dispatch {
+            scheduling,
+            queues: vec![VecDeque::new(); workers],
+            promised: vec![false; workers],
+            unserved: lost.iter().map(|lost| !lost).collect(),
+            owners: vec![None; chunks],
+        };
+        dispatch.hand_out((0..chunks).collect(), lost);
+        dispatch
+    }
+
+    // Queues `chunks` round the workers not lost with static scheduling,
+    // ahead of the others with dynamic scheduling
+    fn hand_out(&mut self, chunks: Vec<usize>, lost: &[bool]) {
+        match self.scheduling {
+            ChunkScheduling::Static => {
+                let alive = (0..lost.len())
+                    .filter(|worker| !lost[*worker])
+                    .collect::<Vec<_>>();
+                for (i, sequence) in chunks.into_iter().enumerate() {
+                    self.queues[alive[i % alive.len()]].push_back(sequence);
+                }
+            }
+            ChunkScheduling::Dynamic => {
+                for sequence in chunks.into_iter().rev() {
+                    self.queues[0].push_front(sequence);
+                }
+            }
+        }
+    }
+
+    fn holds(&self, worker: usize) -> bool {
+        self.owners.contains(&Some(worker))
+    }
+
+    fn returned(&mut self, sequence: usize) {
+        self.owners[sequence] = None;
+    }
+
+    // Every chunk `worker` has or would get, which then gets nothing more
+    fn take_back(&mut self, worker: usize) -> Vec<usize> {
+        self.promised[worker] = false;
+        self.unserved[worker] = false;
+        let mut chunks = match self.scheduling {
+            ChunkScheduling::Static => self.queues[worker].drain(..).collect(),
+            ChunkScheduling::Dynamic => Vec::new(),
+        };
+        for (sequence, owner) in self.owners.iter_mut().enumerate() {
+            if *owner == Some(worker) {
+                *owner = None;
+                chunks.push(sequence);
+            }
+        }
+        chunks.sort_unstable();
+        chunks
+    }
+
+    // The next chunk of `worker` and whether another one follows it
+    fn next(&mut self, worker: usize) -> Option<(usize, bool)> {
+        let (sequence, follows) = match self.scheduling {
+            ChunkScheduling::Static => {
+                let queue = &mut self.queues[worker];
+                let sequence = queue.pop_front()?;
+                (sequence, !queue.is_empty())
+            }
+            ChunkScheduling::Dynamic => {
+                let held = std::mem::take(&mut self.promised[worker])
+                    | std::mem::take(&mut self.unserved[worker]);
+                let reserved = self.promised.iter().filter(|promised| **promised).count()
+                    + self.unserved.iter().filter(|unserved| **unserved).count();
+                if !held && self.queues[0].len() <= reserved {
+                    return None;
+                }
+                let sequence = self.queues[0].pop_front()?;
+                (sequence, self.queues[0].len() > reserved)
+            }
+        };
+        self.promised[worker] = follows;
+        self.owners[sequence] = Some(worker);
+        Some((sequence, follows))
+    }
+}
+
+// The population is split into `chunks_per_worker` chunks for every worker,
+// possibly empty so the gather never waits on a rank that got nothing, handed
+// out as `scheduling` says. A worker gets its next chunk while it still
+// evaluates the current one, so with several chunks per worker sending them
+// overlaps with evaluating. A single process evaluates everything itself.
+// The chunks of a worker silent for longer than the timeout of `health` go
+// to the others, and the root evaluates what nobody is left to.
+pub fn evaluate_population<T: Transport>(
+    transport: &T,
+    population: &[TSP],
+    map: &TspProblem,
+    chunks_per_worker: usize,
+    scheduling: ChunkScheduling,
+    health: &mut WorkerHealth,
+) -> EvaluatedPopulation {
+    let solutions = driver::solutions_of(population);
+
+    let workers = transport.size() as usize - 1;
+    // A group of a batch grows between two generations
+    health.lost.resize(workers, false);
+    if health.lost.iter().all(|lost| *lost) {
+        let (evaluated_population, summary) = driver::evaluate_chunk(map, solutions);
+        return (evaluated_population, summary, None, Vec::new());
+    }
+
+    let chunks = driver::chunk_ranges(solutions.len(), workers * chunks_per_worker.max(1));
+    let evaluate_here = |sequence: usize| {
+        let (evaluated_population, partial) =
+            driver::evaluate_chunk(map, solutions[chunks[sequence].clone()].to_vec());
+        Some((evaluated_population, partial, None))
+    };
+    let mut dispatch = Dispatch::new(scheduling, &health.lost, chunks.len());
+    // Since when every worker holding chunks has been silent
+    let mut silent_since = vec![Instant::now(); workers];
+    let send_next = |dispatch: &mut Dispatch, silent_since: &mut [Instant], worker: usize| {
+        if !dispatch.holds(worker) {
+            silent_since[worker] = Instant::now();
+        }
+        if let Some((sequence, follows)) = dispatch.next(worker) {
+            let message = Message::Population(
+                sequence as u32,
+                packed::pack_all(&solutions[chunks[sequence].clone()]),
+                follows,
+            );
+            send_immediate(transport, worker as i32 + 1, &message);
+        }
+    };
+
+    // The chunk being evaluated and the one waiting behind it, every worker
+    // receiving its own at the same time. A worker told another chunk
+    // follows waits for it before returning the first.
+    let start = |dispatch: &mut Dispatch, silent_since: &mut [Instant], idle: &[usize]| {
+        for round in 0..2 {
+            for worker in idle.iter().copied() {
+                if round == 0 || dispatch.promised[worker] {
+                    send_next(dispatch, silent_since, worker);
+                }
+            }
+        }
+    };
+    let alive = (0..workers)
+        .filter(|worker| !health.lost[*worker])
+        .collect::<Vec<_>>();
+    start(&mut dispatch, &mut silent_since, &alive);
+
+    let started = Instant::now();
+    let mut results = (0..chunks.len()).map(|_| None).collect::<Vec<_>>();
+    let mut left = chunks.len();
+    let mut handed_back = vec![0; chunks.len()];
+    let mut worker_stats = BTreeMap::new();
+    // Taken as they arrive, so a slow rank doesn't hold up the others
+    while left > 0 {
+        // Until the first worker holding chunks has been silent too long
+        let deadline = health.timeout.and_then(|timeout| {
+            (0..workers)
+                .filter(|worker| dispatch.holds(*worker))
+                .map(|worker| silent_since[worker] + timeout)
+                .min()
+        });
+        let received = match deadline {
+            Some(deadline) => comm::receive_any_timeout(
+                transport,
+                deadline.saturating_duration_since(Instant::now()),
+            ),
+            None => Some(comm::receive_any(transport)),
+        };
+
+        let Some((source, message)) = received else {
+            let now = Instant::now();
+            let timeout = health.timeout.unwrap();
+            let silent = (0..workers)
+                .filter(|worker| dispatch.holds(*worker) && now >= silent_since[*worker] + timeout)
+                .collect::<Vec<_>>();
+            for worker in silent {
+                warn!(
+                    "Rank {} was silent for {:?} holding chunks, handing them out again",
+                    worker + 1,
+                    timeout
+                );
+                health.lost[worker] = true;
+                let mut retried = Vec::new();
+                for sequence in dispatch.take_back(worker) {
+                    handed_back[sequence] += 1;
+                    if handed_back[sequence] > CHUNK_RETRIES {
+                        results[sequence] = evaluate_here(sequence);
+                        left -= 1;
+                    } else {
+                        retried.push(sequence);
+                    }
+                }
+                if health.lost.contains(&false) {
+                    dispatch.hand_out(retried, &health.lost);
+                }
+            }
+
+            if !health.lost.contains(&false) {
+                warn!("Every worker is lost, the root evaluates the rest itself");
+                for (sequence, result) in results.iter_mut().enumerate() {
+                    if result.is_none() {
+                        *result = evaluate_here(sequence);
+                    }
+                }
+                break;
+            }
+            // Workers done with their chunks would wait for the next
+            // generation otherwise
+            let idle = (0..workers)
+                .filter(|worker| !health.lost[*worker] && !dispatch.holds(*worker))
+                .collect::<Vec<_>>();
+            start(&mut dispatch, &mut silent_since, &idle);
+            continue;
+        };
+        if health.lost[source as usize - 1] {
+            debug!("Dropped a late message from lost rank {}", source);
+            continue;
+        }
+        silent_since[source as usize - 1] = Instant::now();
+
+        match message {
+            Ok(Message::EvaluatedPopulation(
+                sequence,
+                evaluated_population,
+                partial,
+                resident,
+                stats,
+            )) => {
+                let sequence = sequence as usize;
+                if dispatch.owners.get(sequence) != Some(&Some(source as usize - 1))
+                    || results[sequence].is_some()
+                {
+                    panic!(
+                        "Error receiving evaluated population, rank {} sent chunk {} unexpectedly",
+                        source, sequence
+                    );
+                }
+                // Every individual sent comes back, or the generation would
+                // silently shrink
+                if evaluated_population.len() != chunks[sequence].len() {
+                    panic!(
+                        "Error receiving evaluated population, rank {} returned {} individuals of chunk {} instead of {}",
+                        source,
+                        evaluated_population.len(),
+                        sequence,
+                        chunks[sequence].len()
+                    );
+                }
+                let evaluated_population = packed::unpack_evaluated(evaluated_population);
+                results[sequence] = Some((evaluated_population, partial, resident));
+                dispatch.returned(sequence);
+                left -= 1;
+                if let Some(stats) = stats {
+                    worker_stats
+                        .entry(source)
+                        .or_insert_with(WorkerStats::default)
+                        .merge(&stats);
+                }
+                send_next(&mut dispatch, &mut silent_since, source as usize - 1);
+            }
+            Ok(other) => panic!(
+                "Error receiving evaluated population, rank {} sent {}",
+                source,
+                other.kind()
+            ),
+            Err(error) => panic!("Error receiving evaluated population, {}", error),
+        }
+    }
+    // Sends to a lost worker may never be done
+    if !health.lost.contains(&true) {
+        transport.complete_sends();
+    }
+    debug!(
+        "Gathered {} chunks in {:?}",
+        chunks.len(),
+        started.elapsed()
+    );
+
+    // Put back in chunk order, whichever rank finished first, and merged in
+    // that fixed order so the logged numbers are reproducible
+    let mut eval_pop = Vec::with_capacity(solutions.len());
+    let mut summary = FitnessSummary::new();
+    let mut worker_peak: Option<u64> = None;
+    for (mut evaluated_population, partial, resident) in results.into_iter().flatten() {
+        eval_pop.append(&mut evaluated_population);
+        summary.merge(&partial);
+        worker_peak = worker_peak.max(resident);
+    }
+
+    (
+        eval_pop,
+        summary,
+        worker_peak,
+        worker_stats.into_iter().collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::RunCli;
+    use crate::transport::{MockTransport, NetworkConditions};
+    use crate::tsp::builtin;
+    use clap::Parser;
+    use std::panic::{self, AssertUnwindSafe};
+
+    fn run_args(extra: &[&str]) -> RunArgs {
+        let args = [
+            "ga-run",
+            "--pop-size",
+            "60",
+            "--iterations",
+            "5",
+            "--seed",
+            "11",
+        ];
+        RunCli::parse_from(args.iter().chain(extra)).run
+    }
+
+    // The root coordinates on this thread and every worker works on its own,
+    // a worker that panics comes back as None
+    fn distribute(
+        run: &RunArgs,
+        instance: &TspInstance,
+        ranks: usize,
+        conditions: &NetworkConditions,
+    ) -> (RunSummary, Vec<Option<usize>>) {
+        thread::scope(|scope| {
+            let mut transports = MockTransport::network_with(ranks, conditions).into_iter();
+            let root = transports.next().unwrap();
+            let workers = transports
+                .map(|transport| {
+                    scope.spawn(move || {
+                        panic::catch_unwind(AssertUnwindSafe(|| {
+                            work(&transport, false, !run.open_path)
+                        }))
+                        .ok()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let run_summary = coordinate(&root, run, instance);
+            let evaluated = workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect();
+            (run_summary, evaluated)
+        })
+    }
+
+    fn assert_same_run(expected: &RunSummary, found: &RunSummary) {
+        assert_eq!(expected.history.generations(), found.history.generations());
+        assert_eq!(
+            expected.best_tour.as_ref().map(|tour| &tour.path),
+            found.best_tour.as_ref().map(|tour| &tour.path)
+        );
+    }
+
+    #[test]
+    fn workers_evaluate_what_the_root_would() {
+        let run = run_args(&["--chunks-per-worker", "2"]);
+        let instance = builtin::instance("wi29").unwrap();
+        let local = LocalBackend.solve(&run, &instance).unwrap();
+
+        let (distributed, evaluated) =
+            distribute(&run, &instance, 3, &NetworkConditions::default());
+
+        assert_same_run(&local, &distributed);
+        assert_eq!(distributed.processes, 3);
+        // Every worker got its two chunks of every generation and stopped
+        // when told to
+        let generations = distributed.history.generations().len();
+        assert_eq!(evaluated, [Some(2 * generations), Some(2 * generations)]);
+    }
+
+    #[test]
+    fn chunks_of_a_failed_worker_go_to_the_others() {
+        let run = run_args(&["--worker-timeout", "1"]);
+        let instance = builtin::instance("wi29").unwrap();
+        let local = LocalBackend.solve(&run, &instance).unwrap();
+
+        // Rank 2 dies once it returned its first chunk
+        let conditions = NetworkConditions {
+            failure: Some((2, 1)),
+            ..NetworkConditions::default()
+        };
+        let (distributed, evaluated) = distribute(&run, &instance, 3, &conditions);
+
+        assert_same_run(&local, &distributed);
+        assert_eq!(evaluated[1], None);
+        assert!(evaluated[0].is_some_and(|chunks| chunks > 1));
+    }
+
+    #[test]
+    fn workers_stop_when_told() {
+        let instance = builtin::instance("wi29").unwrap();
+        let evaluated = thread::scope(|scope| {
+            let mut transports = MockTransport::network(4).into_iter();
+            let root = transports.next().unwrap();
+            let workers = transports
+                .map(|transport| scope.spawn(move || work(&transport, false, true)))
+                .collect::<Vec<_>>();
+
+            broadcast_map(&root, &instance.matrix(), None, None);
+            terminate_workers(&root);
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(evaluated, [0, 0, 0]);
+    }
+}
@@ -0,0 +1,403 @@
+use crate::budget::{BudgetCoordinator, BudgetDecision, BudgetReport, IslandBudget};
+use crate::cli::RunArgs;
+use crate::comm::{self, Payload};
+use crate::distributed::ROOT_PROCESS;
+use crate::driver;
+use crate::initialization;
+use crate::islands::{receive_migrants, select_migrants, MigrationPolicy};
+use crate::organism::Organism;
+use crate::runner::{PopulationSolver, RunParameters, Solver};
+use crate::scheduler::{self, RunSummary};
+use crate::statistics::{FitnessSummary, RunHistory};
+use crate::termination::StopReason;
+use crate::topology::{self, Topology};
+use crate::transport::Transport;
+use crate::tsp::packed::{self, PackedTour};
+use crate::tsp::{TspInstance, TspProblem, TspSolution, TSP};
+use log::{info, warn};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+
+// The island model over MPI: every rank evolves its own share of the
+// population, the root included, and every `--migration-interval` generations
+// sends migrants to the next rank of the ring. Only migrants and the final
+// reports go through the network, never whole populations. Under an
+// evaluation budget the root also coordinates it, every rank reporting to it
+// at each migration.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum IslandMessage {
+    // Seed of the run, of the first populations, of the breeding and of the
+    // topology, from the root so every rank draws from the same ones
+    Seeds(u64, u64, u64, u64),
+    // Copies of the migrants, with the fitness they had where they come from
+    Migrants(Vec<(f32, PackedTour)>),
+    Budget(BudgetReport),
+    Decision(BudgetDecision),
+    Report(IslandReport),
+}
+
+impl Payload for IslandMessage {
+    fn kind(&self) -> &'static str {
+        match self {
+            IslandMessage::Seeds(..) => "Seeds",
+            IslandMessage::Migrants(_) => "Migrants",
+            IslandMessage::Budget(_) => "BudgetReport",
+            IslandMessage::Decision(_) => "BudgetDecision",
+            IslandMessage::Report(_) => "IslandReport",
+        }
+    }
+}
+
+// How the island of a rank ended
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IslandReport {
+    // Statistics of the last generation the island evaluated
+    pub summary: FitnessSummary,
+    pub best: Option<(f32, TspSolution)>,
+    pub migrations: usize,
+    pub duplicates: usize,
+    pub quarantined: usize,
+    // Evaluations of the generations the island bred
+    pub evaluations: u64,
+}
+
+// Every rank is an island of `--pop-size` / ranks individuals. With the same
+// seed the islands are the ones `--islands` with as many islands evolves on
+// threads, but for the broadcast of the best individual which only threads
+// have. Only the root returns a summary.
+pub fn evolve<T: Transport>(
+    transport: &T,
+    run: &RunArgs,
+    instance: &TspInstance,
+) -> Option<RunSummary> {
+    let started = Instant::now();
+    let rank = transport.rank();
+    let size = transport.size();
+    let graph_weights = instance.matrix();
+    let parameters = RunParameters {
+        population_size: run.pop_size / size as usize,
+        ..run.run_parameters()
+    };
+    let policy = run.migration_policy();
+    if rank == ROOT_PROCESS && policy.broadcast_best {
+        warn!("Islands on MPI ranks don't broadcast the best individual");
+    }
+
+    let mut seeds = IslandMessage::Seeds(0, 0, 0, 0);
+    if rank == ROOT_PROCESS {
+        let seed = driver::run_seed(run);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let island_seed = run.island_seed.unwrap_or_else(|| rng.gen());
+        let breeding_seed = rng.gen();
+        seeds = IslandMessage::Seeds(seed, island_seed, breeding_seed, rng.gen());
+    }
+    comm::broadcast(transport, ROOT_PROCESS, &mut seeds)
+        .unwrap_or_else(|error| panic!("Rank {} failed to receive the seeds: {}", rank, error));
+    let IslandMessage::Seeds(seed, island_seed, breeding_seed, topology_seed) = seeds else {
+        panic!(
+            "Rank {} received {} instead of the seeds",
+            rank,
+            seeds.kind()
+        );
+    };
+
+    let inits = initialization::island_inits(&run.island_init, island_seed, size as usize);
+    let init = inits[rank as usize];
+    if rank == ROOT_PROCESS {
+        info!(
+            "Running {} islands of {} individuals on as many ranks, migrating {} every {} generations, seeds from {}",
+            size, parameters.population_size, policy.migrants, policy.interval, island_seed
+        );
+    }
+
+    let mut rng = StdRng::seed_from_u64(breeding_seed.wrapping_add(rank as u64));
+    let problem = Arc::new(driver::run_problem(run, &graph_weights, instance));
+    let population = driver::island_population(&problem, parameters.population_size, init);
+    let solver = PopulationSolver::new(population, parameters)
+        .with_selection(run.selection_operator())
+        .with_seed(rng.gen());
+    let topology = run.migration_topology(topology_seed);
+    let budget = run.budget_policy().map(|budget| {
+        let coordinator = (rank == ROOT_PROCESS)
+            .then(|| BudgetCoordinator::new(budget.evaluations, size as usize, budget.stagnation));
+        (
+            IslandBudget::new(budget, size as usize, rank as usize),
+            coordinator,
+        )
+    });
+    let report = evolve_island(transport, solver, &problem, policy, &*topology, budget, rng);
+
+    if rank != ROOT_PROCESS {
+        send(transport, ROOT_PROCESS, &IslandMessage::Report(report));
+        return None;
+    }
+
+    let mut reports = vec![report];
+    for source in 1..size {
+        match comm::receive(transport, source) {
+            Ok(IslandMessage::Report(report)) => reports.push(report),
+            Ok(other) => panic!(
+                "Rank {} sent {} instead of its report",
+                source,
+                other.kind()
+            ),
+            Err(error) => panic!("Failed to receive the report of an island, {}", error),
+        }
+    }
+
+    let mut summary = FitnessSummary::new();
+    for (island, report) in reports.iter().enumerate() {
+        let init = inits[island];
+        println!(
+            "Island {} ({} from seed {}), best: {}, mean: {:.3}, migrations: {}, duplicates dropped: {}, quarantined: {}, evaluations: {}",
+            island,
+            init.strategy,
+            init.seed,
+            report.summary.best,
+            report.summary.mean(),
+            report.migrations,
+            report.duplicates,
+            report.quarantined,
+            report.evaluations
+        );
+        summary.merge(&report.summary);
+    }
+    let best = reports
+        .iter()
+        .filter_map(|report| report.best.as_ref())
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+    if let Some((fitness, best)) = best {
+        println!("Best one: {:?} -> {:?}", fitness, best);
+    }
+
+    Some(RunSummary {
+        job: scheduler::detect(),
+        problem: run.problem.clone(),
+        instance: run.instance.clone(),
+        crossover: run.crossover.to_string(),
+        mutation: run.mutation.to_string(),
+        processes: size,
+        seed,
+        generations: parameters.iterations,
+        generations_completed: parameters.iterations,
+        stopped_by_deadline: false,
+        stop_reason: StopReason::MaxGenerations,
+        elapsed_seconds: started.elapsed().as_secs_f64(),
+        best: summary.best,
+        mean: (summary.valid() > 0).then(|| summary.mean()),
+        invalid: summary.invalid,
+        tag: run.tag.clone(),
+        traffic: transport.traffic().map(|traffic| traffic.stats()),
+        history: RunHistory::new(),
+        best_tour: best.map(|(_, best)| best.clone()),
+    })
+}
+
+// `budget` is the share of the rank and, on the root, the coordinator
+fn evolve_island<T: Transport>(
+    transport: &T,
+    mut solver: PopulationSolver<TSP>,
+    problem: &Arc<TspProblem>,
+    policy: MigrationPolicy,
+    topology: &dyn Topology,
+    mut budget: Option<(IslandBudget, Option<BudgetCoordinator>)>,
+    mut rng: StdRng,
+) -> IslandReport {
+    let rank = transport.rank();
+    let generation_cost = solver.parameters().population_size as u64;
+    let mut summary = FitnessSummary::new();
+    let mut migrations = 0;
+    let mut duplicates = 0;
+    let mut quarantined = 0;
+
+    for generation in 0..solver.parameters().iterations {
+        // A rank out of budget still takes part in the migrations
+        if budget
+            .as_mut()
+            .is_none_or(|(budget, _)| budget.spend(generation_cost))
+        {
+            summary = solver.step();
+            info!(
+                "Island {} generation {}, best: {}, mean: {:.3}",
+                rank,
+                generation,
+                summary.best,
+                summary.mean()
+            );
+        }
+
+        if policy.migrates_after(generation) {
+            if let Some((budget, coordinator)) = budget.as_mut() {
+                let best = solver
+                    .population()
+                    .iter()
+                    .map(|individual| individual.fitness())
+                    .min_by(|a, b| a.total_cmp(b))
+                    .unwrap_or(f32::INFINITY);
+                let decision = settle_budget(transport, coordinator.as_mut(), budget.report(best));
+                if decision == BudgetDecision::Terminate && !budget.is_terminated() {
+                    info!(
+                        "Island {} stops breeding after {} evaluations",
+                        rank,
+                        budget.evaluations()
+                    );
+                }
+                budget.apply(decision);
+            }
+            let migrants = select_migrants(
+                solver.population(),
+                policy.migrants,
+                policy.selection,
+                &mut rng,
+            )
+            .into_iter()
+            .map(|migrant| (migrant.fitness(), migrant.get_solution().clone()))
+            .collect::<Vec<_>>();
+            let arrived = exchange(transport, topology, migrations, migrants)
+                .into_iter()
+                // Arriving migrants join the problem of this island
+                .map(|(fitness, solution)| (fitness, TSP::repaired(problem.clone(), solution)))
+                .collect();
+
+            let reception = receive_migrants(solver.population_mut(), arrived, &policy, &mut rng);
+            duplicates += reception.duplicates;
+            for (reason, migrant) in reception.refused.iter() {
+                warn!(
+                    "Island {} quarantined {:?} after generation {}: {}",
+                    rank,
+                    migrant.get_solution(),
+                    generation,
+                    reason
+                );
+            }
+            quarantined += reception.refused.len();
+            migrations += 1;
+        }
+    }
+
+    let best = solver
+        .population()
+        .iter()
+        .map(|individual| (individual.fitness(), individual))
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(fitness, individual)| (fitness, individual.get_solution().clone()));
+    IslandReport {
+        summary,
+        best,
+        migrations,
+        duplicates,
+        quarantined,
+        evaluations: budget.map_or(
+            generation_cost * solver.parameters().iterations as u64,
+            |(budget, _)| budget.evaluations(),
+        ),
+    }
+}
+
+// Every rank reports to the root, which settles the reports in the order of
+// the ranks, its own first, and answers each with its decision
+fn settle_budget<T: Transport>(
+    transport: &T,
+    coordinator: Option<&mut BudgetCoordinator>,
+    report: BudgetReport,
+) -> BudgetDecision {
+    let Some(coordinator) = coordinator else {
+        send(transport, ROOT_PROCESS, &IslandMessage::Budget(report));
+        return match comm::receive(transport, ROOT_PROCESS) {
+            Ok(IslandMessage::Decision(decision)) => decision,
+            Ok(other) => panic!(
+                "Rank {} received {} instead of its budget",
+                transport.rank(),
+                other.kind()
+            ),
+            Err(error) => panic!("Rank {} lost its budget, {}", transport.rank(), error),
+        };
+    };
+
+    let decision = coordinator.report(report);
+    for source in 1..transport.size() {
+        let report = match comm::receive(transport, source) {
+            Ok(IslandMessage::Budget(report)) => report,
+            Ok(other) => panic!(
+                "Rank {} sent {} instead of its budget report",
+                source,
+                other.kind()
+            ),
+            Err(error) => panic!(
+                "Failed to receive the budget report of an island, {}",
+                error
+            ),
+        };
+        let decision = coordinator.report(report);
+        send(transport, source, &IslandMessage::Decision(decision));
+    }
+    decision
+}
+
+// Sends the migrants to the ranks the topology names and returns those of
+// the ranks sending here, in the order of the ranks like the thread islands.
+// Every rank goes through the links in the same order, so blocking sends of
+// large messages can't wait on each other.
+fn exchange<T: Transport>(
+    transport: &T,
+    topology: &dyn Topology,
+    migration: usize,
+    migrants: Vec<(f32, TspSolution)>,
+) -> Vec<(f32, TspSolution)> {
+    let rank = transport.rank() as usize;
+    let size = transport.size() as usize;
+
+    let mut arrived = Vec::new();
+    for (source, target) in topology::links(topology, size, migration) {
+        if source == rank && target == rank {
+            arrived.push((source, migrants.clone()));
+        } else if source == rank {
+            send(
+                transport,
+                target as i32,
+                &IslandMessage::Migrants(packed::pack_evaluated(&migrants)),
+            );
+        } else if target == rank {
+            arrived.push((source, receive(transport, source as i32)));
+        }
+    }
+    arrived.sort_by_key(|(source, _)| *source);
+    arrived
+        .into_iter()
+        .flat_map(|(_, migrants)| migrants)
+        .collect()
+}
+
+fn send<T: Transport>(transport: &T, destination: i32, message: &IslandMessage) {
+    comm::send(transport, destination, message).unwrap_or_else(|error| {
+        panic!(
+            "Failed to send {} to rank {}: {}",
+            message.kind(),
+            destination,
+            error
+        )
+    });
+}
+
+// A lost or corrupted batch of migrants only costs a migration
+fn receive<T: Transport>(transport: &T, source: i32) -> Vec<(f32, TspSolution)> {
+    match comm::receive(transport, source) {
+        Ok(IslandMessage::Migrants(migrants)) => packed::unpack_evaluated(migrants),
+        Ok(other) => {
+            warn!(
+                "Rank {} expected migrants from rank {}, got {}",
+                transport.rank(),
+                source,
+                other.kind()
+            );
+            Vec::new()
+        }
+        Err(error) => {
+            warn!("Rank {} dropped the migrants, {}", transport.rank(), error);
+            Vec::new()
+        }
+    }
+}
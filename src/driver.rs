@@ -0,0 +1,1797 @@
+use crate::backend::BackendKind;
+use crate::checkpoint::{Checkpoint, CheckpointParameters};
+use crate::cli::RunArgs;
+use crate::clustering;
+#[cfg(feature = "database")]
+use crate::database::{ResultDatabase, RunRecord};
+use crate::distributed::WorkerStats;
+use crate::edge_model::EdgeModel;
+use crate::edge_statistics::EdgeFrequencies;
+use crate::initialization::{self, diverse_population, DiversityOptions, InitStrategy, IslandInit};
+use crate::islands;
+use crate::journal::{JournalEvent, JournalWriter, Origin};
+use crate::local_search::{LocalSearchKind, MemeticPolicy, PolishBudget};
+use crate::memory::{self, Bytes};
+use crate::operator_schedule::Operators;
+use crate::organism::{Organism, Problem};
+use crate::parallel::prelude::*;
+use crate::permutation::{self, MutationMix, MutationOperator};
+use crate::portfolio::{InstanceFeatures, Portfolio};
+use crate::registry::{self, ComponentKind};
+use crate::robustness;
+use crate::runner::{self, PopulationSolver, RunParameters};
+use crate::scheduler::{self, JobInfo, RunSummary};
+use crate::selection::SelectionKind;
+use crate::statistics::{FitnessSummary, GenerationStats, RunHistory};
+use crate::streaming::stream_evaluate;
+use crate::termination::{Progress, StopReason, Termination};
+use crate::tsp::clustered::{self, ClusteredProblem, ClusteredTsp};
+use crate::tsp::constraints;
+use crate::tsp::matrix::WeightMatrix;
+use crate::tsp::scenarios::Scenarios;
+use crate::tsp::{builtin, explain, loader, overrides, TspInstance, TspProblem, TspSolution, TSP};
+use itertools::Itertools;
+use log::{error, info, warn};
+use rand::distributions::uniform::UniformSampler;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Defaults of the run parameters, see `RunArgs`
+pub const ITERATIONS: usize = 50;
+pub const POPULATION_SIZE: usize = 10000;
+pub const ELITE: usize = 20;
+pub const MUTATION_RATE: f32 = 0.1;
+pub const CROSSOVER_RATE: f32 = 0.9;
+const MIN_INITIAL_DISTANCE: f32 = 1.0;
+// Name of the clustered TSP for `--problem`, see `tsp::clustered`
+pub const CLUSTERED_PROBLEM: &str = "clustered-tsp";
+// Nearest neighbors kept per city for the distance aware crossovers
+const CANDIDATES: usize = 10;
+// Greedy tours an island initialized greedily starts with, and the noise on
+// the edge weights telling them apart
+const GREEDY_TOURS: usize = 32;
+const GREEDY_NOISE: f32 = 0.1;
+const CLUSTERS: usize = 5;
+const CLUSTERING_SAMPLE: usize = 500;
+
+// The evaluated individuals, their statistics, the largest resident set of a
+// worker and the statistics of every worker that sent some, by rank
+pub type EvaluatedPopulation = (
+    Vec<(f32, TspSolution)>,
+    FitnessSummary,
+    Option<u64>,
+    Vec<(i32, WorkerStats)>,
+);
+
+// Process wide set up every binary does before looking at its arguments
+pub fn setup(run: &RunArgs) {
+    // A single worker that is the main thread itself turns every par_iter
+    // into a plain loop on this thread
+    #[cfg(feature = "parallel")]
+    if run.sequential {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .use_current_thread()
+            .build_global()
+            .expect("Failed to set up sequential execution");
+    }
+
+    #[cfg(feature = "plugins")]
+    for path in run.plugin.iter() {
+        if let Err(error) = crate::plugin::load_plugin(path) {
+            eprintln!("{}: {}", path.display(), error);
+            std::process::exit(1);
+        }
+    }
+
+    #[cfg(feature = "scripting")]
+    for path in run.script.iter() {
+        if let Err(error) = crate::scripting::load_script(path) {
+            eprintln!("{}: {}", path.display(), error);
+            std::process::exit(1);
+        }
+    }
+
+    #[cfg(not(any(feature = "parallel", feature = "plugins", feature = "scripting")))]
+    let _ = run;
+}
+
+// The thread island model, every island breeds its share of the population
+// with the plain generational GA
+pub fn evolve_islands(run: &RunArgs, instance: &TspInstance) -> RunSummary {
+    let started = Instant::now();
+    let graph_weights = instance.matrix();
+    let parameters = island_parameters(run);
+    let policy = run.migration_policy();
+    let seed = run_seed(run);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let island_seed = run.island_seed.unwrap_or_else(|| rng.gen());
+    let inits = initialization::island_inits(&run.island_init, island_seed, run.islands);
+    info!(
+        "Running {} islands of {} individuals, migrating {} every {} generations, seeds from {}",
+        run.islands, parameters.population_size, policy.migrants, policy.interval, island_seed
+    );
+
+    let selection = run.selection_operator();
+    let breeding_seed = rng.gen();
+    let topology = run.migration_topology(rng.gen());
+    let problem = Arc::new(run_problem(run, &graph_weights, instance));
+    let outcomes = islands::run_thread_islands(
+        run.islands,
+        parameters,
+        selection,
+        policy,
+        topology,
+        run.budget_policy(),
+        breeding_seed,
+        |island| island_population(&problem, parameters.population_size, inits[island]),
+    );
+
+    let mut summary = FitnessSummary::new();
+    for outcome in outcomes.iter() {
+        let init = inits[outcome.island];
+        println!(
+            "Island {} ({} from seed {}), best: {}, mean: {:.3}, migrations: {}, duplicates dropped: {}, quarantined: {}, evaluations: {}",
+            outcome.island,
+            init.strategy,
+            init.seed,
+            outcome.summary.best,
+            outcome.summary.mean(),
+            outcome.migrations,
+            outcome.duplicates,
+            outcome.quarantine.refused,
+            outcome.evaluations
+        );
+        for (reason, migrant) in outcome.quarantine.held.iter() {
+            println!("  quarantined {:?}: {}", migrant.get_solution(), reason);
+        }
+        summary.merge(&outcome.summary);
+    }
+    if let Some((fitness, best)) = outcomes
+        .iter()
+        .filter_map(|outcome| outcome.best.as_ref())
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+    {
+        println!("Best one: {:?} -> {:?}", fitness, best.get_solution());
+    }
+    if let Some(samples) = run.robustness {
+        let bests = outcomes
+            .iter()
+            .filter_map(|outcome| outcome.best.as_ref())
+            .map(|(fitness, best)| (*fitness, best.get_solution().clone()))
+            .sorted_by(|a, b| a.0.total_cmp(&b.0))
+            .collect::<Vec<_>>();
+        print_robustness(run, &graph_weights, &bests, samples, seed);
+    }
+
+    RunSummary {
+        job: scheduler::detect(),
+        problem: run.problem.clone(),
+        instance: run.instance.clone(),
+        crossover: run.crossover.to_string(),
+        mutation: run.mutation.to_string(),
+        processes: 1,
+        seed,
+        generations: parameters.iterations,
+        generations_completed: parameters.iterations,
+        stopped_by_deadline: false,
+        stop_reason: StopReason::MaxGenerations,
+        elapsed_seconds: started.elapsed().as_secs_f64(),
+        best: summary.best,
+        mean: (summary.valid() > 0).then(|| summary.mean()),
+        invalid: summary.invalid,
+        tag: run.tag.clone(),
+        traffic: None,
+        history: RunHistory::new(),
+        best_tour: outcomes
+            .iter()
+            .filter_map(|outcome| outcome.best.as_ref())
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, best)| best.get_solution().clone()),
+    }
+}
+
+// The checkpoint `--resume` names, exits when it can't be read or was written
+// by a run with other settings
+fn resume(path: &Path, parameters: &CheckpointParameters) -> Checkpoint {
+    let checkpoint = Checkpoint::read(path).unwrap_or_else(|error| {
+        eprintln!("Failed to read {}: {}", path.display(), error);
+        std::process::exit(1);
+    });
+
+    let differences = checkpoint.parameters.differences(parameters);
+    if !differences.is_empty() {
+        eprintln!(
+            "Can't resume {}, it was written with other settings:",
+            path.display()
+        );
+        for difference in differences.iter() {
+            eprintln!("  {}", difference);
+        }
+        std::process::exit(1);
+    }
+
+    info!(
+        "Resuming {} at generation {}, random seed {}",
+        path.display(),
+        checkpoint.generation,
+        checkpoint.seed
+    );
+    checkpoint
+}
+
+// `--seed`, or a random one that is logged so the run can still be repeated
+pub fn run_seed(run: &RunArgs) -> u64 {
+    let seed = run.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    info!("Random seed {}", seed);
+    seed
+}
+
+// The generator that breeds generation `generation` of a run seeded with
+// `seed`, independent of the generations before it
+fn generation_rng(seed: u64, generation: usize) -> StdRng {
+    StdRng::seed_from_u64(seed ^ (generation as u64 + 1).wrapping_mul(0x9e3779b97f4a7c15))
+}
+
+// The problem every individual of a run shares, with the operators it starts
+// from and the constraints, scenarios and duration of `instance`
+pub fn run_problem(
+    run: &RunArgs,
+    graph_weights: &Arc<WeightMatrix>,
+    instance: &TspInstance,
+) -> TspProblem {
+    TspProblem::new(graph_weights.clone())
+        .with_operators(run.crossover, run.mutation)
+        .with_constraints(instance.constraints.as_ref())
+        .with_closed_tour(!run.open_path)
+        .with_scenarios(instance.scenarios.as_ref())
+        .with_duration(instance.duration.as_ref())
+}
+
+// The first population of an island, the same for the same seed, every
+// individual sharing `problem`
+pub fn island_population(problem: &Arc<TspProblem>, size: usize, init: IslandInit) -> Vec<TSP> {
+    let mut rng = StdRng::seed_from_u64(init.seed);
+    let mut population = match init.strategy {
+        InitStrategy::Random => Vec::new(),
+        InitStrategy::NearestNeighbor => {
+            let mut starts = (0..problem.graph_weights.len()).collect::<Vec<usize>>();
+            starts.shuffle(&mut rng);
+            starts
+                .into_iter()
+                .take(size)
+                .map(|start| TSP::new_nearest_neighbor(problem, start))
+                .collect()
+        }
+        InitStrategy::Greedy => (0..GREEDY_TOURS.min(size))
+            .map(|tour| {
+                let noise = if tour == 0 { 0.0 } else { GREEDY_NOISE };
+                TSP::new_greedy(problem, noise, &mut rng)
+            })
+            .collect(),
+    };
+    while population.len() < size {
+        population.push(TSP::new_shuffled(problem, &mut rng));
+    }
+
+    population
+}
+
+// `count` tours, nearest neighbor tours from distinct random start cities for
+// half of them and greedy edge tours for the others, the first greedy one
+// without noise. Fewer when there are fewer cities than nearest neighbor tours.
+fn heuristic_tours(problem: &Arc<TspProblem>, count: usize, rng: &mut StdRng) -> Vec<TSP> {
+    let mut starts = (0..problem.graph_weights.len()).collect::<Vec<usize>>();
+    starts.shuffle(rng);
+    let mut tours = starts
+        .into_iter()
+        .take(count / 2)
+        .map(|start| TSP::new_nearest_neighbor(problem, start))
+        .collect::<Vec<TSP>>();
+    tours.extend((0..count - count / 2).map(|tour| {
+        let noise = if tour == 0 { 0.0 } else { GREEDY_NOISE };
+        TSP::new_greedy(problem, noise, rng)
+    }));
+    tours
+}
+
+fn island_parameters(run: &RunArgs) -> RunParameters {
+    RunParameters {
+        population_size: run.pop_size / run.islands.max(1),
+        ..run.run_parameters()
+    }
+}
+
+// Everything that would make the run fail, all ranks agree on it since they
+// are started with the same arguments
+pub fn check_configuration(run: &RunArgs, instance: &TspInstance) -> Vec<String> {
+    let parameters = run.run_parameters();
+    let mut problems = parameters.problems();
+
+    if run.islands == 0 {
+        problems.push("there must be at least 1 island".to_string());
+    } else if run.islands > 1 {
+        problems.extend(
+            island_parameters(run)
+                .problems()
+                .into_iter()
+                .map(|problem| format!("every island: {}", problem)),
+        );
+    }
+    // An island breeds generation after generation of the plain GA until the
+    // last, in step with the others
+    if BackendKind::for_run(run).runs_islands() {
+        if run.time_limit.is_some() {
+            problems.push("islands run every generation, they take no time limit".to_string());
+        }
+        if run.memetic != MemeticPolicy::Off {
+            problems.push("islands don't polish, the memetic policy must be off".to_string());
+        }
+        if !run.phases.is_empty() {
+            problems.push("islands breed with the same operators, they take no phases".to_string());
+        }
+        if run.stagnation.is_some() || run.target_fitness.is_some() || run.stop.is_some() {
+            problems.push(
+                "islands stop after the last generation, they take no stagnation, target fitness or stop rule"
+                    .to_string(),
+            );
+        }
+        if run.stats_out.is_some() {
+            problems.push(
+                "islands collect no generation statistics, they take no stats out".to_string(),
+            );
+        }
+        if run.checkpoint.is_some() || run.resume.is_some() || run.journal.is_some() {
+            problems.push(
+                "islands write no checkpoint or journal, they take no checkpoint, resume or journal"
+                    .to_string(),
+            );
+        }
+        if run.eda_interval.is_some() || run.seed_samples.is_some() {
+            problems.push(
+                "islands initialize and breed on their own, they take no eda interval or seed samples"
+                    .to_string(),
+            );
+        }
+    } else if run.evaluation_budget.is_some() {
+        problems
+            .push("the evaluation budget is shared between islands, it needs islands".to_string());
+    }
+    if run.evaluation_budget == Some(0) {
+        problems.push("the evaluation budget must be at least 1".to_string());
+    }
+
+    if registry::components_of(ComponentKind::Problem)
+        .iter()
+        .all(|component| component.name != run.problem)
+    {
+        problems.push(format!("unknown problem {}", run.problem));
+    }
+
+    if run.problem == CLUSTERED_PROBLEM {
+        if let Err(error) = clustered_problem(run, instance) {
+            problems.push(error);
+        }
+        if instance.constraints.is_some() {
+            problems.push(format!("{} takes no constraints", CLUSTERED_PROBLEM));
+        }
+        if instance.scenarios.is_some() {
+            problems.push(format!("{} takes no scenarios", CLUSTERED_PROBLEM));
+        }
+        if instance.duration.is_some() {
+            problems.push(format!("{} takes no route duration", CLUSTERED_PROBLEM));
+        }
+    }
+    if instance.scenarios.is_some() && run.batch.is_some() {
+        problems.push("a batch can't be measured in scenarios".to_string());
+    }
+    if run.problem == "tsp" || run.problem == CLUSTERED_PROBLEM {
+        let nodes = instance.graph_weights.len();
+        // A single city is a tour of its own, of length 0
+        if nodes == 0 {
+            problems.push(format!("instance {} has no cities", instance.name));
+        }
+        if instance.graph_weights.iter().any(|row| row.len() != nodes) {
+            problems.push(format!(
+                "instance {} has a non square matrix",
+                instance.name
+            ));
+        }
+    }
+
+    if run.time_limit == Some(0) {
+        problems.push("time limit must be at least 1 second".to_string());
+    }
+    if run.stagnation == Some(0) {
+        problems.push("stagnation must be at least 1 generation".to_string());
+    }
+    if run.worker_timeout == Some(0) {
+        problems.push("worker timeout must be at least 1 second".to_string());
+    }
+    if run.memetic != MemeticPolicy::Off && run.polish_passes == 0 {
+        problems.push("2-opt must sweep a polished tour at least once".to_string());
+    }
+    if let Some(fraction) = run.heuristic_fraction {
+        if !(0.0..=1.0).contains(&fraction) {
+            problems.push("heuristic fraction must be between 0 and 1".to_string());
+        }
+    }
+    if run.robustness == Some(0) {
+        problems.push("robustness needs at least 1 perturbed sample".to_string());
+    }
+    if !run.robustness_scale.is_finite() || run.robustness_scale < 0.0 {
+        problems.push("robustness scale must be a non negative number".to_string());
+    }
+    if run.robustness.is_some() && run.robustness_tours == 0 {
+        problems.push("robustness must compare at least 1 tour".to_string());
+    }
+    if run.eda_interval == Some(0) {
+        problems.push("edge model interval must be at least 1 generation".to_string());
+    }
+    if run.eda_interval.is_some() && run.eda_elite == 0 {
+        problems.push("the edge model must learn from at least 1 tour".to_string());
+    }
+    if run.chunks_per_worker == 0 {
+        problems.push("chunks per worker must be at least 1".to_string());
+    }
+    if run.checkpoint.is_some() && run.checkpoint_every == 0 {
+        problems.push("checkpoint interval must be at least 1 generation".to_string());
+    }
+    if run.batch.is_some() && (run.checkpoint.is_some() || run.resume.is_some()) {
+        problems.push("a batch can't be checkpointed or resumed".to_string());
+    }
+    if !(0.0..=1.0).contains(&run.warm_start_threshold) {
+        problems.push("warm start threshold must be between 0 and 1".to_string());
+    }
+    if let Some(tag) = &run.tag {
+        // It names directories, keep it to one plain path component
+        let plain = tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if tag.is_empty() || !plain || tag.starts_with('.') {
+            problems.push(format!(
+                "tag {:?} must be letters, digits, '-', '_' or '.', not starting with '.'",
+                tag
+            ));
+        }
+    }
+
+    problems
+}
+
+// `--check`, only the process that prints reports the problems
+pub fn check(run: &RunArgs, instance: &TspInstance, processes: i32, print: bool) -> bool {
+    let problems = check_configuration(run, instance);
+    if print {
+        print_configuration(run, instance, processes);
+        for problem in problems.iter() {
+            eprintln!("error: {}", problem);
+        }
+        if problems.is_empty() {
+            println!("Configuration is valid");
+        }
+    }
+
+    problems.is_empty()
+}
+
+pub fn print_configuration(run: &RunArgs, instance: &TspInstance, size: i32) {
+    let parameters = run.run_parameters();
+
+    println!("Effective configuration:");
+    println!("  problem:          {}", run.problem);
+    println!(
+        "  instance:         {} ({} cities)",
+        instance.name,
+        instance.graph_weights.len()
+    );
+    if run.open_path {
+        println!("  objective:        open path");
+    } else {
+        println!("  objective:        closed tour");
+    }
+    if let Some(scenarios) = &instance.scenarios {
+        println!("  scenarios:        {}", scenarios);
+    }
+    if let Some(path) = &run.edge_overrides {
+        let forbidden = instance
+            .graph_weights
+            .iter()
+            .flatten()
+            .filter(|weight| weight.is_infinite())
+            .count();
+        println!(
+            "  edge overrides:   {} ({} one way edges forbidden)",
+            path.display(),
+            forbidden
+        );
+    }
+    if let Some(constraints) = &instance.constraints {
+        println!("  constraints:      {}", constraints);
+    }
+    if let Some(duration) = &instance.duration {
+        println!("  route duration:   {}", duration);
+    }
+    if run.problem == CLUSTERED_PROBLEM {
+        if let Ok(problem) = clustered_problem(run, instance) {
+            println!("  groups:           {}", problem.groups.len());
+        }
+    }
+    println!("  processes:        {}", size);
+    if size > 1 {
+        println!(
+            "  chunks:           {} per worker, {} scheduling",
+            run.chunks_per_worker, run.chunk_scheduling
+        );
+        if let Some(timeout) = run.worker_timeout {
+            println!("  worker timeout:   {} s", timeout);
+        }
+        #[cfg(feature = "compression")]
+        if run.compress {
+            println!(
+                "  compression:      lz4, payloads of {} and more",
+                Bytes(crate::comm::COMPRESS_MIN_BYTES as u64)
+            );
+        }
+    }
+    println!("  iterations:       {}", parameters.iterations);
+    println!("  population size:  {}", parameters.population_size);
+    println!("  elite:            {}", parameters.elite);
+    println!("  mutation rate:    {}", parameters.mutation_rate);
+    println!("  crossover rate:   {}", parameters.crossover_rate);
+    match run.selection {
+        SelectionKind::Tournament => {
+            println!("  selection:        tournament of {}", run.tournament_size)
+        }
+        selection => println!("  selection:        {}", selection),
+    }
+    println!("  crossover:        {}", run.crossover);
+    println!("  mutation:         {}", run.mutation);
+    if run.mutation == MutationOperator::Composite {
+        println!("  mutation weights: {}", run.mutation_weights);
+    }
+    if let Some(path) = &run.portfolio {
+        println!("  portfolio:        {}", path.display());
+    }
+    println!(
+        "  seed:             {}",
+        run.seed
+            .map_or_else(|| "random".to_string(), |seed| seed.to_string())
+    );
+    for (start, end, operators) in run.phases.describe(base_operators(run)) {
+        println!(
+            "  phase {:>3}%-{:>3}%:  {}",
+            (start * 100.0).round(),
+            (end * 100.0).round(),
+            operators
+        );
+    }
+    if run.islands > 1 {
+        println!(
+            "  islands:          {} of {} individuals",
+            run.islands,
+            island_parameters(run).population_size
+        );
+        println!(
+            "  migration:        {} {} migrants every {} generations over a {} topology, {} replacing {}",
+            run.migrants,
+            run.migrant_selection,
+            run.migration_interval,
+            run.topology,
+            run.migration_mode,
+            run.migrant_acceptance
+        );
+        if run.broadcast_best {
+            println!("  broadcast:        the best individual to every island at each migration");
+        }
+        println!(
+            "  initialization:   {} from seed {}",
+            run.island_init.iter().join(", "),
+            run.island_seed
+                .map_or_else(|| "random".to_string(), |seed| seed.to_string())
+        );
+    } else {
+        match run.heuristic_fraction {
+            Some(fraction) => println!(
+                "  initialization:   {:.0}% nearest neighbor and greedy tours, the rest random",
+                100.0 * fraction
+            ),
+            None => println!("  initialization:   a nearest neighbor tour from every city"),
+        }
+    }
+    println!("  memetic:          {}", run.memetic);
+    if run.memetic != MemeticPolicy::Off {
+        println!("  polish passes:    {}", run.polish_passes);
+        let searches = run
+            .local_search
+            .iter()
+            .map(LocalSearchKind::to_string)
+            .collect::<Vec<_>>();
+        println!("  local search:     {}", searches.join(", "));
+        match run.polish_budget {
+            Some(budget) => println!("  polish budget:    {} per generation", budget),
+            None => println!("  polish budget:    unlimited"),
+        }
+    }
+    if let Some(interval) = run.eda_interval {
+        println!(
+            "  edge model:       {} offspring sampled from the best {} every {} generations",
+            run.eda_samples, run.eda_elite, interval
+        );
+    }
+    if let Some(samples) = run.robustness {
+        println!(
+            "  robustness:       best {} tours under {} {} perturbations of scale {}",
+            run.robustness_tours, samples, run.robustness_noise, run.robustness_scale
+        );
+    }
+    println!("  sequential:       {}", run.sequential);
+    if let (Some(similarity), Some(_)) = (run.warm_start, &run.batch) {
+        println!(
+            "  warm start:       from instances at least {} {} similar",
+            run.warm_start_threshold, similarity
+        );
+    }
+    if let Some(tag) = &run.tag {
+        println!("  tag:              {}", tag);
+    }
+    println!("  log level:        {}", run.log_level);
+    match run.time_limit {
+        Some(seconds) => println!("  time limit:       {} seconds", seconds),
+        None => println!("  time limit:       none"),
+    }
+    if let Some(target) = run.target_fitness {
+        println!("  target fitness:   {}", target);
+    }
+    if let Some(generations) = run.stagnation {
+        println!("  stagnation:       {} generations", generations);
+    }
+    if let Some(rule) = &run.stop {
+        println!("  stop rule:        {}", rule);
+    }
+    if let Some(path) = &run.checkpoint {
+        println!(
+            "  checkpoint:       {} every {} generations",
+            path.display(),
+            run.checkpoint_every
+        );
+    }
+    if let Some(path) = &run.resume {
+        println!("  resume:           {}", path.display());
+    }
+
+    let estimate = memory::estimate_tsp(
+        parameters.population_size,
+        instance.graph_weights.len(),
+        size as usize - 1,
+    );
+    println!("Estimated memory:");
+    println!("  distance matrix:  {}", Bytes(estimate.distance_matrix));
+    println!("  root:             {}", Bytes(estimate.root()));
+    if size > 1 {
+        println!("  each worker:      {}", Bytes(estimate.worker()));
+    }
+    if let Some(available) = memory::available_per_rank() {
+        println!("  available/rank:   {}", Bytes(available));
+    }
+}
+
+// The groups of `--problem clustered-tsp` on `instance`
+pub fn clustered_problem(
+    run: &RunArgs,
+    instance: &TspInstance,
+) -> Result<ClusteredProblem, String> {
+    let Some(path) = beside_instance(run.groups.as_ref(), &run.instance, "groups") else {
+        return Err(format!(
+            "{} needs --groups or a .groups file next to the instance",
+            CLUSTERED_PROBLEM
+        ));
+    };
+    clustered::read(&path)
+        .and_then(|groups| ClusteredProblem::new(instance.matrix(), groups))
+        .map(|problem| {
+            problem
+                .with_operators(run.crossover, run.mutation)
+                .with_closed_tour(!run.open_path)
+        })
+        .map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+// Clustered TSP on the root alone, with the plain generational GA
+pub fn run_clustered(run: &RunArgs, instance: &TspInstance) {
+    let problem = match clustered_problem(run, instance) {
+        Ok(problem) => Arc::new(problem),
+        Err(error) => {
+            eprintln!("{}", error);
+            return;
+        }
+    };
+    info!(
+        "{} cities of {} in {} groups",
+        instance.graph_weights.len(),
+        instance.name,
+        problem.groups.len()
+    );
+
+    let mut rng = StdRng::seed_from_u64(run_seed(run));
+    let parameters = run.run_parameters();
+    let population = (0..parameters.population_size)
+        .map(|_| ClusteredTsp::new_shuffled(problem.clone(), &mut rng))
+        .collect();
+    let mut solver = PopulationSolver::new(population, parameters)
+        .with_selection(run.selection_operator())
+        .with_seed(rng.gen());
+    runner::run(&mut solver, parameters.iterations);
+}
+
+pub fn run_registered_problem(run: &RunArgs) {
+    let name = &run.problem;
+    let parameters = run.run_parameters();
+
+    match registry::create_problem(name, &parameters) {
+        Some(mut solver) => runner::run(solver.as_mut(), parameters.iterations),
+        None => eprintln!(
+            "Unknown problem {}, available problems: {}",
+            name,
+            registry::components_of(ComponentKind::Problem)
+                .iter()
+                .map(|component| component.name.as_str())
+                .join(", ")
+        ),
+    }
+}
+
+// The generation loop of the TSP, `processes` is only reported. Writes the
+// journal, edge statistics and database record that were asked for and
+// returns the summary of the run, see `write_summary`.
+pub fn evolve<E>(
+    run: &RunArgs,
+    instance: &TspInstance,
+    processes: i32,
+    mut evaluate: E,
+) -> RunSummary
+where
+    E: FnMut(&[TSP], &TspProblem) -> EvaluatedPopulation,
+{
+    let started = Instant::now();
+    let job = scheduler::detect();
+    if let Some(job) = &job {
+        info!(
+            "Running as {} job {} on {}",
+            job.scheduler,
+            job.job_id,
+            job.node_list.as_deref().unwrap_or("unknown nodes")
+        );
+    }
+
+    // Stop early enough that the final population still gets written
+    let time_limit = run
+        .time_limit
+        .map(Duration::from_secs)
+        .or_else(|| job.as_ref().and_then(JobInfo::wall_clock_budget));
+    if let Some(limit) = time_limit {
+        info!("Wall clock budget is {} seconds", limit.as_secs());
+    }
+    let mut termination = run.termination(time_limit);
+    let mut worker_totals = BTreeMap::new();
+    let parameters = CheckpointParameters::of(run, instance, processes);
+
+    let graph_weights = instance.matrix();
+    let first_problem = Arc::new(run_problem(run, &graph_weights, instance));
+    let (seed, mut tsp, mut generations_completed, mut history) = match &run.resume {
+        Some(path) => {
+            let checkpoint = resume(path, &parameters);
+            let tsp = checkpoint
+                .population
+                .into_iter()
+                .map(|solution| TSP::repaired(first_problem.clone(), solution))
+                .collect::<Vec<TSP>>();
+            (
+                checkpoint.seed,
+                tsp,
+                checkpoint.generation,
+                checkpoint.history,
+            )
+        }
+        None => {
+            let seed = run_seed(run);
+            let mut rng = StdRng::seed_from_u64(seed);
+            let tsp = initialize(run, &first_problem, &mut rng);
+            (seed, tsp, 0, RunHistory::new())
+        }
+    };
+    // Built once, every generation is evaluated against it
+    let map = TspProblem {
+        closed_tour: !run.open_path,
+        scenarios: instance.scenarios.clone(),
+        duration: instance.duration.clone(),
+        ..TspProblem::new(graph_weights.clone())
+    };
+
+    let edge_stats = run
+        .edge_stats
+        .as_deref()
+        .map(|directory| run.tagged(directory));
+    if let Some(directory) = &edge_stats {
+        std::fs::create_dir_all(directory).expect("Failed to create the edge stats directory");
+    }
+    let distribution = rand::distributions::uniform::UniformFloat::<f32>::new(0.0, 1.0);
+    let base = base_operators(run);
+    let selection = run.selection_operator();
+    let local_search = run.local_search_operator();
+    let mut mutation_mix = MutationMix::new(run.mutation_weights);
+    let mut phase = None;
+    let uses_distances = run
+        .phases
+        .all(base)
+        .any(|operators| operators.crossover.uses_distances());
+    let candidates = uses_distances.then(|| {
+        Arc::new(permutation::candidate_lists(
+            graph_weights.as_ref(),
+            CANDIDATES,
+        ))
+    });
+
+    let mut journal = run
+        .journal
+        .as_ref()
+        .map(|path| JournalWriter::open(path).expect("Failed to open the journal"));
+    record(
+        &mut journal,
+        JournalEvent::RunStarted {
+            instance: run.instance.clone(),
+            population_size: tsp.len() as u32,
+            elite: run.elite as u32,
+            crossover: run.crossover.to_string(),
+            mutation: run.mutation.to_string(),
+        },
+    );
+
+    // Every generation is evaluated, then bred unless a termination
+    // criterion is met, the last evaluated population is the result
+    let mut known = vec![None; tsp.len()];
+    let (eval_pop, summary, stop_reason) = loop {
+        let i = generations_completed;
+        let (eval_pop, summary, worker_peak, worker_stats) =
+            evaluate_unknown(&mut evaluate, &tsp, &known, &map);
+        merge_worker_stats(&mut worker_totals, &worker_stats);
+
+        // Sort all the populations
+        let (eval_pop, ranking) = sort_by_fitness(eval_pop);
+        let fitness = eval_pop
+            .iter()
+            .map(|(fitness, _)| *fitness)
+            .collect::<Vec<f32>>();
+        let stats = GenerationStats::from_sorted(i, &summary, &fitness);
+        history.push(stats);
+        record(
+            &mut journal,
+            JournalEvent::Selection {
+                generation: i as u32,
+                best: summary.best,
+                ranking,
+            },
+        );
+
+        let progress = Progress {
+            generations: i,
+            elapsed: started.elapsed(),
+            best: summary.best,
+        };
+        if let Some(reason) = termination.check(&progress) {
+            print_summary(&stats);
+            print_memory(worker_peak);
+            break (eval_pop, summary, reason);
+        }
+        print_worker_balance(&worker_stats);
+
+        let operators = run.phases.operators_at(i, run.iterations, base);
+        if run.phases.phase_of(i, run.iterations) != phase {
+            phase = run.phases.phase_of(i, run.iterations);
+            info!("Generation {} switches to {}", i, operators);
+            mutation_mix = mutation_mix.with_weights(operators.mutation_weights);
+        }
+
+        // Print the best ones
+
+        println!(
+            "Iteration {}, Best ones: {:?}",
+            i,
+            eval_pop
+                .iter()
+                .take(10)
+                .map(|(fit, _)| fit)
+                .collect::<Vec<_>>()
+        );
+        print_summary(&stats);
+        print_memory(worker_peak);
+
+        if let Some(directory) = &edge_stats {
+            export_edge_frequencies(directory, i, instance, &eval_pop)
+                .expect("Failed to write the edge frequencies");
+        }
+
+        // Select the best individuals to reproduce, all of them sharing the
+        // problem of this generation
+        let problem = Arc::new(TspProblem {
+            crossover: operators.crossover,
+            mutation: operators.mutation,
+            mutation_mix: mutation_mix.clone(),
+            candidates: candidates.clone(),
+            constraints: instance.constraints.clone(),
+            ..map.clone()
+        });
+        let tsp_population = eval_pop
+            .par_iter()
+            .cloned()
+            .map(|(fitness, solution)| (fitness, TSP::new(problem.clone(), solution)))
+            .collect::<Vec<(f32, TSP)>>();
+
+        // Every non elite individual makes room for a child of two parents
+        // chosen by `selection`, so the population size never changes. The
+        // crossover and mutation draws of child k come from `seed + k` so
+        // the journal can tell which operators made every child.
+        let mut rng = generation_rng(seed, i);
+        let elite = run.elite.min(tsp_population.len());
+        let pairs = selection.pairs(&fitness, elite, tsp_population.len() - elite, &mut rng);
+        let generation_seed = rng.gen::<u64>();
+        let (mut new_population, tagged): (Vec<TSP>, Vec<_>) = (0..pairs.len())
+            .into_par_iter()
+            .map(|k| {
+                let mut rng = StdRng::seed_from_u64(generation_seed.wrapping_add(k as u64));
+                let (first_parent, second_parent) = pairs[k];
+                let first = &tsp_population[first_parent].1;
+                let second = &tsp_population[second_parent].1;
+
+                let crossed_over = distribution.sample(&mut rng) < operators.crossover_rate;
+                let mut child = if crossed_over {
+                    first.cross_over(second, &mut rng)
+                } else {
+                    first.clone()
+                };
+
+                // Mutate the new_population
+                let mutated = distribution.sample(&mut rng) < operators.mutation_rate;
+                if mutated {
+                    child.mutate(&mut rng);
+                }
+
+                // A copy of its first parent keeps its fitness
+                let mut fitness =
+                    (!crossed_over && !mutated).then_some(tsp_population[first_parent].0);
+
+                // Parents are already evaluated, only the child costs a fitness
+                let wants_polish = run.memetic != MemeticPolicy::Off && {
+                    let child_fitness = *fitness.get_or_insert_with(|| child.fitness());
+                    run.memetic.polishes(
+                        child_fitness,
+                        tsp_population[first_parent].0,
+                        tsp_population[second_parent].0,
+                    )
+                };
+
+                let origin = Origin::Offspring {
+                    first_parent: first_parent as u32,
+                    second_parent: second_parent as u32,
+                    crossed_over,
+                    mutated,
+                };
+                (child, (origin, fitness, wants_polish))
+            })
+            .unzip();
+        let mut origins = Vec::with_capacity(tsp_population.len());
+        let mut next_known = Vec::with_capacity(tsp_population.len());
+        let mut polish_budget = PolishBudget::new(run.polish_budget);
+        let mut polished = Vec::with_capacity(tagged.len());
+        for (origin, known, wants_polish) in tagged {
+            origins.push(origin);
+            next_known.push(known);
+            polished.push(wants_polish && polish_budget.take());
+        }
+        new_population
+            .par_iter_mut()
+            .zip(next_known.par_iter_mut())
+            .zip(polished.par_iter())
+            .filter(|(_, polished)| **polished)
+            .for_each(|((child, known), _)| {
+                child.polish(local_search.as_ref(), run.polish_passes);
+                *known = None;
+            });
+
+        // Return the new population, including the elite. A polished elite
+        // keeps its fitness when 2-opt found nothing to shorten.
+        let elite_polished = (0..elite)
+            .map(|_| run.memetic.polishes_elites() && polish_budget.take())
+            .collect::<Vec<bool>>();
+        let (elites, elite_known): (Vec<TSP>, Vec<Option<f32>>) = tsp_population[..elite]
+            .par_iter()
+            .zip(elite_polished.par_iter())
+            .map(|((fitness, individual), polished)| {
+                let mut individual = individual.clone();
+                if *polished && individual.polish(local_search.as_ref(), run.polish_passes) != 0.0 {
+                    return (individual, None);
+                }
+                (individual, Some(*fitness))
+            })
+            .unzip();
+        new_population.extend(elites);
+        origins.extend((0..elite).map(|parent| Origin::Elite {
+            parent: parent as u32,
+        }));
+        next_known.extend(elite_known);
+        if run.memetic != MemeticPolicy::Off {
+            info!(
+                "Generation {} polished {} individuals",
+                i,
+                polish_budget.used()
+            );
+        }
+        if run
+            .eda_interval
+            .is_some_and(|interval| (i + 1).is_multiple_of(interval))
+        {
+            let sampled = sample_edge_model(run, &eval_pop, &problem, &mut rng);
+            let count = sampled.len().min(pairs.len());
+            for (k, individual) in sampled.into_iter().take(count).enumerate() {
+                new_population[k] = individual;
+                origins[k] = Origin::Sampled;
+                next_known[k] = None;
+            }
+            info!(
+                "Generation {} sampled {} offspring from the edge model of its elite",
+                i, count
+            );
+        }
+        record(
+            &mut journal,
+            JournalEvent::Variation {
+                generation: i as u32,
+                seed: generation_seed,
+                children: origins,
+            },
+        );
+
+        tsp = new_population;
+        known = next_known;
+        generations_completed += 1;
+
+        if let Some(path) = &run.checkpoint {
+            if generations_completed.is_multiple_of(run.checkpoint_every) {
+                let checkpoint = Checkpoint {
+                    parameters: parameters.clone(),
+                    seed,
+                    generation: generations_completed,
+                    population: solutions_of(&tsp),
+                    history: history.clone(),
+                };
+                match checkpoint.write(path) {
+                    Ok(()) => info!(
+                        "Checkpoint of generation {} written to {}",
+                        generations_completed,
+                        path.display()
+                    ),
+                    Err(error) => error!("Failed to write {}: {}", path.display(), error),
+                }
+            }
+        }
+    };
+    match stop_reason {
+        StopReason::MaxGenerations => {}
+        StopReason::TimeLimit => warn!(
+            "Wall clock budget reached after {} generations",
+            generations_completed
+        ),
+        reason => info!(
+            "Stopped by {} after {} generations",
+            reason, generations_completed
+        ),
+    }
+    print_worker_totals(&worker_totals);
+
+    // Print the best ones
+
+    eval_pop
+        .iter()
+        .take(10)
+        .for_each(|(fit, solution)| println!("Best ones: {:?} -> {:?}", fit, solution));
+    // The tour is a single route, its time over the limit is in the fitness
+    if let (Some(duration), Some((_, best))) = (&instance.duration, eval_pop.first()) {
+        let weights = instance.graph_weights.as_slice();
+        println!(
+            "Route duration of the best one: {}, {} over the limit",
+            duration.duration(weights, &best.path, !run.open_path),
+            duration.overtime(weights, &best.path, !run.open_path)
+        );
+    }
+
+    print_clusters(&map, &eval_pop);
+    if let Some(samples) = run.robustness {
+        print_robustness(run, &graph_weights, &eval_pop, samples, seed);
+    }
+    if run
+        .phases
+        .all(base)
+        .any(|operators| operators.mutation == MutationOperator::Composite)
+    {
+        print_mutation_statistics(&mutation_mix);
+    }
+
+    #[cfg(feature = "database")]
+    if let Some(path) = &run.database {
+        let mut record = RunRecord::from_summary(&run.problem, &run.instance, &summary);
+        record.crossover = run.crossover.to_string();
+        record.mutation = run.mutation.to_string();
+        record.mutation_rate = run.mutation_rate;
+        record.crossover_rate = run.crossover_rate;
+        record.population_size = run.pop_size;
+        record.elite = run.elite;
+        record.generations = run.iterations;
+        record.seed = Some(seed);
+        record.tag = run.tag.clone();
+        open_database(path)
+            .record(&record)
+            .expect("Failed to record the run");
+    }
+
+    RunSummary {
+        job,
+        problem: run.problem.clone(),
+        instance: run.instance.clone(),
+        crossover: run.crossover.to_string(),
+        mutation: run.mutation.to_string(),
+        processes,
+        seed,
+        generations: run.iterations,
+        generations_completed,
+        stopped_by_deadline: stop_reason == StopReason::TimeLimit,
+        stop_reason,
+        elapsed_seconds: started.elapsed().as_secs_f64(),
+        best: summary.best,
+        mean: (summary.valid() > 0).then(|| summary.mean()),
+        invalid: summary.invalid,
+        tag: run.tag.clone(),
+        traffic: None,
+        history,
+        best_tour: eval_pop.first().map(|(_, best)| best.clone()),
+    }
+}
+
+// What breeds every generation outside of the phases of `run`
+fn base_operators(run: &RunArgs) -> Operators {
+    Operators {
+        crossover: run.crossover,
+        mutation: run.mutation,
+        mutation_weights: run.mutation_weights,
+        crossover_rate: run.crossover_rate,
+        mutation_rate: run.mutation_rate,
+    }
+}
+
+// To `--summary-file`, or `summary-<job id>.json` under a scheduler
+pub fn write_summary(run: &RunArgs, run_summary: &RunSummary) {
+    let summary_file = run.summary_file.clone().or_else(|| {
+        run_summary.job.as_ref().map(|job| match &run.tag {
+            Some(tag) => PathBuf::from(format!("summary-{}-{}.json", tag, job.job_id)),
+            None => PathBuf::from(format!("summary-{}.json", job.job_id)),
+        })
+    });
+    if let Some(path) = summary_file {
+        if let Err(error) = run_summary.write_json(&path) {
+            error!("Failed to write {}: {}", path.display(), error);
+        }
+    }
+}
+
+// To `--stats-out`, in the format its extension names
+pub fn write_history(run: &RunArgs, run_summary: &RunSummary) {
+    let Some(path) = &run.stats_out else {
+        return;
+    };
+    if run_summary.history.is_empty() {
+        warn!(
+            "No generation statistics for {}, island runs don't collect them",
+            path.display()
+        );
+        return;
+    }
+
+    let json = path
+        .extension()
+        .is_some_and(|extension| extension == "json");
+    let written = File::create(path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        if json {
+            run_summary
+                .history
+                .write_json(&mut writer, run.tag.as_deref())?;
+        } else {
+            run_summary
+                .history
+                .write_csv(&mut writer, run.tag.as_deref())?;
+        }
+        writer.flush()
+    });
+    if let Err(error) = written {
+        error!("Failed to write {}: {}", path.display(), error);
+    }
+}
+
+// To `--explain`, the legs of the best tour in the format its extension names
+pub fn write_explanation(run: &RunArgs, instance: &TspInstance, run_summary: &RunSummary) {
+    let (Some(path), Some(best)) = (&run.explain, &run_summary.best_tour) else {
+        return;
+    };
+    let legs = explain::legs(instance, &best.path, !run.open_path);
+    if path.as_os_str() == "-" {
+        let written = explain::write_table(&legs, &mut io::stdout().lock());
+        if let Err(error) = written {
+            error!("Failed to print the legs of the best tour: {}", error);
+        }
+        return;
+    }
+
+    let json = path
+        .extension()
+        .is_some_and(|extension| extension == "json");
+    let written = File::create(path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        if json {
+            explain::write_json(&legs, &mut writer)?;
+        } else {
+            explain::write_csv(&legs, &mut writer)?;
+        }
+        writer.flush()
+    });
+    if let Err(error) = written {
+        error!("Failed to write {}: {}", path.display(), error);
+    }
+}
+
+// Sorts best first. Equal fitness is ordered by genome hash so neither the
+// allocation order nor the sort decides which of them makes the elite, only
+// identical tours fall back to the population order. Also returns the
+// population position of every rank.
+fn sort_by_fitness(eval_pop: Vec<(f32, TspSolution)>) -> (Vec<(f32, TspSolution)>, Vec<u32>) {
+    let hashes = eval_pop
+        .par_iter()
+        .map(|(_, solution)| permutation::genome_hash(&solution.path))
+        .collect::<Vec<u64>>();
+
+    let mut ranking = (0..eval_pop.len() as u32).collect::<Vec<u32>>();
+    ranking.par_sort_unstable_by(|a, b| {
+        let (a, b) = (*a as usize, *b as usize);
+        eval_pop[a]
+            .0
+            .total_cmp(&eval_pop[b].0)
+            .then(hashes[a].cmp(&hashes[b]))
+            .then(a.cmp(&b))
+    });
+
+    let mut individuals = eval_pop.into_iter().map(Some).collect::<Vec<_>>();
+    let sorted = ranking
+        .iter()
+        .map(|position| individuals[*position as usize].take().unwrap())
+        .collect();
+
+    (sorted, ranking)
+}
+
+fn record(journal: &mut Option<JournalWriter>, event: JournalEvent) {
+    if let Some(writer) = journal {
+        if let Err(error) = writer.record(&event) {
+            error!("Failed to write the journal, it is disabled: {}", error);
+            *journal = None;
+        }
+    }
+}
+
+// Only the individuals whose fitness isn't known yet go to `evaluate`, the
+// elite and the offspring identical to a parent keep theirs. The summary is
+// taken over the whole population in its order, as `evaluate_chunk` does.
+fn evaluate_unknown<E>(
+    evaluate: &mut E,
+    population: &[TSP],
+    known: &[Option<f32>],
+    map: &TspProblem,
+) -> EvaluatedPopulation
+where
+    E: FnMut(&[TSP], &TspProblem) -> EvaluatedPopulation,
+{
+    if known.iter().all(Option::is_none) {
+        return evaluate(population, map);
+    }
+
+    let unknown = population
+        .iter()
+        .zip(known)
+        .filter(|(_, fitness)| fitness.is_none())
+        .map(|(individual, _)| individual.clone())
+        .collect::<Vec<TSP>>();
+    let (evaluated, _, worker_peak, worker_stats) = evaluate(&unknown, map);
+
+    let mut evaluated = evaluated.into_iter();
+    let evaluated_population = population
+        .iter()
+        .zip(known)
+        .map(|(individual, fitness)| match fitness {
+            Some(fitness) => (*fitness, individual.get_solution().clone()),
+            None => evaluated.next().unwrap(),
+        })
+        .collect::<Vec<(f32, TspSolution)>>();
+    let summary =
+        FitnessSummary::from_fitness(evaluated_population.iter().map(|(fitness, _)| *fitness));
+
+    (evaluated_population, summary, worker_peak, worker_stats)
+}
+
+// `--eda-samples` tours drawn from the edge model of the best `--eda-elite`
+// ones of the sorted `eval_pop`, repaired to respect the constraints
+fn sample_edge_model(
+    run: &RunArgs,
+    eval_pop: &[(f32, TspSolution)],
+    problem: &Arc<TspProblem>,
+    rng: &mut StdRng,
+) -> Vec<TSP> {
+    let elite = eval_pop
+        .iter()
+        .take(run.eda_elite)
+        .filter(|(fitness, _)| fitness.is_finite())
+        .map(|(_, solution)| solution.path.as_slice());
+    let model = EdgeModel::learn(problem.graph_weights.len(), elite);
+    (0..run.eda_samples)
+        .map(|_| {
+            let solution = TspSolution {
+                path: model.sample(rng),
+            };
+            TSP::repaired(problem.clone(), solution)
+        })
+        .collect()
+}
+
+pub fn solutions_of(population: &[TSP]) -> Vec<TspSolution> {
+    population
+        .iter()
+        .map(|individual| individual.get_solution().clone())
+        .collect()
+}
+
+// Splits `length` individuals into `parts` contiguous ranges whose sizes
+// differ by at most one, some are empty when there are more parts than items
+pub fn chunk_ranges(length: usize, parts: usize) -> Vec<Range<usize>> {
+    let base = length / parts;
+    let remainder = length % parts;
+
+    let mut start = 0;
+    (0..parts)
+        .map(|part| {
+            let end = start + base + usize::from(part < remainder);
+            let range = start..end;
+            start = end;
+            range
+        })
+        .collect()
+}
+
+// Only the tours are sent, every one is evaluated against the map this
+// process built once
+pub fn evaluate_chunk(
+    problem: &TspProblem,
+    population: Vec<TspSolution>,
+) -> (Vec<(f32, TspSolution)>, FitnessSummary) {
+    let evaluated_population = population
+        .into_par_iter()
+        .map(|solution| (problem.evaluate(&solution), solution))
+        .collect::<Vec<(f32, TspSolution)>>();
+
+    // Summarize sequentially so the partial sums are reproducible
+    let summary =
+        FitnessSummary::from_fitness(evaluated_population.iter().map(|(fitness, _)| *fitness));
+
+    (evaluated_population, summary)
+}
+
+fn print_summary(stats: &GenerationStats) {
+    println!(
+        "Iteration {}, best: {}, mean: {:.3}, median: {}, std dev: {:.3}, invalid: {}/{}",
+        stats.generation,
+        stats.best,
+        stats.mean,
+        stats.median,
+        stats.std_dev,
+        stats.invalid,
+        stats.count
+    );
+}
+
+// How evenly the generation was spread, the root waits for the slowest worker
+fn print_worker_balance(worker_stats: &[(i32, WorkerStats)]) {
+    let seconds = |(_, stats): &&(i32, WorkerStats)| stats.seconds;
+    let (Some(fastest), Some(slowest)) = (
+        worker_stats
+            .iter()
+            .min_by(|a, b| seconds(a).total_cmp(&seconds(b))),
+        worker_stats
+            .iter()
+            .max_by(|a, b| seconds(a).total_cmp(&seconds(b))),
+    ) else {
+        return;
+    };
+
+    info!(
+        "Workers evaluated in {:.3} s (rank {}) to {:.3} s (rank {}), imbalance {:.2}",
+        fastest.1.seconds,
+        fastest.0,
+        slowest.1.seconds,
+        slowest.0,
+        slowest.1.seconds / fastest.1.seconds.max(f64::EPSILON)
+    );
+}
+
+fn merge_worker_stats(
+    totals: &mut BTreeMap<i32, WorkerStats>,
+    worker_stats: &[(i32, WorkerStats)],
+) {
+    for (rank, stats) in worker_stats.iter() {
+        totals.entry(*rank).or_default().merge(stats);
+    }
+}
+
+fn print_worker_totals(totals: &BTreeMap<i32, WorkerStats>) {
+    if totals.is_empty() {
+        return;
+    }
+
+    println!("Worker statistics:");
+    for (rank, stats) in totals.iter() {
+        println!(
+            "  rank {:<4} evaluated: {:<12} busy: {:.3} s",
+            rank, stats.evaluated, stats.seconds
+        );
+    }
+}
+
+fn print_memory(worker_peak: Option<u64>) {
+    let format = |bytes: Option<u64>| match bytes {
+        Some(bytes) => Bytes(bytes).to_string(),
+        None => "unknown".to_string(),
+    };
+    println!(
+        "Resident memory, root: {}, largest worker: {}",
+        format(memory::resident_set()),
+        format(worker_peak)
+    );
+}
+
+// Warns when the estimate for the role of this rank doesn't fit what is available
+pub fn check_memory(rank: i32, size: i32, population: usize, nodes: usize) {
+    let estimate = memory::estimate_tsp(population, nodes, size as usize - 1);
+    let needed = if rank == 0 || size == 1 {
+        estimate.root() + if size == 1 { estimate.worker() } else { 0 }
+    } else {
+        estimate.worker()
+    };
+
+    if let Some(available) = memory::available_per_rank() {
+        if needed > available {
+            warn!(
+                "Rank {} needs about {} but only {} is available to it",
+                rank,
+                Bytes(needed),
+                Bytes(available)
+            );
+        }
+    }
+}
+
+fn export_edge_frequencies(
+    directory: &Path,
+    iteration: usize,
+    instance: &TspInstance,
+    eval_pop: &[(f32, TspSolution)],
+) -> std::io::Result<()> {
+    let frequencies = EdgeFrequencies::from_paths(
+        instance.graph_weights.len(),
+        eval_pop
+            .par_iter()
+            .map(|(_, solution)| solution.path.as_slice()),
+    );
+
+    let mut csv = File::create(directory.join(format!("edges-{:05}.csv", iteration)))?;
+    frequencies.write_csv(&mut csv)?;
+
+    #[cfg(feature = "plotting")]
+    if let Some(coordinates) = &instance.coordinates {
+        let mut svg = File::create(directory.join(format!("edges-{:05}.svg", iteration)))?;
+        frequencies.write_svg(coordinates, &mut svg)?;
+    }
+
+    Ok(())
+}
+
+fn print_mutation_statistics(mix: &MutationMix) {
+    println!("Composite mutation:");
+    for (operator, applied, improved) in mix.statistics.counts() {
+        println!(
+            "  {:<10} weight: {:<6} applied: {:<10} improved: {:<10} ({:.1}%)",
+            operator,
+            mix.weights.weight(operator),
+            applied,
+            improved,
+            100.0 * improved as f64 / applied.max(1) as f64
+        );
+    }
+}
+
+// Tells whether the run converged to a single basin or is spread over several
+fn print_clusters(map: &TspProblem, eval_pop: &[(f32, TspSolution)]) {
+    // Measured as the problem measures tours, round or not
+    let map = Arc::new(map.clone());
+    let solutions = eval_pop
+        .iter()
+        .map(|(_, solution)| TSP::new(map.clone(), solution.clone()))
+        .collect::<Vec<TSP>>();
+
+    let clusters = clustering::k_medoids(&solutions, CLUSTERS, CLUSTERING_SAMPLE);
+
+    println!("Final population clusters:");
+    for (i, cluster) in clusters.iter().enumerate() {
+        // The population is sorted, so the first member is the best one
+        let best = cluster.members.first().map(|member| eval_pop[*member].0);
+        println!(
+            "Cluster {}: size: {}, best: {:?}, medoid fitness: {}, spread: {:.2}, medoid: {:?}",
+            i,
+            cluster.members.len(),
+            best,
+            eval_pop[cluster.medoid].0,
+            cluster.spread,
+            eval_pop[cluster.medoid].1.path
+        );
+    }
+}
+
+// How the best distinct tours, best first, hold up once the edge costs are
+// perturbed, so one that degrades gracefully can be picked over the shortest
+fn print_robustness(
+    run: &RunArgs,
+    graph_weights: &WeightMatrix,
+    bests: &[(f32, TspSolution)],
+    samples: usize,
+    seed: u64,
+) {
+    let tours = bests
+        .iter()
+        .map(|(_, solution)| solution)
+        .unique_by(|solution| robustness::edge_set(&solution.path, !run.open_path))
+        .take(run.robustness_tours)
+        .collect::<Vec<_>>();
+    let reports = robustness::assess(
+        graph_weights,
+        !run.open_path,
+        &tours,
+        samples,
+        run.robustness_noise,
+        run.robustness_scale,
+        seed,
+    );
+
+    println!(
+        "Robustness under {} {} perturbations of scale {}:",
+        samples, run.robustness_noise, run.robustness_scale
+    );
+    for (i, (tour, report)) in tours.iter().zip(reports.iter()).enumerate() {
+        println!(
+            "Tour {}: length: {}, mean: {:.3}, p95: {}, worst: {}, increase at p95: {:.2}%, tour: {:?}",
+            i,
+            report.nominal,
+            report.mean,
+            report.p95,
+            report.worst,
+            100.0 * (report.p95 / report.nominal - 1.0),
+            tour.path
+        );
+    }
+}
+
+#[cfg(feature = "database")]
+pub fn open_database(path: &Path) -> ResultDatabase {
+    match ResultDatabase::open(path) {
+        Ok(database) => database,
+        Err(error) => {
+            eprintln!("{}: {}", path.display(), error);
+            std::process::exit(1);
+        }
+    }
+}
+
+// A TSPLIB file or `builtin:<name>`, as it is
+fn read_instance(instance: &str) -> Result<TspInstance, String> {
+    match instance.strip_prefix(builtin::PREFIX) {
+        Some(name) => builtin::instance(name).ok_or_else(|| {
+            format!(
+                "Unknown builtin instance {}, available: {}",
+                name,
+                builtin::names().join(", ")
+            )
+        }),
+        None => {
+            loader::load(Path::new(instance)).map_err(|error| format!("{}: {}", instance, error))
+        }
+    }
+}
+
+// `instance` as `--instance` takes it, with the `--edge-overrides`, the
+// `--constraints` and the `--scenario`s of `run`
+pub fn load_instance(run: &RunArgs, instance: &str) -> Result<TspInstance, String> {
+    let mut loaded = read_instance(instance)?;
+
+    if let Some(path) = &run.edge_overrides {
+        overrides::read(path)
+            .and_then(|edges| overrides::apply(&mut loaded, &edges))
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+    }
+    if let Some(path) = beside_instance(run.constraints.as_ref(), instance, "constraints") {
+        let constraints = constraints::read(&path)
+            .and_then(
+                |constraints| match constraints.infeasibility(loaded.graph_weights.len()) {
+                    Some(reason) => Err(format!("no tour is left, {}", reason)),
+                    None => Ok(constraints),
+                },
+            )
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+        loaded.constraints = Some(Arc::new(constraints));
+    }
+    if !run.scenario.is_empty() {
+        let matrices = run
+            .scenario
+            .iter()
+            .map(|scenario| {
+                read_instance(scenario).map(|scenario| scenario.matrix().as_ref().clone())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let scenarios = Scenarios::new(
+            loaded.graph_weights.len(),
+            matrices,
+            &run.scenario_weights,
+            run.scenario_aggregate,
+        )?;
+        loaded.scenarios = Some(Arc::new(scenarios));
+    }
+    Ok(loaded)
+}
+
+// `run` with the operators and rates of the portfolio entry matching
+// `instance`, but those given explicitly
+pub fn with_portfolio(run: &RunArgs, instance: &TspInstance) -> Result<RunArgs, String> {
+    let run = run.clone();
+    let Some(path) = &run.portfolio else {
+        return Ok(run);
+    };
+    let portfolio =
+        Portfolio::read(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    let features = InstanceFeatures::of(&instance.matrix());
+    let Some(entry) = portfolio.lookup(&features) else {
+        info!("No portfolio entry matches {} ({})", run.instance, features);
+        return Ok(run);
+    };
+    let invalid = |error| format!("{}: entry of {}: {}", path.display(), entry.instance, error);
+
+    let given = |key: &str| run.given.iter().any(|given| given == key);
+    let crossover = if given("crossover") {
+        run.crossover
+    } else {
+        entry.crossover().map_err(invalid)?
+    };
+    let mutation = if given("mutation") {
+        run.mutation
+    } else {
+        entry.mutation().map_err(invalid)?
+    };
+    let mutation_rate = if given("mutation_rate") {
+        run.mutation_rate
+    } else {
+        entry.mutation_rate
+    };
+    let crossover_rate = if given("crossover_rate") {
+        run.crossover_rate
+    } else {
+        entry.crossover_rate
+    };
+    let run = RunArgs {
+        crossover,
+        mutation,
+        mutation_rate,
+        crossover_rate,
+        ..run
+    };
+    info!(
+        "Portfolio entry of {} ({}) applied to {} ({}): crossover {}, mutation {}, mutation rate {}, crossover rate {}",
+        entry.instance,
+        entry.features,
+        run.instance,
+        features,
+        run.crossover,
+        run.mutation,
+        run.mutation_rate,
+        run.crossover_rate
+    );
+    Ok(run)
+}
+
+// `given` by its flag, or else the file with `extension` next to the
+// instance file, so every instance of a batch can have its own
+pub fn beside_instance(
+    given: Option<&PathBuf>,
+    instance: &str,
+    extension: &str,
+) -> Option<PathBuf> {
+    if given.is_some() || instance.starts_with(builtin::PREFIX) {
+        return given.cloned();
+    }
+    Some(Path::new(instance).with_extension(extension)).filter(|path| path.is_file())
+}
+
+pub fn instance_or_exit(run: &RunArgs, instance: &str) -> TspInstance {
+    match load_instance(run, instance) {
+        Ok(instance) => instance,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn initialize(run: &RunArgs, problem: &Arc<TspProblem>, rng: &mut StdRng) -> Vec<TSP> {
+    // The tours of similar instances a batch warm starts from, then the
+    // tours of the heuristics, or stratified starts with one nearest neighbor
+    // tour from every city
+    let mut seeds = run
+        .warm_start_tours
+        .iter()
+        .map(|tour| TSP::repaired(problem.clone(), tour.clone()))
+        .collect::<Vec<TSP>>();
+    match run.heuristic_fraction {
+        Some(fraction) => {
+            let count = (fraction * run.pop_size as f32).round() as usize;
+            seeds.extend(heuristic_tours(problem, count, rng));
+        }
+        None => seeds.extend(
+            (0..problem.graph_weights.len()).map(|start| TSP::new_nearest_neighbor(problem, start)),
+        ),
+    }
+
+    if let Some(samples) = run.seed_samples {
+        let keep = run.pop_size.saturating_sub(seeds.len());
+        info!(
+            "Keeping the best {} of {} random tours, {} at a time",
+            keep, samples, run.seed_slice
+        );
+
+        let mut population = seeds;
+        population.extend(
+            stream_evaluate(samples.max(keep), run.seed_slice, keep, rng.gen(), |rng| {
+                TSP::new_shuffled(problem, rng)
+            })
+            .into_iter()
+            .map(|(_, individual)| individual),
+        );
+        population.truncate(run.pop_size);
+        return population;
+    }
+
+    let options = DiversityOptions {
+        min_distance: MIN_INITIAL_DISTANCE,
+        ..Default::default()
+    };
+
+    diverse_population(run.pop_size, options, seeds, || {
+        TSP::new_shuffled(problem, rng)
+    })
+}
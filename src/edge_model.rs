@@ -0,0 +1,65 @@
+use crate::edge_statistics::EdgeFrequencies;
+use rand::Rng;
+
+// Weight of an edge no tour of the model uses, relative to one every tour
+// uses, so sampling can still leave the edges of the elite
+const BIAS: f32 = 0.05;
+
+// Edge histogram of a set of tours, the generative model of an EDA: a tour is
+// sampled city after city, every next city drawn among the unvisited ones in
+// proportion to how many of the tours go there from the current one.
+pub struct EdgeModel {
+    nodes: usize,
+    frequencies: EdgeFrequencies,
+}
+
+impl EdgeModel {
+    pub fn learn<'a, I>(nodes: usize, tours: I) -> Self
+    where
+        I: IntoIterator<Item = &'a [usize]>,
+    {
+        let tours = tours.into_iter().collect::<Vec<_>>();
+        EdgeModel {
+            nodes,
+            frequencies: EdgeFrequencies::from_paths(nodes, tours),
+        }
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<usize> {
+        if self.nodes == 0 {
+            return Vec::new();
+        }
+        let mut visited = vec![false; self.nodes];
+        let mut current = rng.gen_range(0..self.nodes);
+        visited[current] = true;
+        let mut path = Vec::with_capacity(self.nodes);
+        path.push(current);
+
+        let mut weights = vec![0.0; self.nodes];
+        while path.len() < self.nodes {
+            for (city, weight) in weights.iter_mut().enumerate() {
+                *weight = if visited[city] {
+                    0.0
+                } else {
+                    self.frequencies.frequency(current, city) + BIAS
+                };
+            }
+            let mut draw = rng.gen::<f32>() * weights.iter().sum::<f32>();
+            // Rounding may leave the draw past the last weight, the last
+            // unvisited city takes it
+            let mut next = (0..self.nodes).rev().find(|city| !visited[*city]).unwrap();
+            for (city, weight) in weights.iter().enumerate() {
+                if *weight > 0.0 && draw < *weight {
+                    next = city;
+                    break;
+                }
+                draw -= weight;
+            }
+
+            visited[next] = true;
+            path.push(next);
+            current = next;
+        }
+        path
+    }
+}
@@ -0,0 +1,143 @@
+use crate::parallel::prelude::*;
+use std::io::{self, Write};
+
+// How many tours of a population use each undirected edge
+pub struct EdgeFrequencies {
+    nodes: usize,
+    counts: Vec<u32>,
+    tours: usize,
+}
+
+impl EdgeFrequencies {
+    pub fn from_paths<'a, I>(nodes: usize, paths: I) -> Self
+    where
+        I: IntoParallelIterator<Item = &'a [usize]>,
+    {
+        let (counts, tours) = paths
+            .into_par_iter()
+            .fold(
+                || (vec![0u32; nodes * nodes], 0),
+                |(mut counts, tours), path| {
+                    for window in path.windows(2) {
+                        counts[index(nodes, window[0], window[1])] += 1;
+                    }
+                    (counts, tours + 1)
+                },
+            )
+            .reduce(
+                || (vec![0u32; nodes * nodes], 0),
+                |(mut acc, acc_tours), (counts, tours)| {
+                    acc.iter_mut().zip(counts).for_each(|(a, b)| *a += b);
+                    (acc, acc_tours + tours)
+                },
+            );
+
+        EdgeFrequencies {
+            nodes,
+            counts,
+            tours,
+        }
+    }
+
+    pub fn count(&self, a: usize, b: usize) -> u32 {
+        self.counts[index(self.nodes, a, b)]
+    }
+
+    pub fn frequency(&self, a: usize, b: usize) -> f32 {
+        if self.tours == 0 {
+            return 0.0;
+        }
+        self.count(a, b) as f32 / self.tours as f32
+    }
+
+    // Edges used by at least one tour, a converged population uses about `nodes`
+    pub fn distinct_edges(&self) -> usize {
+        self.edges().count()
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (usize, usize, u32)> + '_ {
+        (0..self.nodes)
+            .flat_map(move |a| (a + 1..self.nodes).map(move |b| (a, b)))
+            .map(|(a, b)| (a, b, self.count(a, b)))
+            .filter(|(_, _, count)| *count > 0)
+    }
+
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "from,to,count,frequency")?;
+        for (a, b, count) in self.edges() {
+            writeln!(
+                writer,
+                "{},{},{},{}",
+                a,
+                b,
+                count,
+                count as f32 / self.tours as f32
+            )?;
+        }
+        Ok(())
+    }
+
+    // Draws the cities and every used edge with an opacity proportional to its frequency
+    #[cfg(feature = "plotting")]
+    pub fn write_svg<W: Write>(
+        &self,
+        coordinates: &[(f32, f32)],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        const SIZE: f32 = 800.0;
+        const MARGIN: f32 = 20.0;
+
+        let (min_x, max_x, min_y, max_y) = coordinates.iter().fold(
+            (
+                f32::INFINITY,
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+                f32::NEG_INFINITY,
+            ),
+            |(min_x, max_x, min_y, max_y), (x, y)| {
+                (min_x.min(*x), max_x.max(*x), min_y.min(*y), max_y.max(*y))
+            },
+        );
+        let scale = (SIZE - 2.0 * MARGIN) / (max_x - min_x).max(max_y - min_y).max(f32::EPSILON);
+        // SVG grows downwards, so the y axis is flipped
+        let point = |node: usize| {
+            let (x, y) = coordinates[node];
+            (
+                MARGIN + (x - min_x) * scale,
+                SIZE - MARGIN - (y - min_y) * scale,
+            )
+        };
+
+        writeln!(
+            writer,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#,
+            SIZE
+        )?;
+        writeln!(writer, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+        for (a, b, count) in self.edges() {
+            let ((x1, y1), (x2, y2)) = (point(a), point(b));
+            writeln!(
+                writer,
+                r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="crimson" stroke-width="2" stroke-opacity="{:.3}"/>"#,
+                x1,
+                y1,
+                x2,
+                y2,
+                count as f32 / self.tours as f32
+            )?;
+        }
+        for node in 0..coordinates.len() {
+            let (x, y) = point(node);
+            writeln!(
+                writer,
+                r#"<circle cx="{:.1}" cy="{:.1}" r="3" fill="black"/>"#,
+                x, y
+            )?;
+        }
+        writeln!(writer, "</svg>")
+    }
+}
+
+fn index(nodes: usize, a: usize, b: usize) -> usize {
+    a.min(b) * nodes + a.max(b)
+}
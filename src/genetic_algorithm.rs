@@ -1,60 +1,102 @@
-use crate::organism::Organism;
-use rand::distributions::uniform::UniformSampler;
-use rayon::prelude::*;
+use crate::organism::{Fitness, Organism};
+use crate::parallel::prelude::*;
+use crate::portable::{self, Parameters};
+use crate::selection::{Selection, TournamentSelection};
+use crate::statistics::{GenerationStats, RunHistory};
+use crate::termination::{Progress, StopReason, Termination};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Instant;
 
 pub fn ga_iteraration<T>(
     population: &Vec<T>,
     mutation_rate: f32,
     crossover_rate: f32,
     elite_size: usize,
+    selection: &dyn Selection,
+    rng: &mut dyn RngCore,
 ) -> Vec<T>
 where
     T: Organism + Clone + Sync + Send + Sized,
 {
-    let distribution = rand::distributions::uniform::UniformFloat::<f32>::new(0.0, 1.0);
-
     // Evaluate the population
-    let mut evaluated_population = ga_evaluate_population(population);
+    let evaluated_population = ga_evaluate_population(population);
 
-    // Select the best individuals to reproduce
-    evaluated_population.par_sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+    ga_breed(
+        evaluated_population,
+        mutation_rate,
+        crossover_rate,
+        elite_size,
+        selection,
+        rng,
+    )
+}
 
-    let mut new_population = evaluated_population[elite_size..]
-        .par_windows(2)
-        .map(|window| {
-            let first = window[0].1.clone();
-            let second = window[1].1;
+// The next generation out of an evaluated one, of the same size. The same
+// `rng` state breeds the same generation however many threads do it.
+pub fn ga_breed<T>(
+    mut evaluated_population: Vec<(T::Fitness, &T)>,
+    mutation_rate: f32,
+    crossover_rate: f32,
+    elite_size: usize,
+    selection: &dyn Selection,
+    rng: &mut dyn RngCore,
+) -> Vec<T>
+where
+    T: Organism + Clone + Sync + Send + Sized,
+{
+    // Nothing to breed, and an elite as large as the population keeps it
+    // all, see `breeding_problems` for the parameters that make sense
+    if evaluated_population.is_empty() {
+        return Vec::new();
+    }
+    let elite_size = elite_size.min(evaluated_population.len());
+    let parameters = Parameters {
+        elite: elite_size,
+        mutation_rate,
+        crossover_rate,
+    };
 
-            if distribution.sample(&mut rand::thread_rng()) < crossover_rate {
-                let child = first.cross_over(second);
-                return child;
-            }
+    // Sorted for the elite
+    // Stable so equal fitness keeps the population order and runs are reproducible
+    evaluated_population.par_sort_by(|a, b| a.0.compare(&b.0));
+    let fitness = evaluated_population
+        .iter()
+        .map(|(fitness, _)| fitness.to_f32())
+        .collect::<Vec<f32>>();
 
-            return first.clone();
+    // Both parents of every child are chosen by `selection`, out of the whole
+    // population elite included
+    let children = evaluated_population.len() - elite_size;
+    let pairs = selection.pairs(&fitness, elite_size, children, rng);
+
+    // The crossover and mutation draws of child k come from `seed + k`
+    let seed = rng.gen::<u64>();
+    let mut new_population = pairs
+        .into_par_iter()
+        .enumerate()
+        .map(|(k, (first, second))| {
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(k as u64));
+            portable::breed(
+                evaluated_population[first].1,
+                evaluated_population[second].1,
+                &parameters,
+                &mut rng,
+            )
         })
         .collect::<Vec<T>>();
 
-    // Mutate the new_population
-    new_population.par_iter_mut().for_each(|child| {
-        if distribution.sample(&mut rand::thread_rng()) < mutation_rate {
-            child.mutate();
-        }
-    });
-
     // Return the new population, including the elite
-    new_population.extend(
-        evaluated_population[..=elite_size]
-            .iter()
-            .cloned()
-            .map(|(_, individual)| individual.clone()),
-    );
+    portable::keep_elite(&mut new_population, &evaluated_population, elite_size);
 
-    assert_eq!(new_population.len(), population.len());
+    assert_eq!(new_population.len(), evaluated_population.len());
 
     new_population
 }
 
-pub fn ga_evaluate_population<T>(population: &Vec<T>) -> Vec<(f32, &T)>
+pub fn ga_evaluate_population<T>(population: &Vec<T>) -> Vec<(T::Fitness, &T)>
 where
     T: Organism + Clone + Sync + Send + Sized,
 {
@@ -62,5 +104,402 @@ where
     population
         .par_iter()
         .map(|individual| (individual.fitness(), individual))
-        .collect::<Vec<(f32, &T)>>()
+        .collect::<Vec<(T::Fitness, &T)>>()
+}
+
+// Everything that keeps these parameters from breeding, empty when they are fine
+pub fn breeding_problems(
+    population_size: usize,
+    elite: usize,
+    mutation_rate: f32,
+    crossover_rate: f32,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for (name, rate) in [
+        ("mutation rate", mutation_rate),
+        ("crossover rate", crossover_rate),
+    ] {
+        if !(0.0..=1.0).contains(&rate) {
+            problems.push(format!("{} must be in [0, 1], got {}", name, rate));
+        }
+    }
+
+    if population_size == 0 {
+        problems.push("population size must be at least 1".to_string());
+    }
+    if elite >= population_size {
+        problems.push(format!(
+            "elite ({}) must be smaller than the population size ({})",
+            elite, population_size
+        ));
+    }
+
+    problems
+}
+
+// A population and the parameters it breeds with, see `GeneticAlgorithm::builder`
+pub struct GeneticAlgorithm<T> {
+    population: Vec<T>,
+    elite: usize,
+    mutation_rate: f32,
+    crossover_rate: f32,
+    selection: Arc<dyn Selection>,
+    rng: StdRng,
+    generation: usize,
+}
+
+impl<T> GeneticAlgorithm<T>
+where
+    T: Organism + Clone + Sync + Send + Sized,
+{
+    pub fn builder() -> GeneticAlgorithmBuilder<T> {
+        GeneticAlgorithmBuilder::default()
+    }
+
+    // Evaluates the population, breeds the next one and returns the statistics
+    // of the evaluated one
+    pub fn step(&mut self) -> GenerationStats {
+        let evaluated_population = ga_evaluate_population(&self.population);
+        let stats = self.stats(&evaluated_population);
+
+        let new_population = ga_breed(
+            evaluated_population,
+            self.mutation_rate,
+            self.crossover_rate,
+            self.elite,
+            self.selection.as_ref(),
+            &mut self.rng,
+        );
+        self.population = new_population;
+        self.generation += 1;
+
+        stats
+    }
+
+    // The statistics of every generation run
+    pub fn run(&mut self, iterations: usize) -> RunHistory {
+        let mut history = RunHistory::new();
+        for _ in 0..iterations {
+            history.push(self.step());
+        }
+        history
+    }
+
+    // Steps until `termination` is met, checked on every evaluated population
+    // before it breeds. Returns the statistics of the generations bred and why
+    // it stopped, the population is then the last one evaluated.
+    pub fn run_until(&mut self, termination: &mut dyn Termination) -> (RunHistory, StopReason) {
+        let started = Instant::now();
+        let mut history = RunHistory::new();
+        loop {
+            let evaluated_population = ga_evaluate_population(&self.population);
+            let stats = self.stats(&evaluated_population);
+            let progress = Progress {
+                generations: self.generation,
+                elapsed: started.elapsed(),
+                best: stats.best,
+            };
+            if let Some(reason) = termination.check(&progress) {
+                return (history, reason);
+            }
+
+            self.population = ga_breed(
+                evaluated_population,
+                self.mutation_rate,
+                self.crossover_rate,
+                self.elite,
+                self.selection.as_ref(),
+                &mut self.rng,
+            );
+            self.generation += 1;
+            history.push(stats);
+        }
+    }
+
+    fn stats(&self, evaluated_population: &[(T::Fitness, &T)]) -> GenerationStats {
+        let fitness = evaluated_population
+            .iter()
+            .map(|(fitness, _)| fitness.to_f32())
+            .collect::<Vec<f32>>();
+        GenerationStats::from_fitness(self.generation, &fitness)
+    }
+
+    pub fn best(&self) -> Option<(T::Fitness, &T)> {
+        ga_evaluate_population(&self.population)
+            .into_iter()
+            .min_by(|a, b| a.0.compare(&b.0))
+    }
+
+    pub fn population(&self) -> &Vec<T> {
+        &self.population
+    }
+
+    // Generations bred so far
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GeneticAlgorithmBuilder<T> {
+    population_size: usize,
+    elite: usize,
+    mutation_rate: f32,
+    crossover_rate: f32,
+    selection: Arc<dyn Selection>,
+    seed: Option<u64>,
+    organism: PhantomData<T>,
+}
+
+impl<T> Default for GeneticAlgorithmBuilder<T> {
+    fn default() -> Self {
+        GeneticAlgorithmBuilder {
+            population_size: 100,
+            elite: 2,
+            mutation_rate: 0.1,
+            crossover_rate: 0.9,
+            selection: Arc::new(TournamentSelection::default()),
+            seed: None,
+            organism: PhantomData,
+        }
+    }
+}
+
+impl<T> GeneticAlgorithmBuilder<T>
+where
+    T: Organism + Clone + Sync + Send + Sized,
+{
+    pub fn population_size(mut self, population_size: usize) -> Self {
+        self.population_size = population_size;
+        self
+    }
+
+    pub fn elite(mut self, elite: usize) -> Self {
+        self.elite = elite;
+        self
+    }
+
+    pub fn mutation_rate(mut self, mutation_rate: f32) -> Self {
+        self.mutation_rate = mutation_rate;
+        self
+    }
+
+    pub fn crossover_rate(mut self, crossover_rate: f32) -> Self {
+        self.crossover_rate = crossover_rate;
+        self
+    }
+
+    pub fn selection<S: Selection + 'static>(mut self, selection: S) -> Self {
+        self.selection = Arc::new(selection);
+        self
+    }
+
+    // Seeds the generator of the run, which is otherwise seeded from the
+    // operating system and never the same twice
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    // `population_size` individuals made by `create`, with the generator of
+    // the run
+    pub fn build<F>(self, mut create: F) -> Result<GeneticAlgorithm<T>, Vec<String>>
+    where
+        F: FnMut(&mut StdRng) -> T,
+    {
+        let mut rng = self.rng();
+        let population = std::iter::repeat_with(|| create(&mut rng))
+            .take(self.population_size)
+            .collect();
+        self.build_with(population, rng)
+    }
+
+    // Starts from `population`, its size replaces `population_size`
+    pub fn build_from(self, population: Vec<T>) -> Result<GeneticAlgorithm<T>, Vec<String>> {
+        let rng = self.rng();
+        self.build_with(population, rng)
+    }
+
+    fn rng(&self) -> StdRng {
+        self.seed
+            .map_or_else(StdRng::from_entropy, StdRng::seed_from_u64)
+    }
+
+    fn build_with(
+        self,
+        population: Vec<T>,
+        rng: StdRng,
+    ) -> Result<GeneticAlgorithm<T>, Vec<String>> {
+        let problems = breeding_problems(
+            population.len(),
+            self.elite,
+            self.mutation_rate,
+            self.crossover_rate,
+        );
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+
+        Ok(GeneticAlgorithm {
+            population,
+            elite: self.elite,
+            mutation_rate: self.mutation_rate,
+            crossover_rate: self.crossover_rate,
+            selection: self.selection,
+            rng,
+            generation: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tsp::matrix::WeightMatrix;
+    use crate::tsp::{TspProblem, TspSolution, TSP};
+
+    // Remembers how it was bred, its fitness is its value
+    #[derive(Clone, Debug, PartialEq)]
+    struct Tracked {
+        value: u32,
+        crossed: bool,
+        mutated: bool,
+    }
+
+    impl Tracked {
+        fn new(value: u32) -> Self {
+            Tracked {
+                value,
+                crossed: false,
+                mutated: false,
+            }
+        }
+    }
+
+    impl Organism for Tracked {
+        type Fitness = u32;
+
+        fn fitness(&self) -> u32 {
+            self.value
+        }
+
+        fn mutate<R: Rng + ?Sized>(&mut self, _: &mut R) {
+            self.mutated = true;
+        }
+
+        fn cross_over<R: Rng + ?Sized>(&self, other: &Self, _: &mut R) -> Self {
+            Tracked {
+                value: self.value.min(other.value),
+                crossed: true,
+                mutated: false,
+            }
+        }
+    }
+
+    fn population(size: u32) -> Vec<Tracked> {
+        (0..size).rev().map(Tracked::new).collect()
+    }
+
+    fn breed(
+        population: &Vec<Tracked>,
+        mutation_rate: f32,
+        crossover_rate: f32,
+        elite: usize,
+    ) -> Vec<Tracked> {
+        let mut rng = StdRng::seed_from_u64(7);
+        ga_iteraration(
+            population,
+            mutation_rate,
+            crossover_rate,
+            elite,
+            &TournamentSelection::default(),
+            &mut rng,
+        )
+    }
+
+    #[test]
+    fn elite_is_kept_untouched() {
+        let next = breed(&population(10), 1.0, 1.0, 3);
+
+        assert_eq!(next.len(), 10);
+        let untouched = next
+            .iter()
+            .filter(|individual| !individual.mutated)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            untouched,
+            [&Tracked::new(0), &Tracked::new(1), &Tracked::new(2)]
+        );
+    }
+
+    #[test]
+    fn no_elite_breeds_every_individual() {
+        let next = breed(&population(10), 1.0, 1.0, 0);
+
+        assert_eq!(next.len(), 10);
+        assert!(next
+            .iter()
+            .all(|individual| individual.crossed && individual.mutated));
+    }
+
+    #[test]
+    fn population_smaller_than_elite_and_two_parents() {
+        let next = breed(&population(3), 1.0, 1.0, 2);
+        assert_eq!(next.len(), 3);
+        assert_eq!(
+            next.iter().filter(|individual| individual.mutated).count(),
+            1
+        );
+
+        // An elite as large as the population keeps it all
+        let next = breed(&population(2), 1.0, 1.0, 5);
+        assert_eq!(next, [Tracked::new(0), Tracked::new(1)]);
+    }
+
+    #[test]
+    fn no_crossover_copies_the_parents() {
+        let parents = population(10);
+        let next = breed(&parents, 0.0, 0.0, 2);
+
+        assert_eq!(next.len(), 10);
+        assert!(next
+            .iter()
+            .all(|individual| !individual.crossed && !individual.mutated));
+        assert!(next.iter().all(|individual| parents.contains(individual)));
+    }
+
+    #[test]
+    fn elite_is_ranked_in_the_fitness_type() {
+        // Both are 16777216 as an f32
+        let population = vec![Tracked::new((1 << 24) + 1), Tracked::new(1 << 24)];
+        let next = breed(&population, 0.0, 0.0, 1);
+
+        assert_eq!(next.last(), Some(&Tracked::new(1 << 24)));
+    }
+
+    #[test]
+    fn single_city_instance() {
+        let problem = Arc::new(TspProblem::new(Arc::new(WeightMatrix::from_rows(&[vec![
+            0.0,
+        ]]))));
+        let mut population = (0..5)
+            .map(|_| TSP::new(problem.clone(), TspSolution::new(1)))
+            .collect::<Vec<TSP>>();
+        for _ in 0..3 {
+            population = ga_iteraration(
+                &population,
+                1.0,
+                1.0,
+                1,
+                &TournamentSelection::default(),
+                &mut StdRng::seed_from_u64(3),
+            );
+        }
+
+        assert_eq!(population.len(), 5);
+        assert!(population
+            .iter()
+            .all(|individual| individual.get_solution().path == [0] && individual.score() == 0.0));
+    }
 }
@@ -0,0 +1,148 @@
+use crate::organism::Distance;
+use crate::parallel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug)]
+pub struct DiversityOptions {
+    // Candidates closer than this to an accepted individual are rejected,
+    // a value of 1.0 rejects only exact duplicates for permutation genomes
+    pub min_distance: f32,
+    // How many candidates are drawn before accepting one regardless of distance
+    pub max_attempts: usize,
+}
+
+impl Default for DiversityOptions {
+    fn default() -> Self {
+        DiversityOptions {
+            min_distance: 1.0,
+            max_attempts: 100,
+        }
+    }
+}
+
+// How an island builds its first population
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InitStrategy {
+    Random,
+    // Nearest neighbor tours from shuffled start cities, then random ones
+    NearestNeighbor,
+    // Greedy edge tours, all but the first with noisy edge weights, then
+    // random ones
+    Greedy,
+}
+
+impl InitStrategy {
+    pub const ALL: [InitStrategy; 3] = [
+        InitStrategy::Random,
+        InitStrategy::NearestNeighbor,
+        InitStrategy::Greedy,
+    ];
+}
+
+impl fmt::Display for InitStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            InitStrategy::Random => "random",
+            InitStrategy::NearestNeighbor => "nearest-neighbor",
+            InitStrategy::Greedy => "greedy",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for InitStrategy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        InitStrategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.to_string() == name)
+            .ok_or_else(|| format!("unknown initialization strategy: {}", name))
+    }
+}
+
+// What one island starts from, decided once for the whole archipelago so a
+// run is reproducible from its base seed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IslandInit {
+    pub strategy: InitStrategy,
+    pub seed: u64,
+}
+
+// Island i gets the strategies in turn and the seed after island i - 1
+pub fn island_inits(strategies: &[InitStrategy], seed: u64, islands: usize) -> Vec<IslandInit> {
+    (0..islands)
+        .map(|island| IslandInit {
+            strategy: strategies
+                .get(island % strategies.len().max(1))
+                .copied()
+                .unwrap_or(InitStrategy::Random),
+            seed: seed.wrapping_add(island as u64),
+        })
+        .collect()
+}
+
+pub fn diverse_population<T, F>(
+    size: usize,
+    options: DiversityOptions,
+    seeds: Vec<T>,
+    mut generate: F,
+) -> Vec<T>
+where
+    T: Distance + Sync + Send,
+    F: FnMut() -> T,
+{
+    let mut population: Vec<T> = Vec::with_capacity(size);
+    let mut rejected = 0;
+
+    // The stratified seeds go first, they are still checked against each other
+    for seed in seeds.into_iter() {
+        if population.len() == size {
+            break;
+        }
+        if is_diverse(&population, &seed, options.min_distance) {
+            population.push(seed);
+        } else {
+            rejected += 1;
+        }
+    }
+
+    while population.len() < size {
+        let mut candidate = generate();
+        let mut attempts = 1;
+
+        while attempts < options.max_attempts
+            && !is_diverse(&population, &candidate, options.min_distance)
+        {
+            candidate = generate();
+            attempts += 1;
+            rejected += 1;
+        }
+
+        population.push(candidate);
+    }
+
+    if rejected > 0 {
+        println!(
+            "Initialization rejected {} candidates closer than {}",
+            rejected, options.min_distance
+        );
+    }
+
+    population
+}
+
+fn is_diverse<T>(population: &[T], candidate: &T, min_distance: f32) -> bool
+where
+    T: Distance + Sync + Send,
+{
+    if min_distance <= 0.0 {
+        return true;
+    }
+
+    !population
+        .par_iter()
+        .any(|individual| individual.distance(candidate) < min_distance)
+}
@@ -0,0 +1,823 @@
+use crate::budget::{BudgetCoordinator, BudgetDecision, BudgetPolicy, BudgetReport, IslandBudget};
+use crate::organism::{Distance, Fitness, GenomeHash, Organism, Validate};
+use crate::runner::{PopulationSolver, RunParameters, Solver};
+use crate::selection::Selection;
+use crate::statistics::FitnessSummary;
+use crate::topology::{self, Topology};
+use log::{info, warn};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// Largest relative difference between the fitness a migrant had on its island
+// and the one it has here before it is quarantined
+const FITNESS_TOLERANCE: f32 = 1e-4;
+// Quarantined migrants an island keeps for the diagnostics, the others are
+// only counted
+const QUARANTINE_HELD: usize = 10;
+// How often an island waiting for migrants checks that no island panicked
+const STOP_POLL: Duration = Duration::from_millis(100);
+
+// How islands exchange individuals, whatever runs them. Every `interval`
+// generations each island sends copies of `migrants` of its individuals to
+// every island the topology names, which takes them in, or their offspring
+// when pollinating, as `acceptance` says. With `broadcast_best` every island also
+// receives the best individual found by any island so far.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationPolicy {
+    pub interval: usize,
+    pub migrants: usize,
+    pub selection: MigrantSelection,
+    pub acceptance: MigrantAcceptance,
+    pub mode: MigrationMode,
+    pub broadcast_best: bool,
+}
+
+impl MigrationPolicy {
+    // Never with an interval of zero, the islands then evolve in isolation
+    pub fn migrates_after(&self, generation: usize) -> bool {
+        (generation + 1).is_multiple_of(self.interval)
+    }
+}
+
+impl Default for MigrationPolicy {
+    fn default() -> Self {
+        MigrationPolicy {
+            interval: 10,
+            migrants: 5,
+            selection: MigrantSelection::Best,
+            acceptance: MigrantAcceptance::ReplaceWorst,
+            mode: MigrationMode::Migrate,
+            broadcast_best: false,
+        }
+    }
+}
+
+// What arrives on an island
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MigrationMode {
+    // The migrants themselves
+    Migrate,
+    // Hybrids of every migrant and a random resident, the migrants only lend
+    // their genes
+    Pollinate,
+}
+
+impl MigrationMode {
+    pub const ALL: [MigrationMode; 2] = [MigrationMode::Migrate, MigrationMode::Pollinate];
+}
+
+impl fmt::Display for MigrationMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            MigrationMode::Migrate => "migrate",
+            MigrationMode::Pollinate => "pollinate",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for MigrationMode {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        MigrationMode::ALL
+            .into_iter()
+            .find(|mode| mode.to_string() == name)
+            .ok_or_else(|| format!("unknown migration mode: {}", name))
+    }
+}
+
+// Which individuals an island sends
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MigrantSelection {
+    Best,
+    Random,
+    // The best one, then every next one as far as possible from those already
+    // chosen, so the migrants don't all come from the same basin
+    Diverse,
+}
+
+impl MigrantSelection {
+    pub const ALL: [MigrantSelection; 3] = [
+        MigrantSelection::Best,
+        MigrantSelection::Random,
+        MigrantSelection::Diverse,
+    ];
+}
+
+// Whom the migrants replace on the island receiving them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MigrantAcceptance {
+    ReplaceWorst,
+    // The resident closest to the migrant, only when the migrant is fitter
+    ReplaceSimilar,
+    // A random resident, with a probability growing as the migrant is fitter
+    // than it
+    Probabilistic,
+}
+
+impl MigrantAcceptance {
+    pub const ALL: [MigrantAcceptance; 3] = [
+        MigrantAcceptance::ReplaceWorst,
+        MigrantAcceptance::ReplaceSimilar,
+        MigrantAcceptance::Probabilistic,
+    ];
+}
+
+impl fmt::Display for MigrantSelection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            MigrantSelection::Best => "best",
+            MigrantSelection::Random => "random",
+            MigrantSelection::Diverse => "diverse",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for MigrantSelection {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        MigrantSelection::ALL
+            .into_iter()
+            .find(|selection| selection.to_string() == name)
+            .ok_or_else(|| format!("unknown migrant selection: {}", name))
+    }
+}
+
+impl fmt::Display for MigrantAcceptance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            MigrantAcceptance::ReplaceWorst => "worst",
+            MigrantAcceptance::ReplaceSimilar => "similar",
+            MigrantAcceptance::Probabilistic => "probabilistic",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for MigrantAcceptance {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        MigrantAcceptance::ALL
+            .into_iter()
+            .find(|acceptance| acceptance.to_string() == name)
+            .ok_or_else(|| format!("unknown migrant acceptance: {}", name))
+    }
+}
+
+// Copies of `count` individuals chosen by `selection`
+pub fn select_migrants<T, R>(
+    population: &[T],
+    count: usize,
+    selection: MigrantSelection,
+    rng: &mut R,
+) -> Vec<T>
+where
+    T: Organism + Distance + GenomeHash + Validate + Clone,
+    R: Rng + ?Sized,
+{
+    let count = count.min(population.len());
+    let chosen = match selection {
+        MigrantSelection::Best => ranked(population).into_iter().take(count).collect(),
+        MigrantSelection::Random => {
+            rand::seq::index::sample(rng, population.len(), count).into_vec()
+        }
+        MigrantSelection::Diverse => farthest_points(population, count),
+    };
+
+    chosen.into_iter().map(|i| population[i].clone()).collect()
+}
+
+// Indices of the population, fittest first
+fn ranked<T: Organism>(population: &[T]) -> Vec<usize> {
+    let fitness = population.iter().map(T::fitness).collect::<Vec<_>>();
+    let mut indices = (0..population.len()).collect::<Vec<usize>>();
+    indices.sort_by(|a, b| fitness[*a].compare(&fitness[*b]));
+    indices
+}
+
+fn farthest_points<T: Organism + Distance + GenomeHash + Validate>(
+    population: &[T],
+    count: usize,
+) -> Vec<usize> {
+    let Some(best) = ranked(population).first().copied() else {
+        return Vec::new();
+    };
+
+    // Distance of every individual to the closest one chosen so far
+    let mut closest = population
+        .iter()
+        .map(|individual| individual.distance(&population[best]))
+        .collect::<Vec<f32>>();
+    let mut chosen = vec![best];
+
+    while chosen.len() < count {
+        let farthest = (0..population.len())
+            .max_by(|a, b| closest[*a].total_cmp(&closest[*b]))
+            .unwrap();
+        chosen.push(farthest);
+        for (i, individual) in population.iter().enumerate() {
+            closest[i] = closest[i].min(individual.distance(&population[farthest]));
+        }
+    }
+
+    chosen
+}
+
+// Migrants an island refused, the first few kept along with why
+#[derive(Clone, Debug)]
+pub struct Quarantine<T> {
+    pub refused: usize,
+    pub held: Vec<(String, T)>,
+}
+
+impl<T> Default for Quarantine<T> {
+    fn default() -> Self {
+        Quarantine {
+            refused: 0,
+            held: Vec::new(),
+        }
+    }
+}
+
+impl<T> Quarantine<T> {
+    pub fn admit(&mut self, reason: String, migrant: T) {
+        self.refused += 1;
+        if self.held.len() < QUARANTINE_HELD {
+            self.held.push((reason, migrant));
+        }
+    }
+}
+
+// Splits what arrived, with the fitness every migrant had where it came from,
+// into the migrants fit for this island and the refused ones with the reason.
+// A migrant bred for another instance, or by a buggy island, is refused
+// before it can take the place of a resident.
+pub fn validate_migrants<T>(arrivals: Vec<(f32, T)>) -> (Vec<T>, Vec<(String, T)>)
+where
+    T: Organism + Validate,
+{
+    let mut valid = Vec::with_capacity(arrivals.len());
+    let mut refused = Vec::new();
+
+    for (claimed, migrant) in arrivals {
+        let problem = migrant.validate().err().or_else(|| {
+            let fitness = migrant.score();
+            if !fitness.is_finite() {
+                Some(format!("its fitness is {}", fitness))
+            } else if (fitness - claimed).abs() > FITNESS_TOLERANCE * claimed.abs().max(1.0) {
+                Some(format!(
+                    "its fitness is {} here but was {} where it came from",
+                    fitness, claimed
+                ))
+            } else {
+                None
+            }
+        });
+        match problem {
+            Some(problem) => refused.push((problem, migrant)),
+            None => valid.push(migrant),
+        }
+    }
+
+    (valid, refused)
+}
+
+// What became of the migrants an island received
+#[derive(Debug, Default)]
+pub struct Reception<T> {
+    pub duplicates: usize,
+    pub refused: Vec<(String, T)>,
+}
+
+// Takes in what other islands sent, with the fitness the migrants had there,
+// as `policy` says
+pub fn receive_migrants<T, R>(
+    population: &mut [T],
+    arrivals: Vec<(f32, T)>,
+    policy: &MigrationPolicy,
+    rng: &mut R,
+) -> Reception<T>
+where
+    T: Organism + Distance + GenomeHash + Validate,
+    R: Rng + ?Sized,
+{
+    let (migrants, refused) = validate_migrants(arrivals);
+    let arrived = match policy.mode {
+        MigrationMode::Migrate => migrants,
+        MigrationMode::Pollinate => pollinate(population, &migrants, rng),
+    };
+    let (arrived, duplicates) = deduplicate(population, arrived);
+    accept_migrants(population, arrived, policy.acceptance, rng);
+
+    Reception {
+        duplicates,
+        refused,
+    }
+}
+
+// The arrivals whose genome is not on the island yet, each once, and how many
+// were dropped. Without it the best tours pile up copies of themselves on every
+// island they reach.
+pub fn deduplicate<T: GenomeHash>(population: &[T], arrivals: Vec<T>) -> (Vec<T>, usize) {
+    let mut seen = population
+        .iter()
+        .map(T::genome_hash)
+        .collect::<HashSet<u64>>();
+    let arrived = arrivals.len();
+    let unique = arrivals
+        .into_iter()
+        .filter(|arrival| seen.insert(arrival.genome_hash()))
+        .collect::<Vec<T>>();
+    let duplicates = arrived - unique.len();
+    (unique, duplicates)
+}
+
+// The breeding hook of the receiving island, every migrant crosses with a
+// random resident
+pub fn pollinate<T, R>(population: &[T], pollen: &[T], rng: &mut R) -> Vec<T>
+where
+    T: Organism,
+    R: Rng + ?Sized,
+{
+    if population.is_empty() {
+        return Vec::new();
+    }
+
+    pollen
+        .iter()
+        .map(|migrant| {
+            let resident = rng.gen_range(0..population.len());
+            population[resident].cross_over(migrant, rng)
+        })
+        .collect()
+}
+
+// The population keeps its size whatever the migrants replace
+pub fn accept_migrants<T, R>(
+    population: &mut [T],
+    migrants: Vec<T>,
+    acceptance: MigrantAcceptance,
+    rng: &mut R,
+) where
+    T: Organism + Distance + GenomeHash + Validate,
+    R: Rng + ?Sized,
+{
+    match acceptance {
+        MigrantAcceptance::ReplaceWorst => {
+            let worst_first = ranked(population).into_iter().rev();
+            for (worst, migrant) in worst_first.zip(migrants) {
+                population[worst] = migrant;
+            }
+        }
+        MigrantAcceptance::ReplaceSimilar => {
+            for migrant in migrants {
+                let Some(similar) = (0..population.len()).min_by(|a, b| {
+                    migrant
+                        .distance(&population[*a])
+                        .total_cmp(&migrant.distance(&population[*b]))
+                }) else {
+                    return;
+                };
+                if migrant
+                    .fitness()
+                    .compare(&population[similar].fitness())
+                    .is_lt()
+                {
+                    population[similar] = migrant;
+                }
+            }
+        }
+        MigrantAcceptance::Probabilistic => {
+            for migrant in migrants {
+                if population.is_empty() {
+                    return;
+                }
+                let resident = rng.gen_range(0..population.len());
+                let probability =
+                    acceptance_probability(migrant.score(), population[resident].score());
+                if rng.gen_bool(probability) {
+                    population[resident] = migrant;
+                }
+            }
+        }
+    }
+}
+
+// Odds of a migrant of fitness `theirs` replacing a resident of fitness
+// `ours`: even at equal fitness, zero included, and growing with how much
+// fitter the migrant is relative to the larger of the two. An invalid
+// resident always goes, an invalid migrant never comes in.
+fn acceptance_probability(theirs: f32, ours: f32) -> f64 {
+    if !ours.is_finite() {
+        return 1.0;
+    }
+    if !theirs.is_finite() {
+        return 0.0;
+    }
+    let (theirs, ours) = (theirs as f64, ours as f64);
+    let scale = theirs.abs().max(ours.abs()).max(f64::MIN_POSITIVE);
+    1.0 / (1.0 + ((theirs - ours) / scale).exp())
+}
+
+#[derive(Clone, Debug)]
+pub struct IslandOutcome<T> {
+    pub island: usize,
+    // Statistics of the last generation the island evaluated
+    pub summary: FitnessSummary,
+    pub best: Option<(f32, T)>,
+    pub migrations: usize,
+    // Arrivals dropped as copies of individuals already on the island
+    pub duplicates: usize,
+    pub quarantine: Quarantine<T>,
+    // Evaluations of the generations the island bred
+    pub evaluations: u64,
+}
+
+// Runs `islands` populations on threads of this process, for machines without
+// MPI. Island i starts from `make_population(i)` and sends its migrants over
+// channels to the islands `topology` names. An island waits for the migrants
+// of all the islands sending to it before going on, so they stay in step like
+// MPI ranks would and island i draws from `seed + i` whatever the threads do.
+// The best individual broadcast to every island is kept on a board they share.
+// Under a `budget` every island reports to a coordinator they share at each
+// migration, and stops breeding once it decides so.
+#[allow(clippy::too_many_arguments)]
+pub fn run_thread_islands<T, F>(
+    islands: usize,
+    parameters: RunParameters,
+    selection: Arc<dyn Selection>,
+    policy: MigrationPolicy,
+    topology: Arc<dyn Topology>,
+    budget: Option<BudgetPolicy>,
+    seed: u64,
+    make_population: F,
+) -> Vec<IslandOutcome<T>>
+where
+    T: Organism + Distance + GenomeHash + Validate + Clone + Sync + Send + Debug,
+    F: Fn(usize) -> Vec<T> + Sync,
+{
+    let islands = islands.max(1);
+    let (senders, receivers): (Vec<_>, Vec<_>) =
+        (0..islands).map(|_| channel::<Shipment<T>>()).unzip();
+    let champion = Mutex::new(None);
+    let stopped = AtomicBool::new(false);
+    let board = budget.map(|budget| BudgetBoard::new(budget, islands));
+
+    thread::scope(|scope| {
+        let handles = receivers
+            .into_iter()
+            .enumerate()
+            .map(|(island, inbox)| {
+                let make_population = &make_population;
+                let champion = &champion;
+                let stopped = &stopped;
+                let board = board.as_ref();
+                let budget = budget.map(|budget| IslandBudget::new(budget, islands, island));
+                let selection = selection.clone();
+                let mailbox = Mailbox {
+                    island,
+                    topology: topology.clone(),
+                    inbox,
+                    outboxes: senders.clone(),
+                    early: Vec::new(),
+                };
+                thread::Builder::new()
+                    .name(format!("island-{}", island))
+                    .spawn_scoped(scope, move || {
+                        let _stopped = Stopped(stopped);
+                        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(island as u64));
+                        let solver = PopulationSolver::new(make_population(island), parameters)
+                            .with_selection(selection)
+                            .with_seed(rng.gen());
+                        let shared = Shared {
+                            champion,
+                            board,
+                            stopped,
+                        };
+                        evolve_island(solver, policy, mailbox, budget, shared, rng)
+                    })
+                    .expect("Failed to start an island")
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("An island panicked"))
+            .collect()
+    })
+}
+
+// Migrants an island sent at one of its migrations
+struct Shipment<T> {
+    migration: usize,
+    source: usize,
+    migrants: Vec<(f32, T)>,
+}
+
+// The channels of an island, to every island since a topology may change
+// between migrations
+struct Mailbox<T> {
+    island: usize,
+    topology: Arc<dyn Topology>,
+    inbox: Receiver<Shipment<T>>,
+    outboxes: Vec<Sender<Shipment<T>>>,
+    // Shipments of a later migration, from islands already past this one
+    early: Vec<Shipment<T>>,
+}
+
+impl<T: Clone> Mailbox<T> {
+    // Sends `migrants` to the targets of this island and returns the migrants
+    // of its sources, in the order of the sources
+    fn exchange(
+        &mut self,
+        migration: usize,
+        migrants: Vec<(f32, T)>,
+        stopped: &AtomicBool,
+    ) -> Vec<(f32, T)> {
+        let islands = self.outboxes.len();
+        for target in self.topology.targets(self.island, islands, migration) {
+            let shipment = Shipment {
+                migration,
+                source: self.island,
+                migrants: migrants.clone(),
+            };
+            self.outboxes[target]
+                .send(shipment)
+                .expect("An island stopped");
+        }
+
+        let expected = topology::sources(&*self.topology, self.island, islands, migration).len();
+        let (mut arrived, early) = std::mem::take(&mut self.early)
+            .into_iter()
+            .partition::<Vec<_>, _>(|shipment| shipment.migration == migration);
+        self.early = early;
+        while arrived.len() < expected {
+            match self.inbox.recv_timeout(STOP_POLL) {
+                Ok(shipment) if shipment.migration == migration => arrived.push(shipment),
+                Ok(shipment) => self.early.push(shipment),
+                Err(RecvTimeoutError::Timeout) if !stopped.load(Ordering::Relaxed) => {}
+                Err(_) => panic!("Island {} lost an island sending to it", self.island),
+            }
+        }
+        arrived.sort_by_key(|shipment| shipment.source);
+        arrived
+            .into_iter()
+            .flat_map(|shipment| shipment.migrants)
+            .collect()
+    }
+}
+
+// The coordinator of the budget, which settles the reports of a migration in
+// the order of the islands once they all came in, whatever thread came first.
+// No island goes past a migration before it is settled, so only the decisions
+// of the last one are kept.
+struct BudgetBoard {
+    rounds: Mutex<BudgetRounds>,
+    settled: Condvar,
+}
+
+struct BudgetRounds {
+    coordinator: BudgetCoordinator,
+    reports: Vec<BudgetReport>,
+    // Migrations settled so far, and the decisions of the last one by island
+    settled: usize,
+    decisions: Vec<BudgetDecision>,
+}
+
+impl BudgetBoard {
+    fn new(budget: BudgetPolicy, islands: usize) -> Self {
+        BudgetBoard {
+            rounds: Mutex::new(BudgetRounds {
+                coordinator: BudgetCoordinator::new(budget.evaluations, islands, budget.stagnation),
+                reports: Vec::new(),
+                settled: 0,
+                decisions: Vec::new(),
+            }),
+            settled: Condvar::new(),
+        }
+    }
+
+    fn settle(
+        &self,
+        migration: usize,
+        report: BudgetReport,
+        stopped: &AtomicBool,
+    ) -> BudgetDecision {
+        let mut rounds = self.rounds.lock().unwrap();
+        rounds.reports.push(report);
+        if rounds.reports.len() == rounds.coordinator.islands() {
+            let mut reports = std::mem::take(&mut rounds.reports);
+            reports.sort_by_key(|report| report.island);
+            rounds.decisions = reports
+                .into_iter()
+                .map(|report| rounds.coordinator.report(report))
+                .collect();
+            rounds.settled = migration + 1;
+            self.settled.notify_all();
+        }
+        while rounds.settled <= migration {
+            if stopped.load(Ordering::Relaxed) {
+                panic!(
+                    "Island {} lost an island reporting to the budget",
+                    report.island
+                );
+            }
+            rounds = self.settled.wait_timeout(rounds, STOP_POLL).unwrap().0;
+        }
+        rounds.decisions[report.island]
+    }
+}
+
+// What the thread islands share
+struct Shared<'a, T> {
+    champion: &'a Mutex<Option<(f32, T)>>,
+    board: Option<&'a BudgetBoard>,
+    stopped: &'a AtomicBool,
+}
+
+// Raised when an island unwinds, so the others stop waiting for its migrants
+struct Stopped<'a>(&'a AtomicBool);
+
+impl Drop for Stopped<'_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+fn evolve_island<T>(
+    mut solver: PopulationSolver<T>,
+    policy: MigrationPolicy,
+    mut mailbox: Mailbox<T>,
+    mut budget: Option<IslandBudget>,
+    shared: Shared<T>,
+    mut rng: StdRng,
+) -> IslandOutcome<T>
+where
+    T: Organism + Distance + GenomeHash + Validate + Clone + Sync + Send + Debug,
+{
+    let island = mailbox.island;
+    let generation_cost = solver.parameters().population_size as u64;
+    let mut summary = FitnessSummary::new();
+    let mut migrations = 0;
+    let mut duplicates = 0;
+    let mut quarantine = Quarantine::default();
+
+    for generation in 0..solver.parameters().iterations {
+        // An island out of budget still takes part in the migrations
+        if budget
+            .as_mut()
+            .is_none_or(|budget| budget.spend(generation_cost))
+        {
+            summary = solver.step();
+            info!(
+                "Island {} generation {}, best: {}, mean: {:.3}",
+                island,
+                generation,
+                summary.best,
+                summary.mean()
+            );
+        }
+
+        if policy.migrates_after(generation) {
+            if let (Some(budget), Some(board)) = (budget.as_mut(), shared.board) {
+                let best = best_of(solver.population()).map_or(f32::INFINITY, |(best, _)| best);
+                let decision = board.settle(migrations, budget.report(best), shared.stopped);
+                if decision == BudgetDecision::Terminate && !budget.is_terminated() {
+                    info!(
+                        "Island {} stops breeding after {} evaluations",
+                        island,
+                        budget.evaluations()
+                    );
+                }
+                budget.apply(decision);
+            }
+            let migrants = select_migrants(
+                solver.population(),
+                policy.migrants,
+                policy.selection,
+                &mut rng,
+            );
+            let migrants = migrants
+                .into_iter()
+                .map(|migrant| (migrant.score(), migrant))
+                .collect();
+            let mut arrived = mailbox.exchange(migrations, migrants, shared.stopped);
+            if policy.broadcast_best {
+                arrived.extend(post_best(shared.champion, solver.population()));
+            }
+
+            let reception = receive_migrants(solver.population_mut(), arrived, &policy, &mut rng);
+            duplicates += reception.duplicates;
+            for (reason, migrant) in reception.refused {
+                warn!(
+                    "Island {} quarantined a migrant after generation {}: {}",
+                    island, generation, reason
+                );
+                quarantine.admit(reason, migrant);
+            }
+            migrations += 1;
+        }
+    }
+
+    IslandOutcome {
+        island,
+        summary,
+        best: best_of(solver.population()),
+        migrations,
+        duplicates,
+        quarantine,
+        evaluations: budget.map_or(
+            generation_cost * solver.parameters().iterations as u64,
+            |budget| budget.evaluations(),
+        ),
+    }
+}
+
+fn best_of<T: Organism + Clone>(population: &[T]) -> Option<(f32, T)> {
+    population
+        .iter()
+        .map(|individual| (individual.fitness(), individual))
+        .min_by(|a, b| a.0.compare(&b.0))
+        .map(|(fitness, individual)| (fitness.to_f32(), individual.clone()))
+}
+
+// Puts the best one of the island on the board when it beats the one there,
+// and returns a copy of the one on the board
+fn post_best<T: Organism + Clone>(
+    champion: &Mutex<Option<(f32, T)>>,
+    population: &[T],
+) -> Option<(f32, T)> {
+    let mut champion = champion.lock().unwrap();
+    if let Some((fitness, best)) = best_of(population) {
+        if champion
+            .as_ref()
+            .is_none_or(|(champion, _)| fitness < *champion)
+        {
+            *champion = Some((fitness, best));
+        }
+    }
+    champion.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tsp::matrix::WeightMatrix;
+    use crate::tsp::{TspProblem, TspSolution, TSP};
+
+    #[test]
+    fn even_odds_at_equal_fitness() {
+        assert_eq!(acceptance_probability(0.0, 0.0), 0.5);
+        assert_eq!(acceptance_probability(42.0, 42.0), 0.5);
+        assert_eq!(acceptance_probability(-3.0, -3.0), 0.5);
+    }
+
+    #[test]
+    fn fitter_migrants_are_likelier() {
+        assert!(acceptance_probability(1.0, 2.0) > 0.5);
+        assert!(acceptance_probability(2.0, 1.0) < 0.5);
+        assert!(acceptance_probability(-2.0, 1.0) > 0.5);
+        assert!(acceptance_probability(0.0, 1.0) > acceptance_probability(0.5, 1.0));
+        assert_eq!(acceptance_probability(1.0, f32::INFINITY), 1.0);
+        assert_eq!(acceptance_probability(f32::NAN, 1.0), 0.0);
+        for (theirs, ours) in [(0.0, -0.0), (f32::MAX, -f32::MAX), (1e-30, 0.0)] {
+            let probability = acceptance_probability(theirs, ours);
+            assert!((0.0..=1.0).contains(&probability));
+        }
+    }
+
+    #[test]
+    fn zero_fitness_migrants_are_accepted_without_panicking() {
+        // Every city at the same place, so every tour is 0 long
+        let weights = Arc::new(WeightMatrix::from_rows(&vec![vec![0.0; 4]; 4]));
+        let problem = Arc::new(TspProblem::new(weights));
+        let mut population = (0..5)
+            .map(|_| TSP::new(problem.clone(), TspSolution::new(4)))
+            .collect::<Vec<TSP>>();
+        let migrants = population.clone();
+
+        let mut rng = StdRng::seed_from_u64(1);
+        accept_migrants(
+            &mut population,
+            migrants,
+            MigrantAcceptance::Probabilistic,
+            &mut rng,
+        );
+        assert_eq!(population.len(), 5);
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"GAJ1";
+
+// How an individual of the next generation was produced, parents are ranks in
+// the sorted population of the generation that bred it
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Origin {
+    Elite {
+        parent: u32,
+    },
+    Offspring {
+        first_parent: u32,
+        second_parent: u32,
+        crossed_over: bool,
+        mutated: bool,
+    },
+    // Drawn from the edge model of the elite, see `--eda-interval`
+    Sampled,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum JournalEvent {
+    RunStarted {
+        instance: String,
+        population_size: u32,
+        elite: u32,
+        crossover: String,
+        mutation: String,
+    },
+    // `ranking[rank]` is the position in the population of the generation
+    Selection {
+        generation: u32,
+        best: f32,
+        ranking: Vec<u32>,
+    },
+    // `children[position]` built the next generation, the crossover and
+    // mutation decisions of child k were drawn from a generator seeded with
+    // `seed + k`
+    Variation {
+        generation: u32,
+        seed: u64,
+        children: Vec<Origin>,
+    },
+}
+
+// Append only, every record is a little endian length followed by the
+// bincode encoded event so a truncated tail only loses the last record
+pub struct JournalWriter {
+    output: BufWriter<File>,
+}
+
+impl JournalWriter {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if file.metadata()?.len() == 0 {
+            file.write_all(MAGIC)?;
+        }
+
+        Ok(JournalWriter {
+            output: BufWriter::new(file),
+        })
+    }
+
+    pub fn record(&mut self, event: &JournalEvent) -> io::Result<()> {
+        let encoded = bincode::serialize(event).map_err(io::Error::other)?;
+        self.output
+            .write_all(&(encoded.len() as u32).to_le_bytes())?;
+        self.output.write_all(&encoded)?;
+        // Flushed per event, the journal is meant to survive a crash
+        self.output.flush()
+    }
+}
+
+pub fn read(path: &Path) -> io::Result<Vec<JournalEvent>> {
+    let mut input = BufReader::new(File::open(path)?);
+
+    let mut magic = [0; 4];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a journal file",
+        ));
+    }
+
+    let mut events = Vec::new();
+    loop {
+        let mut length = [0; 4];
+        match input.read_exact(&mut length) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(error),
+        }
+
+        let mut encoded = vec![0; u32::from_le_bytes(length) as usize];
+        if input.read_exact(&mut encoded).is_err() {
+            // A record cut short by a crash, everything before it is fine
+            break;
+        }
+        events.push(bincode::deserialize(&encoded).map_err(io::Error::other)?);
+    }
+
+    Ok(events)
+}
+
+// Follows the first parent of the individual ranked `rank` in `generation`
+// back to the initial population, one step per generation
+pub fn ancestry(events: &[JournalEvent], generation: u32, rank: u32) -> Vec<(u32, u32, Origin)> {
+    let ranking = |wanted: u32| {
+        events.iter().rev().find_map(|event| match event {
+            JournalEvent::Selection {
+                generation,
+                ranking,
+                ..
+            } if *generation == wanted => Some(ranking),
+            _ => None,
+        })
+    };
+    let variation = |wanted: u32| {
+        events.iter().rev().find_map(|event| match event {
+            JournalEvent::Variation {
+                generation,
+                children,
+                ..
+            } if *generation == wanted => Some(children),
+            _ => None,
+        })
+    };
+
+    let mut steps = Vec::new();
+    let mut current = (generation, rank);
+    while current.0 > 0 {
+        let (generation, rank) = current;
+        let Some(position) = ranking(generation).and_then(|ranking| ranking.get(rank as usize))
+        else {
+            break;
+        };
+        let Some(origin) =
+            variation(generation - 1).and_then(|children| children.get(*position as usize))
+        else {
+            break;
+        };
+
+        steps.push((generation, rank, *origin));
+        current = match origin {
+            Origin::Elite { parent } => (generation - 1, *parent),
+            Origin::Offspring { first_parent, .. } => (generation - 1, *first_parent),
+            Origin::Sampled => break,
+        };
+    }
+
+    steps
+}
@@ -0,0 +1,134 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod ablation;
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod backend;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod budget;
+#[cfg(feature = "std")]
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub mod cli;
+#[cfg(feature = "std")]
+pub mod clustering;
+#[cfg(feature = "std")]
+pub mod comm;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "database")]
+pub mod database;
+#[cfg(feature = "std")]
+pub mod distributed;
+#[cfg(feature = "std")]
+pub mod distributed_islands;
+#[cfg(feature = "std")]
+pub mod driver;
+#[cfg(feature = "std")]
+pub mod edge_model;
+#[cfg(feature = "std")]
+pub mod edge_statistics;
+#[cfg(feature = "std")]
+pub mod genetic_algorithm;
+#[cfg(feature = "std")]
+pub mod initialization;
+#[cfg(feature = "std")]
+pub mod islands;
+#[cfg(feature = "std")]
+pub mod journal;
+#[cfg(feature = "std")]
+pub mod local_search;
+#[cfg(feature = "std")]
+pub mod logging;
+#[cfg(feature = "std")]
+pub mod memory;
+#[cfg(feature = "std")]
+pub mod objective;
+#[cfg(feature = "std")]
+pub mod operator_schedule;
+#[cfg(feature = "std")]
+pub mod organism;
+#[cfg(feature = "std")]
+pub mod parallel;
+#[cfg(feature = "std")]
+pub mod permutation;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+pub mod portable;
+#[cfg(feature = "std")]
+pub mod portfolio;
+#[cfg(feature = "std")]
+pub mod recommend;
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub mod repl;
+#[cfg(feature = "std")]
+pub mod robustness;
+#[cfg(feature = "std")]
+pub mod runner;
+#[cfg(feature = "std")]
+pub mod scheduler;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+#[cfg(feature = "std")]
+pub mod selection;
+#[cfg(feature = "std")]
+pub mod self_test;
+#[cfg(feature = "std")]
+pub mod simulation;
+#[cfg(feature = "std")]
+pub mod statistics;
+#[cfg(feature = "std")]
+pub mod streaming;
+#[cfg(feature = "std")]
+pub mod termination;
+#[cfg(feature = "std")]
+pub mod tools;
+#[cfg(feature = "std")]
+pub mod topology;
+#[cfg(feature = "std")]
+pub mod transport;
+#[cfg(feature = "std")]
+pub mod tsp;
+#[cfg(feature = "std")]
+pub mod tuning;
+#[cfg(feature = "std")]
+pub mod warm_start;
+
+// The engine, what a crate evolving its own organisms needs. The other modules
+// are the pieces the solver binaries are made of.
+#[cfg(feature = "std")]
+pub use self::genetic_algorithm::{
+    ga_evaluate_population, ga_iteraration, GeneticAlgorithm, GeneticAlgorithmBuilder,
+};
+#[cfg(feature = "std")]
+pub use self::islands::{run_thread_islands, MigrationPolicy};
+#[cfg(feature = "std")]
+pub use self::objective::{Composite, CompositeFitness, CompositeFitnessBuilder};
+#[cfg(feature = "std")]
+pub use self::organism::{
+    Distance, GenomeDistance, GenomeHash, Individual, Organism, Problem, Validate,
+};
+#[cfg(feature = "std")]
+pub use self::runner::{PopulationSolver, RunParameters, Solver};
+#[cfg(feature = "std")]
+pub use self::selection::{
+    AdjacentSelection, RankSelection, RouletteSelection, Selection, TournamentSelection,
+};
+#[cfg(feature = "std")]
+pub use self::statistics::{FitnessSummary, GenerationStats, ObjectiveSummary, RunHistory};
+#[cfg(feature = "std")]
+pub use self::termination::{
+    MaxGenerations, Progress, Stagnation, StopReason, TargetFitness, Termination, TimeLimit,
+};
+#[cfg(feature = "std")]
+pub use self::topology::Topology;
+#[cfg(feature = "std")]
+pub use self::tsp::{TspInstance, TspProblem, TspSolution, TSP};
@@ -0,0 +1,382 @@
+use crate::permutation::DistanceMatrix;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+
+// Which individuals get polished by local search before they are evaluated
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MemeticPolicy {
+    Off,
+    // Only children already shorter than both of their parents, polishing
+    // every child costs far more than the rest of a generation
+    ImprovedOffspring,
+    AllOffspring,
+    // The elite carried over to the next generation and none of the children
+    Elites,
+}
+
+impl MemeticPolicy {
+    pub const ALL: [MemeticPolicy; 4] = [
+        MemeticPolicy::Off,
+        MemeticPolicy::ImprovedOffspring,
+        MemeticPolicy::AllOffspring,
+        MemeticPolicy::Elites,
+    ];
+
+    pub fn polishes(&self, child: f32, first_parent: f32, second_parent: f32) -> bool {
+        match self {
+            MemeticPolicy::Off | MemeticPolicy::Elites => false,
+            MemeticPolicy::ImprovedOffspring => child < first_parent && child < second_parent,
+            MemeticPolicy::AllOffspring => true,
+        }
+    }
+
+    pub fn polishes_elites(&self) -> bool {
+        *self == MemeticPolicy::Elites
+    }
+}
+
+impl fmt::Display for MemeticPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            MemeticPolicy::Off => "off",
+            MemeticPolicy::ImprovedOffspring => "improved-offspring",
+            MemeticPolicy::AllOffspring => "all-offspring",
+            MemeticPolicy::Elites => "elites",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for MemeticPolicy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        MemeticPolicy::ALL
+            .into_iter()
+            .find(|policy| policy.to_string() == name)
+            .ok_or_else(|| format!("unknown memetic policy: {}", name))
+    }
+}
+
+// How many individuals a generation may still polish, handed out in the
+// order of the population once it is bred so the same seed polishes the
+// same individuals whatever the threads do
+pub struct PolishBudget {
+    left: usize,
+    used: usize,
+}
+
+impl PolishBudget {
+    // Unlimited without a cap
+    pub fn new(cap: Option<usize>) -> Self {
+        PolishBudget {
+            left: cap.unwrap_or(usize::MAX),
+            used: 0,
+        }
+    }
+
+    pub fn take(&mut self) -> bool {
+        let taken = self.left > 0;
+        if taken {
+            self.left -= 1;
+            self.used += 1;
+        }
+        taken
+    }
+
+    pub fn used(&self) -> usize {
+        self.used
+    }
+}
+
+// First improvement 2-opt over a tour, or an open path unless `closed`, at
+// most `passes` sweeps over every pair of edges and less once no reversal
+// shortens it. Assumes symmetric distances, the reversed segment is not
+// repriced. Returns how much shorter the path got.
+pub fn two_opt<D>(path: &mut [usize], distances: &D, passes: usize, closed: bool) -> f32
+where
+    D: DistanceMatrix + ?Sized,
+{
+    let length = path.len();
+    let mut gained = 0.0;
+
+    for _ in 0..passes {
+        let mut improved = false;
+        // Reversing path[i..=j] replaces the edges entering and leaving it, a
+        // segment at either end of an open path has one edge less to replace
+        // while the tour wraps around to the other end
+        for i in 0..length.saturating_sub(1) {
+            for j in i + 1..length {
+                if i == 0 && j == length - 1 {
+                    continue;
+                }
+                let before = match i.checked_sub(1) {
+                    Some(before) => Some(path[before]),
+                    None => closed.then(|| path[length - 1]),
+                };
+                let after = match path.get(j + 1) {
+                    Some(after) => Some(*after),
+                    None => closed.then(|| path[0]),
+                };
+                let edge = |from: Option<usize>, to: Option<usize>| match (from, to) {
+                    (Some(from), Some(to)) => distances.distance(from, to),
+                    _ => 0.0,
+                };
+
+                let current = edge(before, Some(path[i])) + edge(Some(path[j]), after);
+                let reversed = edge(before, Some(path[j])) + edge(Some(path[i]), after);
+                if reversed < current {
+                    path[i..=j].reverse();
+                    gained += current - reversed;
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    gained
+}
+
+// A neighborhood of moves improving a tour, or an open path unless `closed`
+pub trait LocalSearch: Debug + Send + Sync {
+    // One sweep over the neighborhood taking every move that shortens the
+    // path as it is found, returns how much shorter it got
+    fn sweep(&self, path: &mut [usize], distances: &dyn DistanceMatrix, closed: bool) -> f32;
+
+    // At most `passes` sweeps, fewer once one finds nothing
+    fn improve(
+        &self,
+        path: &mut [usize],
+        distances: &dyn DistanceMatrix,
+        passes: usize,
+        closed: bool,
+    ) -> f32 {
+        let mut gained = 0.0;
+        for _ in 0..passes {
+            let gain = self.sweep(path, distances, closed);
+            if gain <= 0.0 {
+                break;
+            }
+            gained += gain;
+        }
+        gained
+    }
+}
+
+// Every neighborhood in turn within each sweep, so the cheap ones go first
+// and the costly ones only look at what they left
+impl LocalSearch for Vec<Box<dyn LocalSearch>> {
+    fn sweep(&self, path: &mut [usize], distances: &dyn DistanceMatrix, closed: bool) -> f32 {
+        self.iter()
+            .map(|search| search.sweep(path, distances, closed))
+            .fold(0.0, |gained, gain| gained + gain)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TwoOpt;
+
+impl LocalSearch for TwoOpt {
+    fn sweep(&self, path: &mut [usize], distances: &dyn DistanceMatrix, closed: bool) -> f32 {
+        two_opt(path, distances, 1, closed)
+    }
+}
+
+// Moves a segment of up to `max_segment` cities elsewhere in the path, as it
+// is or reversed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrOpt {
+    pub max_segment: usize,
+}
+
+impl LocalSearch for OrOpt {
+    fn sweep(&self, path: &mut [usize], distances: &dyn DistanceMatrix, closed: bool) -> f32 {
+        let length = path.len();
+        let edge = |from: Option<usize>, to: Option<usize>| match (from, to) {
+            (Some(from), Some(to)) => distances.distance(from, to),
+            _ => 0.0,
+        };
+        let mut gained = 0.0;
+
+        for size in 1..=self.max_segment {
+            // Two cities at least stay around the segment
+            if size + 2 > length {
+                break;
+            }
+            let left = length - size;
+            for i in 0..=left {
+                let (first, last) = (path[i], path[i + size - 1]);
+                // The cities left around the segment, and the neighbors of
+                // the slot before the `slot`th of them. The last slot of a
+                // tour is the same as the first.
+                let rest = |index: usize| path[if index < i { index } else { index + size }];
+                let neighbors = |slot: usize| {
+                    let before = match slot.checked_sub(1) {
+                        Some(before) => Some(rest(before)),
+                        None => closed.then(|| rest(left - 1)),
+                    };
+                    let after = if slot < left {
+                        Some(rest(slot))
+                    } else {
+                        closed.then(|| rest(0))
+                    };
+                    (before, after)
+                };
+                let slots = if closed { left } else { left + 1 };
+                let (previous, next) = neighbors(i);
+                let removed = edge(previous, Some(first)) + edge(Some(last), next);
+
+                let mut best = None;
+                for slot in (0..slots).filter(|slot| *slot != i % slots) {
+                    let (before, after) = neighbors(slot);
+                    let current = removed + edge(before, after);
+                    let kept = edge(previous, next);
+                    let forward = kept + edge(before, Some(first)) + edge(Some(last), after);
+                    let reversed = kept + edge(before, Some(last)) + edge(Some(first), after);
+                    let (moved, reverse) = if reversed < forward {
+                        (reversed, true)
+                    } else {
+                        (forward, false)
+                    };
+                    if moved < current {
+                        best = Some((slot, reverse, current - moved));
+                        break;
+                    }
+                }
+
+                if let Some((slot, reverse, gain)) = best {
+                    let mut segment = path[i..i + size].to_vec();
+                    if reverse {
+                        segment.reverse();
+                    }
+                    let moved = (0..slot)
+                        .map(rest)
+                        .chain(segment)
+                        .chain((slot..left).map(rest))
+                        .collect::<Vec<usize>>();
+                    path.copy_from_slice(&moved);
+                    gained += gain;
+                }
+            }
+        }
+        gained
+    }
+}
+
+// The pure 3-opt reconnections of three removed edges, those a 2-opt move
+// can't make. A sweep looks at every triple of edges, n³ moves. Assumes
+// symmetric distances as 2-opt does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThreeOpt;
+
+impl LocalSearch for ThreeOpt {
+    fn sweep(&self, path: &mut [usize], distances: &dyn DistanceMatrix, closed: bool) -> f32 {
+        let length = path.len();
+        let mut gained = 0.0;
+        if length < 4 {
+            return gained;
+        }
+
+        // The edges after positions i < j < k are replaced, B = path[i+1..=j]
+        // and C = path[j+1..=k]. The one after the last city closes the tour.
+        for i in 0..length - 2 {
+            for j in i + 1..length - 1 {
+                for k in j + 1..length {
+                    let a = path[i];
+                    let (b, c) = (path[i + 1], path[j]);
+                    let (d, e) = (path[j + 1], path[k]);
+                    let f = match path.get(k + 1) {
+                        Some(f) => Some(*f),
+                        None => closed.then(|| path[0]),
+                    };
+                    let dist = |from: usize, to: usize| distances.distance(from, to);
+                    let end = |from: usize| f.map_or(0.0, |f| dist(from, f));
+
+                    let current = dist(a, b) + dist(c, d) + end(e);
+                    // A B' C' D, A C B D, A C B' D and A C' B D
+                    let moves = [
+                        dist(a, c) + dist(b, e) + end(d),
+                        dist(a, d) + dist(e, b) + end(c),
+                        dist(a, d) + dist(e, c) + end(b),
+                        dist(a, e) + dist(d, b) + end(c),
+                    ];
+                    let Some((reconnection, moved)) = moves
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(_, moved)| *moved < current)
+                        .min_by(|x, y| x.1.total_cmp(&y.1))
+                    else {
+                        continue;
+                    };
+
+                    let (first, second) = (&path[i + 1..=j], &path[j + 1..=k]);
+                    let reconnected: Vec<usize> = match reconnection {
+                        0 => first
+                            .iter()
+                            .rev()
+                            .chain(second.iter().rev())
+                            .copied()
+                            .collect(),
+                        1 => second.iter().chain(first.iter()).copied().collect(),
+                        2 => second.iter().chain(first.iter().rev()).copied().collect(),
+                        _ => second.iter().rev().chain(first.iter()).copied().collect(),
+                    };
+                    path[i + 1..=k].copy_from_slice(&reconnected);
+                    gained += current - moved;
+                }
+            }
+        }
+        gained
+    }
+}
+
+// The neighborhoods `--local-search` chooses from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LocalSearchKind {
+    TwoOpt,
+    // Segments of up to 3 cities
+    OrOpt,
+    ThreeOpt,
+}
+
+impl LocalSearchKind {
+    pub const ALL: [LocalSearchKind; 3] = [
+        LocalSearchKind::TwoOpt,
+        LocalSearchKind::OrOpt,
+        LocalSearchKind::ThreeOpt,
+    ];
+
+    pub fn create(&self) -> Box<dyn LocalSearch> {
+        match self {
+            LocalSearchKind::TwoOpt => Box::new(TwoOpt),
+            LocalSearchKind::OrOpt => Box::new(OrOpt { max_segment: 3 }),
+            LocalSearchKind::ThreeOpt => Box::new(ThreeOpt),
+        }
+    }
+}
+
+impl fmt::Display for LocalSearchKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LocalSearchKind::TwoOpt => "2-opt",
+            LocalSearchKind::OrOpt => "or-opt",
+            LocalSearchKind::ThreeOpt => "3-opt",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for LocalSearchKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        LocalSearchKind::ALL
+            .into_iter()
+            .find(|kind| kind.to_string() == name)
+            .ok_or_else(|| format!("unknown local search: {}", name))
+    }
+}
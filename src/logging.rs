@@ -0,0 +1,235 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+// Shared by every rank, the root broadcasts its own so all logs follow it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogConfig {
+    pub level: LevelFilter,
+    // When set each rank writes `rank-<n>.log` there instead of stderr
+    pub directory: Option<PathBuf>,
+    pub max_bytes: u64,
+    pub max_files: usize,
+    // Experiment the run belongs to, on every line when set
+    pub tag: Option<String>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            level: LevelFilter::Info,
+            directory: None,
+            max_bytes: 10 * 1024 * 1024,
+            max_files: 5,
+            tag: None,
+        }
+    }
+}
+
+// A log file that is moved to `<name>.1` (shifting the older ones up to
+// `<name>.<max_files>`) once it grows past `max_bytes`
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+
+        Ok(RotatingFile {
+            path,
+            file,
+            written,
+            max_bytes,
+            max_files,
+        })
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.max_files == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = fs::remove_file(self.rotated(self.max_files));
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated(1))?;
+            self.file = File::create(&self.path)?;
+        }
+
+        self.written = 0;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.written > 0 && self.written + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+}
+
+enum Sink {
+    Stderr,
+    File(RotatingFile),
+}
+
+// Filters with the level of the `log` crate, so `set_level` changes it
+pub struct RankLogger {
+    rank: i32,
+    // `rank <n>`, followed by the tag of the run
+    label: String,
+    started: Instant,
+    sink: Mutex<Sink>,
+}
+
+impl RankLogger {
+    pub fn new(rank: i32, config: &LogConfig) -> io::Result<Self> {
+        let sink = match &config.directory {
+            Some(directory) => {
+                fs::create_dir_all(directory)?;
+                Sink::File(RotatingFile::open(
+                    log_path(directory, rank),
+                    config.max_bytes,
+                    config.max_files,
+                )?)
+            }
+            None => Sink::Stderr,
+        };
+
+        let label = match &config.tag {
+            Some(tag) => format!("rank {}] [{}", rank, tag),
+            None => format!("rank {}", rank),
+        };
+
+        Ok(RankLogger {
+            rank,
+            label,
+            started: Instant::now(),
+            sink: Mutex::new(sink),
+        })
+    }
+}
+
+impl Log for RankLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "[{:>10.3}s] [{}] [{}] {}\n",
+            self.started.elapsed().as_secs_f64(),
+            self.label,
+            record.level(),
+            record.args()
+        );
+
+        let mut sink = self.sink.lock().unwrap();
+        match &mut *sink {
+            Sink::Stderr => {
+                let _ = io::stderr().write_all(line.as_bytes());
+            }
+            Sink::File(file) => {
+                if let Err(error) = file.write_line(&line) {
+                    eprintln!("[rank {}] Failed to write the log: {}", self.rank, error);
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {
+        let mut sink = self.sink.lock().unwrap();
+        if let Sink::File(file) = &mut *sink {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+pub fn log_path(directory: &Path, rank: i32) -> PathBuf {
+    directory.join(format!("rank-{}.log", rank))
+}
+
+// Changes the level of this rank for the rest of the run, or until the next
+// change
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+// A file holding a log level, so the verbosity of a long run can be changed
+// without restarting it: `echo debug > level`. Only a change of its content
+// counts, a file that is missing or empty leaves the level alone.
+pub struct LevelControl {
+    path: PathBuf,
+    seen: Option<String>,
+}
+
+impl LevelControl {
+    pub fn new(path: PathBuf) -> Self {
+        LevelControl { path, seen: None }
+    }
+
+    // Applies the level the file asks for when it changed since the last
+    // call, and returns it so it can be passed on to the other ranks
+    pub fn update(&mut self) -> Option<LevelFilter> {
+        let level = self.poll()?;
+        log::info!("{} changes the log level to {}", self.path.display(), level);
+        set_level(level);
+        Some(level)
+    }
+
+    fn poll(&mut self) -> Option<LevelFilter> {
+        let content = fs::read_to_string(&self.path).ok()?;
+        let content = content.trim();
+        if content.is_empty() || self.seen.as_deref() == Some(content) {
+            return None;
+        }
+        self.seen = Some(content.to_string());
+
+        match content.parse() {
+            Ok(level) => Some(level),
+            Err(_) => {
+                log::warn!(
+                    "{} asks for log level {:?}, which doesn't exist",
+                    self.path.display(),
+                    content
+                );
+                None
+            }
+        }
+    }
+}
+
+// Installs the logger of this rank, can only be called once per process
+pub fn init(rank: i32, config: &LogConfig) -> io::Result<()> {
+    let logger = RankLogger::new(rank, config)?;
+    log::set_boxed_logger(Box::new(logger)).map_err(io::Error::other)?;
+    log::set_max_level(config.level);
+    Ok(())
+}
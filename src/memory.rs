@@ -0,0 +1,125 @@
+use std::fmt;
+use std::mem::size_of;
+
+// Bytes a TSP run will need, split by what holds them. Copies are counted
+// where the root and the workers keep several views of the same population.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemoryEstimate {
+    pub distance_matrix: u64,
+    pub root_population: u64,
+    pub root_buffers: u64,
+    pub worker_population: u64,
+    pub worker_buffers: u64,
+}
+
+impl MemoryEstimate {
+    pub fn root(&self) -> u64 {
+        self.distance_matrix + self.root_population + self.root_buffers
+    }
+
+    pub fn worker(&self) -> u64 {
+        self.distance_matrix + self.worker_population + self.worker_buffers
+    }
+}
+
+// Population copies alive at once on the root: the individuals, the solutions
+// being scattered, the gathered evaluation, the ranked parents and the children
+const ROOT_COPIES: u64 = 5;
+// A worker holds the received solutions, the individuals and the evaluation
+const WORKER_COPIES: u64 = 3;
+
+pub fn estimate_tsp(population_size: usize, nodes: usize, workers: usize) -> MemoryEstimate {
+    let nodes = nodes as u64;
+    let population_size = population_size as u64;
+
+    // A tour is a Vec<usize>, an individual also carries the shared map handle
+    let tour = size_of::<Vec<usize>>() as u64 + nodes * size_of::<usize>() as u64;
+    let individual = tour + 2 * size_of::<usize>() as u64;
+    // bincode writes a u64 length followed by the u64 cities
+    let serialized_tour = 8 + nodes * 8;
+    let row = size_of::<Vec<f32>>() as u64 + nodes * size_of::<f32>() as u64;
+
+    let chunk = match workers {
+        0 => population_size,
+        workers => population_size.div_ceil(workers as u64),
+    };
+
+    MemoryEstimate {
+        // The loaded instance, the shared copy and its broadcast serialization
+        distance_matrix: nodes * row + nodes * row + nodes * nodes * 4,
+        root_population: ROOT_COPIES * population_size * individual,
+        // Every chunk is serialized before it is sent, the answers are
+        // deserialized one rank at a time
+        root_buffers: population_size * serialized_tour + chunk * (serialized_tour + 4),
+        worker_population: WORKER_COPIES * chunk * individual,
+        worker_buffers: chunk * serialized_tour + chunk * (serialized_tour + 4),
+    }
+}
+
+// Memory this rank can use: what the kernel reports available (or what the
+// cgroup of the job still allows) shared by the ranks of this node
+pub fn available_per_rank() -> Option<u64> {
+    let available = [meminfo_available(), cgroup_available()]
+        .into_iter()
+        .flatten()
+        .min()?;
+
+    let local_ranks = [
+        "OMPI_COMM_WORLD_LOCAL_SIZE",
+        "MPI_LOCALNRANKS",
+        "SLURM_NTASKS_PER_NODE",
+    ]
+    .iter()
+    .find_map(|name| std::env::var(name).ok()?.parse::<u64>().ok())
+    .unwrap_or(1)
+    .max(1);
+
+    Some(available / local_ranks)
+}
+
+fn meminfo_available() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    kilobytes_field(&meminfo, "MemAvailable:")
+}
+
+fn cgroup_available() -> Option<u64> {
+    let read = |name| std::fs::read_to_string(format!("/sys/fs/cgroup/{}", name)).ok();
+    let limit = read("memory.max")?.trim().parse::<u64>().ok()?;
+    let current = read("memory.current")?.trim().parse::<u64>().unwrap_or(0);
+    Some(limit.saturating_sub(current))
+}
+
+// Resident set size of this process, Linux only
+pub fn resident_set() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    kilobytes_field(&status, "VmRSS:")
+}
+
+fn kilobytes_field(source: &str, name: &str) -> Option<u64> {
+    source
+        .lines()
+        .find_map(|line| line.strip_prefix(name))
+        .and_then(|value| value.split_whitespace().next()?.parse::<u64>().ok())
+        .map(|kilobytes| kilobytes * 1024)
+}
+
+// Formats a byte count with a binary unit, e.g. 1.5 GiB
+pub struct Bytes(pub u64);
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < units.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        if unit == 0 {
+            write!(f, "{} B", self.0)
+        } else {
+            write!(f, "{:.1} {}", value, units[unit])
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use crate::organism::{Distance, GenomeHash, Organism, Validate};
+use crate::parallel::prelude::*;
+use crate::statistics::{FitnessSummary, ObjectiveSummary};
+use rand::Rng;
+use std::fmt;
+use std::sync::Arc;
+
+// One cost of an individual, lower is better like the fitness
+pub type Cost<T> = Arc<dyn Fn(&T) -> f32 + Send + Sync>;
+
+// A fitness made of named costs, each scaled by its weight and summed. The
+// sum drives selection, the costs are reported one by one.
+pub struct CompositeFitness<T> {
+    names: Vec<String>,
+    weights: Vec<f32>,
+    costs: Vec<Cost<T>>,
+}
+
+impl<T> CompositeFitness<T> {
+    pub fn builder() -> CompositeFitnessBuilder<T> {
+        CompositeFitnessBuilder::default()
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    // Every cost of `individual`, unweighted, in the order of `names`
+    pub fn costs(&self, individual: &T) -> Vec<f32> {
+        self.costs.iter().map(|cost| cost(individual)).collect()
+    }
+
+    pub fn fitness(&self, individual: &T) -> f32 {
+        self.costs
+            .iter()
+            .zip(self.weights.iter())
+            .filter(|(_, weight)| **weight != 0.0)
+            .map(|(cost, weight)| weight * cost(individual))
+            .sum()
+    }
+}
+
+impl<T: Sync> CompositeFitness<T> {
+    // Statistics of every cost over `population`, unweighted
+    pub fn summaries(&self, population: &[Composite<T>]) -> Vec<ObjectiveSummary> {
+        let costs = population
+            .par_iter()
+            .map(|individual| self.costs(&individual.inner))
+            .collect::<Vec<_>>();
+        self.names
+            .iter()
+            .zip(self.weights.iter())
+            .enumerate()
+            .map(|(term, (name, weight))| ObjectiveSummary {
+                name: name.clone(),
+                weight: *weight,
+                summary: FitnessSummary::from_fitness(costs.iter().map(|costs| costs[term])),
+            })
+            .collect()
+    }
+}
+
+impl<T> fmt::Debug for CompositeFitness<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(self.names.iter().zip(self.weights.iter()))
+            .finish()
+    }
+}
+
+pub struct CompositeFitnessBuilder<T> {
+    fitness: CompositeFitness<T>,
+    overrides: Vec<(String, f32)>,
+}
+
+impl<T> Default for CompositeFitnessBuilder<T> {
+    fn default() -> Self {
+        CompositeFitnessBuilder {
+            fitness: CompositeFitness {
+                names: Vec::new(),
+                weights: Vec::new(),
+                costs: Vec::new(),
+            },
+            overrides: Vec::new(),
+        }
+    }
+}
+
+impl<T> CompositeFitnessBuilder<T> {
+    pub fn term<F>(mut self, name: &str, weight: f32, cost: F) -> Self
+    where
+        F: Fn(&T) -> f32 + Send + Sync + 'static,
+    {
+        self.fitness.names.push(name.to_string());
+        self.fitness.weights.push(weight);
+        self.fitness.costs.push(Arc::new(cost));
+        self
+    }
+
+    // Replaces the weight of the term `name`, whenever it is added, so the
+    // weights can come from a configuration
+    pub fn weight(mut self, name: &str, weight: f32) -> Self {
+        self.overrides.push((name.to_string(), weight));
+        self
+    }
+
+    pub fn build(mut self) -> Result<Arc<CompositeFitness<T>>, Vec<String>> {
+        let mut problems = Vec::new();
+        for (name, weight) in self.overrides.iter() {
+            match self.fitness.names.iter().position(|term| term == name) {
+                Some(term) => self.fitness.weights[term] = *weight,
+                None => problems.push(format!("no term named {}", name)),
+            }
+        }
+
+        if self.fitness.names.is_empty() {
+            problems.push("a composite fitness needs at least 1 term".to_string());
+        }
+        for (term, name) in self.fitness.names.iter().enumerate() {
+            if self.fitness.names[..term].contains(name) {
+                problems.push(format!("term {} is added twice", name));
+            }
+        }
+        for (name, weight) in self.fitness.names.iter().zip(self.fitness.weights.iter()) {
+            if !weight.is_finite() || *weight < 0.0 {
+                problems.push(format!(
+                    "weight of {} must be finite and not negative, got {}",
+                    name, weight
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(Arc::new(self.fitness))
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+// `inner` bred as it is, but judged by a composite fitness, so an objective
+// of several terms doesn't need an organism of its own
+pub struct Composite<T> {
+    inner: T,
+    fitness: Arc<CompositeFitness<T>>,
+}
+
+impl<T> Composite<T> {
+    pub fn new(inner: T, fitness: Arc<CompositeFitness<T>>) -> Self {
+        Composite { inner, fitness }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    pub fn costs(&self) -> Vec<f32> {
+        self.fitness.costs(&self.inner)
+    }
+}
+
+impl<T: Clone> Clone for Composite<T> {
+    fn clone(&self) -> Self {
+        Composite {
+            inner: self.inner.clone(),
+            fitness: self.fitness.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Composite<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T: Organism> Organism for Composite<T> {
+    type Fitness = f32;
+
+    fn fitness(&self) -> f32 {
+        self.fitness.fitness(&self.inner)
+    }
+
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.inner.mutate(rng);
+    }
+
+    fn cross_over<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self {
+        Composite {
+            inner: self.inner.cross_over(&other.inner, rng),
+            fitness: self.fitness.clone(),
+        }
+    }
+}
+
+impl<T: Distance> Distance for Composite<T> {
+    fn distance(&self, other: &Self) -> f32 {
+        self.inner.distance(&other.inner)
+    }
+}
+
+impl<T: GenomeHash> GenomeHash for Composite<T> {
+    fn genome_hash(&self) -> u64 {
+        self.inner.genome_hash()
+    }
+}
+
+impl<T: Validate> Validate for Composite<T> {
+    fn validate(&self) -> Result<(), String> {
+        self.inner.validate()
+    }
+}
@@ -0,0 +1,113 @@
+use crate::permutation::{CrossoverOperator, MutationOperator, MutationWeights};
+use std::fmt;
+
+// The operators and rates breeding one generation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Operators {
+    pub crossover: CrossoverOperator,
+    pub mutation: MutationOperator,
+    pub mutation_weights: MutationWeights,
+    pub crossover_rate: f32,
+    pub mutation_rate: f32,
+}
+
+impl fmt::Display for Operators {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "crossover {} at {}, mutation {} at {}",
+            self.crossover, self.crossover_rate, self.mutation, self.mutation_rate
+        )?;
+        if self.mutation == MutationOperator::Composite {
+            write!(f, " ({})", self.mutation_weights)?;
+        }
+        Ok(())
+    }
+}
+
+// Runs until `until`, a fraction of the generations, with whatever it sets
+// replacing the operators of the run
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OperatorPhase {
+    pub until: f64,
+    pub crossover: Option<CrossoverOperator>,
+    pub mutation: Option<MutationOperator>,
+    pub mutation_weights: Option<MutationWeights>,
+    pub crossover_rate: Option<f32>,
+    pub mutation_rate: Option<f32>,
+}
+
+impl OperatorPhase {
+    fn over(&self, base: Operators) -> Operators {
+        Operators {
+            crossover: self.crossover.unwrap_or(base.crossover),
+            mutation: self.mutation.unwrap_or(base.mutation),
+            mutation_weights: self.mutation_weights.unwrap_or(base.mutation_weights),
+            crossover_rate: self.crossover_rate.unwrap_or(base.crossover_rate),
+            mutation_rate: self.mutation_rate.unwrap_or(base.mutation_rate),
+        }
+    }
+}
+
+// Operator mixes changing over the run, e.g. exploring crossovers early and
+// distance aware ones late. Generations after the last phase, or all of them
+// without phases, use the operators of the run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OperatorSchedule {
+    phases: Vec<OperatorPhase>,
+}
+
+impl OperatorSchedule {
+    // The phases must end in increasing order, within the run
+    pub fn new(phases: Vec<OperatorPhase>) -> Result<Self, String> {
+        let mut start = 0.0;
+        for (i, phase) in phases.iter().enumerate() {
+            if !(phase.until > start && phase.until <= 1.0) {
+                return Err(format!(
+                    "phase {} ends at {}, it must end after {} and no later than 1",
+                    i + 1,
+                    phase.until,
+                    start
+                ));
+            }
+            start = phase.until;
+        }
+        Ok(OperatorSchedule { phases })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.phases.is_empty()
+    }
+
+    // Index of the phase `generation` out of `generations` falls in
+    pub fn phase_of(&self, generation: usize, generations: usize) -> Option<usize> {
+        let progress = generation as f64 / generations.max(1) as f64;
+        self.phases.iter().position(|phase| progress < phase.until)
+    }
+
+    pub fn operators_at(
+        &self,
+        generation: usize,
+        generations: usize,
+        base: Operators,
+    ) -> Operators {
+        match self.phase_of(generation, generations) {
+            Some(phase) => self.phases[phase].over(base),
+            None => base,
+        }
+    }
+
+    // Everything the run may use, the operators of the run first
+    pub fn all(&self, base: Operators) -> impl Iterator<Item = Operators> + '_ {
+        std::iter::once(base).chain(self.phases.iter().map(move |phase| phase.over(base)))
+    }
+
+    // Start and end of every phase as fractions of the run, with its operators
+    pub fn describe(&self, base: Operators) -> Vec<(f64, f64, Operators)> {
+        let starts = std::iter::once(0.0).chain(self.phases.iter().map(|phase| phase.until));
+        starts
+            .zip(self.phases.iter())
+            .map(|(start, phase)| (start, phase.until, phase.over(base)))
+            .collect()
+    }
+}
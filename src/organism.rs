@@ -1,7 +1,124 @@
-pub trait Organism {
-    fn fitness(&self) -> f32;
-    fn mutate(&mut self);
-    fn cross_over(&self, other: &Self) -> Self
-    where
-        Self: Sized;
+pub use crate::portable::{Fitness, Organism};
+use rand::Rng;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+// What every individual of a problem shares, the distance matrix of a TSP
+// say, apart from the genomes it evaluates and breeds. Only genomes need to
+// go from one rank to another.
+pub trait Problem {
+    type Genome: Clone;
+
+    // Lower is better, as the fitness of an `Organism`
+    fn evaluate(&self, genome: &Self::Genome) -> f32;
+    fn mutate<R: Rng + ?Sized>(&self, genome: &mut Self::Genome, rng: &mut R);
+    fn cross_over<R: Rng + ?Sized>(
+        &self,
+        first: &Self::Genome,
+        second: &Self::Genome,
+        rng: &mut R,
+    ) -> Self::Genome;
+}
+
+// A genome with the problem it belongs to, so the GA breeds any `Problem`
+pub struct Individual<P: Problem> {
+    problem: Arc<P>,
+    genome: P::Genome,
+}
+
+impl<P: Problem> Individual<P> {
+    pub fn new(problem: Arc<P>, genome: P::Genome) -> Self {
+        Individual { problem, genome }
+    }
+
+    pub fn genome(&self) -> &P::Genome {
+        &self.genome
+    }
+
+    pub fn genome_mut(&mut self) -> &mut P::Genome {
+        &mut self.genome
+    }
+
+    pub fn problem(&self) -> &Arc<P> {
+        &self.problem
+    }
+
+    pub fn into_genome(self) -> P::Genome {
+        self.genome
+    }
+}
+
+impl<P: Problem> Clone for Individual<P> {
+    fn clone(&self) -> Self {
+        Individual {
+            problem: self.problem.clone(),
+            genome: self.genome.clone(),
+        }
+    }
+}
+
+// The problem is shared by every individual, only the genome is worth printing
+impl<P: Problem> fmt::Debug for Individual<P>
+where
+    P::Genome: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.genome)
+    }
+}
+
+impl<P: Problem> Organism for Individual<P> {
+    type Fitness = f32;
+
+    fn fitness(&self) -> f32 {
+        self.problem.evaluate(&self.genome)
+    }
+
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.problem.mutate(&mut self.genome, rng)
+    }
+
+    fn cross_over<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self {
+        Individual {
+            problem: self.problem.clone(),
+            genome: self.problem.cross_over(&self.genome, &other.genome, rng),
+        }
+    }
+}
+
+impl<P: GenomeDistance> Distance for Individual<P> {
+    fn distance(&self, other: &Self) -> f32 {
+        self.problem.distance(&self.genome, &other.genome)
+    }
+}
+
+impl<P: Problem> GenomeHash for Individual<P>
+where
+    P::Genome: GenomeHash,
+{
+    fn genome_hash(&self) -> u64 {
+        self.genome.genome_hash()
+    }
+}
+
+pub trait Distance {
+    fn distance(&self, other: &Self) -> f32;
+}
+
+// How far apart two genomes of a problem are, which may depend on the
+// problem, whether a tour is round say
+pub trait GenomeDistance: Problem {
+    fn distance(&self, first: &Self::Genome, second: &Self::Genome) -> f32;
+}
+
+// Equal for identical genomes, and stable across processes so hashes can be
+// compared wherever the genomes came from
+pub trait GenomeHash {
+    fn genome_hash(&self) -> u64;
+}
+
+// Checks an individual bred elsewhere against the local problem before its
+// fitness is computed here
+pub trait Validate {
+    fn validate(&self) -> Result<(), String>;
 }
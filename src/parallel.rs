@@ -0,0 +1,118 @@
+// Rayon when the `parallel` feature is enabled, otherwise sequential stand ins
+// with the same method names so the GA code doesn't need two versions
+#[cfg(feature = "parallel")]
+pub use rayon::prelude;
+
+#[cfg(not(feature = "parallel"))]
+pub mod prelude {
+    use std::cmp::Ordering;
+
+    // A plain iterator that also offers rayon's `fold` and `reduce`, which take
+    // an identity closure instead of an initial value
+    pub struct Sequential<I>(I);
+
+    impl<I: Iterator> Iterator for Sequential<I> {
+        type Item = I::Item;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.0.next()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            self.0.size_hint()
+        }
+    }
+
+    impl<I: Iterator> Sequential<I> {
+        pub fn fold<T, ID, F>(self, identity: ID, fold: F) -> Sequential<std::iter::Once<T>>
+        where
+            ID: Fn() -> T,
+            F: FnMut(T, I::Item) -> T,
+        {
+            Sequential(std::iter::once(self.0.fold(identity(), fold)))
+        }
+
+        pub fn reduce<ID, F>(self, identity: ID, reduce: F) -> I::Item
+        where
+            ID: Fn() -> I::Item,
+            F: FnMut(I::Item, I::Item) -> I::Item,
+        {
+            self.0.fold(identity(), reduce)
+        }
+    }
+
+    pub trait IntoParallelIterator {
+        type Item;
+        type Iter: Iterator<Item = Self::Item>;
+
+        fn into_par_iter(self) -> Sequential<Self::Iter>;
+    }
+
+    impl<I: IntoIterator> IntoParallelIterator for I {
+        type Item = I::Item;
+        type Iter = I::IntoIter;
+
+        fn into_par_iter(self) -> Sequential<Self::Iter> {
+            Sequential(self.into_iter())
+        }
+    }
+
+    pub trait IntoParallelRefIterator<'a> {
+        type Iter: Iterator;
+
+        fn par_iter(&'a self) -> Sequential<Self::Iter>;
+    }
+
+    impl<'a, C: ?Sized + 'a> IntoParallelRefIterator<'a> for C
+    where
+        &'a C: IntoIterator,
+    {
+        type Iter = <&'a C as IntoIterator>::IntoIter;
+
+        fn par_iter(&'a self) -> Sequential<Self::Iter> {
+            Sequential(self.into_iter())
+        }
+    }
+
+    pub trait IntoParallelRefMutIterator<'a> {
+        type Iter: Iterator;
+
+        fn par_iter_mut(&'a mut self) -> Sequential<Self::Iter>;
+    }
+
+    impl<'a, C: ?Sized + 'a> IntoParallelRefMutIterator<'a> for C
+    where
+        &'a mut C: IntoIterator,
+    {
+        type Iter = <&'a mut C as IntoIterator>::IntoIter;
+
+        fn par_iter_mut(&'a mut self) -> Sequential<Self::Iter> {
+            Sequential(self.into_iter())
+        }
+    }
+
+    pub trait ParallelSlice<T> {
+        fn par_windows(&self, size: usize) -> Sequential<std::slice::Windows<'_, T>>;
+    }
+
+    impl<T> ParallelSlice<T> for [T] {
+        fn par_windows(&self, size: usize) -> Sequential<std::slice::Windows<'_, T>> {
+            Sequential(self.windows(size))
+        }
+    }
+
+    pub trait ParallelSliceMut<T> {
+        fn par_sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F);
+        fn par_sort_unstable_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F);
+    }
+
+    impl<T> ParallelSliceMut<T> for [T] {
+        fn par_sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F) {
+            self.sort_by(compare);
+        }
+
+        fn par_sort_unstable_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F) {
+            self.sort_unstable_by(compare);
+        }
+    }
+}
@@ -0,0 +1,608 @@
+// The operators that need no standard library live in `portable`
+pub use crate::portable::{
+    insertion_mutation, inversion_mutation, order_crossover, partially_mapped_crossover,
+    swap_mutation, three_swap_mutation,
+};
+use itertools::Itertools;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+// Operators shared by every genome encoded as a permutation of node indices
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MutationOperator {
+    Swap,
+    Inversion,
+    Insertion,
+    ThreeSwap,
+    // One of the others per mutation event, drawn from a `MutationWeights`
+    Composite,
+}
+
+impl MutationOperator {
+    pub const ALL: [MutationOperator; 5] = [
+        MutationOperator::Swap,
+        MutationOperator::Inversion,
+        MutationOperator::Insertion,
+        MutationOperator::ThreeSwap,
+        MutationOperator::Composite,
+    ];
+
+    // What the composite mutation chooses from, in `MutationWeights` order
+    pub const BASIC: [MutationOperator; 4] = [
+        MutationOperator::Swap,
+        MutationOperator::Inversion,
+        MutationOperator::Insertion,
+        MutationOperator::ThreeSwap,
+    ];
+
+    // The composite mutation uses equal weights, see `apply_weighted`
+    pub fn apply<R: Rng + ?Sized>(&self, path: &mut [usize], rng: &mut R) {
+        self.apply_weighted(path, &MutationWeights::default(), rng);
+    }
+
+    // Returns the basic operator that was actually applied
+    pub fn apply_weighted<R: Rng + ?Sized>(
+        &self,
+        path: &mut [usize],
+        weights: &MutationWeights,
+        rng: &mut R,
+    ) -> MutationOperator {
+        match self {
+            MutationOperator::Swap => swap_mutation(path, rng),
+            MutationOperator::Inversion => inversion_mutation(path, rng),
+            MutationOperator::Insertion => insertion_mutation(path, rng),
+            MutationOperator::ThreeSwap => three_swap_mutation(path, rng),
+            MutationOperator::Composite => {
+                return weights.choose(rng).apply_weighted(path, weights, rng);
+            }
+        }
+        *self
+    }
+
+    fn basic_index(&self) -> Option<usize> {
+        MutationOperator::BASIC
+            .iter()
+            .position(|operator| operator == self)
+    }
+}
+
+// Relative probabilities of the basic operators in a composite mutation
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MutationWeights {
+    weights: [f64; 4],
+}
+
+impl MutationWeights {
+    // Operators left out get a weight of zero, at least one must be positive
+    pub fn from_pairs<'a, I>(pairs: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = (&'a str, f64)>,
+    {
+        let mut weights = [0.0; 4];
+        for (name, weight) in pairs {
+            let operator = name.parse::<MutationOperator>()?;
+            let index = operator
+                .basic_index()
+                .ok_or_else(|| format!("{} can't be part of a composite mutation", name))?;
+            if !(weight >= 0.0 && weight.is_finite()) {
+                return Err(format!("weight of {} must be a non negative number", name));
+            }
+            weights[index] = weight;
+        }
+
+        if weights.iter().all(|weight| *weight == 0.0) {
+            return Err("at least one mutation weight must be positive".to_string());
+        }
+        Ok(MutationWeights { weights })
+    }
+
+    pub fn weight(&self, operator: MutationOperator) -> f64 {
+        operator
+            .basic_index()
+            .map_or(0.0, |index| self.weights[index])
+    }
+
+    fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> MutationOperator {
+        let index = WeightedIndex::new(self.weights)
+            .expect("Validated weights")
+            .sample(rng);
+        MutationOperator::BASIC[index]
+    }
+}
+
+impl Default for MutationWeights {
+    fn default() -> Self {
+        MutationWeights { weights: [1.0; 4] }
+    }
+}
+
+impl fmt::Display for MutationWeights {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pairs = MutationOperator::BASIC
+            .iter()
+            .zip(self.weights)
+            .filter(|(_, weight)| *weight > 0.0)
+            .map(|(operator, weight)| format!("{}={}", operator, weight))
+            .join(",");
+        f.pad(&pairs)
+    }
+}
+
+// `swap=2,inversion=1`
+impl FromStr for MutationWeights {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let pairs = text
+            .split(',')
+            .map(|pair| {
+                let (name, weight) = pair
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected <operator>=<weight>, found {}", pair))?;
+                let weight = weight
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid weight {}", weight))?;
+                Ok((name.trim(), weight))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        MutationWeights::from_pairs(pairs)
+    }
+}
+
+// How often each basic operator was applied by a composite mutation and how
+// often that made the tour shorter, shared by every individual of a run
+#[derive(Debug, Default)]
+pub struct MutationStatistics {
+    applied: [AtomicU64; 4],
+    improved: [AtomicU64; 4],
+}
+
+impl MutationStatistics {
+    pub fn record(&self, operator: MutationOperator, before: f32, after: f32) {
+        if let Some(index) = operator.basic_index() {
+            self.applied[index].fetch_add(1, Ordering::Relaxed);
+            if after < before {
+                self.improved[index].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    // (operator, applied, improved) of every basic operator
+    pub fn counts(&self) -> Vec<(MutationOperator, u64, u64)> {
+        MutationOperator::BASIC
+            .iter()
+            .enumerate()
+            .map(|(index, operator)| {
+                (
+                    *operator,
+                    self.applied[index].load(Ordering::Relaxed),
+                    self.improved[index].load(Ordering::Relaxed),
+                )
+            })
+            .collect()
+    }
+}
+
+// The weights of a composite mutation and where it keeps its statistics
+#[derive(Clone, Debug, Default)]
+pub struct MutationMix {
+    pub weights: MutationWeights,
+    pub statistics: Arc<MutationStatistics>,
+}
+
+impl MutationMix {
+    pub fn new(weights: MutationWeights) -> Self {
+        MutationMix {
+            weights,
+            statistics: Arc::new(MutationStatistics::default()),
+        }
+    }
+
+    // Other weights, still counting into the same statistics
+    pub fn with_weights(&self, weights: MutationWeights) -> Self {
+        MutationMix {
+            weights,
+            statistics: self.statistics.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CrossoverOperator {
+    // Splices the parents as they are, the child may visit a city twice
+    Slice,
+    Order,
+    PartiallyMapped,
+    Cycle,
+    Heuristic,
+    SequentialConstructive,
+}
+
+impl CrossoverOperator {
+    pub const ALL: [CrossoverOperator; 6] = [
+        CrossoverOperator::Slice,
+        CrossoverOperator::Order,
+        CrossoverOperator::PartiallyMapped,
+        CrossoverOperator::Cycle,
+        CrossoverOperator::Heuristic,
+        CrossoverOperator::SequentialConstructive,
+    ];
+
+    // The distances are the problem data, only distance aware operators read
+    // them, and whether the parents return to their first node
+    pub fn apply<D, R>(
+        &self,
+        first: &[usize],
+        second: &[usize],
+        distances: &D,
+        closed: bool,
+        rng: &mut R,
+    ) -> Vec<usize>
+    where
+        D: DistanceMatrix + ?Sized,
+        R: Rng + ?Sized,
+    {
+        match self {
+            CrossoverOperator::Slice => slice_crossover(first, second, rng),
+            CrossoverOperator::Order => order_crossover(first, second, rng),
+            CrossoverOperator::PartiallyMapped => partially_mapped_crossover(first, second, rng),
+            CrossoverOperator::Cycle => cycle_crossover(first, second),
+            CrossoverOperator::Heuristic => heuristic_crossover(first, second, distances, closed),
+            CrossoverOperator::SequentialConstructive => {
+                sequential_constructive_crossover(first, second, distances)
+            }
+        }
+    }
+
+    // Worth building candidate lists for, see `CandidateMatrix`
+    pub fn uses_distances(&self) -> bool {
+        matches!(
+            self,
+            CrossoverOperator::Heuristic | CrossoverOperator::SequentialConstructive
+        )
+    }
+}
+
+// Cost of going from one node to another
+pub trait DistanceMatrix {
+    fn nodes(&self) -> usize;
+    fn distance(&self, from: usize, to: usize) -> f32;
+
+    // Closest node not visited yet, a full scan unless candidate lists are known
+    fn nearest_unvisited(&self, from: usize, visited: &[bool]) -> Option<usize> {
+        (0..self.nodes())
+            .filter(|node| !visited[*node])
+            .min_by(|a, b| self.distance(from, *a).total_cmp(&self.distance(from, *b)))
+    }
+}
+
+impl DistanceMatrix for [Vec<f32>] {
+    fn nodes(&self) -> usize {
+        self.len()
+    }
+
+    fn distance(&self, from: usize, to: usize) -> f32 {
+        self[from][to]
+    }
+}
+
+impl fmt::Display for MutationOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            MutationOperator::Swap => "swap",
+            MutationOperator::Inversion => "inversion",
+            MutationOperator::Insertion => "insertion",
+            MutationOperator::ThreeSwap => "3-swap",
+            MutationOperator::Composite => "composite",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for MutationOperator {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        MutationOperator::ALL
+            .into_iter()
+            .find(|operator| operator.to_string() == name)
+            .ok_or_else(|| format!("unknown mutation operator {}", name))
+    }
+}
+
+impl fmt::Display for CrossoverOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            CrossoverOperator::Slice => "slice",
+            CrossoverOperator::Order => "order",
+            CrossoverOperator::PartiallyMapped => "pmx",
+            CrossoverOperator::Cycle => "cycle",
+            CrossoverOperator::Heuristic => "heuristic",
+            CrossoverOperator::SequentialConstructive => "scx",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for CrossoverOperator {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        CrossoverOperator::ALL
+            .into_iter()
+            .find(|operator| operator.to_string() == name)
+            .ok_or_else(|| format!("unknown crossover operator {}", name))
+    }
+}
+
+pub fn slice_crossover<R: Rng + ?Sized>(
+    first: &[usize],
+    second: &[usize],
+    rng: &mut R,
+) -> Vec<usize> {
+    let mut new_path = vec![0; first.len()];
+
+    let start_index = rng.gen_range(0..first.len());
+    let end_index = rng.gen_range(start_index..first.len());
+
+    new_path[0..start_index].clone_from_slice(&first[0..start_index]);
+    new_path[start_index..end_index].clone_from_slice(&second[start_index..end_index]);
+    new_path[end_index..first.len()].clone_from_slice(&first[end_index..first.len()]);
+
+    new_path
+}
+
+// Cycle crossover (CX): the positions split into the cycles the parents form,
+// the child takes them from either parent in turn so every node stays where
+// one of the parents has it
+pub fn cycle_crossover(first: &[usize], second: &[usize]) -> Vec<usize> {
+    let length = first.len();
+    let in_first = positions(first);
+    let mut filled = vec![false; length];
+    let mut new_path = vec![0; length];
+
+    let mut from_first = true;
+    for start in 0..length {
+        if filled[start] {
+            continue;
+        }
+        let parent = if from_first { first } else { second };
+        let mut i = start;
+        while !filled[i] {
+            filled[i] = true;
+            new_path[i] = parent[i];
+            i = in_first[second[i]];
+        }
+        from_first = !from_first;
+    }
+
+    new_path
+}
+
+// Distances along with the nearest neighbors of every node, closest first, so
+// most nearest unvisited lookups don't scan every node
+pub struct CandidateMatrix<'a, D: ?Sized> {
+    distances: &'a D,
+    candidates: &'a [Vec<usize>],
+}
+
+impl<'a, D: DistanceMatrix + ?Sized> CandidateMatrix<'a, D> {
+    pub fn new(distances: &'a D, candidates: &'a [Vec<usize>]) -> Self {
+        CandidateMatrix {
+            distances,
+            candidates,
+        }
+    }
+}
+
+impl<D: DistanceMatrix + ?Sized> DistanceMatrix for CandidateMatrix<'_, D> {
+    fn nodes(&self) -> usize {
+        self.distances.nodes()
+    }
+
+    fn distance(&self, from: usize, to: usize) -> f32 {
+        self.distances.distance(from, to)
+    }
+
+    fn nearest_unvisited(&self, from: usize, visited: &[bool]) -> Option<usize> {
+        self.candidates[from]
+            .iter()
+            .copied()
+            .find(|node| !visited[*node])
+            .or_else(|| self.distances.nearest_unvisited(from, visited))
+    }
+}
+
+// The `count` nearest other nodes of every node, closest first
+pub fn candidate_lists<D>(distances: &D, count: usize) -> Vec<Vec<usize>>
+where
+    D: DistanceMatrix + ?Sized,
+{
+    (0..distances.nodes())
+        .map(|from| {
+            let mut neighbors = (0..distances.nodes())
+                .filter(|to| *to != from)
+                .collect::<Vec<usize>>();
+            neighbors.sort_by(|a, b| {
+                distances
+                    .distance(from, *a)
+                    .total_cmp(&distances.distance(from, *b))
+            });
+            neighbors.truncate(count);
+            neighbors
+        })
+        .collect()
+}
+
+// Greedy edge crossover: starting from the first node of the first parent,
+// follow whichever of the two parental successors of the current node is
+// closer and still unvisited, or the nearest unvisited node when both are
+// taken. The last node of a `closed` parent is followed by its first.
+pub fn heuristic_crossover<D>(
+    first: &[usize],
+    second: &[usize],
+    distances: &D,
+    closed: bool,
+) -> Vec<usize>
+where
+    D: DistanceMatrix + ?Sized,
+{
+    let length = first.len();
+    let (first_successors, second_successors) =
+        (successors(first, closed), successors(second, closed));
+
+    let mut visited = vec![false; length];
+    let mut new_path = Vec::with_capacity(length);
+    let Some(mut current) = first.first().copied() else {
+        return new_path;
+    };
+    visited[current] = true;
+    new_path.push(current);
+
+    while new_path.len() < length {
+        let next = [first_successors[current], second_successors[current]]
+            .into_iter()
+            .filter(|node| *node != usize::MAX && !visited[*node])
+            .min_by(|a, b| {
+                distances
+                    .distance(current, *a)
+                    .total_cmp(&distances.distance(current, *b))
+            })
+            .or_else(|| distances.nearest_unvisited(current, &visited))
+            .unwrap();
+
+        visited[next] = true;
+        new_path.push(next);
+        current = next;
+    }
+
+    new_path
+}
+
+// Sequential constructive crossover (SCX, Ahmed 2010): from the current node,
+// each parent proposes the first unvisited node after it in that parent's
+// order, and the closer proposal is taken. A parent with nothing left after the
+// current node proposes the nearest unvisited node instead of the first one in
+// index order, as the original does.
+pub fn sequential_constructive_crossover<D>(
+    first: &[usize],
+    second: &[usize],
+    distances: &D,
+) -> Vec<usize>
+where
+    D: DistanceMatrix + ?Sized,
+{
+    let length = first.len();
+    let (first_positions, second_positions) = (positions(first), positions(second));
+
+    let mut visited = vec![false; length];
+    let mut new_path = Vec::with_capacity(length);
+    let Some(mut current) = first.first().copied() else {
+        return new_path;
+    };
+    visited[current] = true;
+    new_path.push(current);
+
+    while new_path.len() < length {
+        let legitimate = |parent: &[usize], positions: &[usize]| {
+            parent[positions[current] + 1..]
+                .iter()
+                .copied()
+                .find(|node| !visited[*node])
+                .or_else(|| distances.nearest_unvisited(current, &visited))
+                .unwrap()
+        };
+        let from_first = legitimate(first, &first_positions);
+        let from_second = legitimate(second, &second_positions);
+
+        let next =
+            if distances.distance(current, from_second) < distances.distance(current, from_first) {
+                from_second
+            } else {
+                from_first
+            };
+
+        visited[next] = true;
+        new_path.push(next);
+        current = next;
+    }
+
+    new_path
+}
+
+// Where each node is along the path
+fn positions(path: &[usize]) -> Vec<usize> {
+    let mut positions = vec![0; path.len()];
+    for (position, node) in path.iter().enumerate() {
+        positions[*node] = position;
+    }
+    positions
+}
+
+// The edges along the path, and from the last node back to the first when
+// `closed`
+fn edges(path: &[usize], closed: bool) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let wrap = path
+        .last()
+        .zip(path.first())
+        .filter(|_| closed && path.len() > 1);
+    path.iter()
+        .copied()
+        .tuple_windows()
+        .chain(wrap.map(|(last, first)| (*last, *first)))
+}
+
+// The node following each node along the path, usize::MAX after the last one
+// unless the path is `closed`
+fn successors(path: &[usize], closed: bool) -> Vec<usize> {
+    let mut successors = vec![usize::MAX; path.len()];
+    for (a, b) in edges(path, closed) {
+        successors[a] = b;
+    }
+    successors
+}
+
+// Number of edges of the first path that are not present in the second one,
+// either way round. Both return to their first node when `closed`.
+pub fn edge_distance(first: &[usize], second: &[usize], closed: bool) -> usize {
+    let mut neighbors = vec![[usize::MAX; 2]; second.len()];
+
+    for (a, b) in edges(second, closed) {
+        neighbors[a][1] = b;
+        neighbors[b][0] = a;
+    }
+
+    edges(first, closed)
+        .filter(|(a, b)| !neighbors[*a].contains(b))
+        .count()
+}
+
+// Whether `path` visits every node of 0..len exactly once
+pub fn is_permutation(path: &[usize], len: usize) -> bool {
+    let mut seen = vec![false; len];
+    path.len() == len
+        && path
+            .iter()
+            .all(|node| *node < len && !std::mem::replace(&mut seen[*node], true))
+}
+
+// FNV-1a over the genes, stable across runs, platforms and compiler versions
+// so it can order individuals of equal fitness reproducibly
+pub fn genome_hash(path: &[usize]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for gene in path {
+        for byte in (*gene as u64).to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
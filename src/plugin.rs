@@ -0,0 +1,196 @@
+use crate::organism::{Distance, Organism};
+use crate::permutation;
+use crate::registry::{self, ComponentInfo, ComponentKind};
+use crate::runner::PopulationSolver;
+use libloading::{Library, Symbol};
+use once_cell::sync::Lazy;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::ffi::{c_char, CStr};
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+pub const PLUGIN_ENTRY_POINT: &[u8] = b"ga_plugin_problem\0";
+
+// Stable layout a plugin returns from its `ga_plugin_problem` entry point.
+// Genomes are permutations of `genome_length` indices, the operators are
+// optional and the built in permutation ones are used when they are null.
+#[repr(C)]
+pub struct PluginProblem {
+    pub abi_version: u32,
+    pub name: *const c_char,
+    pub description: *const c_char,
+    pub genome_length: usize,
+    pub fitness: extern "C" fn(genome: *const usize, length: usize) -> f32,
+    pub mutate: Option<extern "C" fn(genome: *mut usize, length: usize, seed: u64)>,
+    pub cross_over: Option<
+        extern "C" fn(
+            first: *const usize,
+            second: *const usize,
+            child: *mut usize,
+            length: usize,
+            seed: u64,
+        ),
+    >,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct PluginApi {
+    genome_length: usize,
+    fitness: extern "C" fn(*const usize, usize) -> f32,
+    mutate: Option<extern "C" fn(*mut usize, usize, u64)>,
+    cross_over: Option<extern "C" fn(*const usize, *const usize, *mut usize, usize, u64)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PluginGenome {
+    path: Vec<usize>,
+    api: PluginApi,
+}
+
+impl PluginGenome {
+    fn new_random(api: PluginApi) -> Self {
+        let mut path = (0..api.genome_length).collect::<Vec<usize>>();
+        path.shuffle(&mut rand::thread_rng());
+
+        PluginGenome { path, api }
+    }
+
+    pub fn get_path(&self) -> &Vec<usize> {
+        &self.path
+    }
+}
+
+impl Organism for PluginGenome {
+    type Fitness = f32;
+
+    fn fitness(&self) -> f32 {
+        (self.api.fitness)(self.path.as_ptr(), self.path.len())
+    }
+
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        match self.api.mutate {
+            Some(mutate) => mutate(self.path.as_mut_ptr(), self.path.len(), rng.gen()),
+            None => permutation::swap_mutation(&mut self.path, rng),
+        }
+    }
+
+    fn cross_over<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self
+    where
+        Self: Sized,
+    {
+        let path = match self.api.cross_over {
+            Some(cross_over) => {
+                let mut child = vec![0; self.path.len()];
+                cross_over(
+                    self.path.as_ptr(),
+                    other.path.as_ptr(),
+                    child.as_mut_ptr(),
+                    self.path.len(),
+                    rng.gen(),
+                );
+                child
+            }
+            None => permutation::slice_crossover(&self.path, &other.path, rng),
+        };
+
+        PluginGenome {
+            path,
+            api: self.api,
+        }
+    }
+}
+
+impl Distance for PluginGenome {
+    fn distance(&self, other: &Self) -> f32 {
+        permutation::edge_distance(&self.path, &other.path, false) as f32
+    }
+}
+
+#[derive(Debug)]
+pub enum PluginError {
+    Load(libloading::Error),
+    AbiVersion { found: u32, expected: u32 },
+    InvalidProblem(String),
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PluginError::Load(error) => write!(f, "failed to load plugin: {}", error),
+            PluginError::AbiVersion { found, expected } => write!(
+                f,
+                "plugin ABI version {} is not supported, expected {}",
+                found, expected
+            ),
+            PluginError::InvalidProblem(reason) => write!(f, "invalid plugin problem: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
+
+// Loaded libraries are never unloaded, the registered factories point into them
+static LIBRARIES: Lazy<Mutex<Vec<Library>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// Loads a shared library and registers its problem, returning the problem name
+pub fn load_plugin(path: &Path) -> Result<String, PluginError> {
+    let library = unsafe { Library::new(path) }.map_err(PluginError::Load)?;
+
+    let problem = unsafe {
+        let entry: Symbol<unsafe extern "C" fn() -> *const PluginProblem> =
+            library.get(PLUGIN_ENTRY_POINT).map_err(PluginError::Load)?;
+        entry()
+            .as_ref()
+            .ok_or_else(|| PluginError::InvalidProblem("entry point returned null".to_string()))?
+    };
+
+    if problem.abi_version != PLUGIN_ABI_VERSION {
+        return Err(PluginError::AbiVersion {
+            found: problem.abi_version,
+            expected: PLUGIN_ABI_VERSION,
+        });
+    }
+    if problem.name.is_null() {
+        return Err(PluginError::InvalidProblem("missing name".to_string()));
+    }
+    if problem.genome_length == 0 {
+        return Err(PluginError::InvalidProblem("empty genome".to_string()));
+    }
+
+    let name = unsafe { CStr::from_ptr(problem.name) }
+        .to_string_lossy()
+        .into_owned();
+    let description = if problem.description.is_null() {
+        format!("Plugin loaded from {}", path.display())
+    } else {
+        unsafe { CStr::from_ptr(problem.description) }
+            .to_string_lossy()
+            .into_owned()
+    };
+
+    let api = PluginApi {
+        genome_length: problem.genome_length,
+        fitness: problem.fitness,
+        mutate: problem.mutate,
+        cross_over: problem.cross_over,
+    };
+
+    LIBRARIES.lock().unwrap().push(library);
+
+    registry::register_component(ComponentInfo::new(
+        ComponentKind::Problem,
+        &name,
+        &description,
+    ));
+    registry::register_problem(&name, move |parameters| {
+        let population = (0..parameters.population_size)
+            .map(|_| PluginGenome::new_random(api))
+            .collect::<Vec<PluginGenome>>();
+        Box::new(PopulationSolver::new(population, *parameters))
+    });
+
+    Ok(name)
+}
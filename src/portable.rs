@@ -0,0 +1,253 @@
+// GA core that only needs `core` and `alloc`, so it builds without the
+// standard library (`default-features = false`). Randomness comes from a
+// generator owned by the caller, nothing runs in parallel. The std GA breeds
+// with the same traits, operators and `breed`, in parallel.
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt::Debug;
+use rand::Rng;
+
+// Randomness comes from the generator of the caller, so a run started from
+// the same seed breeds the same individuals
+pub trait Organism {
+    // Lower is better, in whatever type the problem counts it
+    type Fitness: Fitness;
+
+    fn fitness(&self) -> Self::Fitness;
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R);
+    fn cross_over<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self
+    where
+        Self: Sized;
+
+    // The fitness as statistics and reports show it
+    fn score(&self) -> f32 {
+        self.fitness().to_f32()
+    }
+}
+
+// A fitness the engine can rank. Individuals are sorted and selected in the
+// type of the problem, only statistics, reports and the messages between
+// ranks carry it as an f32.
+pub trait Fitness: PartialOrd + Copy + Debug + Send + Sync {
+    fn to_f32(self) -> f32;
+
+    // A total order for sorting: as `PartialOrd`, with the values that don't
+    // even compare to themselves, NaN say, after all others
+    fn compare(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other).unwrap_or_else(|| {
+            let comparable = |value: &Self| value.partial_cmp(value).is_some();
+            comparable(other).cmp(&comparable(self))
+        })
+    }
+}
+
+macro_rules! numeric_fitness {
+    ($($numeric:ty),*) => {
+        $(
+            impl Fitness for $numeric {
+                fn to_f32(self) -> f32 {
+                    self as f32
+                }
+            }
+        )*
+    };
+}
+
+numeric_fitness!(f32, f64, i32, i64, u32, u64, usize);
+
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Parameters {
+    pub elite: usize,
+    pub mutation_rate: f32,
+    pub crossover_rate: f32,
+}
+
+// Fitness of every individual, best first. The sort is stable so equal
+// fitness keeps the population order.
+pub fn rank<T: Organism>(population: &[T]) -> Vec<(T::Fitness, &T)> {
+    let mut ranked = population
+        .iter()
+        .map(|individual| (individual.fitness(), individual))
+        .collect::<Vec<(T::Fitness, &T)>>();
+    ranked.sort_by(|a, b| a.0.compare(&b.0));
+    ranked
+}
+
+// The child of two parents: their crossover with the crossover rate, a copy
+// of `first` otherwise, mutated with the mutation rate
+pub fn breed<T: Organism + Clone, R: Rng + ?Sized>(
+    first: &T,
+    second: &T,
+    parameters: &Parameters,
+    rng: &mut R,
+) -> T {
+    let mut child = if rng.gen::<f32>() < parameters.crossover_rate {
+        first.cross_over(second, rng)
+    } else {
+        first.clone()
+    };
+    if rng.gen::<f32>() < parameters.mutation_rate {
+        child.mutate(rng);
+    }
+    child
+}
+
+// Appends the first `elite` individuals of a ranked population to `children`
+pub fn keep_elite<F, T: Clone>(children: &mut Vec<T>, ranked: &[(F, &T)], elite: usize) {
+    children.extend(
+        ranked[..elite]
+            .iter()
+            .map(|(_, individual)| (*individual).clone()),
+    );
+}
+
+// One generation: the elite is copied, every other ranked individual breeds
+// with the next one (the last with the first non elite). The population size
+// never changes.
+pub fn iteration<T: Organism + Clone, R: Rng + ?Sized>(
+    population: &[T],
+    parameters: &Parameters,
+    rng: &mut R,
+) -> Vec<T> {
+    let ranked = rank(population);
+    let elite = parameters.elite.min(ranked.len());
+    let parents = &ranked[elite..];
+
+    let mut next = Vec::with_capacity(population.len());
+    for k in 0..parents.len() {
+        let first = parents[k].1;
+        let second = parents[(k + 1) % parents.len()].1;
+        next.push(breed(first, second, parameters, rng));
+    }
+    keep_elite(&mut next, &ranked, elite);
+    next
+}
+
+// Runs `generations` iterations and returns the final population
+pub fn run<T: Organism + Clone, R: Rng + ?Sized>(
+    mut population: Vec<T>,
+    parameters: &Parameters,
+    generations: usize,
+    rng: &mut R,
+) -> Vec<T> {
+    for _ in 0..generations {
+        population = iteration(&population, parameters, rng);
+    }
+    population
+}
+
+pub fn best<T: Organism>(population: &[T]) -> Option<(T::Fitness, &T)> {
+    rank(population).first().copied()
+}
+
+// Permutation operators for tour like genomes
+
+pub fn swap_mutation<R: Rng + ?Sized>(path: &mut [usize], rng: &mut R) {
+    let first_index = rng.gen_range(0..path.len());
+    let second_index = rng.gen_range(0..path.len());
+
+    path.swap(first_index, second_index);
+}
+
+// Rotates the nodes at three distinct random positions, a plain swap on
+// shorter paths
+pub fn three_swap_mutation<R: Rng + ?Sized>(path: &mut [usize], rng: &mut R) {
+    if path.len() < 3 {
+        return swap_mutation(path, rng);
+    }
+
+    let positions = rand::seq::index::sample(rng, path.len(), 3);
+    let (a, b, c) = (positions.index(0), positions.index(1), positions.index(2));
+    let first = path[a];
+    path[a] = path[b];
+    path[b] = path[c];
+    path[c] = first;
+}
+
+// Reverses a random segment
+pub fn inversion_mutation<R: Rng + ?Sized>(path: &mut [usize], rng: &mut R) {
+    let first_index = rng.gen_range(0..path.len());
+    let second_index = rng.gen_range(0..path.len());
+
+    path[first_index.min(second_index)..=first_index.max(second_index)].reverse();
+}
+
+// Moves a random node to another random position
+pub fn insertion_mutation<R: Rng + ?Sized>(path: &mut [usize], rng: &mut R) {
+    let from = rng.gen_range(0..path.len());
+    let to = rng.gen_range(0..path.len());
+
+    if from < to {
+        path[from..=to].rotate_left(1);
+    } else {
+        path[to..=from].rotate_right(1);
+    }
+}
+
+// Order crossover (OX): keeps a slice of the first parent in place and fills the
+// rest with the missing nodes in the order they appear in the second parent,
+// starting right after the slice, so the child is always a valid permutation
+pub fn order_crossover<R: Rng + ?Sized>(
+    first: &[usize],
+    second: &[usize],
+    rng: &mut R,
+) -> Vec<usize> {
+    let length = first.len();
+
+    let start_index = rng.gen_range(0..length);
+    let end_index = rng.gen_range(start_index..length);
+
+    let mut used = alloc::vec![false; length];
+    let mut new_path = alloc::vec![0; length];
+    for i in start_index..end_index {
+        new_path[i] = first[i];
+        used[first[i]] = true;
+    }
+
+    let mut position = end_index % length;
+    for offset in 0..length {
+        let node = second[(end_index + offset) % length];
+        if used[node] {
+            continue;
+        }
+        used[node] = true;
+        new_path[position] = node;
+        position = (position + 1) % length;
+    }
+
+    new_path
+}
+
+// Partially mapped crossover (PMX): keeps a slice of the first parent in place
+// and the other positions of the second parent. A node of the second parent
+// already in the slice is replaced by the node the second parent has where the
+// first one has it, until that one is not in the slice either.
+pub fn partially_mapped_crossover<R: Rng + ?Sized>(
+    first: &[usize],
+    second: &[usize],
+    rng: &mut R,
+) -> Vec<usize> {
+    let length = first.len();
+
+    let start_index = rng.gen_range(0..length);
+    let end_index = rng.gen_range(start_index..length);
+
+    // Where the slice has every node it keeps
+    let mut in_slice = alloc::vec![None; length];
+    let mut new_path = alloc::vec![0; length];
+    for i in start_index..end_index {
+        new_path[i] = first[i];
+        in_slice[first[i]] = Some(i);
+    }
+
+    for i in (0..start_index).chain(end_index..length) {
+        let mut node = second[i];
+        while let Some(position) = in_slice[node] {
+            node = second[position];
+        }
+        new_path[i] = node;
+    }
+
+    new_path
+}
@@ -0,0 +1,164 @@
+use crate::permutation::{CrossoverOperator, MutationOperator};
+use crate::tsp::matrix::WeightMatrix;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+// Nearest cities every city is linked to in the graph the clustering
+// coefficient is measured on
+const NEIGHBORS: usize = 8;
+// An entry matches instances with up to this many times more or fewer cities
+const SIZE_RATIO: f64 = 2.0;
+// and a clustering coefficient at most this far from its own
+const CLUSTERING_TOLERANCE: f64 = 0.1;
+
+// What a portfolio entry is keyed by, cheap to measure before the run
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InstanceFeatures {
+    pub cities: usize,
+    // Of the graph linking every city to its nearest ones, in both
+    // directions: how often two neighbors of a city are neighbors too, high
+    // when the cities come in clusters
+    pub clustering: f64,
+}
+
+impl InstanceFeatures {
+    pub fn of(weights: &WeightMatrix) -> Self {
+        let cities = weights.len();
+        let mut neighbors = vec![Vec::new(); cities];
+        for from in 0..cities {
+            let mut nearest = (0..cities).filter(|to| *to != from).collect::<Vec<_>>();
+            nearest.sort_by(|a, b| weights.dist(from, *a).total_cmp(&weights.dist(from, *b)));
+            for to in nearest.into_iter().take(NEIGHBORS) {
+                neighbors[from].push(to);
+                neighbors[to].push(from);
+            }
+        }
+        for list in neighbors.iter_mut() {
+            list.sort_unstable();
+            list.dedup();
+        }
+
+        // Cities with fewer than two neighbors have no pair to count
+        let coefficients = neighbors
+            .iter()
+            .filter(|list| list.len() > 1)
+            .map(|list| {
+                let links = list
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, a)| list[i + 1..].iter().map(move |b| (*a, *b)))
+                    .filter(|(a, b)| neighbors[*a].binary_search(b).is_ok())
+                    .count();
+                let pairs = list.len() * (list.len() - 1) / 2;
+                links as f64 / pairs as f64
+            })
+            .collect::<Vec<f64>>();
+
+        InstanceFeatures {
+            cities,
+            clustering: if coefficients.is_empty() {
+                0.0
+            } else {
+                coefficients.iter().sum::<f64>() / coefficients.len() as f64
+            },
+        }
+    }
+
+    // How far apart two instances are, None when too far for one's entry to
+    // be applied to the other
+    fn distance(&self, other: &InstanceFeatures) -> Option<f64> {
+        let size = (self.cities.max(1) as f64 / other.cities.max(1) as f64)
+            .ln()
+            .abs();
+        let clustering = (self.clustering - other.clustering).abs();
+        (size <= SIZE_RATIO.ln() && clustering <= CLUSTERING_TOLERANCE)
+            .then(|| size / SIZE_RATIO.ln() + clustering / CLUSTERING_TOLERANCE)
+    }
+}
+
+impl fmt::Display for InstanceFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} cities, clustering {:.3}",
+            self.cities, self.clustering
+        )
+    }
+}
+
+// The best configuration found for one instance. The operators are kept by
+// their command line names so the file can be edited by hand.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioEntry {
+    pub instance: String,
+    pub features: InstanceFeatures,
+    pub crossover: String,
+    pub mutation: String,
+    pub mutation_rate: f32,
+    pub crossover_rate: f32,
+    // What the configuration reached on the instance
+    pub best: f32,
+}
+
+impl PortfolioEntry {
+    pub fn crossover(&self) -> Result<CrossoverOperator, String> {
+        self.crossover.parse()
+    }
+
+    pub fn mutation(&self) -> Result<MutationOperator, String> {
+        self.mutation.parse()
+    }
+}
+
+// Configurations learned on past runs by `tune` and `history`, the run
+// starts from the one of the most similar instance
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Portfolio {
+    pub entries: Vec<PortfolioEntry>,
+}
+
+impl Portfolio {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        serde_json::from_str(&source).map_err(|error| error.to_string())
+    }
+
+    // An empty portfolio when there is no file yet
+    pub fn read_or_default(path: &Path) -> Result<Self, String> {
+        if path.exists() {
+            Portfolio::read(path)
+        } else {
+            Ok(Portfolio::default())
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let file = BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    // Replaces the entry of the same instance unless it reached better
+    pub fn add(&mut self, entry: PortfolioEntry) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|known| known.instance == entry.instance)
+        {
+            Some(known) if known.best <= entry.best => {}
+            Some(known) => *known = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    // The entry of the closest instance among those that match
+    pub fn lookup(&self, features: &InstanceFeatures) -> Option<&PortfolioEntry> {
+        self.entries
+            .iter()
+            .filter_map(|entry| Some((entry.features.distance(features)?, entry)))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, entry)| entry)
+    }
+}
@@ -0,0 +1,81 @@
+use crate::analysis;
+use crate::organism::Organism;
+use crate::permutation::{CrossoverOperator, MutationOperator};
+use crate::runner::{PopulationSolver, RunParameters, Solver};
+use crate::tsp::matrix::WeightMatrix;
+use crate::tsp::{TspProblem, TSP};
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug)]
+pub struct RecommendOptions {
+    pub generations: usize,
+    pub population_size: usize,
+    pub repeats: usize,
+    pub elite: usize,
+    pub mutation_rate: f32,
+    pub crossover_rate: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Trial {
+    pub crossover: CrossoverOperator,
+    pub mutation: MutationOperator,
+    // Mean over the repeats of the best fitness at the end of the short run
+    pub mean_best: f32,
+    // Lag 1 autocorrelation of random walks under the mutation, smoother is higher
+    pub autocorrelation: f64,
+}
+
+// Runs short probes for every crossover and mutation pair, best pair first
+pub fn recommend_tsp(graph_weights: Arc<WeightMatrix>, options: RecommendOptions) -> Vec<Trial> {
+    let parameters = RunParameters {
+        iterations: options.generations,
+        population_size: options.population_size,
+        elite: options.elite.min(options.population_size.saturating_sub(2)),
+        mutation_rate: options.mutation_rate,
+        crossover_rate: options.crossover_rate,
+    };
+
+    let mut trials = Vec::new();
+    for mutation in MutationOperator::ALL {
+        let probed = Arc::new(
+            TspProblem::new(graph_weights.clone())
+                .with_operators(CrossoverOperator::Slice, mutation),
+        );
+        let autocorrelation =
+            analysis::probe(|| TSP::new_with_random_path(&probed), 20, 200, 2).autocorrelation;
+
+        for crossover in CrossoverOperator::ALL {
+            let problem = Arc::new(
+                TspProblem::new(graph_weights.clone()).with_operators(crossover, mutation),
+            );
+            let total = (0..options.repeats)
+                .map(|_| {
+                    let population = (0..parameters.population_size)
+                        .map(|_| TSP::new_with_random_path(&problem))
+                        .collect::<Vec<TSP>>();
+
+                    let mut solver = PopulationSolver::new(population, parameters);
+                    for _ in 0..parameters.iterations {
+                        solver.step();
+                    }
+                    solver
+                        .population()
+                        .iter()
+                        .map(|individual| individual.fitness())
+                        .fold(f32::INFINITY, f32::min)
+                })
+                .sum::<f32>();
+
+            trials.push(Trial {
+                crossover,
+                mutation,
+                mean_best: total / options.repeats.max(1) as f32,
+                autocorrelation,
+            });
+        }
+    }
+
+    trials.sort_by(|a, b| a.mean_best.total_cmp(&b.mean_best));
+    trials
+}
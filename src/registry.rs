@@ -0,0 +1,298 @@
+use crate::runner::{RunParameters, Solver};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ComponentKind {
+    Problem,
+    Crossover,
+    Mutation,
+    Selection,
+    Distance,
+}
+
+impl fmt::Display for ComponentKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ComponentKind::Problem => "problems",
+            ComponentKind::Crossover => "crossover operators",
+            ComponentKind::Mutation => "mutation operators",
+            ComponentKind::Selection => "selection operators",
+            ComponentKind::Distance => "distance providers",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ConfigKey {
+    pub name: String,
+    pub description: String,
+    pub default: Option<String>,
+}
+
+impl ConfigKey {
+    pub fn new(name: &str, description: &str, default: Option<&str>) -> Self {
+        ConfigKey {
+            name: name.to_string(),
+            description: description.to_string(),
+            default: default.map(|value| value.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ComponentInfo {
+    pub kind: ComponentKind,
+    pub name: String,
+    pub description: String,
+    pub config_keys: Vec<ConfigKey>,
+}
+
+impl ComponentInfo {
+    pub fn new(kind: ComponentKind, name: &str, description: &str) -> Self {
+        ComponentInfo {
+            kind,
+            name: name.to_string(),
+            description: description.to_string(),
+            config_keys: Vec::new(),
+        }
+    }
+
+    pub fn with_key(mut self, name: &str, description: &str, default: Option<&str>) -> Self {
+        self.config_keys
+            .push(ConfigKey::new(name, description, default));
+        self
+    }
+}
+
+pub type ProblemFactory = Arc<dyn Fn(&RunParameters) -> Box<dyn Solver> + Send + Sync>;
+
+static COMPONENTS: Lazy<RwLock<Vec<ComponentInfo>>> = Lazy::new(|| RwLock::new(builtins()));
+static PROBLEMS: Lazy<RwLock<HashMap<String, ProblemFactory>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+fn builtins() -> Vec<ComponentInfo> {
+    vec![
+        ComponentInfo::new(
+            ComponentKind::Problem,
+            "tsp",
+            "Travelling salesman over a distance matrix",
+        )
+        .with_key(
+            "min_initial_distance",
+            "Reject initial tours closer than this edge distance",
+            Some("1.0"),
+        )
+        .with_key(
+            "nearest_neighbor_seeding",
+            "Seed one nearest neighbor tour from every city",
+            Some("true"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Problem,
+            "clustered-tsp",
+            "Travelling salesman visiting the cities of every --groups group one after another",
+        ),
+        ComponentInfo::new(
+            ComponentKind::Crossover,
+            "slice",
+            "Copies a random slice of the second parent into the first, may visit a city twice",
+        )
+        .with_key(
+            "crossover_rate",
+            "Probability of crossing a pair",
+            Some("0.9"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Crossover,
+            "order",
+            "Order crossover (OX), keeps a slice of the first parent and the relative order of the second",
+        )
+        .with_key(
+            "crossover_rate",
+            "Probability of crossing a pair",
+            Some("0.9"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Crossover,
+            "pmx",
+            "Partially mapped crossover (PMX), keeps a slice of the first parent and maps the rest of the second around it",
+        )
+        .with_key(
+            "crossover_rate",
+            "Probability of crossing a pair",
+            Some("0.9"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Crossover,
+            "cycle",
+            "Cycle crossover (CX), every city keeps the position it has in one of the parents",
+        )
+        .with_key(
+            "crossover_rate",
+            "Probability of crossing a pair",
+            Some("0.9"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Crossover,
+            "heuristic",
+            "Greedy edge crossover, follows the closer of the two parental successors",
+        )
+        .with_key(
+            "crossover_rate",
+            "Probability of crossing a pair",
+            Some("0.9"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Crossover,
+            "scx",
+            "Sequential constructive crossover, takes the closer of the next unvisited cities of both parents",
+        )
+        .with_key(
+            "crossover_rate",
+            "Probability of crossing a pair",
+            Some("0.9"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Mutation,
+            "swap",
+            "Swaps two random positions of the genome",
+        )
+        .with_key(
+            "mutation_rate",
+            "Probability of mutating a child",
+            Some("0.1"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Mutation,
+            "inversion",
+            "Reverses a random segment of the genome",
+        )
+        .with_key(
+            "mutation_rate",
+            "Probability of mutating a child",
+            Some("0.1"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Mutation,
+            "insertion",
+            "Moves a random node to another position",
+        )
+        .with_key(
+            "mutation_rate",
+            "Probability of mutating a child",
+            Some("0.1"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Mutation,
+            "3-swap",
+            "Rotates the nodes at three random positions",
+        )
+        .with_key(
+            "mutation_rate",
+            "Probability of mutating a child",
+            Some("0.1"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Mutation,
+            "composite",
+            "Applies one of swap, inversion, insertion and 3-swap per mutation, drawn by weight",
+        )
+        .with_key(
+            "mutation_rate",
+            "Probability of mutating a child",
+            Some("0.1"),
+        )
+        .with_key(
+            "mutation_weights",
+            "Relative weight of every operator",
+            Some("swap=1,inversion=1,insertion=1,3-swap=1"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Selection,
+            "adjacent",
+            "Sorts by fitness and pairs adjacent individuals, keeping the elite",
+        )
+        .with_key(
+            "elite",
+            "Individuals copied unchanged to the next generation",
+            Some("20"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Selection,
+            "tournament",
+            "Every parent is the fittest of a few individuals drawn at random",
+        )
+        .with_key(
+            "tournament_size",
+            "Individuals drawn for every tournament",
+            Some("3"),
+        ),
+        ComponentInfo::new(
+            ComponentKind::Selection,
+            "roulette",
+            "Draws parents with a probability growing as they are shorter than the worst",
+        ),
+        ComponentInfo::new(
+            ComponentKind::Selection,
+            "rank",
+            "Linear ranking, draws parents by their rank rather than their fitness",
+        ),
+        ComponentInfo::new(
+            ComponentKind::Distance,
+            "edge",
+            "Number of tour edges not shared between two genomes",
+        ),
+    ]
+}
+
+pub fn register_component(info: ComponentInfo) {
+    let mut components = COMPONENTS.write().unwrap();
+    components.retain(|component| !(component.kind == info.kind && component.name == info.name));
+    components.push(info);
+}
+
+pub fn components() -> Vec<ComponentInfo> {
+    let mut components = COMPONENTS.read().unwrap().clone();
+    components.sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+    components
+}
+
+pub fn components_of(kind: ComponentKind) -> Vec<ComponentInfo> {
+    components()
+        .into_iter()
+        .filter(|component| component.kind == kind)
+        .collect()
+}
+
+// Makes a problem selectable by name, the factory builds a ready to run solver
+pub fn register_problem<F>(name: &str, factory: F)
+where
+    F: Fn(&RunParameters) -> Box<dyn Solver> + Send + Sync + 'static,
+{
+    PROBLEMS
+        .write()
+        .unwrap()
+        .insert(name.to_string(), Arc::new(factory));
+
+    let known = COMPONENTS
+        .read()
+        .unwrap()
+        .iter()
+        .any(|component| component.kind == ComponentKind::Problem && component.name == name);
+    if !known {
+        register_component(ComponentInfo::new(
+            ComponentKind::Problem,
+            name,
+            "User registered problem",
+        ));
+    }
+}
+
+pub fn create_problem(name: &str, parameters: &RunParameters) -> Option<Box<dyn Solver>> {
+    let factory = PROBLEMS.read().unwrap().get(name).cloned();
+    factory.map(|factory| factory(parameters))
+}
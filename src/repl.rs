@@ -0,0 +1,239 @@
+use crate::genetic_algorithm::ga_evaluate_population;
+use crate::organism::Organism;
+use crate::runner::{PopulationSolver, RunParameters, Solver};
+use crate::statistics::FitnessSummary;
+use crate::tsp::{TspProblem, TspSolution, TSP};
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+const HELP: &str = "Commands:
+  step [n]                  run n generations (default 1)
+  stats                     statistics of the current population
+  best [k]                  the k best tours (default 1)
+  set <key> <value>         change mutation_rate, crossover_rate or elite
+  inject <tour-file>        replace the worst individual with a tour
+  dump population [file]    print every individual, or write them to a file
+  help                      show this message
+  quit                      leave";
+
+// Drives a local TSP run one command at a time, nothing is distributed
+pub struct Session {
+    solver: PopulationSolver<TSP>,
+    // Shared by the population, and by the tours injected into it
+    problem: Arc<TspProblem>,
+    generation: usize,
+}
+
+impl Session {
+    pub fn new(population: Vec<TSP>, parameters: RunParameters) -> Self {
+        let problem = population
+            .first()
+            .expect("Empty population")
+            .problem()
+            .clone();
+
+        Session {
+            solver: PopulationSolver::new(population, parameters),
+            problem,
+            generation: 0,
+        }
+    }
+
+    pub fn run(&mut self, input: impl BufRead) {
+        println!("{}", HELP);
+        prompt();
+
+        for line in input.lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let words = line.split_whitespace().collect::<Vec<&str>>();
+
+            match words[..] {
+                [] => {}
+                ["quit"] | ["exit"] => break,
+                ["help"] => println!("{}", HELP),
+                ["step"] => self.step(1),
+                ["step", count] => match count.parse() {
+                    Ok(count) => self.step(count),
+                    Err(_) => println!("step takes a number of generations"),
+                },
+                ["stats"] => self.stats(),
+                ["best"] => self.best(1),
+                ["best", count] => match count.parse() {
+                    Ok(count) => self.best(count),
+                    Err(_) => println!("best takes a number of tours"),
+                },
+                ["set", key, value] => {
+                    if let Err(error) = self.set(key, value) {
+                        println!("{}", error);
+                    }
+                }
+                ["inject", path] => match self.inject(Path::new(path)) {
+                    Ok(fitness) => println!("Injected a tour of length {}", fitness),
+                    Err(error) => println!("{}: {}", path, error),
+                },
+                ["dump", "population"] => self.dump(&mut io::stdout().lock()),
+                ["dump", "population", path] => {
+                    match File::create(path) {
+                        Ok(file) => self.dump(&mut BufWriter::new(file)),
+                        Err(error) => println!("{}: {}", path, error),
+                    };
+                }
+                _ => println!("Unknown command, type help to list them"),
+            }
+
+            prompt();
+        }
+    }
+
+    fn step(&mut self, count: usize) {
+        for _ in 0..count {
+            let summary = self.solver.step();
+            print_summary(self.generation, &summary);
+            self.generation += 1;
+        }
+    }
+
+    fn stats(&self) {
+        let summary = FitnessSummary::from_fitness(
+            ga_evaluate_population(self.solver.population())
+                .iter()
+                .map(|(fitness, _)| *fitness),
+        );
+        print_summary(self.generation, &summary);
+
+        let parameters = self.solver.parameters();
+        println!(
+            "population: {}, elite: {}, mutation rate: {}, crossover rate: {}",
+            self.solver.population().len(),
+            parameters.elite,
+            parameters.mutation_rate,
+            parameters.crossover_rate
+        );
+    }
+
+    fn best(&self, count: usize) {
+        let mut evaluated = ga_evaluate_population(self.solver.population());
+        evaluated.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for (fitness, individual) in evaluated.iter().take(count) {
+            println!("{} -> {:?}", fitness, individual.get_path());
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let mut parameters = *self.solver.parameters();
+        let invalid = |_| format!("invalid value {} for {}", value, key);
+
+        match key {
+            "mutation_rate" => parameters.mutation_rate = value.parse().map_err(invalid)?,
+            "crossover_rate" => parameters.crossover_rate = value.parse().map_err(invalid)?,
+            "elite" => {
+                parameters.elite = value
+                    .parse()
+                    .map_err(|_| format!("invalid value {} for {}", value, key))?
+            }
+            _ => return Err(format!("unknown parameter {}", key)),
+        }
+
+        // The population size is fixed for the session
+        parameters.population_size = self.solver.population().len();
+        if let Some(problem) = parameters.problems().into_iter().next() {
+            return Err(problem);
+        }
+
+        self.solver.set_parameters(parameters);
+        Ok(())
+    }
+
+    fn inject(&mut self, path: &Path) -> Result<f32, String> {
+        let source = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let tour = parse_tour(&source, self.problem.graph_weights.len())?;
+
+        let individual = TSP::new(self.problem.clone(), TspSolution { path: tour });
+
+        let population = self.solver.population_mut();
+        let worst = ga_evaluate_population(population)
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1 .0.total_cmp(&b.1 .0))
+            .map(|(index, _)| index)
+            .ok_or("the population is empty")?;
+
+        let fitness = individual.fitness();
+        population[worst] = individual;
+        Ok(fitness)
+    }
+
+    fn dump(&self, output: &mut impl Write) {
+        for (fitness, individual) in ga_evaluate_population(self.solver.population()) {
+            if writeln!(output, "{} {:?}", fitness, individual.get_path()).is_err() {
+                println!("Failed to write the population");
+                return;
+            }
+        }
+    }
+}
+
+fn prompt() {
+    print!("> ");
+    let _ = io::stdout().flush();
+}
+
+fn print_summary(generation: usize, summary: &FitnessSummary) {
+    println!(
+        "Generation {}, best: {}, mean: {:.3}, std dev: {:.3}, invalid: {}/{}",
+        generation,
+        summary.best,
+        summary.mean(),
+        summary.std_dev(),
+        summary.invalid,
+        summary.count
+    );
+}
+
+// Either a TSPLIB tour file (1 based, ended by -1) or plain 0 based indices
+// separated by whitespace or commas
+fn parse_tour(source: &str, nodes: usize) -> Result<Vec<usize>, String> {
+    let tour = match source.find("TOUR_SECTION") {
+        Some(start) => source[start + "TOUR_SECTION".len()..]
+            .split_whitespace()
+            .map(|word| word.parse::<i64>())
+            .take_while(|node| !matches!(node, Ok(-1)))
+            .map(|node| match node {
+                Ok(node) if node >= 1 => Ok(node as usize - 1),
+                _ => Err("invalid city in TOUR_SECTION".to_string()),
+            })
+            .collect::<Result<Vec<usize>, String>>()?,
+        None => source
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                word.parse::<usize>()
+                    .map_err(|_| format!("invalid city {}", word))
+            })
+            .collect::<Result<Vec<usize>, String>>()?,
+    };
+
+    let mut seen = vec![false; nodes];
+    for node in tour.iter() {
+        if *node >= nodes || std::mem::replace(&mut seen[*node], true) {
+            return Err(format!(
+                "the tour is not a permutation of the {} cities",
+                nodes
+            ));
+        }
+    }
+    if tour.len() != nodes {
+        return Err(format!(
+            "the tour visits {} of {} cities",
+            tour.len(),
+            nodes
+        ));
+    }
+
+    Ok(tour)
+}
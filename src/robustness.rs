@@ -0,0 +1,143 @@
+use crate::tsp::matrix::WeightMatrix;
+use crate::tsp::TspSolution;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+// How the cost of every edge is perturbed, as a factor around 1 drawn for
+// the edge in every sample. An edge costs the same both ways.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NoiseModel {
+    // Between 1 - scale and 1 + scale
+    Uniform,
+    // Normal around 1 with the scale as standard deviation, never below 0
+    Gaussian,
+    // e to the power of a normal around 0, a cost never goes below 0 and
+    // rises more than it falls
+    LogNormal,
+}
+
+impl NoiseModel {
+    pub const ALL: [NoiseModel; 3] = [
+        NoiseModel::Uniform,
+        NoiseModel::Gaussian,
+        NoiseModel::LogNormal,
+    ];
+
+    fn factor<R: Rng>(&self, scale: f32, rng: &mut R) -> f32 {
+        match self {
+            NoiseModel::Uniform => 1.0 + scale * rng.gen_range(-1.0..=1.0),
+            NoiseModel::Gaussian => (1.0 + scale * standard_normal(rng)).max(0.0),
+            NoiseModel::LogNormal => (scale * standard_normal(rng)).exp(),
+        }
+    }
+}
+
+// Box-Muller, the first uniform is kept away from 0 for the logarithm
+fn standard_normal<R: Rng>(rng: &mut R) -> f32 {
+    let radius = (-2.0 * (1.0 - rng.gen::<f32>()).ln()).sqrt();
+    radius * (std::f32::consts::TAU * rng.gen::<f32>()).cos()
+}
+
+impl fmt::Display for NoiseModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            NoiseModel::Uniform => "uniform",
+            NoiseModel::Gaussian => "gaussian",
+            NoiseModel::LogNormal => "log-normal",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for NoiseModel {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        NoiseModel::ALL
+            .into_iter()
+            .find(|model| model.to_string() == name)
+            .ok_or_else(|| format!("unknown noise model: {}", name))
+    }
+}
+
+// What a tour costs under the perturbed samples, next to its cost as given
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Robustness {
+    pub nominal: f32,
+    pub mean: f32,
+    // Nearest rank, 95 samples in 100 cost as much or less
+    pub p95: f32,
+    pub worst: f32,
+}
+
+// The edges of a tour each way round, sorted, the same for its rotations and
+// its reverse when `closed` so they are told apart from other tours
+pub fn edge_set(path: &[usize], closed: bool) -> Vec<(usize, usize)> {
+    let wrap = path.last().zip(path.first()).filter(|_| closed);
+    let mut edges = path
+        .windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .chain(wrap.map(|(last, first)| (*last, *first)))
+        .map(|(from, to)| (from.min(to), from.max(to)))
+        .collect::<Vec<_>>();
+    edges.sort_unstable();
+    edges
+}
+
+// The cost of every tour in `samples` perturbations of `weights`, the same
+// ones for every tour so they compare fairly. The tours return to their
+// first city when `closed`.
+pub fn assess(
+    weights: &WeightMatrix,
+    closed: bool,
+    tours: &[&TspSolution],
+    samples: usize,
+    model: NoiseModel,
+    scale: f32,
+    seed: u64,
+) -> Vec<Robustness> {
+    let length = |path: &[usize], cost: &mut dyn FnMut(usize, usize) -> f32| {
+        let mut length = path
+            .windows(2)
+            .map(|pair| cost(pair[0], pair[1]))
+            .fold(0.0, |total, cost| total + cost);
+        if let (true, Some(first), Some(last)) = (closed, path.first(), path.last()) {
+            length += cost(*last, *first);
+        }
+        length
+    };
+
+    let mut costs = vec![Vec::with_capacity(samples); tours.len()];
+    for sample in 0..samples {
+        // Drawn for the edges of the tours only, as they are first met
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(sample as u64));
+        let mut factors = HashMap::new();
+        let mut perturbed = |from: usize, to: usize| {
+            let factor = *factors
+                .entry((from.min(to), from.max(to)))
+                .or_insert_with(|| model.factor(scale, &mut rng));
+            weights.dist(from, to) * factor
+        };
+        for (tour, costs) in tours.iter().zip(costs.iter_mut()) {
+            costs.push(length(&tour.path, &mut perturbed));
+        }
+    }
+
+    tours
+        .iter()
+        .zip(costs)
+        .map(|(tour, mut costs)| {
+            costs.sort_by(f32::total_cmp);
+            let rank = (costs.len() * 95).div_ceil(100).max(1) - 1;
+            Robustness {
+                nominal: length(&tour.path, &mut |from, to| weights.dist(from, to)),
+                mean: costs.iter().sum::<f32>() / costs.len() as f32,
+                p95: costs[rank],
+                worst: costs[costs.len() - 1],
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,138 @@
+use crate::genetic_algorithm::{breeding_problems, ga_evaluate_population, ga_iteraration};
+use crate::organism::{Fitness, Organism};
+use crate::selection::{Selection, TournamentSelection};
+use crate::statistics::FitnessSummary;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, Debug)]
+pub struct RunParameters {
+    pub iterations: usize,
+    pub population_size: usize,
+    pub elite: usize,
+    pub mutation_rate: f32,
+    pub crossover_rate: f32,
+}
+
+impl RunParameters {
+    // Every reason the parameters can't be run, empty when they are fine
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = breeding_problems(
+            self.population_size,
+            self.elite,
+            self.mutation_rate,
+            self.crossover_rate,
+        );
+        if self.iterations == 0 {
+            problems.push("iterations must be at least 1".to_string());
+        }
+        problems
+    }
+}
+
+// Object safe view of a running GA, so problems can be picked by name at runtime
+pub trait Solver: Send {
+    // Advances one generation and returns the statistics of the evaluated population
+    fn step(&mut self) -> FitnessSummary;
+    fn best(&self) -> Option<(f32, String)>;
+}
+
+pub struct PopulationSolver<T> {
+    population: Vec<T>,
+    parameters: RunParameters,
+    selection: Arc<dyn Selection>,
+    rng: StdRng,
+}
+
+impl<T> PopulationSolver<T>
+where
+    T: Organism + Clone + Sync + Send + Sized + Debug,
+{
+    pub fn new(population: Vec<T>, parameters: RunParameters) -> Self {
+        PopulationSolver {
+            population,
+            parameters,
+            selection: Arc::new(TournamentSelection::default()),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    // Breeds from `seed` instead of a generator seeded by the operating system
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    pub fn with_selection(mut self, selection: Arc<dyn Selection>) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    pub fn population(&self) -> &Vec<T> {
+        &self.population
+    }
+
+    pub fn population_mut(&mut self) -> &mut Vec<T> {
+        &mut self.population
+    }
+
+    pub fn parameters(&self) -> &RunParameters {
+        &self.parameters
+    }
+
+    pub fn set_parameters(&mut self, parameters: RunParameters) {
+        self.parameters = parameters;
+    }
+}
+
+impl<T> Solver for PopulationSolver<T>
+where
+    T: Organism + Clone + Sync + Send + Sized + Debug,
+{
+    fn step(&mut self) -> FitnessSummary {
+        let summary = FitnessSummary::from_fitness(
+            ga_evaluate_population(&self.population)
+                .iter()
+                .map(|(fitness, _)| fitness.to_f32()),
+        );
+
+        self.population = ga_iteraration(
+            &self.population,
+            self.parameters.mutation_rate,
+            self.parameters.crossover_rate,
+            self.parameters.elite,
+            self.selection.as_ref(),
+            &mut self.rng,
+        );
+
+        summary
+    }
+
+    fn best(&self) -> Option<(f32, String)> {
+        ga_evaluate_population(&self.population)
+            .into_iter()
+            .min_by(|a, b| a.0.compare(&b.0))
+            .map(|(fitness, individual)| (fitness.to_f32(), format!("{:?}", individual)))
+    }
+}
+
+pub fn run(solver: &mut dyn Solver, iterations: usize) {
+    for i in 0..iterations {
+        let summary = solver.step();
+        println!(
+            "Iteration {}, best: {}, mean: {:.3}, std dev: {:.3}, invalid: {}/{}",
+            i,
+            summary.best,
+            summary.mean(),
+            summary.std_dev(),
+            summary.invalid,
+            summary.count
+        );
+    }
+
+    if let Some((fitness, description)) = solver.best() {
+        println!("Best one: {:?} -> {}", fitness, description);
+    }
+}
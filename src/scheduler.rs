@@ -0,0 +1,208 @@
+use crate::statistics::RunHistory;
+use crate::termination::StopReason;
+use crate::transport::TrafficStats;
+use crate::tsp::TspSolution;
+use serde::Serialize;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Never stop closer than this to the scheduler's kill, writing the final
+// population and the summary has to fit in what is left
+const MIN_SAFETY_MARGIN: Duration = Duration::from_secs(30);
+const SAFETY_FRACTION: f64 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheduler {
+    Slurm,
+    Pbs,
+}
+
+impl fmt::Display for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Scheduler::Slurm => f.pad("slurm"),
+            Scheduler::Pbs => f.pad("pbs"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JobInfo {
+    pub scheduler: Scheduler,
+    pub job_id: String,
+    pub node_list: Option<String>,
+    // Wall clock left to the job when it was detected, if the scheduler tells
+    #[serde(rename = "remaining_seconds", serialize_with = "as_seconds")]
+    pub remaining: Option<Duration>,
+}
+
+fn as_seconds<S: serde::Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_some(&duration.map(|duration| duration.as_secs()))
+}
+
+impl JobInfo {
+    // Time the run may use, slightly under what the scheduler allows
+    pub fn wall_clock_budget(&self) -> Option<Duration> {
+        self.remaining.map(|remaining| {
+            let margin = remaining.mul_f64(SAFETY_FRACTION).max(MIN_SAFETY_MARGIN);
+            remaining.saturating_sub(margin)
+        })
+    }
+}
+
+pub fn detect() -> Option<JobInfo> {
+    detect_from(|name| std::env::var(name).ok())
+}
+
+// Reads the job through `var` so other sources than the process environment
+// can be used
+pub fn detect_from(var: impl Fn(&str) -> Option<String>) -> Option<JobInfo> {
+    if let Some(job_id) = var("SLURM_JOB_ID") {
+        let remaining = match var("SLURM_JOB_END_TIME").and_then(|end| end.parse::<u64>().ok()) {
+            Some(end) => Some(Duration::from_secs(end.saturating_sub(unix_now()))),
+            None => var("SLURM_TIMELIMIT")
+                .or_else(|| var("SBATCH_TIMELIMIT"))
+                .and_then(|limit| parse_slurm_time(&limit)),
+        };
+
+        return Some(JobInfo {
+            scheduler: Scheduler::Slurm,
+            job_id,
+            node_list: var("SLURM_JOB_NODELIST").or_else(|| var("SLURM_NODELIST")),
+            remaining,
+        });
+    }
+
+    if let Some(job_id) = var("PBS_JOBID") {
+        // The node file has one line per slot, keep every node once
+        let node_list = var("PBS_NODEFILE")
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|nodes| {
+                let mut unique: Vec<&str> = Vec::new();
+                for node in nodes.lines().map(str::trim).filter(|node| !node.is_empty()) {
+                    if !unique.contains(&node) {
+                        unique.push(node);
+                    }
+                }
+                unique.join(",")
+            });
+
+        let remaining = var("PBS_WALLTIME").and_then(|walltime| {
+            walltime
+                .parse::<u64>()
+                .ok()
+                .map(Duration::from_secs)
+                .or_else(|| parse_clock_time(&walltime))
+        });
+
+        return Some(JobInfo {
+            scheduler: Scheduler::Pbs,
+            job_id,
+            node_list,
+            remaining,
+        });
+    }
+
+    None
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+// `hours:minutes:seconds`
+fn parse_clock_time(time: &str) -> Option<Duration> {
+    let parts = time
+        .split(':')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+
+    match parts[..] {
+        [hours, minutes, seconds] => {
+            Some(Duration::from_secs(hours * 3600 + minutes * 60 + seconds))
+        }
+        _ => None,
+    }
+}
+
+// SLURM accepts `minutes`, `minutes:seconds`, `hours:minutes:seconds`,
+// `days-hours`, `days-hours:minutes` and `days-hours:minutes:seconds`
+pub fn parse_slurm_time(time: &str) -> Option<Duration> {
+    let time = time.trim();
+    if time.eq_ignore_ascii_case("unlimited") || time.eq_ignore_ascii_case("infinite") {
+        return None;
+    }
+
+    let (days, clock) = match time.split_once('-') {
+        Some((days, clock)) => (Some(days.parse::<u64>().ok()?), clock),
+        None => (None, time),
+    };
+
+    let parts = clock
+        .split(':')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+
+    let seconds = match (days, &parts[..]) {
+        (None, [minutes]) => minutes * 60,
+        (None, [minutes, seconds]) => minutes * 60 + seconds,
+        (None, [hours, minutes, seconds]) => hours * 3600 + minutes * 60 + seconds,
+        (Some(days), [hours]) => days * 86400 + hours * 3600,
+        (Some(days), [hours, minutes]) => days * 86400 + hours * 3600 + minutes * 60,
+        (Some(days), [hours, minutes, seconds]) => {
+            days * 86400 + hours * 3600 + minutes * 60 + seconds
+        }
+        _ => return None,
+    };
+
+    Some(Duration::from_secs(seconds))
+}
+
+// Written once the run ends, for accounting tooling
+#[derive(Clone, Debug, Serialize)]
+pub struct RunSummary {
+    pub job: Option<JobInfo>,
+    pub problem: String,
+    pub instance: String,
+    pub crossover: String,
+    pub mutation: String,
+    pub processes: i32,
+    // Seed the run drew from, `--seed` repeats it
+    pub seed: u64,
+    pub generations: usize,
+    pub generations_completed: usize,
+    pub stopped_by_deadline: bool,
+    pub stop_reason: StopReason,
+    pub elapsed_seconds: f64,
+    pub best: f32,
+    pub mean: Option<f64>,
+    pub invalid: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    // Messages the root sent and received, only for distributed runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub traffic: Option<TrafficStats>,
+    // Statistics of every evaluated generation, empty for island runs. Kept
+    // out of the summary file, it grows with the run.
+    #[serde(skip)]
+    pub history: RunHistory,
+    // The best individual of the last generation, for a batch to warm start
+    // similar instances from
+    #[serde(skip)]
+    pub best_tour: Option<TspSolution>,
+}
+
+impl RunSummary {
+    pub fn write_json(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
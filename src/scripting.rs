@@ -0,0 +1,229 @@
+use crate::organism::{Distance, Organism};
+use crate::permutation;
+use crate::registry::{self, ComponentInfo, ComponentKind};
+use crate::runner::PopulationSolver;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rhai::{Array, Dynamic, Engine, Scope, AST};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// A script must define `fn genome_length()` and `fn fitness(genome)`, where the
+// genome is an array with a permutation of 0..genome_length. It may also define
+// `fn mutate(genome)` returning the mutated array.
+struct ScriptSource {
+    id: usize,
+    source: String,
+    genome_length: usize,
+    has_mutate: bool,
+}
+
+impl fmt::Debug for ScriptSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ScriptSource({})", self.id)
+    }
+}
+
+static NEXT_SCRIPT_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // Engines are not shared between threads, each rayon worker compiles its own copy
+    static ENGINES: RefCell<HashMap<usize, (Engine, AST)>> = RefCell::new(HashMap::new());
+}
+
+fn new_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.register_fn("random_index", |bound: i64| {
+        rand::thread_rng().gen_range(0..bound.max(1))
+    });
+    engine
+}
+
+fn with_script<R>(script: &ScriptSource, f: impl FnOnce(&Engine, &AST) -> R) -> R {
+    ENGINES.with(|engines| {
+        let mut engines = engines.borrow_mut();
+        let (engine, ast) = engines.entry(script.id).or_insert_with(|| {
+            let engine = new_engine();
+            // The source was already compiled once when the script was loaded
+            let ast = engine.compile(&script.source).unwrap();
+            (engine, ast)
+        });
+        f(engine, ast)
+    })
+}
+
+fn to_array(path: &[usize]) -> Array {
+    path.iter()
+        .map(|node| Dynamic::from(*node as i64))
+        .collect()
+}
+
+fn to_number(value: Dynamic) -> Option<f32> {
+    value
+        .as_float()
+        .map(|value| value as f32)
+        .or_else(|_| value.as_int().map(|value| value as f32))
+        .ok()
+}
+
+#[derive(Clone, Debug)]
+pub struct ScriptGenome {
+    path: Vec<usize>,
+    script: Arc<ScriptSource>,
+}
+
+impl ScriptGenome {
+    fn new_random(script: Arc<ScriptSource>) -> Self {
+        let mut path = (0..script.genome_length).collect::<Vec<usize>>();
+        path.shuffle(&mut rand::thread_rng());
+
+        ScriptGenome { path, script }
+    }
+
+    pub fn get_path(&self) -> &Vec<usize> {
+        &self.path
+    }
+}
+
+impl Organism for ScriptGenome {
+    type Fitness = f32;
+
+    fn fitness(&self) -> f32 {
+        with_script(&self.script, |engine, ast| {
+            engine
+                .call_fn::<Dynamic>(&mut Scope::new(), ast, "fitness", (to_array(&self.path),))
+                .ok()
+                .and_then(to_number)
+                .unwrap_or(f32::INFINITY)
+        })
+    }
+
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        if !self.script.has_mutate {
+            permutation::swap_mutation(&mut self.path, rng);
+            return;
+        }
+
+        let mutated = with_script(&self.script, |engine, ast| {
+            engine
+                .call_fn::<Array>(&mut Scope::new(), ast, "mutate", (to_array(&self.path),))
+                .ok()
+        });
+
+        // A failing or malformed mutation leaves the genome untouched, the
+        // script must return every node exactly once
+        if let Some(mutated) = mutated {
+            let path = mutated
+                .into_iter()
+                .map(|node| usize::try_from(node.as_int().ok()?).ok())
+                .collect::<Option<Vec<usize>>>();
+            if let Some(path) =
+                path.filter(|path| permutation::is_permutation(path, self.path.len()))
+            {
+                self.path = path;
+            }
+        }
+    }
+
+    fn cross_over<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self
+    where
+        Self: Sized,
+    {
+        ScriptGenome {
+            path: permutation::slice_crossover(&self.path, &other.path, rng),
+            script: self.script.clone(),
+        }
+    }
+}
+
+impl Distance for ScriptGenome {
+    fn distance(&self, other: &Self) -> f32 {
+        permutation::edge_distance(&self.path, &other.path, false) as f32
+    }
+}
+
+#[derive(Debug)]
+pub enum ScriptError {
+    Io(std::io::Error),
+    Compile(String),
+    InvalidScript(String),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScriptError::Io(error) => write!(f, "failed to read script: {}", error),
+            ScriptError::Compile(error) => write!(f, "failed to compile script: {}", error),
+            ScriptError::InvalidScript(reason) => write!(f, "invalid script: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+// Compiles a fitness script and registers it as a problem named after the file
+pub fn load_script(path: &Path) -> Result<String, ScriptError> {
+    let source = std::fs::read_to_string(path).map_err(ScriptError::Io)?;
+
+    let engine = new_engine();
+    let ast = engine
+        .compile(&source)
+        .map_err(|error| ScriptError::Compile(error.to_string()))?;
+
+    let has_function = |name: &str| ast.iter_functions().any(|function| function.name == name);
+    if !has_function("fitness") {
+        return Err(ScriptError::InvalidScript(
+            "missing fn fitness(genome)".to_string(),
+        ));
+    }
+
+    let genome_length = engine
+        .call_fn::<i64>(&mut Scope::new(), &ast, "genome_length", ())
+        .map_err(|error| ScriptError::InvalidScript(format!("genome_length(): {}", error)))?;
+    if genome_length <= 0 {
+        return Err(ScriptError::InvalidScript(
+            "genome_length() must be positive".to_string(),
+        ));
+    }
+
+    // Evaluate once up front, errors inside a run are only seen as an infinite fitness
+    let identity = (0..genome_length as usize).collect::<Vec<usize>>();
+    engine
+        .call_fn::<Dynamic>(&mut Scope::new(), &ast, "fitness", (to_array(&identity),))
+        .map_err(|error| ScriptError::InvalidScript(format!("fitness(): {}", error)))
+        .and_then(|value| {
+            to_number(value).ok_or_else(|| {
+                ScriptError::InvalidScript("fitness() must return a number".to_string())
+            })
+        })?;
+
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .ok_or_else(|| ScriptError::InvalidScript("script path has no file name".to_string()))?;
+
+    let script = Arc::new(ScriptSource {
+        id: NEXT_SCRIPT_ID.fetch_add(1, Ordering::Relaxed),
+        source,
+        genome_length: genome_length as usize,
+        has_mutate: has_function("mutate"),
+    });
+
+    registry::register_component(ComponentInfo::new(
+        ComponentKind::Problem,
+        &name,
+        &format!("Rhai script {}", path.display()),
+    ));
+    registry::register_problem(&name, move |parameters| {
+        let population = (0..parameters.population_size)
+            .map(|_| ScriptGenome::new_random(script.clone()))
+            .collect::<Vec<ScriptGenome>>();
+        Box::new(PopulationSolver::new(population, *parameters))
+    });
+
+    Ok(name)
+}
@@ -0,0 +1,247 @@
+use rand::{Rng, RngCore};
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+use std::sync::Arc;
+
+// Picks the parents of the next generation. `fitness` is that of a population
+// sorted fittest first, as the problem ranks it: selections that only compare
+// individuals go by their position, only those that weigh them use the
+// values. Fitness is minimized and an invalid individual has an infinite one.
+pub trait Selection: Debug + Send + Sync {
+    // Index of one parent in `fitness`, which is never empty
+    fn select(&self, fitness: &[f32], rng: &mut dyn RngCore) -> usize;
+
+    // Both parents of `children` children, child k taking the place of
+    // individual `first + k`. Selections that weigh the whole population draw
+    // them all from one computation.
+    fn pairs(
+        &self,
+        fitness: &[f32],
+        first: usize,
+        children: usize,
+        rng: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)> {
+        let _ = first;
+        (0..children)
+            .map(|_| (self.select(fitness, rng), self.select(fitness, rng)))
+            .collect()
+    }
+}
+
+// Every individual breeds with the next one, the last with the first. Only
+// meaningful over a population sorted by fitness, alone it draws uniformly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AdjacentSelection;
+
+impl Selection for AdjacentSelection {
+    fn select(&self, fitness: &[f32], rng: &mut dyn RngCore) -> usize {
+        rng.gen_range(0..fitness.len())
+    }
+
+    fn pairs(
+        &self,
+        fitness: &[f32],
+        first: usize,
+        children: usize,
+        _: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)> {
+        let length = fitness.len();
+        (first..first + children)
+            .map(|individual| (individual % length, (individual + 1) % length))
+            .collect()
+    }
+}
+
+// The fittest of `k` individuals drawn at random, with replacement, the first
+// one drawn of the sorted population. A larger `k` raises the selection
+// pressure, 1 is a uniform draw.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TournamentSelection {
+    pub k: usize,
+}
+
+impl Default for TournamentSelection {
+    fn default() -> Self {
+        TournamentSelection { k: 3 }
+    }
+}
+
+impl Selection for TournamentSelection {
+    fn select(&self, fitness: &[f32], rng: &mut dyn RngCore) -> usize {
+        (0..self.k.max(1))
+            .map(|_| rng.gen_range(0..fitness.len()))
+            .min()
+            .unwrap()
+    }
+}
+
+// Fitness proportionate selection for a minimized fitness: the weight of an
+// individual is how much shorter it is than the worst valid one, plus a share
+// of the spread so the worst still breeds now and then. Invalid individuals
+// never breed unless the whole population is invalid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RouletteSelection;
+
+impl RouletteSelection {
+    fn cumulative(fitness: &[f32]) -> Vec<f64> {
+        let valid = fitness.iter().filter(|fitness| fitness.is_finite());
+        let (best, worst) = valid.fold((f64::INFINITY, f64::NEG_INFINITY), |(best, worst), f| {
+            (best.min(*f as f64), worst.max(*f as f64))
+        });
+        let floor = if worst > best {
+            (worst - best) / fitness.len() as f64
+        } else {
+            1.0
+        };
+
+        cumulative(fitness.iter().map(|fitness| {
+            if fitness.is_finite() {
+                worst - *fitness as f64 + floor
+            } else {
+                0.0
+            }
+        }))
+    }
+}
+
+impl Selection for RouletteSelection {
+    fn select(&self, fitness: &[f32], rng: &mut dyn RngCore) -> usize {
+        spin(&RouletteSelection::cumulative(fitness), rng)
+    }
+
+    fn pairs(
+        &self,
+        fitness: &[f32],
+        _: usize,
+        children: usize,
+        rng: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)> {
+        let wheel = RouletteSelection::cumulative(fitness);
+        (0..children)
+            .map(|_| (spin(&wheel, rng), spin(&wheel, rng)))
+            .collect()
+    }
+}
+
+// Linear ranking: the best individual is drawn `pressure` times as often as
+// the median one and the worst 2 - `pressure` times as often, whatever their
+// fitness, so a few outliers don't take over the population. `pressure` is
+// within [1, 2].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RankSelection {
+    pub pressure: f64,
+}
+
+impl Default for RankSelection {
+    fn default() -> Self {
+        RankSelection { pressure: 1.5 }
+    }
+}
+
+impl RankSelection {
+    fn cumulative(&self, fitness: &[f32]) -> Vec<f64> {
+        let length = fitness.len();
+        let pressure = self.pressure.clamp(1.0, 2.0);
+
+        // The population is sorted, the individual at `position` has rank
+        // `length - 1 - position` counted from the worst
+        cumulative((0..length).map(|position| match length {
+            1 => 1.0,
+            _ => {
+                let rank = length - 1 - position;
+                2.0 - pressure + 2.0 * (pressure - 1.0) * rank as f64 / (length - 1) as f64
+            }
+        }))
+    }
+}
+
+impl Selection for RankSelection {
+    fn select(&self, fitness: &[f32], rng: &mut dyn RngCore) -> usize {
+        spin(&self.cumulative(fitness), rng)
+    }
+
+    fn pairs(
+        &self,
+        fitness: &[f32],
+        _: usize,
+        children: usize,
+        rng: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)> {
+        let wheel = self.cumulative(fitness);
+        (0..children)
+            .map(|_| (spin(&wheel, rng), spin(&wheel, rng)))
+            .collect()
+    }
+}
+
+fn cumulative(weights: impl Iterator<Item = f64>) -> Vec<f64> {
+    weights
+        .scan(0.0, |total, weight| {
+            *total += weight;
+            Some(*total)
+        })
+        .collect()
+}
+
+// An index drawn with the probability of its weight, uniformly when every
+// weight is zero
+fn spin(cumulative: &[f64], rng: &mut dyn RngCore) -> usize {
+    let total = cumulative.last().copied().unwrap_or_default();
+    if total <= 0.0 {
+        return rng.gen_range(0..cumulative.len());
+    }
+    let point = rng.gen_range(0.0..total);
+    cumulative
+        .partition_point(|sum| *sum <= point)
+        .min(cumulative.len() - 1)
+}
+
+// The selections that can be chosen by name
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SelectionKind {
+    Adjacent,
+    Tournament,
+    Roulette,
+    Rank,
+}
+
+impl SelectionKind {
+    pub const ALL: [SelectionKind; 4] = [
+        SelectionKind::Adjacent,
+        SelectionKind::Tournament,
+        SelectionKind::Roulette,
+        SelectionKind::Rank,
+    ];
+
+    pub fn create(&self, tournament_size: usize) -> Arc<dyn Selection> {
+        match self {
+            SelectionKind::Adjacent => Arc::new(AdjacentSelection),
+            SelectionKind::Tournament => Arc::new(TournamentSelection { k: tournament_size }),
+            SelectionKind::Roulette => Arc::new(RouletteSelection),
+            SelectionKind::Rank => Arc::new(RankSelection::default()),
+        }
+    }
+}
+
+impl fmt::Display for SelectionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SelectionKind::Adjacent => "adjacent",
+            SelectionKind::Tournament => "tournament",
+            SelectionKind::Roulette => "roulette",
+            SelectionKind::Rank => "rank",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for SelectionKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        SelectionKind::ALL
+            .into_iter()
+            .find(|kind| kind.to_string() == name)
+            .ok_or_else(|| format!("unknown selection: {}", name))
+    }
+}
@@ -0,0 +1,135 @@
+use crate::backend::{ExecutionBackend, LocalBackend};
+use crate::cli::RunArgs;
+use crate::scheduler::RunSummary;
+use crate::tsp::TspInstance;
+use std::fmt;
+use std::str::FromStr;
+
+// What `--self-test` checks on this machine before its results are relied on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelfTest {
+    // The same seed gives the same run whatever the number of threads
+    Determinism,
+}
+
+impl SelfTest {
+    pub const ALL: [SelfTest; 1] = [SelfTest::Determinism];
+}
+
+impl fmt::Display for SelfTest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SelfTest::Determinism => "determinism",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for SelfTest {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        SelfTest::ALL
+            .into_iter()
+            .find(|test| test.to_string() == name)
+            .ok_or_else(|| format!("unknown self test: {}", name))
+    }
+}
+
+// False when the test failed
+pub fn run(run: &RunArgs, instance: &TspInstance, test: SelfTest) -> bool {
+    match test {
+        SelfTest::Determinism => determinism(run, instance),
+    }
+}
+
+// Solves locally with 1, 2 and as many threads as the machine has, every
+// run from the seed of the first, and compares the statistics of every
+// generation and the best tour. Whatever depends on the clock or writes files
+// is left out, a second run would find them changed.
+fn determinism(run: &RunArgs, instance: &TspInstance) -> bool {
+    let mut run = run.repeatable();
+
+    let available = std::thread::available_parallelism().map_or(1, usize::from);
+    let mut threads = vec![1, 2, available];
+    threads.sort_unstable();
+    threads.dedup();
+
+    let mut reference: Option<(usize, RunSummary)> = None;
+    for count in threads {
+        println!("Self test: solving with {} threads", count);
+        let Some(summary) = with_threads(count, || LocalBackend.solve(&run, instance)) else {
+            println!(
+                "Self test failed: the run with {} threads gave no summary",
+                count
+            );
+            return false;
+        };
+        run.seed = Some(summary.seed);
+
+        if let Some((first, expected)) = &reference {
+            if let Some(difference) = difference(expected, &summary) {
+                println!(
+                    "Self test failed: {} threads and {} threads differ, {}",
+                    first, count, difference
+                );
+                return false;
+            }
+        } else {
+            reference = Some((count, summary));
+        }
+    }
+
+    let (_, summary) = reference.unwrap();
+    println!(
+        "Self test passed: seed {} gives the same {} generations with every thread count",
+        summary.seed,
+        summary.history.generations().len()
+    );
+    true
+}
+
+// The first thing that differs between two runs, None when nothing does
+fn difference(expected: &RunSummary, found: &RunSummary) -> Option<String> {
+    let (expected_history, found_history) =
+        (expected.history.generations(), found.history.generations());
+    if let Some((expected, found)) = expected_history
+        .iter()
+        .zip(found_history.iter())
+        .find(|(expected, found)| expected != found)
+    {
+        return Some(format!(
+            "generation {} has best {} and mean {} instead of best {} and mean {}",
+            found.generation, found.best, found.mean, expected.best, expected.mean
+        ));
+    }
+    if expected_history.len() != found_history.len() {
+        return Some(format!(
+            "{} generations ran instead of {}",
+            found_history.len(),
+            expected_history.len()
+        ));
+    }
+    let tour = |summary: &RunSummary| summary.best_tour.as_ref().map(|tour| tour.path.clone());
+    if tour(expected) != tour(found) {
+        return Some("the best tours differ".to_string());
+    }
+    None
+}
+
+// `solve` on a thread pool of its own with `threads` threads
+fn with_threads<R: Send>(threads: usize, solve: impl FnOnce() -> R + Send) -> R {
+    #[cfg(feature = "parallel")]
+    {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("Failed to set up the thread pool")
+            .install(solve)
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        let _ = threads;
+        solve()
+    }
+}
@@ -0,0 +1,156 @@
+use crate::batch::{self, BatchEntry};
+use crate::cli::RunArgs;
+use crate::distributed::{self, Role, ROOT_PROCESS};
+use crate::distributed_islands;
+use crate::driver;
+#[cfg(feature = "compression")]
+use crate::transport::Compressing;
+use crate::transport::{Metered, MockTransport, NetworkConditions, Transport};
+use crate::tsp::TspInstance;
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How a virtual rank ended
+#[derive(Clone, Debug)]
+pub enum RankOutcome {
+    Coordinated,
+    Evaluated(usize),
+    // An island of its own, see `distributed_islands`
+    Evolved,
+    // Led and helped with the instances of a batch
+    Served,
+    Failed(String),
+}
+
+impl fmt::Display for RankOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RankOutcome::Coordinated => write!(f, "coordinated the run"),
+            RankOutcome::Evaluated(chunks) => write!(f, "evaluated {} chunks", chunks),
+            RankOutcome::Evolved => write!(f, "evolved its island"),
+            RankOutcome::Served => write!(f, "served the batch"),
+            RankOutcome::Failed(reason) => write!(f, "failed: {}", reason),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SimulationReport {
+    pub ranks: Vec<RankOutcome>,
+    pub elapsed: Duration,
+}
+
+impl SimulationReport {
+    pub fn completed(&self) -> bool {
+        self.ranks
+            .iter()
+            .all(|outcome| !matches!(outcome, RankOutcome::Failed(_)))
+    }
+}
+
+// Runs the distributed protocol with `ranks` virtual ranks, each on its own
+// thread and talking through an in memory network behaving as `conditions`
+// says. A rank that panics, simulated failure or not, is reported as failed
+// and the others see it hang up.
+pub fn simulate(
+    run: &RunArgs,
+    instance: &TspInstance,
+    ranks: usize,
+    conditions: &NetworkConditions,
+    role: Role,
+) -> SimulationReport {
+    let started = Instant::now();
+    let batch = run.batch.as_ref().map(|path| {
+        batch::read_batch(path).unwrap_or_else(|error| {
+            eprintln!("{}: {}", path.display(), error);
+            std::process::exit(1);
+        })
+    });
+
+    let outcomes = thread::scope(|scope| {
+        let handles = MockTransport::network_with(ranks.max(1), conditions)
+            .into_iter()
+            .map(|transport| {
+                let transport = Metered::new(transport);
+                let rank = transport.rank();
+                let batch = batch.as_deref();
+                thread::Builder::new()
+                    .name(format!("rank-{}", rank))
+                    .spawn_scoped(scope, move || {
+                        panic::catch_unwind(AssertUnwindSafe(|| {
+                            #[cfg(feature = "compression")]
+                            if run.compress {
+                                let transport = Compressing::new(&transport);
+                                return play(&transport, run, instance, batch, role);
+                            }
+                            play(&transport, run, instance, batch, role)
+                        }))
+                        .unwrap_or_else(|payload| RankOutcome::Failed(panic_message(payload)))
+                    })
+                    .expect("Failed to start a virtual rank")
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    SimulationReport {
+        ranks: outcomes,
+        elapsed: started.elapsed(),
+    }
+}
+
+// What `transport.rank()` does in the run
+fn play<T: Transport>(
+    transport: &T,
+    run: &RunArgs,
+    instance: &TspInstance,
+    batch: Option<&[BatchEntry]>,
+    role: Role,
+) -> RankOutcome {
+    if let Some(batch) = batch {
+        match batch::solve(transport, run, batch) {
+            Some(results) => {
+                batch::write_index(run, &results);
+                RankOutcome::Coordinated
+            }
+            None => RankOutcome::Served,
+        }
+    } else if role == Role::Islands {
+        match distributed_islands::evolve(transport, run, instance) {
+            Some(run_summary) => {
+                driver::write_summary(run, &run_summary);
+                RankOutcome::Coordinated
+            }
+            None => RankOutcome::Evolved,
+        }
+    } else if transport.rank() == ROOT_PROCESS {
+        let run_summary = distributed::coordinate(transport, run, instance);
+        driver::write_summary(run, &run_summary);
+        driver::write_history(run, &run_summary);
+        driver::write_explanation(run, instance, &run_summary);
+        RankOutcome::Coordinated
+    } else {
+        RankOutcome::Evaluated(distributed::work(
+            transport,
+            run.worker_stats,
+            !run.open_path,
+        ))
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else {
+        "panicked".to_string()
+    }
+}
@@ -0,0 +1,301 @@
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+// Partial fitness statistics of a slice of the population.
+// Sums use Neumaier compensated summation and summaries are merged in a fixed
+// order, so the reported numbers don't depend on how the work was split.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct FitnessSummary {
+    pub count: usize,
+    // Individuals with a non finite fitness, kept out of the mean and std dev
+    pub invalid: usize,
+    pub best: f32,
+    pub worst: f32,
+    sum: f64,
+    sum_compensation: f64,
+    sum_squares: f64,
+    sum_squares_compensation: f64,
+}
+
+impl FitnessSummary {
+    pub fn new() -> Self {
+        FitnessSummary {
+            count: 0,
+            invalid: 0,
+            best: f32::INFINITY,
+            worst: f32::NEG_INFINITY,
+            sum: 0.0,
+            sum_compensation: 0.0,
+            sum_squares: 0.0,
+            sum_squares_compensation: 0.0,
+        }
+    }
+
+    pub fn from_fitness<I>(values: I) -> Self
+    where
+        I: IntoIterator<Item = f32>,
+    {
+        let mut summary = FitnessSummary::new();
+        values.into_iter().for_each(|value| summary.add(value));
+        summary
+    }
+
+    pub fn add(&mut self, value: f32) {
+        self.count += 1;
+
+        if !value.is_finite() {
+            self.invalid += 1;
+            return;
+        }
+
+        self.best = self.best.min(value);
+        self.worst = self.worst.max(value);
+
+        let value = value as f64;
+        neumaier_add(&mut self.sum, &mut self.sum_compensation, value);
+        neumaier_add(
+            &mut self.sum_squares,
+            &mut self.sum_squares_compensation,
+            value * value,
+        );
+    }
+
+    // Must be called in a fixed order (e.g. by rank) to be reproducible
+    pub fn merge(&mut self, other: &FitnessSummary) {
+        self.count += other.count;
+        self.invalid += other.invalid;
+        self.best = self.best.min(other.best);
+        self.worst = self.worst.max(other.worst);
+
+        neumaier_add(&mut self.sum, &mut self.sum_compensation, other.sum);
+        neumaier_add(
+            &mut self.sum,
+            &mut self.sum_compensation,
+            other.sum_compensation,
+        );
+        neumaier_add(
+            &mut self.sum_squares,
+            &mut self.sum_squares_compensation,
+            other.sum_squares,
+        );
+        neumaier_add(
+            &mut self.sum_squares,
+            &mut self.sum_squares_compensation,
+            other.sum_squares_compensation,
+        );
+    }
+
+    pub fn valid(&self) -> usize {
+        self.count - self.invalid
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.valid() == 0 {
+            return f64::NAN;
+        }
+        (self.sum + self.sum_compensation) / self.valid() as f64
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        if self.valid() == 0 {
+            return f64::NAN;
+        }
+        let mean = self.mean();
+        let mean_of_squares =
+            (self.sum_squares + self.sum_squares_compensation) / self.valid() as f64;
+        (mean_of_squares - mean * mean).max(0.0).sqrt()
+    }
+}
+
+impl Default for FitnessSummary {
+    fn default() -> Self {
+        FitnessSummary::new()
+    }
+}
+
+fn neumaier_add(sum: &mut f64, compensation: &mut f64, value: f64) {
+    let total = *sum + value;
+    if sum.abs() >= value.abs() {
+        *compensation += (*sum - total) + value;
+    } else {
+        *compensation += (value - total) + *sum;
+    }
+    *sum = total;
+}
+
+// Statistics of one evaluated generation. Like the summary they are built
+// from, they leave invalid individuals out of everything but the counts.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub count: usize,
+    pub invalid: usize,
+    pub best: f32,
+    pub worst: f32,
+    pub mean: f64,
+    pub median: f32,
+    pub std_dev: f64,
+}
+
+impl GenerationStats {
+    // `sorted` is the fitness of the whole generation in ascending order, as
+    // a ranked population has it, `summary` the summary of the same values
+    pub fn from_sorted(generation: usize, summary: &FitnessSummary, sorted: &[f32]) -> Self {
+        GenerationStats {
+            generation,
+            count: summary.count,
+            invalid: summary.invalid,
+            best: summary.best,
+            worst: summary.worst,
+            mean: summary.mean(),
+            median: median(&sorted[..summary.valid().min(sorted.len())]),
+            std_dev: summary.std_dev(),
+        }
+    }
+
+    pub fn from_fitness(generation: usize, fitness: &[f32]) -> Self {
+        let summary = FitnessSummary::from_fitness(fitness.iter().copied());
+        let mut sorted = fitness
+            .iter()
+            .copied()
+            .filter(|fitness| fitness.is_finite())
+            .collect::<Vec<f32>>();
+        sorted.sort_by(f32::total_cmp);
+        GenerationStats::from_sorted(generation, &summary, &sorted)
+    }
+}
+
+// Statistics of one term of a composite fitness over a population, its
+// unweighted cost in `summary`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ObjectiveSummary {
+    pub name: String,
+    pub weight: f32,
+    pub summary: FitnessSummary,
+}
+
+// NaN for no values at all
+fn median(sorted: &[f32]) -> f32 {
+    let middle = sorted.len() / 2;
+    match sorted.len() {
+        0 => f32::NAN,
+        length if length.is_multiple_of(2) => (sorted[middle - 1] + sorted[middle]) / 2.0,
+        _ => sorted[middle],
+    }
+}
+
+#[derive(Serialize)]
+struct TaggedStats<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<&'a str>,
+    #[serde(flatten)]
+    stats: &'a GenerationStats,
+}
+
+// The statistics of every generation of a run, in order
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RunHistory {
+    generations: Vec<GenerationStats>,
+}
+
+impl RunHistory {
+    pub fn new() -> Self {
+        RunHistory::default()
+    }
+
+    pub fn push(&mut self, stats: GenerationStats) {
+        self.generations.push(stats);
+    }
+
+    pub fn generations(&self) -> &[GenerationStats] {
+        &self.generations
+    }
+
+    pub fn len(&self) -> usize {
+        self.generations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.generations.is_empty()
+    }
+
+    pub fn last(&self) -> Option<&GenerationStats> {
+        self.generations.last()
+    }
+
+    // Best fitness of the whole run
+    pub fn best(&self) -> Option<f32> {
+        self.generations
+            .iter()
+            .map(|stats| stats.best)
+            .min_by(f32::total_cmp)
+    }
+
+    // Best fitness found up to and including every generation, the
+    // convergence curve
+    pub fn best_so_far(&self) -> Vec<f32> {
+        self.generations
+            .iter()
+            .scan(f32::INFINITY, |best, stats| {
+                *best = best.min(stats.best);
+                Some(*best)
+            })
+            .collect()
+    }
+
+    // One line per generation, for plotting tools
+    pub fn write_csv<W: Write>(&self, writer: &mut W, tag: Option<&str>) -> io::Result<()> {
+        if tag.is_some() {
+            write!(writer, "tag,")?;
+        }
+        writeln!(
+            writer,
+            "generation,count,invalid,best,worst,mean,median,std_dev"
+        )?;
+        for stats in self.generations.iter() {
+            if let Some(tag) = tag {
+                write!(writer, "{},", tag)?;
+            }
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{}",
+                stats.generation,
+                stats.count,
+                stats.invalid,
+                stats.best,
+                stats.worst,
+                stats.mean,
+                stats.median,
+                stats.std_dev
+            )?;
+        }
+        Ok(())
+    }
+
+    // An array of generations, statistics without a value (no valid
+    // individual) are null. A tagged history carries the tag in every
+    // generation, so files of several experiments can be concatenated.
+    pub fn write_json<W: Write>(&self, writer: &mut W, tag: Option<&str>) -> io::Result<()> {
+        let generations = self
+            .generations
+            .iter()
+            .map(|stats| TaggedStats { tag, stats })
+            .collect::<Vec<_>>();
+        serde_json::to_writer_pretty(&mut *writer, &generations)?;
+        writeln!(writer)
+    }
+
+    // Generation that last found a better best, None for an empty history
+    pub fn last_improvement(&self) -> Option<usize> {
+        let mut best = f32::INFINITY;
+        let mut last = None;
+        for stats in self.generations.iter() {
+            if last.is_none() || stats.best < best {
+                best = stats.best;
+                last = Some(stats.generation);
+            }
+        }
+        last
+    }
+}
@@ -0,0 +1,140 @@
+use crate::organism::Organism;
+use crate::parallel::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// `order` counts the offers, on equal fitness the earlier one is kept
+struct Entry<T> {
+    fitness: f32,
+    order: u64,
+    individual: T,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.fitness
+            .total_cmp(&other.fitness)
+            .then(self.order.cmp(&other.order))
+    }
+}
+
+// Keeps the `capacity` lowest fitness individuals offered so far, the worst
+// kept one sits on top of the heap so it can be replaced in O(log k)
+pub struct TopK<T> {
+    capacity: usize,
+    offered: u64,
+    heap: BinaryHeap<Entry<T>>,
+}
+
+impl<T> TopK<T> {
+    pub fn new(capacity: usize) -> Self {
+        TopK {
+            capacity,
+            offered: 0,
+            heap: BinaryHeap::with_capacity(capacity + 1),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    // Fitness an individual has to beat to be kept, infinite until full
+    pub fn threshold(&self) -> f32 {
+        match self.heap.peek() {
+            Some(worst) if self.heap.len() == self.capacity => worst.fitness,
+            _ => f32::INFINITY,
+        }
+    }
+
+    pub fn offer(&mut self, fitness: f32, individual: T) {
+        if self.capacity == 0 || fitness.is_nan() {
+            return;
+        }
+
+        let entry = Entry {
+            fitness,
+            order: self.offered,
+            individual,
+        };
+        self.offered += 1;
+
+        if self.heap.len() < self.capacity {
+            self.heap.push(entry);
+        } else if fitness < self.threshold() {
+            self.heap.pop();
+            self.heap.push(entry);
+        }
+    }
+
+    // Best first
+    pub fn into_sorted_vec(self) -> Vec<(f32, T)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|entry| (entry.fitness, entry.individual))
+            .collect()
+    }
+}
+
+// Generates and evaluates `total` individuals `slice_size` at a time, keeping
+// only the best `keep`, so at most `slice_size + keep` are alive at once.
+// Individual i is generated from `seed + i`, whatever thread generates it.
+pub fn stream_evaluate<T, F>(
+    total: usize,
+    slice_size: usize,
+    keep: usize,
+    seed: u64,
+    generate: F,
+) -> Vec<(f32, T)>
+where
+    T: Organism + Send,
+    F: Fn(&mut StdRng) -> T + Sync,
+{
+    let slice_size = slice_size.max(1);
+    let mut best = TopK::new(keep);
+
+    let mut remaining = total;
+    while remaining > 0 {
+        let count = remaining.min(slice_size);
+        let first = total - remaining;
+        remaining -= count;
+
+        // Evaluate in parallel but drop the hopeless ones before collecting
+        let threshold = best.threshold();
+        let slice = (first..first + count)
+            .into_par_iter()
+            .map(|i| {
+                let individual = generate(&mut StdRng::seed_from_u64(seed.wrapping_add(i as u64)));
+                (individual.score(), individual)
+            })
+            .filter(|(fitness, _)| *fitness < threshold)
+            .collect::<Vec<(f32, T)>>();
+
+        for (fitness, individual) in slice {
+            best.offer(fitness, individual);
+        }
+    }
+
+    best.into_sorted_vec()
+}
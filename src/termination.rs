@@ -0,0 +1,270 @@
+use serde::Serialize;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+use std::time::Duration;
+
+// Where a run stands once a generation was evaluated, before the next one is
+// bred
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    // Generations bred so far, the evaluated population is the one after them
+    pub generations: usize,
+    pub elapsed: Duration,
+    pub best: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StopReason {
+    MaxGenerations,
+    MinGenerations,
+    TimeLimit,
+    TargetFitness,
+    Stagnation,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            StopReason::MaxGenerations => "max-generations",
+            StopReason::MinGenerations => "min-generations",
+            StopReason::TimeLimit => "time-limit",
+            StopReason::TargetFitness => "target-fitness",
+            StopReason::Stagnation => "stagnation",
+        };
+        f.pad(name)
+    }
+}
+
+// Decides after every evaluated generation whether the run goes on. Called
+// once per generation, in order, so a criterion may keep track of the run.
+pub trait Termination: Debug + Send {
+    fn check(&mut self, progress: &Progress) -> Option<StopReason>;
+}
+
+// The run stops at the first criterion met, every criterion sees every
+// generation until then
+impl Termination for Vec<Box<dyn Termination>> {
+    fn check(&mut self, progress: &Progress) -> Option<StopReason> {
+        self.iter_mut()
+            .map(|criterion| criterion.check(progress))
+            .fold(None, |reason, next| reason.or(next))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaxGenerations(pub usize);
+
+impl Termination for MaxGenerations {
+    fn check(&mut self, progress: &Progress) -> Option<StopReason> {
+        (progress.generations >= self.0).then_some(StopReason::MaxGenerations)
+    }
+}
+
+// Met once the run bred `0` generations, to keep the other members of an
+// `All` from stopping it earlier
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MinGenerations(pub usize);
+
+impl Termination for MinGenerations {
+    fn check(&mut self, progress: &Progress) -> Option<StopReason> {
+        (progress.generations >= self.0).then_some(StopReason::MinGenerations)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeLimit(pub Duration);
+
+impl Termination for TimeLimit {
+    fn check(&mut self, progress: &Progress) -> Option<StopReason> {
+        (progress.elapsed >= self.0).then_some(StopReason::TimeLimit)
+    }
+}
+
+// Fitness is minimized, reaching the target is being at or below it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TargetFitness(pub f32);
+
+impl Termination for TargetFitness {
+    fn check(&mut self, progress: &Progress) -> Option<StopReason> {
+        (progress.best <= self.0).then_some(StopReason::TargetFitness)
+    }
+}
+
+// `generations` generations in a row without a better best individual
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stagnation {
+    generations: usize,
+    best: f32,
+    since: usize,
+}
+
+impl Stagnation {
+    pub fn new(generations: usize) -> Self {
+        Stagnation {
+            generations,
+            best: f32::INFINITY,
+            since: 0,
+        }
+    }
+}
+
+impl Termination for Stagnation {
+    fn check(&mut self, progress: &Progress) -> Option<StopReason> {
+        if progress.best < self.best {
+            self.best = progress.best;
+            self.since = progress.generations;
+        }
+        (progress.generations - self.since >= self.generations).then_some(StopReason::Stagnation)
+    }
+}
+
+// Met as soon as one of the criteria is, the first one met gives the reason
+#[derive(Debug)]
+pub struct Any(pub Vec<Box<dyn Termination>>);
+
+impl Termination for Any {
+    fn check(&mut self, progress: &Progress) -> Option<StopReason> {
+        self.0.check(progress)
+    }
+}
+
+// Met once every criterion is met in the same generation, the last one gives
+// the reason. Every criterion still sees every generation, as stagnation
+// keeps track of the best so far.
+#[derive(Debug)]
+pub struct All(pub Vec<Box<dyn Termination>>);
+
+impl Termination for All {
+    fn check(&mut self, progress: &Progress) -> Option<StopReason> {
+        let reasons = self
+            .0
+            .iter_mut()
+            .map(|criterion| criterion.check(progress))
+            .collect::<Vec<_>>();
+        reasons
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .and_then(|reasons| reasons.last().copied())
+    }
+}
+
+// A stopping rule as written in `--stop` or the `stop` key, the criteria
+// with their value after `=` and the combinators over a list in parentheses:
+// `any(time-limit=600, all(min-generations=100, stagnation=50))`
+#[derive(Clone, Debug, PartialEq)]
+pub enum StopRule {
+    MaxGenerations(usize),
+    MinGenerations(usize),
+    // Seconds
+    TimeLimit(u64),
+    TargetFitness(f32),
+    Stagnation(usize),
+    Any(Vec<StopRule>),
+    All(Vec<StopRule>),
+}
+
+impl StopRule {
+    pub fn create(&self) -> Box<dyn Termination> {
+        let create_all = |rules: &[StopRule]| rules.iter().map(StopRule::create).collect();
+        match self {
+            StopRule::MaxGenerations(generations) => Box::new(MaxGenerations(*generations)),
+            StopRule::MinGenerations(generations) => Box::new(MinGenerations(*generations)),
+            StopRule::TimeLimit(seconds) => Box::new(TimeLimit(Duration::from_secs(*seconds))),
+            StopRule::TargetFitness(target) => Box::new(TargetFitness(*target)),
+            StopRule::Stagnation(generations) => Box::new(Stagnation::new(*generations)),
+            StopRule::Any(rules) => Box::new(Any(create_all(rules))),
+            StopRule::All(rules) => Box::new(All(create_all(rules))),
+        }
+    }
+
+    // The rule at the start of `source`, and what follows it
+    fn parse_prefix(source: &str) -> Result<(StopRule, &str), String> {
+        let source = source.trim_start();
+        let end = source
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(source.len());
+        let (name, rest) = source.split_at(end);
+        let rest = rest.trim_start();
+
+        if let Some(mut rest) = rest.strip_prefix('(') {
+            let mut rules = Vec::new();
+            loop {
+                let (rule, after) = StopRule::parse_prefix(rest)?;
+                rules.push(rule);
+                let after = after.trim_start();
+                if let Some(after) = after.strip_prefix(',') {
+                    rest = after;
+                } else if let Some(after) = after.strip_prefix(')') {
+                    rest = after;
+                    break;
+                } else if after.is_empty() {
+                    return Err(format!("missing ) after {}( in stop rule", name));
+                } else {
+                    return Err(format!("expected , or ) in stop rule at: {}", after));
+                }
+            }
+            return match name {
+                "any" => Ok((StopRule::Any(rules), rest)),
+                "all" => Ok((StopRule::All(rules), rest)),
+                _ => Err(format!("unknown stop rule combinator: {}", name)),
+            };
+        }
+
+        let Some(rest) = rest.strip_prefix('=') else {
+            return Err(format!("expected = or ( after {} in stop rule", name));
+        };
+        let rest = rest.trim_start();
+        let end = rest.find([',', ')']).unwrap_or(rest.len());
+        let (value, rest) = rest.split_at(end);
+        let value = value.trim();
+        let invalid = |_| format!("invalid value for {}: {}", name, value);
+        let rule = match name {
+            "max-generations" => StopRule::MaxGenerations(value.parse().map_err(invalid)?),
+            "min-generations" => StopRule::MinGenerations(value.parse().map_err(invalid)?),
+            "time-limit" => StopRule::TimeLimit(value.parse().map_err(invalid)?),
+            "target-fitness" => StopRule::TargetFitness(
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid value for {}: {}", name, value))?,
+            ),
+            "stagnation" => match value.parse().map_err(invalid)? {
+                0 => return Err("stagnation must be at least 1 generation".to_string()),
+                generations => StopRule::Stagnation(generations),
+            },
+            _ => return Err(format!("unknown stop criterion: {}", name)),
+        };
+        Ok((rule, rest))
+    }
+}
+
+impl fmt::Display for StopRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |name: &str, rules: &[StopRule]| {
+            let rules = rules.iter().map(StopRule::to_string).collect::<Vec<_>>();
+            format!("{}({})", name, rules.join(", "))
+        };
+        let rule = match self {
+            StopRule::MaxGenerations(generations) => format!("max-generations={}", generations),
+            StopRule::MinGenerations(generations) => format!("min-generations={}", generations),
+            StopRule::TimeLimit(seconds) => format!("time-limit={}", seconds),
+            StopRule::TargetFitness(target) => format!("target-fitness={}", target),
+            StopRule::Stagnation(generations) => format!("stagnation={}", generations),
+            StopRule::Any(rules) => list("any", rules),
+            StopRule::All(rules) => list("all", rules),
+        };
+        f.pad(&rule)
+    }
+}
+
+impl FromStr for StopRule {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let (rule, rest) = StopRule::parse_prefix(source)?;
+        if !rest.trim().is_empty() {
+            return Err(format!("unexpected text after stop rule: {}", rest.trim()));
+        }
+        Ok(rule)
+    }
+}
@@ -0,0 +1,424 @@
+use crate::ablation::{self, Ablation};
+use crate::analysis;
+use crate::cli::{self, Command, RunArgs};
+#[cfg(feature = "database")]
+use crate::database::RunRecord;
+use crate::distributed::{Role, ROOT_PROCESS};
+use crate::driver;
+use crate::journal::{self, Origin};
+use crate::logging;
+use crate::portfolio::{InstanceFeatures, Portfolio, PortfolioEntry};
+use crate::recommend::{self, RecommendOptions};
+use crate::repl;
+use crate::runner::RunParameters;
+use crate::simulation;
+#[cfg(feature = "database")]
+use crate::statistics::FitnessSummary;
+use crate::transport::NetworkConditions;
+use crate::tsp::{TspProblem, TSP};
+use crate::tuning::{self, TspConfiguration};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+// The subcommands that inspect, tune or benchmark instead of solving
+pub fn run_command(command: &Command, run: &RunArgs) {
+    match command {
+        Command::List => cli::print_components(),
+        Command::Trace {
+            journal,
+            generation,
+            rank,
+        } => print_trace(journal, *generation, *rank),
+        #[cfg(feature = "database")]
+        Command::History { add_to_portfolio } => print_history(run, add_to_portfolio.as_deref()),
+        Command::Probe {
+            walks,
+            steps,
+            samples,
+        } => probe(run, *walks, *steps, *samples),
+        Command::Recommend {
+            generations,
+            population,
+            repeats,
+        } => recommend(run, *generations, *population, *repeats),
+        Command::Tune {
+            max_generations,
+            eta,
+            population,
+            configurations,
+            add_to_portfolio,
+        } => tune(
+            run,
+            *max_generations,
+            *eta,
+            *population,
+            *configurations,
+            add_to_portfolio.as_deref(),
+        ),
+        Command::Ablate { repeats } => ablate(run, *repeats),
+        Command::Repl { population } => start_repl(run, *population),
+        Command::Simulate {
+            ranks,
+            latency_ms,
+            jitter_ms,
+            drop_rate,
+            fail_rank,
+            fail_after,
+            slow_rank,
+            slow_ms,
+            stall_timeout,
+            seed,
+            island_model,
+        } => {
+            let conditions = NetworkConditions {
+                latency: Duration::from_millis(*latency_ms),
+                jitter: Duration::from_millis(*jitter_ms),
+                drop_rate: *drop_rate,
+                failure: fail_rank.zip(*fail_after),
+                slow: slow_rank.zip(slow_ms.map(Duration::from_millis)),
+                stall_timeout: Some(Duration::from_secs(*stall_timeout)),
+                seed: *seed,
+            };
+            let role = if *island_model {
+                Role::Islands
+            } else {
+                Role::Evaluation
+            };
+            simulate(run, *ranks, &conditions, role)
+        }
+    }
+}
+
+// Every virtual rank shares the one process wide logger, lines are labelled rank 0
+fn simulate(run: &RunArgs, ranks: usize, conditions: &NetworkConditions, role: Role) {
+    let instance = driver::instance_or_exit(run, &run.instance);
+    if let Err(error) = logging::init(ROOT_PROCESS, &run.log_config()) {
+        eprintln!("Failed to set up logging: {}", error);
+        std::process::exit(1);
+    }
+
+    let report = simulation::simulate(run, &instance, ranks, conditions, role);
+
+    println!(
+        "Simulated {} ranks in {:.2} seconds",
+        report.ranks.len(),
+        report.elapsed.as_secs_f64()
+    );
+    for (rank, outcome) in report.ranks.iter().enumerate() {
+        println!("  rank {}: {}", rank, outcome);
+    }
+    if !report.completed() {
+        std::process::exit(1);
+    }
+}
+
+fn probe(run: &RunArgs, walks: usize, steps: usize, samples: usize) {
+    let instance = driver::instance_or_exit(run, &run.instance);
+    let problem = Arc::new(TspProblem::new(instance.matrix()));
+    let report = analysis::probe(
+        || TSP::new_with_random_path(&problem),
+        walks,
+        steps,
+        samples,
+    );
+    println!("{:#?}", report);
+}
+
+// The contribution of a component is how much longer the mean best tour gets
+// without it, negative when the setup does better without
+fn ablate(run: &RunArgs, repeats: usize) {
+    if run.problem != "tsp" {
+        eprintln!("ablate solves the tsp, not {}", run.problem);
+        std::process::exit(1);
+    }
+    if repeats == 0 {
+        eprintln!("ablate needs at least 1 repeat");
+        std::process::exit(1);
+    }
+    let instance = driver::instance_or_exit(run, &run.instance);
+    let problems = driver::check_configuration(run, &instance);
+    if !problems.is_empty() {
+        for problem in problems.iter() {
+            eprintln!("error: {}", problem);
+        }
+        std::process::exit(1);
+    }
+
+    let outcomes = ablation::run(run, &instance, repeats);
+    let baseline = outcomes[0].mean_best();
+    println!("Ablation over {} runs of every setup:", repeats);
+    println!("  {:<18} mean best: {}", "as configured", baseline);
+    for outcome in outcomes.iter().skip(1) {
+        let mean = outcome.mean_best();
+        println!(
+            "  {:<18} mean best: {:<12} contribution: {:+.2} ({:+.1}%)",
+            format!("no {}", outcome.ablation.unwrap()),
+            mean,
+            mean - baseline,
+            100.0 * (mean - baseline) / baseline
+        );
+    }
+    for ablation in Ablation::ALL
+        .into_iter()
+        .filter(|ablation| !ablation.used_by(run))
+    {
+        println!(
+            "  {:<18} not used by this setup",
+            format!("no {}", ablation)
+        );
+    }
+}
+
+fn recommend(run: &RunArgs, generations: usize, population: usize, repeats: usize) {
+    let instance = driver::instance_or_exit(run, &run.instance);
+    let options = RecommendOptions {
+        generations,
+        population_size: population,
+        repeats,
+        elite: run.elite,
+        mutation_rate: run.mutation_rate,
+        crossover_rate: run.crossover_rate,
+    };
+    let trials = recommend::recommend_tsp(instance.matrix(), options);
+
+    for trial in trials.iter() {
+        println!(
+            "crossover: {:<10} mutation: {:<10} mean best: {:<12} walk autocorrelation: {:.3}",
+            trial.crossover, trial.mutation, trial.mean_best, trial.autocorrelation
+        );
+    }
+    if let Some(best) = trials.first() {
+        println!(
+            "Recommended: --crossover {} --mutation {}",
+            best.crossover, best.mutation
+        );
+    }
+}
+
+fn tune(
+    run: &RunArgs,
+    max_generations: usize,
+    eta: usize,
+    population: usize,
+    configurations: Option<usize>,
+    portfolio: Option<&Path>,
+) {
+    let instance = driver::instance_or_exit(run, &run.instance);
+    let graph_weights = instance.matrix();
+    let rng = RefCell::new(StdRng::seed_from_u64(driver::run_seed(run)));
+    let sample = |count| tuning::sample_configurations(count, &mut *rng.borrow_mut());
+    let make_solver = |configuration: &TspConfiguration| {
+        let seed = rng.borrow_mut().gen();
+        tuning::tsp_solver(&graph_weights, configuration, population, run.elite, seed)
+    };
+
+    let results = match configurations {
+        Some(count) => tuning::successive_halving(
+            sample(count),
+            make_solver,
+            tuning::starting_budget(max_generations, count, eta),
+            max_generations,
+            eta,
+        ),
+        None => tuning::hyperband(max_generations, eta, sample, make_solver),
+    };
+
+    for result in results.iter().take(10) {
+        println!(
+            "best: {:<12} generations: {:<5} {}",
+            result.best, result.generations, result.configuration
+        );
+    }
+
+    if let (Some(path), Some(best)) = (portfolio, results.first()) {
+        let configuration = &best.configuration;
+        add_to_portfolio(
+            path,
+            vec![PortfolioEntry {
+                instance: run.instance.clone(),
+                features: InstanceFeatures::of(&graph_weights),
+                crossover: configuration.crossover.to_string(),
+                mutation: configuration.mutation.to_string(),
+                mutation_rate: configuration.mutation_rate,
+                crossover_rate: configuration.crossover_rate,
+                best: best.best,
+            }],
+        );
+    }
+
+    #[cfg(feature = "database")]
+    if let Some(path) = &run.database {
+        let database = driver::open_database(path);
+        for result in results.iter() {
+            let mut summary = FitnessSummary::new();
+            summary.add(result.best);
+
+            let mut record = RunRecord::from_summary(&run.problem, &run.instance, &summary);
+            record.crossover = result.configuration.crossover.to_string();
+            record.mutation = result.configuration.mutation.to_string();
+            record.mutation_rate = result.configuration.mutation_rate;
+            record.crossover_rate = result.configuration.crossover_rate;
+            record.population_size = population;
+            record.elite = run.elite;
+            record.generations = result.generations;
+            record.mean = None;
+            record.std_dev = None;
+            record.tag = run.tag.clone();
+            database
+                .record(&record)
+                .expect("Failed to record the tuning run");
+        }
+    }
+}
+
+fn start_repl(run: &RunArgs, population: usize) {
+    let instance = driver::instance_or_exit(run, &run.instance);
+    let problem = Arc::new(
+        TspProblem::new(instance.matrix())
+            .with_operators(run.crossover, run.mutation)
+            .with_closed_tour(!run.open_path),
+    );
+    let individuals = (0..population.max(1))
+        .map(|_| TSP::new_with_random_path(&problem))
+        .collect::<Vec<TSP>>();
+
+    let parameters = RunParameters {
+        population_size: individuals.len(),
+        elite: run.elite.min(individuals.len() - 1),
+        ..run.run_parameters()
+    };
+    repl::Session::new(individuals, parameters).run(std::io::stdin().lock());
+}
+
+fn print_trace(path: &Path, generation: u32, rank: u32) {
+    let events = match journal::read(path) {
+        Ok(events) => events,
+        Err(error) => {
+            eprintln!("{}: {}", path.display(), error);
+            std::process::exit(1);
+        }
+    };
+
+    let steps = journal::ancestry(&events, generation, rank);
+    if steps.is_empty() {
+        println!(
+            "Nothing recorded about rank {} of generation {}",
+            rank, generation
+        );
+        return;
+    }
+
+    for (generation, rank, origin) in steps {
+        match origin {
+            Origin::Elite { parent } => println!(
+                "generation {:<5} rank {:<6} copied from elite rank {}",
+                generation, rank, parent
+            ),
+            Origin::Offspring {
+                first_parent,
+                second_parent,
+                crossed_over,
+                mutated,
+            } => println!(
+                "generation {:<5} rank {:<6} parents {} and {}, crossover: {}, mutation: {}",
+                generation, rank, first_parent, second_parent, crossed_over, mutated
+            ),
+            Origin::Sampled => println!(
+                "generation {:<5} rank {:<6} sampled from the edge model of the elite",
+                generation, rank
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "database")]
+fn print_history(run: &RunArgs, portfolio: Option<&Path>) {
+    let Some(path) = &run.database else {
+        eprintln!("history needs a results database, pass --database");
+        std::process::exit(1);
+    };
+    let database = driver::open_database(path);
+
+    // With --tag only the runs of that experiment
+    let mut runs = database
+        .runs_for_instance(&run.instance)
+        .expect("Failed to read the results database");
+    if let Some(tag) = &run.tag {
+        runs.retain(|record| record.tag.as_ref() == Some(tag));
+    }
+    for record in runs.iter() {
+        println!(
+            "best: {:<12} generations: {:<5} population: {:<6} crossover: {:<8} mutation: {:<10} mutation rate: {:<5} crossover rate: {:<5} tag: {}",
+            record.best,
+            record.generations,
+            record.population_size,
+            record.crossover,
+            record.mutation,
+            record.mutation_rate,
+            record.crossover_rate,
+            record.tag.as_deref().unwrap_or("-")
+        );
+    }
+
+    match database.best_for_instance(&run.instance) {
+        Ok(Some(best)) => println!(
+            "Best configuration for {}: --crossover {} --mutation {} (mutation rate {}, crossover rate {}, best {})",
+            run.instance, best.crossover, best.mutation, best.mutation_rate, best.crossover_rate, best.best
+        ),
+        Ok(None) => println!("No runs recorded for {}", run.instance),
+        Err(error) => eprintln!("{}", error),
+    }
+
+    if let Some(path) = portfolio {
+        let instances = database
+            .instances()
+            .expect("Failed to read the results database");
+        let mut entries = Vec::new();
+        for name in instances.iter() {
+            let Ok(Some(best)) = database.best_for_instance(name) else {
+                continue;
+            };
+            // Instances moved or deleted since they were solved are left out
+            match driver::load_instance(run, name) {
+                Ok(instance) => entries.push(PortfolioEntry {
+                    instance: name.clone(),
+                    features: InstanceFeatures::of(&instance.matrix()),
+                    crossover: best.crossover,
+                    mutation: best.mutation,
+                    mutation_rate: best.mutation_rate,
+                    crossover_rate: best.crossover_rate,
+                    best: best.best,
+                }),
+                Err(error) => eprintln!("Leaving {} out of the portfolio: {}", name, error),
+            }
+        }
+        add_to_portfolio(path, entries);
+    }
+}
+
+// Adds the entries to the portfolio file, keeping the better of two entries
+// for the same instance
+fn add_to_portfolio(path: &Path, entries: Vec<PortfolioEntry>) {
+    let mut portfolio = Portfolio::read_or_default(path).unwrap_or_else(|error| {
+        eprintln!("{}: {}", path.display(), error);
+        std::process::exit(1);
+    });
+    let count = entries.len();
+    for entry in entries {
+        portfolio.add(entry);
+    }
+    if let Err(error) = portfolio.write(path) {
+        eprintln!("Failed to write {}: {}", path.display(), error);
+        std::process::exit(1);
+    }
+    println!(
+        "Added {} configurations to {}, {} entries",
+        count,
+        path.display(),
+        portfolio.entries.len()
+    );
+}
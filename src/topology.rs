@@ -0,0 +1,171 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+use std::sync::Arc;
+
+// Who sends migrants to whom. Every island asks for its own targets at every
+// migration, a topology must answer the same on every island (and rank) for
+// the same arguments, or islands would wait for migrants nobody sends.
+pub trait Topology: Debug + Send + Sync {
+    // Islands receiving the migrants of `island` at its `migration`th
+    // migration, counted from 0, out of `islands` islands
+    fn targets(&self, island: usize, islands: usize, migration: usize) -> Vec<usize>;
+}
+
+// Every (source, target) of a migration, sorted. Doing their part of them in
+// this order, blocking sends and receives can't wait on each other: the first
+// pair not done yet always has both its islands at it.
+pub fn links(topology: &dyn Topology, islands: usize, migration: usize) -> Vec<(usize, usize)> {
+    let mut links = (0..islands)
+        .flat_map(|source| {
+            topology
+                .targets(source, islands, migration)
+                .into_iter()
+                .map(move |target| (source, target))
+        })
+        .collect::<Vec<_>>();
+    links.sort_by_key(|&(source, target)| (source.min(target), source.max(target), source));
+    links.dedup();
+    links
+}
+
+// Islands sending their migrants to `island` at that migration
+pub fn sources(
+    topology: &dyn Topology,
+    island: usize,
+    islands: usize,
+    migration: usize,
+) -> Vec<usize> {
+    (0..islands)
+        .filter(|&source| {
+            topology
+                .targets(source, islands, migration)
+                .contains(&island)
+        })
+        .collect()
+}
+
+// Every island to the next one, the last one to the first
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Ring;
+
+impl Topology for Ring {
+    fn targets(&self, island: usize, islands: usize, _migration: usize) -> Vec<usize> {
+        vec![(island + 1) % islands]
+    }
+}
+
+// Every island to every other one
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FullyConnected;
+
+impl Topology for FullyConnected {
+    fn targets(&self, island: usize, islands: usize, _migration: usize) -> Vec<usize> {
+        (0..islands).filter(|&other| other != island).collect()
+    }
+}
+
+// A ring through the islands in a new random order at every migration, drawn
+// from `seed` and the migration so every island draws the same one
+#[derive(Clone, Copy, Debug)]
+pub struct RandomRing {
+    pub seed: u64,
+}
+
+impl Topology for RandomRing {
+    fn targets(&self, island: usize, islands: usize, migration: usize) -> Vec<usize> {
+        let mut order = (0..islands).collect::<Vec<_>>();
+        order.shuffle(&mut StdRng::seed_from_u64(
+            self.seed.wrapping_add(migration as u64),
+        ));
+        let position = order.iter().position(|&other| other == island).unwrap();
+        vec![order[(position + 1) % islands]]
+    }
+}
+
+// The islands on a grid wrapping around both ways, as square as their number
+// allows, every island to its neighbors above, below, left and right
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Torus;
+
+impl Torus {
+    // Rows and columns of the grid of `islands` islands
+    pub fn shape(islands: usize) -> (usize, usize) {
+        let rows = (1..=islands)
+            .take_while(|rows| rows * rows <= islands)
+            .filter(|rows| islands.is_multiple_of(*rows))
+            .last()
+            .unwrap_or(1);
+        (rows, islands / rows)
+    }
+}
+
+impl Topology for Torus {
+    fn targets(&self, island: usize, islands: usize, _migration: usize) -> Vec<usize> {
+        let (rows, columns) = Torus::shape(islands);
+        let (row, column) = (island / columns, island % columns);
+        let mut targets = vec![
+            ((row + rows - 1) % rows) * columns + column,
+            ((row + 1) % rows) * columns + column,
+            row * columns + (column + columns - 1) % columns,
+            row * columns + (column + 1) % columns,
+        ];
+        targets.sort_unstable();
+        targets.dedup();
+        targets.retain(|&target| target != island);
+        targets
+    }
+}
+
+// The topologies that can be chosen by name
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TopologyKind {
+    Ring,
+    FullyConnected,
+    Random,
+    Torus,
+}
+
+impl TopologyKind {
+    pub const ALL: [TopologyKind; 4] = [
+        TopologyKind::Ring,
+        TopologyKind::FullyConnected,
+        TopologyKind::Random,
+        TopologyKind::Torus,
+    ];
+
+    // `seed` only matters to the random one
+    pub fn create(&self, seed: u64) -> Arc<dyn Topology> {
+        match self {
+            TopologyKind::Ring => Arc::new(Ring),
+            TopologyKind::FullyConnected => Arc::new(FullyConnected),
+            TopologyKind::Random => Arc::new(RandomRing { seed }),
+            TopologyKind::Torus => Arc::new(Torus),
+        }
+    }
+}
+
+impl fmt::Display for TopologyKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            TopologyKind::Ring => "ring",
+            TopologyKind::FullyConnected => "fully-connected",
+            TopologyKind::Random => "random",
+            TopologyKind::Torus => "torus",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for TopologyKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        TopologyKind::ALL
+            .into_iter()
+            .find(|kind| kind.to_string() == name)
+            .ok_or_else(|| format!("unknown topology: {}", name))
+    }
+}
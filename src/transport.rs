@@ -0,0 +1,845 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::time::{Duration, Instant};
+
+// Point to point byte messages between ranks, what the coordinator and
+// worker protocol needs from MPI. Messages from one rank to another arrive in
+// the order they were sent, like MPI with a single tag. The protocol frames
+// every message, see `comm`.
+pub trait Transport {
+    fn rank(&self) -> i32;
+    fn size(&self) -> i32;
+    fn send(&self, destination: i32, bytes: &[u8]);
+    fn receive(&self, source: i32) -> Vec<u8>;
+    // The first message to arrive from any rank, with the rank it came from
+    fn receive_any(&self) -> (i32, Vec<u8>);
+    // Every rank gets back the bytes `root` passes, whatever their length,
+    // what the other ranks pass is ignored
+    fn broadcast(&self, root: i32, bytes: Vec<u8>) -> Vec<u8>;
+
+    // As `receive_any`, None when nothing arrived within `timeout`.
+    // Transports that can't tell wait as long as it takes.
+    fn receive_any_timeout(&self, _timeout: Duration) -> Option<(i32, Vec<u8>)> {
+        Some(self.receive_any())
+    }
+
+    // Starts sending `bytes` and returns without waiting for `destination`
+    // to receive them, messages to a rank still arrive in the order they were
+    // sent. Transports that can't do better send at once.
+    fn send_immediate(&self, destination: i32, bytes: Vec<u8>) {
+        self.send(destination, &bytes)
+    }
+
+    // Waits for every send `send_immediate` started
+    fn complete_sends(&self) {}
+
+    // Where the protocol records what went through this transport, see `Metered`
+    fn traffic(&self) -> Option<&TrafficRecorder> {
+        None
+    }
+
+    // Whether the protocol compresses the larger payloads it sends, see
+    // `Compressing`
+    fn compresses(&self) -> bool {
+        false
+    }
+}
+
+// Messages of one kind that went one way through a transport. The time is how
+// long the rank was blocked in the calls, for a receive it includes waiting
+// for the other end to be ready.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct MessageTraffic {
+    pub count: u64,
+    pub bytes: u64,
+    pub seconds: f64,
+}
+
+impl MessageTraffic {
+    fn add(&mut self, bytes: usize, elapsed: Duration) {
+        self.count += 1;
+        self.bytes += bytes as u64;
+        self.seconds += elapsed.as_secs_f64();
+    }
+
+    fn merge(&mut self, other: &MessageTraffic) {
+        self.count += other.count;
+        self.bytes += other.bytes;
+        self.seconds += other.seconds;
+    }
+}
+
+// Traffic of one rank by message kind, broadcasts count on both sides
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TrafficStats {
+    pub sent: BTreeMap<String, MessageTraffic>,
+    pub received: BTreeMap<String, MessageTraffic>,
+}
+
+impl TrafficStats {
+    pub fn total_sent(&self) -> MessageTraffic {
+        total(&self.sent)
+    }
+
+    pub fn total_received(&self) -> MessageTraffic {
+        total(&self.received)
+    }
+}
+
+fn total(traffic: &BTreeMap<String, MessageTraffic>) -> MessageTraffic {
+    let mut total = MessageTraffic::default();
+    traffic.values().for_each(|kind| total.merge(kind));
+    total
+}
+
+#[derive(Default)]
+pub struct TrafficRecorder {
+    stats: RefCell<TrafficStats>,
+}
+
+impl TrafficRecorder {
+    pub fn record_sent(&self, kind: &str, bytes: usize, elapsed: Duration) {
+        let mut stats = self.stats.borrow_mut();
+        stats
+            .sent
+            .entry(kind.to_string())
+            .or_default()
+            .add(bytes, elapsed);
+    }
+
+    pub fn record_received(&self, kind: &str, bytes: usize, elapsed: Duration) {
+        let mut stats = self.stats.borrow_mut();
+        stats
+            .received
+            .entry(kind.to_string())
+            .or_default()
+            .add(bytes, elapsed);
+    }
+
+    pub fn stats(&self) -> TrafficStats {
+        self.stats.borrow().clone()
+    }
+}
+
+// Any transport, with its traffic recorded by message kind
+pub struct Metered<T> {
+    inner: T,
+    recorder: TrafficRecorder,
+}
+
+impl<T: Transport> Metered<T> {
+    pub fn new(inner: T) -> Self {
+        Metered {
+            inner,
+            recorder: TrafficRecorder::default(),
+        }
+    }
+}
+
+impl<T: Transport> Transport for Metered<T> {
+    fn rank(&self) -> i32 {
+        self.inner.rank()
+    }
+
+    fn size(&self) -> i32 {
+        self.inner.size()
+    }
+
+    fn send(&self, destination: i32, bytes: &[u8]) {
+        self.inner.send(destination, bytes)
+    }
+
+    fn receive(&self, source: i32) -> Vec<u8> {
+        self.inner.receive(source)
+    }
+
+    fn receive_any(&self) -> (i32, Vec<u8>) {
+        self.inner.receive_any()
+    }
+
+    fn receive_any_timeout(&self, timeout: Duration) -> Option<(i32, Vec<u8>)> {
+        self.inner.receive_any_timeout(timeout)
+    }
+
+    fn broadcast(&self, root: i32, bytes: Vec<u8>) -> Vec<u8> {
+        self.inner.broadcast(root, bytes)
+    }
+
+    fn send_immediate(&self, destination: i32, bytes: Vec<u8>) {
+        self.inner.send_immediate(destination, bytes)
+    }
+
+    fn complete_sends(&self) {
+        self.inner.complete_sends()
+    }
+
+    fn traffic(&self) -> Option<&TrafficRecorder> {
+        Some(&self.recorder)
+    }
+
+    fn compresses(&self) -> bool {
+        self.inner.compresses()
+    }
+}
+
+// Any transport, with every message stamped with a checksum of the instance
+// the sending rank loaded. A rank receiving a stamp other than its own stops,
+// so ranks that read different instance files fail at once instead of mixing
+// up their results.
+pub struct Checked<'a, T> {
+    inner: &'a T,
+    checksum: u32,
+}
+
+impl<'a, T: Transport> Checked<'a, T> {
+    pub fn new(inner: &'a T, checksum: u32) -> Self {
+        Checked { inner, checksum }
+    }
+
+    fn verify(&self, source: i32, mut bytes: Vec<u8>) -> Vec<u8> {
+        let Some(end) = bytes.len().checked_sub(4) else {
+            panic!(
+                "Rank {} received a message from rank {} without an instance checksum",
+                self.rank(),
+                source
+            );
+        };
+        self.compare(source, u32::from_le_bytes(bytes[end..].try_into().unwrap()));
+        bytes.truncate(end);
+        bytes
+    }
+
+    fn compare(&self, source: i32, checksum: u32) {
+        if checksum != self.checksum {
+            panic!(
+                "Rank {} received a message from rank {} for instance {:08x}, but loaded instance {:08x}",
+                self.rank(),
+                source,
+                checksum,
+                self.checksum
+            );
+        }
+    }
+}
+
+impl<T: Transport> Transport for Checked<'_, T> {
+    fn rank(&self) -> i32 {
+        self.inner.rank()
+    }
+
+    fn size(&self) -> i32 {
+        self.inner.size()
+    }
+
+    fn send(&self, destination: i32, bytes: &[u8]) {
+        let mut stamped = bytes.to_vec();
+        stamped.extend(self.checksum.to_le_bytes());
+        self.inner.send(destination, &stamped)
+    }
+
+    fn receive(&self, source: i32) -> Vec<u8> {
+        let bytes = self.inner.receive(source);
+        self.verify(source, bytes)
+    }
+
+    fn receive_any(&self) -> (i32, Vec<u8>) {
+        let (source, bytes) = self.inner.receive_any();
+        (source, self.verify(source, bytes))
+    }
+
+    fn receive_any_timeout(&self, timeout: Duration) -> Option<(i32, Vec<u8>)> {
+        let (source, bytes) = self.inner.receive_any_timeout(timeout)?;
+        Some((source, self.verify(source, bytes)))
+    }
+
+    fn broadcast(&self, root: i32, mut bytes: Vec<u8>) -> Vec<u8> {
+        bytes.extend(self.checksum.to_le_bytes());
+        let bytes = self.inner.broadcast(root, bytes);
+        self.verify(root, bytes)
+    }
+
+    fn send_immediate(&self, destination: i32, mut bytes: Vec<u8>) {
+        bytes.extend(self.checksum.to_le_bytes());
+        self.inner.send_immediate(destination, bytes)
+    }
+
+    fn complete_sends(&self) {
+        self.inner.complete_sends()
+    }
+
+    fn traffic(&self) -> Option<&TrafficRecorder> {
+        self.inner.traffic()
+    }
+
+    fn compresses(&self) -> bool {
+        self.inner.compresses()
+    }
+}
+
+// Any transport, with the protocol compressing the payloads worth it. Only
+// the sender decides, every frame says whether it is compressed.
+#[cfg(feature = "compression")]
+pub struct Compressing<'a, T> {
+    inner: &'a T,
+}
+
+#[cfg(feature = "compression")]
+impl<'a, T: Transport> Compressing<'a, T> {
+    pub fn new(inner: &'a T) -> Self {
+        Compressing { inner }
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<T: Transport> Transport for Compressing<'_, T> {
+    fn rank(&self) -> i32 {
+        self.inner.rank()
+    }
+
+    fn size(&self) -> i32 {
+        self.inner.size()
+    }
+
+    fn send(&self, destination: i32, bytes: &[u8]) {
+        self.inner.send(destination, bytes)
+    }
+
+    fn receive(&self, source: i32) -> Vec<u8> {
+        self.inner.receive(source)
+    }
+
+    fn receive_any(&self) -> (i32, Vec<u8>) {
+        self.inner.receive_any()
+    }
+
+    fn receive_any_timeout(&self, timeout: Duration) -> Option<(i32, Vec<u8>)> {
+        self.inner.receive_any_timeout(timeout)
+    }
+
+    fn broadcast(&self, root: i32, bytes: Vec<u8>) -> Vec<u8> {
+        self.inner.broadcast(root, bytes)
+    }
+
+    fn send_immediate(&self, destination: i32, bytes: Vec<u8>) {
+        self.inner.send_immediate(destination, bytes)
+    }
+
+    fn complete_sends(&self) {
+        self.inner.complete_sends()
+    }
+
+    fn traffic(&self) -> Option<&TrafficRecorder> {
+        self.inner.traffic()
+    }
+
+    fn compresses(&self) -> bool {
+        true
+    }
+}
+
+// Some ranks of `inner` as a transport of their own, rank i of the group
+// being rank `members[i]` of `inner`. Nobody outside the group may send to a
+// member while it receives from any rank, and broadcasts go point to point
+// from the root, so a group needs no collective of `inner`. Ranks can join
+// between two messages.
+pub struct Group<'a, T> {
+    inner: &'a T,
+    members: RefCell<Vec<i32>>,
+}
+
+impl<'a, T: Transport> Group<'a, T> {
+    pub fn new(inner: &'a T, members: Vec<i32>) -> Self {
+        Group {
+            inner,
+            members: RefCell::new(members),
+        }
+    }
+
+    pub fn join(&self, rank: i32) {
+        self.members.borrow_mut().push(rank);
+    }
+
+    fn member(&self, index: i32) -> i32 {
+        self.members.borrow()[index as usize]
+    }
+
+    fn index_of(&self, rank: i32) -> i32 {
+        let members = self.members.borrow();
+        match members.iter().position(|&member| member == rank) {
+            Some(index) => index as i32,
+            None => panic!(
+                "Rank {} received a message from rank {} outside its group {:?}",
+                self.inner.rank(),
+                rank,
+                members
+            ),
+        }
+    }
+}
+
+impl<T: Transport> Transport for Group<'_, T> {
+    fn rank(&self) -> i32 {
+        self.index_of(self.inner.rank())
+    }
+
+    fn size(&self) -> i32 {
+        self.members.borrow().len() as i32
+    }
+
+    fn send(&self, destination: i32, bytes: &[u8]) {
+        self.inner.send(self.member(destination), bytes)
+    }
+
+    fn receive(&self, source: i32) -> Vec<u8> {
+        self.inner.receive(self.member(source))
+    }
+
+    fn receive_any(&self) -> (i32, Vec<u8>) {
+        let (source, bytes) = self.inner.receive_any();
+        (self.index_of(source), bytes)
+    }
+
+    fn receive_any_timeout(&self, timeout: Duration) -> Option<(i32, Vec<u8>)> {
+        let (source, bytes) = self.inner.receive_any_timeout(timeout)?;
+        Some((self.index_of(source), bytes))
+    }
+
+    fn broadcast(&self, root: i32, bytes: Vec<u8>) -> Vec<u8> {
+        if self.rank() == root {
+            let members = self.members.borrow().clone();
+            for (index, member) in members.into_iter().enumerate() {
+                if index as i32 != root {
+                    self.inner.send(member, &bytes);
+                }
+            }
+            bytes
+        } else {
+            self.inner.receive(self.member(root))
+        }
+    }
+
+    fn send_immediate(&self, destination: i32, bytes: Vec<u8>) {
+        self.inner.send_immediate(self.member(destination), bytes)
+    }
+
+    fn complete_sends(&self) {
+        self.inner.complete_sends()
+    }
+
+    fn traffic(&self) -> Option<&TrafficRecorder> {
+        self.inner.traffic()
+    }
+
+    fn compresses(&self) -> bool {
+        self.inner.compresses()
+    }
+}
+
+// What the in memory network does to the messages going through it. The
+// default is a perfect network: instant, lossless and nobody fails.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkConditions {
+    // Every message is delivered this long after it was sent, plus a uniform
+    // random extra of up to `jitter`
+    pub latency: Duration,
+    pub jitter: Duration,
+    // Probability that a point to point message is silently lost, broadcasts
+    // always arrive
+    pub drop_rate: f64,
+    // The rank stops, as if its node died, once it has sent this many messages
+    pub failure: Option<(i32, usize)>,
+    // Every message the rank sends takes this much longer, as if it ran on a
+    // slower node
+    pub slow: Option<(i32, Duration)>,
+    // How long a receive waits before declaring the sender lost, forever when
+    // unset, which is what a dropped message then leads to
+    pub stall_timeout: Option<Duration>,
+    pub seed: u64,
+}
+
+// How often a receive from any rank looks at the inboxes again
+const POLL_INTERVAL: Duration = Duration::from_micros(200);
+
+struct Envelope {
+    deliver_at: Instant,
+    bytes: Vec<u8>,
+}
+
+impl Envelope {
+    fn open(self) -> Vec<u8> {
+        let now = Instant::now();
+        if self.deliver_at > now {
+            std::thread::sleep(self.deliver_at - now);
+        }
+        self.bytes
+    }
+}
+
+// In memory stand in for MPI, one per virtual rank, each meant to be moved to
+// its own thread
+pub struct MockTransport {
+    rank: i32,
+    outboxes: Vec<Sender<Envelope>>,
+    inboxes: Vec<Receiver<Envelope>>,
+    // Taken out of an inbox by a receive from any rank but not delivered yet,
+    // by source
+    early: RefCell<Vec<Option<Envelope>>>,
+    broadcast_outboxes: Vec<Sender<Envelope>>,
+    broadcast_inboxes: Vec<Receiver<Envelope>>,
+    conditions: NetworkConditions,
+    rng: RefCell<StdRng>,
+    sent: Cell<usize>,
+}
+
+impl MockTransport {
+    // A fully connected set of `size` ranks, the transport of rank i is at i
+    pub fn network(size: usize) -> Vec<MockTransport> {
+        MockTransport::network_with(size, &NetworkConditions::default())
+    }
+
+    pub fn network_with(size: usize, conditions: &NetworkConditions) -> Vec<MockTransport> {
+        let (outboxes, inboxes) = channel_matrix(size);
+        let (broadcast_outboxes, broadcast_inboxes) = channel_matrix(size);
+
+        outboxes
+            .into_iter()
+            .zip(inboxes)
+            .zip(broadcast_outboxes.into_iter().zip(broadcast_inboxes))
+            .enumerate()
+            .map(
+                |(rank, ((outboxes, inboxes), (broadcast_outboxes, broadcast_inboxes)))| {
+                    MockTransport {
+                        rank: rank as i32,
+                        outboxes,
+                        inboxes,
+                        early: RefCell::new((0..size).map(|_| None).collect()),
+                        broadcast_outboxes,
+                        broadcast_inboxes,
+                        conditions: conditions.clone(),
+                        rng: RefCell::new(StdRng::seed_from_u64(
+                            conditions.seed.wrapping_add(rank as u64),
+                        )),
+                        sent: Cell::new(0),
+                    }
+                },
+            )
+            .collect()
+    }
+
+    fn envelope(&self, bytes: Vec<u8>) -> Envelope {
+        let jitter = if self.conditions.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            self.conditions
+                .jitter
+                .mul_f64(self.rng.borrow_mut().gen::<f64>())
+        };
+
+        let slowdown = match self.conditions.slow {
+            Some((rank, delay)) if rank == self.rank => delay,
+            _ => Duration::ZERO,
+        };
+
+        Envelope {
+            deliver_at: Instant::now() + self.conditions.latency + jitter + slowdown,
+            bytes,
+        }
+    }
+
+    // Panics once the rank reached its simulated failure, the thread running it
+    // ends and the other ranks see it hang up
+    fn check_alive(&self) {
+        if let Some((rank, after)) = self.conditions.failure {
+            if rank == self.rank && self.sent.get() >= after {
+                panic!(
+                    "Rank {} failed after sending {} messages",
+                    rank,
+                    self.sent.get()
+                );
+            }
+        }
+    }
+
+    fn deliver(&self, outbox: &Sender<Envelope>, destination: i32, bytes: Vec<u8>) {
+        let envelope = self.envelope(bytes);
+        outbox
+            .send(envelope)
+            .unwrap_or_else(|_| panic!("Rank {} hung up", destination));
+    }
+
+    fn take(&self, inbox: &Receiver<Envelope>, source: i32) -> Vec<u8> {
+        let envelope = match self.conditions.stall_timeout {
+            Some(timeout) => inbox
+                .recv_timeout(timeout)
+                .unwrap_or_else(|error| match error {
+                    RecvTimeoutError::Timeout => panic!(
+                        "Rank {} waited {:?} for rank {}, the message was lost",
+                        self.rank, timeout, source
+                    ),
+                    RecvTimeoutError::Disconnected => panic!("Rank {} hung up", source),
+                }),
+            None => inbox
+                .recv()
+                .unwrap_or_else(|_| panic!("Rank {} hung up", source)),
+        };
+        envelope.open()
+    }
+
+    // Channels can't be waited on together, so every inbox is looked at in
+    // turn until one of them has a message due. None once `timeout` is up,
+    // the ranks that hung up are then left to the caller to notice.
+    fn take_any(&self, timeout: Option<Duration>) -> Option<(i32, Vec<u8>)> {
+        let started = Instant::now();
+        loop {
+            let mut early = self.early.borrow_mut();
+            for (source, inbox) in self.inboxes.iter().enumerate() {
+                if source as i32 == self.rank || early[source].is_some() {
+                    continue;
+                }
+                match inbox.try_recv() {
+                    Ok(envelope) => early[source] = Some(envelope),
+                    Err(TryRecvError::Empty) => {}
+                    Err(TryRecvError::Disconnected) if timeout.is_some() => {}
+                    Err(TryRecvError::Disconnected) => panic!("Rank {} hung up", source),
+                }
+            }
+
+            let now = Instant::now();
+            let due = (0..early.len())
+                .filter(|source| {
+                    early[*source]
+                        .as_ref()
+                        .is_some_and(|envelope| envelope.deliver_at <= now)
+                })
+                .min_by_key(|source| early[*source].as_ref().unwrap().deliver_at);
+            if let Some(source) = due {
+                let envelope = early[source].take().unwrap();
+                return Some((source as i32, envelope.open()));
+            }
+
+            if timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+                return None;
+            }
+            if let Some(timeout) = self.conditions.stall_timeout {
+                if started.elapsed() >= timeout {
+                    panic!(
+                        "Rank {} waited {:?} for any rank, the messages were lost",
+                        self.rank, timeout
+                    );
+                }
+            }
+            drop(early);
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+// Row i of the senders goes out of rank i, row j of the receivers comes into
+// rank j, both indexed by the other end
+#[allow(clippy::type_complexity)]
+fn channel_matrix<T>(size: usize) -> (Vec<Vec<Sender<T>>>, Vec<Vec<Receiver<T>>>) {
+    let mut senders = (0..size).map(|_| Vec::new()).collect::<Vec<_>>();
+    let mut receivers = (0..size)
+        .map(|_| (0..size).map(|_| None).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    for (source, outboxes) in senders.iter_mut().enumerate() {
+        for inboxes in receivers.iter_mut() {
+            let (sender, receiver) = channel();
+            outboxes.push(sender);
+            inboxes[source] = Some(receiver);
+        }
+    }
+
+    let receivers = receivers
+        .into_iter()
+        .map(|inboxes| inboxes.into_iter().map(Option::unwrap).collect())
+        .collect();
+    (senders, receivers)
+}
+
+impl Transport for MockTransport {
+    fn rank(&self) -> i32 {
+        self.rank
+    }
+
+    fn size(&self) -> i32 {
+        self.outboxes.len() as i32
+    }
+
+    fn send(&self, destination: i32, bytes: &[u8]) {
+        self.check_alive();
+        self.sent.set(self.sent.get() + 1);
+
+        let dropped = self.conditions.drop_rate > 0.0
+            && self.rng.borrow_mut().gen::<f64>() < self.conditions.drop_rate;
+        if !dropped {
+            // Lost if the destination hung up, as with a node that died the
+            // sender doesn't know
+            let envelope = self.envelope(bytes.to_vec());
+            let _ = self.outboxes[destination as usize].send(envelope);
+        }
+    }
+
+    fn receive(&self, source: i32) -> Vec<u8> {
+        self.check_alive();
+        let early = self.early.borrow_mut()[source as usize].take();
+        match early {
+            Some(envelope) => envelope.open(),
+            None => self.take(&self.inboxes[source as usize], source),
+        }
+    }
+
+    fn receive_any(&self) -> (i32, Vec<u8>) {
+        self.check_alive();
+        self.take_any(None).unwrap()
+    }
+
+    fn receive_any_timeout(&self, timeout: Duration) -> Option<(i32, Vec<u8>)> {
+        self.check_alive();
+        self.take_any(Some(timeout))
+    }
+
+    fn broadcast(&self, root: i32, bytes: Vec<u8>) -> Vec<u8> {
+        self.check_alive();
+        if self.rank == root {
+            for (rank, outbox) in self.broadcast_outboxes.iter().enumerate() {
+                if rank as i32 != root {
+                    self.deliver(outbox, rank as i32, bytes.clone());
+                }
+            }
+            self.sent.set(self.sent.get() + 1);
+            bytes
+        } else {
+            self.take(&self.broadcast_inboxes[root as usize], root)
+        }
+    }
+}
+
+#[cfg(feature = "mpi")]
+pub use self::mpi_transport::MpiTransport;
+
+#[cfg(feature = "mpi")]
+mod mpi_transport {
+    use super::Transport;
+    use mpi::request::{Request, StaticScope};
+    use mpi::topology::SimpleCommunicator;
+    use mpi::traits::{Communicator, Destination, Root, Source};
+    use std::cell::RefCell;
+    use std::time::{Duration, Instant};
+
+    // How often a receive with a timeout looks for a message again
+    const PROBE_INTERVAL: Duration = Duration::from_micros(100);
+
+    pub struct MpiTransport {
+        world: SimpleCommunicator,
+        // Immediate sends not known to be done yet, with the buffer each
+        // sends from, leaked until then so MPI can read it
+        pending: RefCell<Vec<(Request<'static>, *mut [u8])>>,
+    }
+
+    impl MpiTransport {
+        pub fn new(world: SimpleCommunicator) -> Self {
+            MpiTransport {
+                world,
+                pending: RefCell::new(Vec::new()),
+            }
+        }
+
+        // Frees the buffers of the sends that are done
+        fn reap(&self) {
+            let mut pending = self.pending.borrow_mut();
+            let mut running = Vec::with_capacity(pending.len());
+            for (request, buffer) in pending.drain(..) {
+                match request.test() {
+                    // Safety: the buffer was leaked by `send_immediate` and
+                    // MPI is done reading it
+                    Ok(_) => drop(unsafe { Box::from_raw(buffer) }),
+                    Err(request) => running.push((request, buffer)),
+                }
+            }
+            *pending = running;
+        }
+    }
+
+    impl Drop for MpiTransport {
+        fn drop(&mut self) {
+            self.complete_sends();
+        }
+    }
+
+    impl Transport for MpiTransport {
+        fn rank(&self) -> i32 {
+            self.world.rank()
+        }
+
+        fn size(&self) -> i32 {
+            self.world.size()
+        }
+
+        fn send(&self, destination: i32, bytes: &[u8]) {
+            self.world.process_at_rank(destination).send(bytes);
+        }
+
+        fn receive(&self, source: i32) -> Vec<u8> {
+            let (buffer, _) = self.world.process_at_rank(source).receive_vec();
+            buffer
+        }
+
+        fn receive_any(&self) -> (i32, Vec<u8>) {
+            let (buffer, status) = self.world.any_process().receive_vec();
+            (status.source_rank(), buffer)
+        }
+
+        // Probes until a message is there, so nothing is posted that would
+        // still be waiting after giving up
+        fn receive_any_timeout(&self, timeout: Duration) -> Option<(i32, Vec<u8>)> {
+            let started = Instant::now();
+            loop {
+                if let Some((message, status)) = self.world.any_process().immediate_matched_probe()
+                {
+                    let (buffer, _) = message.matched_receive_vec();
+                    return Some((status.source_rank(), buffer));
+                }
+                if started.elapsed() >= timeout {
+                    return None;
+                }
+                std::thread::sleep(PROBE_INTERVAL);
+            }
+        }
+
+        // The length goes first, so every rank can make room for the bytes
+        fn broadcast(&self, root: i32, mut bytes: Vec<u8>) -> Vec<u8> {
+            let process = self.world.process_at_rank(root);
+            let mut length = bytes.len() as u64;
+            process.broadcast_into(&mut length);
+            bytes.resize(length as usize, 0);
+            process.broadcast_into(&mut bytes[..]);
+            bytes
+        }
+
+        fn send_immediate(&self, destination: i32, bytes: Vec<u8>) {
+            self.reap();
+            let pointer = Box::into_raw(bytes.into_boxed_slice());
+            // Safety: freed only once the send is done, see `reap`
+            let buffer: &'static [u8] = unsafe { &*pointer };
+            let request = self
+                .world
+                .process_at_rank(destination)
+                .immediate_send(StaticScope, buffer);
+            self.pending.borrow_mut().push((request, pointer));
+        }
+
+        fn complete_sends(&self) {
+            for (request, buffer) in self.pending.borrow_mut().drain(..) {
+                request.wait();
+                // Safety: as in `reap`
+                drop(unsafe { Box::from_raw(buffer) });
+            }
+        }
+    }
+}
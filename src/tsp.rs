@@ -1,6 +1,24 @@
-use super::organism::Organism;
-use itertools::Itertools;
-use rand::{seq::SliceRandom, Rng};
+pub mod builtin;
+pub mod clustered;
+pub mod constraints;
+pub mod duration;
+pub mod explain;
+pub mod loader;
+pub mod matrix;
+pub mod overrides;
+pub mod packed;
+pub mod scenarios;
+
+use super::comm;
+use super::local_search::LocalSearch;
+use super::organism::{GenomeDistance, GenomeHash, Individual, Organism, Problem, Validate};
+use super::permutation::{self, CandidateMatrix, CrossoverOperator, MutationMix, MutationOperator};
+use constraints::TourConstraints;
+use duration::RouteDuration;
+use matrix::WeightMatrix;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use scenarios::Scenarios;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -16,107 +34,410 @@ impl TspSolution {
     }
 }
 
+// A loaded instance, coordinates are only known for geometric instances
+#[derive(Clone, Debug)]
+pub struct TspInstance {
+    pub name: String,
+    pub graph_weights: Vec<Vec<f32>>,
+    pub coordinates: Option<Vec<(f32, f32)>>,
+    // What its tours must respect, see `--constraints`
+    pub constraints: Option<Arc<TourConstraints>>,
+    // Service times and the route duration limit, when the file gives them
+    pub duration: Option<Arc<RouteDuration>>,
+    // Other edge weights the tours are measured with, see `--scenario`
+    pub scenarios: Option<Arc<Scenarios>>,
+}
+
+impl TspInstance {
+    // The edge weights as every individual of the instance shares them
+    pub fn matrix(&self) -> Arc<WeightMatrix> {
+        Arc::new(WeightMatrix::from_rows(&self.graph_weights))
+    }
+
+    // Over the edge weights, equal on every rank that loaded the same
+    // instance whatever its name or path
+    pub fn checksum(&self) -> u32 {
+        let nodes = self.graph_weights.len() as u32;
+        let weights = self
+            .graph_weights
+            .iter()
+            .flatten()
+            .map(|weight| weight.to_bits());
+        comm::checksum(
+            std::iter::once(nodes)
+                .chain(weights)
+                .flat_map(u32::to_le_bytes),
+        )
+    }
+}
+
 #[derive(Clone)]
 pub struct TspProblem {
-    pub graph_weights: Arc<Vec<Vec<f32>>>,
+    pub graph_weights: Arc<WeightMatrix>,
+    pub crossover: CrossoverOperator,
+    pub mutation: MutationOperator,
+    // Only used by the composite mutation
+    pub mutation_mix: MutationMix,
+    // Nearest neighbors of every city, for the distance aware crossovers
+    pub candidates: Option<Arc<Vec<Vec<usize>>>>,
+    // Every tour is repaired to respect them
+    pub constraints: Option<Arc<TourConstraints>>,
+    // Whether the fitness counts the edge from the last city back to the
+    // first, a Hamiltonian cycle rather than an open path
+    pub closed_tour: bool,
+    // The fitness aggregates the length of the tour in each of them
+    pub scenarios: Option<Arc<Scenarios>>,
+    // What the tour costs over the duration limit is added to its length
+    pub duration: Option<Arc<RouteDuration>>,
 }
 impl TspProblem {
-    pub fn new(graph_weights: Arc<Vec<Vec<f32>>>) -> Self {
+    pub fn new(graph_weights: Arc<WeightMatrix>) -> Self {
         TspProblem {
-            graph_weights: graph_weights,
+            graph_weights,
+            crossover: CrossoverOperator::Order,
+            mutation: MutationOperator::Swap,
+            mutation_mix: MutationMix::default(),
+            candidates: None,
+            constraints: None,
+            closed_tour: true,
+            scenarios: None,
+            duration: None,
         }
     }
-}
 
-pub struct TSP {
-    map: TspProblem,
-    solution: TspSolution,
+    pub fn with_operators(
+        mut self,
+        crossover: CrossoverOperator,
+        mutation: MutationOperator,
+    ) -> Self {
+        self.crossover = crossover;
+        self.mutation = mutation;
+        self
+    }
+
+    // Individuals given the same mix share its statistics
+    pub fn with_mutation_mix(mut self, mix: &MutationMix) -> Self {
+        self.mutation_mix = mix.clone();
+        self
+    }
+
+    pub fn with_closed_tour(mut self, closed_tour: bool) -> Self {
+        self.closed_tour = closed_tour;
+        self
+    }
+
+    pub fn with_scenarios(mut self, scenarios: Option<&Arc<Scenarios>>) -> Self {
+        self.scenarios = scenarios.cloned();
+        self
+    }
+
+    pub fn with_duration(mut self, duration: Option<&Arc<RouteDuration>>) -> Self {
+        self.duration = duration.cloned();
+        self
+    }
+
+    pub fn with_candidates(mut self, candidates: &Arc<Vec<Vec<usize>>>) -> Self {
+        self.candidates = Some(candidates.clone());
+        self
+    }
+
+    // Individuals built with `TSP::repaired` respect them whatever tour they
+    // are given
+    pub fn with_constraints(mut self, constraints: Option<&Arc<TourConstraints>>) -> Self {
+        self.constraints = constraints.cloned();
+        self
+    }
+
+    // Moves the cities of `solution` to respect the constraints, if any
+    pub fn repair(&self, solution: &mut TspSolution) {
+        if let Some(constraints) = &self.constraints {
+            constraints.repair(&mut solution.path);
+        }
+    }
 }
 
-impl TSP {
-    pub fn new(graph_weights: Arc<Vec<Vec<f32>>>, solution: TspSolution) -> Self {
-        let len = graph_weights.len();
-        TSP {
-            map: TspProblem::new(graph_weights),
-            solution,
+// Whether the distinct cities of `path` are all `nodes` of them and nothing
+// else, with one bit per city instead of hashing them as it runs on every
+// fitness
+fn visits_every_city(path: &[usize], nodes: usize) -> bool {
+    let mut seen = vec![0u64; nodes.div_ceil(64)];
+    let mut distinct = 0;
+    for city in path.iter() {
+        if *city >= nodes {
+            return false;
+        }
+        let (word, bit) = (city / 64, 1 << (city % 64));
+        if seen[word] & bit == 0 {
+            seen[word] |= bit;
+            distinct += 1;
         }
     }
+    distinct == nodes
+}
 
-    pub fn new_with_random_path(graph_weights: Arc<Vec<Vec<f32>>>) -> Self {
-        let mut path = (0..graph_weights.len()).collect::<Vec<usize>>();
-        path.shuffle(&mut rand::thread_rng());
+// The length of the tour, or of the open path without its return edge,
+// infinite when a city is missed or visited twice. With scenarios, the
+// lengths in all of them aggregated. Time over the duration limit costs its
+// penalty on top, a tour over a hard limit is infinite.
+impl Problem for TspProblem {
+    type Genome = TspSolution;
 
-        TSP {
-            map: TspProblem { graph_weights },
-            solution: TspSolution { path },
+    fn evaluate(&self, solution: &TspSolution) -> f32 {
+        let path = &solution.path;
+        if !visits_every_city(path, self.graph_weights.len()) {
+            return f32::INFINITY;
         }
-    }
 
-    pub fn get_path(&self) -> &Vec<usize> {
-        &self.solution.path
+        let length = |weights: &WeightMatrix| {
+            let length = path
+                .iter()
+                .zip(path.iter().skip(1))
+                .map(|(a, b)| weights.dist(*a, *b))
+                .fold(0.0, |cost, weight| cost + weight);
+            match (path.first(), path.last()) {
+                (Some(first), Some(last)) if self.closed_tour => {
+                    length + weights.dist(*last, *first)
+                }
+                _ => length,
+            }
+        };
+        let instance = length(&self.graph_weights);
+        let fitness = match &self.scenarios {
+            Some(scenarios) => scenarios.fitness(instance, length),
+            None => instance,
+        };
+        match &self.duration {
+            Some(duration) => {
+                fitness + duration.cost(self.graph_weights.as_ref(), path, self.closed_tour)
+            }
+            None => fitness,
+        }
     }
 
-    pub fn get_solution(&self) -> &TspSolution {
-        &self.solution
+    fn mutate<R: Rng + ?Sized>(&self, solution: &mut TspSolution, rng: &mut R) {
+        if self.mutation != MutationOperator::Composite {
+            self.mutation.apply(&mut solution.path, rng);
+            self.repair(solution);
+            return;
+        }
+
+        let before = self.evaluate(solution);
+        let applied =
+            self.mutation
+                .apply_weighted(&mut solution.path, &self.mutation_mix.weights, rng);
+        self.repair(solution);
+        let after = self.evaluate(solution);
+        self.mutation_mix.statistics.record(applied, before, after);
     }
 
-    pub fn get_map(&self) -> &TspProblem {
-        &self.map
+    fn cross_over<R: Rng + ?Sized>(
+        &self,
+        first: &TspSolution,
+        second: &TspSolution,
+        rng: &mut R,
+    ) -> TspSolution {
+        let distances = self.graph_weights.as_ref();
+        let (first, second) = (&first.path, &second.path);
+        let path = match &self.candidates {
+            Some(candidates) => {
+                let distances = CandidateMatrix::new(distances, candidates);
+                self.crossover
+                    .apply(first, second, &distances, self.closed_tour, rng)
+            }
+            None => self
+                .crossover
+                .apply(first, second, distances, self.closed_tour, rng),
+        };
+
+        let mut child = TspSolution { path };
+        self.repair(&mut child);
+        child
     }
 }
 
-impl Clone for TSP {
-    fn clone(&self) -> Self {
-        TSP {
-            map: self.map.clone(),
-            solution: self.solution.clone(),
+// An individual of the TSP, its tour and the problem the whole population
+// shares
+pub type TSP = Individual<TspProblem>;
+
+impl TSP {
+    // An individual of `problem`, its tour repaired to respect the
+    // constraints of the problem
+    pub fn repaired(problem: Arc<TspProblem>, mut solution: TspSolution) -> Self {
+        problem.repair(&mut solution);
+        Individual::new(problem, solution)
+    }
+
+    pub fn new_with_random_path(problem: &Arc<TspProblem>) -> Self {
+        Self::new_shuffled(problem, &mut rand::thread_rng())
+    }
+
+    pub fn new_shuffled<R: Rng>(problem: &Arc<TspProblem>, rng: &mut R) -> Self {
+        let mut path = (0..problem.graph_weights.len()).collect::<Vec<usize>>();
+        path.shuffle(rng);
+
+        Self::repaired(problem.clone(), TspSolution { path })
+    }
+
+    pub fn new_nearest_neighbor(problem: &Arc<TspProblem>, start: usize) -> Self {
+        let graph_weights = &problem.graph_weights;
+        let nodes = graph_weights.len();
+        let mut visited = vec![false; nodes];
+        let mut path = Vec::with_capacity(nodes);
+
+        let mut current = start;
+        visited[current] = true;
+        path.push(current);
+
+        while path.len() < nodes {
+            let next = (0..nodes)
+                .filter(|node| !visited[*node])
+                .min_by(|a, b| {
+                    graph_weights
+                        .dist(current, *a)
+                        .total_cmp(&graph_weights.dist(current, *b))
+                })
+                .unwrap();
+
+            visited[next] = true;
+            path.push(next);
+            current = next;
         }
+
+        Self::repaired(problem.clone(), TspSolution { path })
     }
-}
 
-impl Organism for TSP {
-    fn fitness(&self) -> f32 {
-        let mut cost = self
-            .solution
-            .path
-            .iter()
-            .zip(self.solution.path.iter().skip(1))
-            .map(|(a, b)| self.map.graph_weights[*a][*b])
-            .sum();
+    // Greedy edge construction: the shortest edges first, skipping those that
+    // would give a city a third edge or close a cycle, until one path is left.
+    // Every weight is scaled by up to 1 + `noise` so tours built with a noise
+    // differ.
+    pub fn new_greedy<R: Rng>(problem: &Arc<TspProblem>, noise: f32, rng: &mut R) -> Self {
+        let graph_weights = &problem.graph_weights;
+        let nodes = graph_weights.len();
+        let mut edges = (0..nodes)
+            .flat_map(|from| (from + 1..nodes).map(move |to| (from, to)))
+            .map(|(from, to)| {
+                let weight = graph_weights
+                    .dist(from, to)
+                    .min(graph_weights.dist(to, from));
+                (weight * (1.0 + noise * rng.gen::<f32>()), from, to)
+            })
+            .collect::<Vec<(f32, usize, usize)>>();
+        edges.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Fragment every city belongs to, and its neighbors on the path
+        let mut fragment = (0..nodes).collect::<Vec<usize>>();
+        let mut neighbors = vec![Vec::with_capacity(2); nodes];
+        let root = |fragment: &mut Vec<usize>, mut node: usize| {
+            while fragment[node] != node {
+                fragment[node] = fragment[fragment[node]];
+                node = fragment[node];
+            }
+            node
+        };
+
+        let mut joined = 0;
+        for (_, from, to) in edges {
+            if joined + 1 >= nodes {
+                break;
+            }
+            if neighbors[from].len() == 2 || neighbors[to].len() == 2 {
+                continue;
+            }
+            let (a, b) = (root(&mut fragment, from), root(&mut fragment, to));
+            if a == b {
+                continue;
+            }
+            fragment[a] = b;
+            neighbors[from].push(to);
+            neighbors[to].push(from);
+            joined += 1;
+        }
 
-        if self.solution.path.iter().unique().count() != self.map.graph_weights.len() {
-            cost = f32::INFINITY;
+        // Walk the path from one of its ends
+        let mut path = Vec::with_capacity(nodes);
+        let mut previous = None;
+        let mut current = (0..nodes).find(|node| neighbors[*node].len() < 2);
+        while let Some(node) = current {
+            path.push(node);
+            current = neighbors[node]
+                .iter()
+                .copied()
+                .find(|next| Some(*next) != previous);
+            previous = Some(node);
         }
 
-        return cost;
+        Self::repaired(problem.clone(), TspSolution { path })
     }
 
-    fn mutate(&mut self) {
-        let first_index = rand::thread_rng().gen_range(0..self.solution.path.len());
-        let second_index = rand::thread_rng().gen_range(0..self.solution.path.len());
+    // `search` on the tour, returns how much shorter it got
+    pub fn polish(&mut self, search: &dyn LocalSearch, passes: usize) -> f32 {
+        let problem = self.problem().clone();
+        if problem.constraints.is_none() {
+            return search.improve(
+                &mut self.genome_mut().path,
+                problem.graph_weights.as_ref(),
+                passes,
+                problem.closed_tour,
+            );
+        }
+        // The repair may give some of it back
+        let before = self.fitness();
+        search.improve(
+            &mut self.genome_mut().path,
+            problem.graph_weights.as_ref(),
+            passes,
+            problem.closed_tour,
+        );
+        problem.repair(self.genome_mut());
+        before - self.fitness()
+    }
 
-        self.solution.path.swap(first_index, second_index);
+    pub fn get_path(&self) -> &Vec<usize> {
+        &self.genome().path
     }
 
-    fn cross_over(&self, other: &Self) -> Self
-    where
-        Self: Sized,
-    {
-        let mut new_path = vec![0; self.solution.path.len()];
-        let mut rng = rand::thread_rng();
+    pub fn get_solution(&self) -> &TspSolution {
+        self.genome()
+    }
+}
 
-        let start_index = rng.gen_range(0..self.solution.path.len());
-        let end_index = rng.gen_range(start_index..self.solution.path.len());
+impl GenomeDistance for TspProblem {
+    fn distance(&self, first: &TspSolution, second: &TspSolution) -> f32 {
+        permutation::edge_distance(&first.path, &second.path, self.closed_tour) as f32
+    }
+}
 
-        new_path[0..start_index].clone_from_slice(&self.solution.path[0..start_index]);
-        new_path[start_index..end_index]
-            .clone_from_slice(&other.solution.path[start_index..end_index]);
-        new_path[end_index..self.solution.path.len()]
-            .clone_from_slice(&self.solution.path[end_index..self.solution.path.len()]);
+impl GenomeHash for TspSolution {
+    fn genome_hash(&self) -> u64 {
+        permutation::genome_hash(&self.path)
+    }
+}
 
-        TSP {
-            map: self.map.clone(),
-            solution: TspSolution { path: new_path },
+impl Validate for TSP {
+    fn validate(&self) -> Result<(), String> {
+        let path = &self.genome().path;
+        let nodes = self.problem().graph_weights.len();
+        if path.len() != nodes {
+            return Err(format!(
+                "the tour visits {} cities, the instance has {}",
+                path.len(),
+                nodes
+            ));
+        }
+        let mut seen = vec![false; nodes];
+        for node in path.iter() {
+            if *node >= nodes {
+                return Err(format!("city {} is not in the instance", node));
+            }
+            if std::mem::replace(&mut seen[*node], true) {
+                return Err(format!("city {} is visited twice", node));
+            }
+        }
+        match &self.problem().constraints {
+            Some(constraints) if !constraints.satisfied(path) => {
+                Err(format!("the tour doesn't respect {}", constraints))
+            }
+            _ => Ok(()),
         }
     }
 }
@@ -0,0 +1,24 @@
+use super::loader::parse;
+use super::TspInstance;
+
+// Small TSPLIB instances compiled into the binary, selected with `builtin:<name>`
+const INSTANCES: &[(&str, &str)] = &[
+    ("bays29", include_str!("../../data/bays29.tsp")),
+    ("berlin52", include_str!("../../data/berlin52.tsp")),
+    ("eil101", include_str!("../../data/eil101.tsp")),
+    ("wi29", include_str!("../../data/wi29.tsp")),
+];
+
+pub const PREFIX: &str = "builtin:";
+
+pub fn names() -> Vec<&'static str> {
+    INSTANCES.iter().map(|(name, _)| *name).collect()
+}
+
+pub fn instance(name: &str) -> Option<TspInstance> {
+    INSTANCES
+        .iter()
+        .find(|(instance, _)| *instance == name)
+        // The bundled files are known to be well formed
+        .map(|(name, source)| parse(name, source).unwrap())
+}
@@ -0,0 +1,322 @@
+use super::matrix::WeightMatrix;
+use super::TspSolution;
+use crate::organism::{Distance, GenomeHash, Organism, Validate};
+use crate::permutation::{
+    self, is_permutation, CrossoverOperator, DistanceMatrix, MutationOperator,
+};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+pub fn read(path: &Path) -> Result<Vec<Vec<usize>>, String> {
+    let source = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+    parse(&source)
+}
+
+// One group per line, its cities numbered from 1 as in the TSPLIB files and
+// separated by spaces. Blank lines and lines starting with # are skipped.
+pub fn parse(source: &str) -> Result<Vec<Vec<usize>>, String> {
+    let mut groups = Vec::new();
+    for (number, line) in source.lines().map(str::trim).enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let group = line
+            .split_whitespace()
+            .map(|field| match field.parse::<usize>() {
+                Ok(city) if city >= 1 => Ok(city - 1),
+                _ => Err(format!("line {}: invalid city {}", number + 1, field)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        groups.push(group);
+    }
+    Ok(groups)
+}
+
+// A TSP whose cities come in groups visited one after another: the tour goes
+// through the groups in some order, and through the cities of every group in
+// some order of their own
+pub struct ClusteredProblem {
+    pub graph_weights: Arc<WeightMatrix>,
+    // Cities of every group, each city in exactly one
+    pub groups: Vec<Vec<usize>>,
+    // Shortest edge between the cities of two groups, for the distance aware
+    // crossovers at the group level
+    group_distances: Vec<Vec<f32>>,
+    pub crossover: CrossoverOperator,
+    pub mutation: MutationOperator,
+    // As for the plain TSP, whether the tour returns to its first city
+    pub closed_tour: bool,
+}
+
+impl ClusteredProblem {
+    // Cities `groups` leaves out are groups of their own
+    pub fn new(graph_weights: Arc<WeightMatrix>, groups: Vec<Vec<usize>>) -> Result<Self, String> {
+        let cities = graph_weights.len();
+        let mut group_of = vec![None; cities];
+        for (group, members) in groups.iter().enumerate() {
+            if members.is_empty() {
+                return Err(format!("group {} has no city", group + 1));
+            }
+            for city in members.iter() {
+                match group_of.get_mut(*city) {
+                    None => return Err(format!("no city {} in {} cities", city + 1, cities)),
+                    Some(Some(other)) => {
+                        return Err(format!(
+                            "city {} is in groups {} and {}",
+                            city + 1,
+                            *other + 1,
+                            group + 1
+                        ))
+                    }
+                    Some(slot) => *slot = Some(group),
+                }
+            }
+        }
+        let mut groups = groups;
+        groups.extend(
+            (0..cities)
+                .filter(|city| group_of[*city].is_none())
+                .map(|city| vec![city]),
+        );
+
+        let weights = graph_weights.as_ref();
+        let group_distances = groups
+            .iter()
+            .map(|from| {
+                groups
+                    .iter()
+                    .map(|to| {
+                        from.iter()
+                            .flat_map(|a| to.iter().map(move |b| weights[*a][*b]))
+                            .fold(f32::INFINITY, f32::min)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(ClusteredProblem {
+            graph_weights,
+            groups,
+            group_distances,
+            crossover: CrossoverOperator::Order,
+            mutation: MutationOperator::Swap,
+            closed_tour: true,
+        })
+    }
+
+    pub fn with_operators(
+        mut self,
+        crossover: CrossoverOperator,
+        mutation: MutationOperator,
+    ) -> Self {
+        self.crossover = crossover;
+        self.mutation = mutation;
+        self
+    }
+
+    pub fn with_closed_tour(mut self, closed_tour: bool) -> Self {
+        self.closed_tour = closed_tour;
+        self
+    }
+}
+
+// The cities of one group as nodes of their own, numbered by their place in
+// the group
+struct GroupMatrix<'a> {
+    graph_weights: &'a WeightMatrix,
+    cities: &'a [usize],
+}
+
+impl DistanceMatrix for GroupMatrix<'_> {
+    fn nodes(&self) -> usize {
+        self.cities.len()
+    }
+
+    fn distance(&self, from: usize, to: usize) -> f32 {
+        self.graph_weights.dist(self.cities[from], self.cities[to])
+    }
+}
+
+// Two level genome: the order of the groups, and the order within every
+// group as indices into its cities. Crossover and mutation work on both
+// levels with the permutation operators, so the groups stay contiguous.
+pub struct ClusteredTsp {
+    problem: Arc<ClusteredProblem>,
+    order: Vec<usize>,
+    within: Vec<Vec<usize>>,
+}
+
+impl ClusteredTsp {
+    pub fn new_shuffled<R: Rng>(problem: Arc<ClusteredProblem>, rng: &mut R) -> Self {
+        let mut order = (0..problem.groups.len()).collect::<Vec<_>>();
+        order.shuffle(rng);
+        let within = problem
+            .groups
+            .iter()
+            .map(|cities| {
+                let mut within = (0..cities.len()).collect::<Vec<_>>();
+                within.shuffle(rng);
+                within
+            })
+            .collect();
+        ClusteredTsp {
+            problem,
+            order,
+            within,
+        }
+    }
+
+    // The tour through every city, group after group
+    pub fn get_solution(&self) -> TspSolution {
+        let path = self
+            .order
+            .iter()
+            .flat_map(|group| {
+                let cities = &self.problem.groups[*group];
+                self.within[*group].iter().map(move |city| cities[*city])
+            })
+            .collect();
+        TspSolution { path }
+    }
+
+    pub fn get_problem(&self) -> &ClusteredProblem {
+        &self.problem
+    }
+}
+
+impl fmt::Debug for ClusteredTsp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.get_solution())
+    }
+}
+
+impl Clone for ClusteredTsp {
+    fn clone(&self) -> Self {
+        ClusteredTsp {
+            problem: self.problem.clone(),
+            order: self.order.clone(),
+            within: self.within.clone(),
+        }
+    }
+}
+
+impl Distance for ClusteredTsp {
+    fn distance(&self, other: &Self) -> f32 {
+        permutation::edge_distance(
+            &self.get_solution().path,
+            &other.get_solution().path,
+            self.problem.closed_tour,
+        ) as f32
+    }
+}
+
+impl GenomeHash for ClusteredTsp {
+    fn genome_hash(&self) -> u64 {
+        self.get_solution().genome_hash()
+    }
+}
+
+impl Validate for ClusteredTsp {
+    fn validate(&self) -> Result<(), String> {
+        let groups = self.problem.groups.len();
+        if !is_permutation(&self.order, groups) {
+            return Err(format!(
+                "the tour doesn't visit each of the {} groups once",
+                groups
+            ));
+        }
+        if self.within.len() != groups {
+            return Err(format!(
+                "the tour orders {} groups, the instance has {}",
+                self.within.len(),
+                groups
+            ));
+        }
+        for (group, within) in self.within.iter().enumerate() {
+            if !is_permutation(within, self.problem.groups[group].len()) {
+                return Err(format!(
+                    "the tour doesn't visit each city of group {} once",
+                    group + 1
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Organism for ClusteredTsp {
+    type Fitness = f32;
+
+    // As the plain TSP, infinite when the slice crossover left a group or a
+    // city out
+    fn fitness(&self) -> f32 {
+        if self.validate().is_err() {
+            return f32::INFINITY;
+        }
+        let path = self.get_solution().path;
+        let weights = &self.problem.graph_weights;
+        let length = path
+            .windows(2)
+            .map(|pair| weights.dist(pair[0], pair[1]))
+            .fold(0.0, |cost, weight| cost + weight);
+        match (path.first(), path.last()) {
+            (Some(first), Some(last)) if self.problem.closed_tour => {
+                length + weights.dist(*last, *first)
+            }
+            _ => length,
+        }
+    }
+
+    // Either the order of the groups or the order within one of the groups
+    // of more than one city, half the time each
+    fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let larger = (0..self.within.len())
+            .filter(|group| self.within[*group].len() > 1)
+            .collect::<Vec<_>>();
+        if !larger.is_empty() && (self.order.len() < 2 || rng.gen_bool(0.5)) {
+            let group = larger[rng.gen_range(0..larger.len())];
+            self.problem.mutation.apply(&mut self.within[group], rng);
+        } else if self.order.len() > 1 {
+            self.problem.mutation.apply(&mut self.order, rng);
+        }
+    }
+
+    fn cross_over<R: Rng + ?Sized>(&self, other: &Self, rng: &mut R) -> Self {
+        let problem = &self.problem;
+        let order = problem.crossover.apply(
+            &self.order,
+            &other.order,
+            problem.group_distances.as_slice(),
+            problem.closed_tour,
+            rng,
+        );
+        let within = self
+            .within
+            .iter()
+            .zip(other.within.iter())
+            .zip(problem.groups.iter())
+            .map(|((first, second), cities)| {
+                if cities.len() < 2 {
+                    return first.clone();
+                }
+                let distances = GroupMatrix {
+                    graph_weights: problem.graph_weights.as_ref(),
+                    cities,
+                };
+                // The cities of a group are a stretch of the tour
+                problem
+                    .crossover
+                    .apply(first, second, &distances, false, rng)
+            })
+            .collect();
+
+        ClusteredTsp {
+            problem: self.problem.clone(),
+            order,
+            within,
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::path::Path;
+
+// What a tour must respect besides visiting every city once: the city it
+// starts at, the city it ends at, and cities that must come before others.
+// Tours are repaired to respect them after every change, cities numbered
+// from 0.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TourConstraints {
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+    // (before, after) pairs
+    pub precedences: Vec<(usize, usize)>,
+}
+
+pub fn read(path: &Path) -> Result<TourConstraints, String> {
+    let source = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+    parse(&source)
+}
+
+// One constraint per line, cities numbered from 1 as in the TSPLIB files:
+// `start 1`, `end 12`, and `3 before 7`. Blank lines and lines starting with
+// # are skipped.
+pub fn parse(source: &str) -> Result<TourConstraints, String> {
+    let mut constraints = TourConstraints::default();
+    for (number, line) in source.lines().map(str::trim).enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let city = |field: &str| match field.parse::<usize>() {
+            Ok(city) if city >= 1 => Ok(city - 1),
+            _ => Err(format!("line {}: invalid city {}", number + 1, field)),
+        };
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        match fields[..] {
+            ["start", start] if constraints.start.is_none() => {
+                constraints.start = Some(city(start)?)
+            }
+            ["end", end] if constraints.end.is_none() => constraints.end = Some(city(end)?),
+            ["start", _] | ["end", _] => {
+                return Err(format!("line {}: {} is given twice", number + 1, fields[0]))
+            }
+            [before, "before", after] => {
+                constraints.precedences.push((city(before)?, city(after)?))
+            }
+            _ => {
+                return Err(format!(
+                    "line {}: expected start <city>, end <city> or <city> before <city>, found {}",
+                    number + 1,
+                    line
+                ))
+            }
+        }
+    }
+    Ok(constraints)
+}
+
+impl TourConstraints {
+    pub fn is_empty(&self) -> bool {
+        self.start.is_none() && self.end.is_none() && self.precedences.is_empty()
+    }
+
+    // Why no tour of `cities` cities respects them, None when one does
+    pub fn infeasibility(&self, cities: usize) -> Option<String> {
+        let named = self
+            .start
+            .iter()
+            .chain(self.end.iter())
+            .chain(self.precedences.iter().flat_map(|(a, b)| [a, b]));
+        if let Some(city) = named.copied().find(|city| *city >= cities) {
+            return Some(format!("no city {} in {} cities", city + 1, cities));
+        }
+        if self.start.is_some() && self.start == self.end && cities > 1 {
+            return Some("a tour can't start and end at the same city".to_string());
+        }
+        for (before, after) in self.precedences.iter() {
+            if before == after {
+                return Some(format!("city {} can't come before itself", before + 1));
+            }
+            if Some(*after) == self.start {
+                return Some(format!(
+                    "city {} can't come before the start {}",
+                    before + 1,
+                    after + 1
+                ));
+            }
+            if Some(*before) == self.end {
+                return Some(format!(
+                    "city {} can't come after the end {}",
+                    after + 1,
+                    before + 1
+                ));
+            }
+        }
+
+        // The precedences must not go round, then the identity repairs
+        let mut path = (0..cities).collect::<Vec<_>>();
+        if !self.repair(&mut path) {
+            return Some("the precedences go round in a cycle".to_string());
+        }
+        None
+    }
+
+    pub fn satisfied(&self, path: &[usize]) -> bool {
+        let mut position = vec![usize::MAX; path.len()];
+        for (i, city) in path.iter().enumerate() {
+            if let Some(slot) = position.get_mut(*city) {
+                *slot = i;
+            }
+        }
+        self.start.is_none_or(|start| path.first() == Some(&start))
+            && self.end.is_none_or(|end| path.last() == Some(&end))
+            && self.precedences.iter().all(|(before, after)| {
+                matches!(
+                    (position.get(*before), position.get(*after)),
+                    (Some(before), Some(after)) if before < after
+                )
+            })
+    }
+
+    // Moves the cities of `path` as little as it takes to respect them: the
+    // start first, the end last, and every other city as early as its
+    // place in `path` while waiting for the cities it must come after. False
+    // and `path` left as it was when the precedences go round.
+    pub fn repair(&self, path: &mut [usize]) -> bool {
+        if self.is_empty() || self.satisfied(path) {
+            return true;
+        }
+        let cities = path.len();
+        let mut position = vec![0; cities];
+        for (i, city) in path.iter().enumerate() {
+            position[*city] = i;
+        }
+        let mut waiting = vec![0; cities];
+        let mut after = vec![Vec::new(); cities];
+        for (before, later) in self.precedences.iter() {
+            waiting[*later] += 1;
+            after[*before].push(*later);
+        }
+        let fixed = |city: usize| Some(city) == self.start || Some(city) == self.end;
+
+        let mut ready = (0..cities)
+            .filter(|city| waiting[*city] == 0 && !fixed(*city))
+            .map(|city| Reverse((position[city], city)))
+            .collect::<BinaryHeap<_>>();
+        let mut order = Vec::with_capacity(cities);
+        let mut next = self.start;
+        loop {
+            let city = match next.take() {
+                Some(city) => city,
+                None => match ready.pop() {
+                    Some(Reverse((_, city))) => city,
+                    None => break,
+                },
+            };
+            order.push(city);
+            for later in after[city].iter() {
+                waiting[*later] -= 1;
+                if waiting[*later] == 0 && !fixed(*later) {
+                    ready.push(Reverse((position[*later], *later)));
+                }
+            }
+        }
+        order.extend(self.end);
+
+        if order.len() != cities {
+            return false;
+        }
+        path.copy_from_slice(&order);
+        true
+    }
+}
+
+impl fmt::Display for TourConstraints {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(start) = self.start {
+            parts.push(format!("start at {}", start + 1));
+        }
+        if let Some(end) = self.end {
+            parts.push(format!("end at {}", end + 1));
+        }
+        if !self.precedences.is_empty() {
+            parts.push(format!("{} precedences", self.precedences.len()));
+        }
+        if parts.is_empty() {
+            parts.push("none".to_string());
+        }
+        f.pad(&parts.join(", "))
+    }
+}
@@ -0,0 +1,77 @@
+use crate::permutation::DistanceMatrix;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// How long a route takes: the travel along its edges plus the service time of
+// every city it visits, and how long one may take at most. Read from the
+// instance file, see `loader::parse`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RouteDuration {
+    // Of every city, numbered from 0
+    pub service: Vec<f32>,
+    pub limit: Option<f32>,
+    // Cost of every unit of time over the limit, a route over it is invalid
+    // without one
+    pub penalty: Option<f32>,
+}
+
+// A `closed` route drives back from its last city to its first.
+impl RouteDuration {
+    pub fn duration<D: DistanceMatrix + ?Sized>(
+        &self,
+        graph_weights: &D,
+        route: &[usize],
+        closed: bool,
+    ) -> f32 {
+        let mut travel = route
+            .windows(2)
+            .map(|pair| graph_weights.distance(pair[0], pair[1]))
+            .sum::<f32>();
+        if let (true, Some(first), Some(last)) = (closed, route.first(), route.last()) {
+            travel += graph_weights.distance(*last, *first);
+        }
+        travel + route.iter().map(|city| self.service[*city]).sum::<f32>()
+    }
+
+    // Time `route` takes over the limit, 0 within it
+    pub fn overtime<D: DistanceMatrix + ?Sized>(
+        &self,
+        graph_weights: &D,
+        route: &[usize],
+        closed: bool,
+    ) -> f32 {
+        match self.limit {
+            Some(limit) => (self.duration(graph_weights, route, closed) - limit).max(0.0),
+            None => 0.0,
+        }
+    }
+
+    // What going over the limit costs, infinite when the limit is hard
+    pub fn cost<D: DistanceMatrix + ?Sized>(
+        &self,
+        graph_weights: &D,
+        route: &[usize],
+        closed: bool,
+    ) -> f32 {
+        let overtime = self.overtime(graph_weights, route, closed);
+        match self.penalty {
+            _ if overtime == 0.0 => 0.0,
+            Some(penalty) => penalty * overtime,
+            None => f32::INFINITY,
+        }
+    }
+}
+
+impl fmt::Display for RouteDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let service = self.service.iter().sum::<f32>();
+        let limit = match (self.limit, self.penalty) {
+            (None, _) => "no limit".to_string(),
+            (Some(limit), None) => format!("at most {}", limit),
+            (Some(limit), Some(penalty)) => {
+                format!("{} before a penalty of {} per unit", limit, penalty)
+            }
+        };
+        f.pad(&format!("{} of service in all, {}", service, limit))
+    }
+}
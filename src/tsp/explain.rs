@@ -0,0 +1,110 @@
+use super::TspInstance;
+use serde::Serialize;
+use std::io::{self, Write};
+
+// One step of a tour, cities numbered from 1 as in the TSPLIB files. The
+// first step has no edge and only the service at the start.
+#[derive(Clone, Debug, Serialize)]
+pub struct Leg {
+    pub step: usize,
+    pub from: Option<usize>,
+    pub to: usize,
+    pub cost: f32,
+    // Travelled since the start, then with the service times as well
+    pub distance: f32,
+    pub time: f32,
+    // Time left before the route duration limit
+    pub slack: Option<f32>,
+}
+
+// Every leg of `path` in `instance`, accounted one by one as the fitness
+// sums them. A `closed` tour ends with the leg back to its first city, its
+// service was counted at the start.
+pub fn legs(instance: &TspInstance, path: &[usize], closed: bool) -> Vec<Leg> {
+    let duration = instance.duration.as_deref();
+
+    let mut distance = 0.0;
+    let mut time = 0.0;
+    let mut legs = Vec::with_capacity(path.len());
+    for (step, to) in path.iter().copied().enumerate() {
+        let from = step.checked_sub(1).map(|previous| path[previous]);
+        let cost = from.map_or(0.0, |from| instance.graph_weights[from][to]);
+        distance += cost;
+        time += cost + duration.map_or(0.0, |duration| duration.service[to]);
+        legs.push(Leg {
+            step,
+            from: from.map(|from| from + 1),
+            to: to + 1,
+            cost,
+            distance,
+            time,
+            slack: duration
+                .and_then(|duration| duration.limit)
+                .map(|limit| limit - time),
+        });
+    }
+    if let (true, Some(first), Some(last)) = (closed && path.len() > 1, path.first(), path.last()) {
+        let cost = instance.graph_weights[*last][*first];
+        distance += cost;
+        time += cost;
+        legs.push(Leg {
+            step: path.len(),
+            from: Some(last + 1),
+            to: first + 1,
+            cost,
+            distance,
+            time,
+            slack: duration
+                .and_then(|duration| duration.limit)
+                .map(|limit| limit - time),
+        });
+    }
+    legs
+}
+
+pub fn write_csv<W: Write>(legs: &[Leg], writer: &mut W) -> io::Result<()> {
+    writeln!(writer, "step,from,to,cost,distance,time,slack")?;
+    for leg in legs {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{}",
+            leg.step,
+            leg.from.map_or(String::new(), |from| from.to_string()),
+            leg.to,
+            leg.cost,
+            leg.distance,
+            leg.time,
+            leg.slack.map_or(String::new(), |slack| slack.to_string())
+        )?;
+    }
+    Ok(())
+}
+
+pub fn write_json<W: Write>(legs: &[Leg], writer: &mut W) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *writer, legs)?;
+    writeln!(writer)
+}
+
+// Aligned for reading on a terminal
+pub fn write_table<W: Write>(legs: &[Leg], writer: &mut W) -> io::Result<()> {
+    writeln!(
+        writer,
+        "{:>5} {:>6} {:>6} {:>10} {:>12} {:>12} {:>12}",
+        "step", "from", "to", "cost", "distance", "time", "slack"
+    )?;
+    for leg in legs {
+        writeln!(
+            writer,
+            "{:>5} {:>6} {:>6} {:>10.2} {:>12.2} {:>12.2} {:>12}",
+            leg.step,
+            leg.from.map_or("-".to_string(), |from| from.to_string()),
+            leg.to,
+            leg.cost,
+            leg.distance,
+            leg.time,
+            leg.slack
+                .map_or("-".to_string(), |slack| format!("{:.2}", slack))
+        )?;
+    }
+    Ok(())
+}
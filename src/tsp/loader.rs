@@ -0,0 +1,306 @@
+use super::duration::RouteDuration;
+use super::{TspInstance, TspProblem};
+use std::path::Path;
+use std::sync::Arc;
+
+// Reads a TSPLIB `.tsp` file, named after its NAME or else after the file
+pub fn load(path: &Path) -> Result<TspInstance, String> {
+    let source = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    parse(&name, &source)
+}
+
+pub fn load_problem(path: &Path) -> Result<TspProblem, String> {
+    load(path).map(|instance| TspProblem::new(instance.matrix()))
+}
+
+// The TSPLIB subset the solver needs: EUC_2D and GEO coordinates, or EXPLICIT
+// weights as a full matrix or one of its triangles. `name` is used when the
+// file has no NAME. As in the VRP files, SERVICE_TIME is spent at every city
+// and DISTANCE limits how long a route takes; a SERVICE_TIME_SECTION of
+// "<node> <time>" lines gives every city its own, and DURATION_PENALTY turns
+// the limit into a cost per unit of time over it.
+pub fn parse(name: &str, source: &str) -> Result<TspInstance, String> {
+    let mut name = name.to_string();
+    let mut problem_type = String::from("TSP");
+    let mut dimension = None;
+    let mut edge_weight_type = String::new();
+    let mut edge_weight_format = String::from("FULL_MATRIX");
+    let mut coordinates = Vec::new();
+    let mut display = Vec::new();
+    let mut weights = Vec::new();
+    let mut service_time = None;
+    let mut service = Vec::new();
+    let mut duration_limit = None;
+    let mut duration_penalty = None;
+
+    // Every section runs from its keyword to the next keyword, the values of
+    // a section may span lines in any layout
+    let mut section = None;
+    for line in source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        if line == "EOF" {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            section = None;
+            let value = value.trim();
+            match key.trim() {
+                "NAME" => name = value.to_string(),
+                "TYPE" => problem_type = value.to_string(),
+                "DIMENSION" => {
+                    dimension = Some(
+                        value
+                            .parse::<usize>()
+                            .map_err(|_| format!("invalid dimension {}", value))?,
+                    )
+                }
+                "EDGE_WEIGHT_TYPE" => edge_weight_type = value.to_string(),
+                "EDGE_WEIGHT_FORMAT" => edge_weight_format = value.to_string(),
+                "SERVICE_TIME" => service_time = Some(time(key, value)?),
+                "DISTANCE" => duration_limit = Some(time(key, value)?),
+                "DURATION_PENALTY" => duration_penalty = Some(time(key, value)?),
+                _ => {}
+            }
+            continue;
+        }
+        match line {
+            "NODE_COORD_SECTION"
+            | "DISPLAY_DATA_SECTION"
+            | "EDGE_WEIGHT_SECTION"
+            | "SERVICE_TIME_SECTION" => {
+                section = Some(line);
+                continue;
+            }
+            _ if line.ends_with("_SECTION") => {
+                return Err(format!("unsupported section {}", line));
+            }
+            _ => {}
+        }
+
+        let values = line
+            .split_whitespace()
+            .map(|value| {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid number {}", value))
+            })
+            .collect::<Result<Vec<f64>, String>>()?;
+        match section {
+            Some("NODE_COORD_SECTION") => coordinates.push(node(&values)?),
+            Some("DISPLAY_DATA_SECTION") => display.push(node(&values)?),
+            Some("SERVICE_TIME_SECTION") => service.push(service_of(&values)?),
+            Some(_) => weights.extend(values),
+            None => return Err(format!("unexpected line {}", line)),
+        }
+    }
+
+    if problem_type != "TSP" && problem_type != "ATSP" {
+        return Err(format!("unsupported problem type {}", problem_type));
+    }
+    let dimension = match dimension {
+        Some(0) | None => return Err("missing DIMENSION".to_string()),
+        Some(dimension) => dimension,
+    };
+
+    let graph_weights = match edge_weight_type.as_str() {
+        "EUC_2D" => pairwise(&nodes(coordinates.clone(), dimension)?, euclidean),
+        "GEO" => pairwise(&nodes(coordinates.clone(), dimension)?, geographical),
+        "EXPLICIT" => matrix(&weights, dimension, &edge_weight_format)?,
+        other => return Err(format!("unsupported edge weight type {}", other)),
+    };
+
+    let duration = if service_time.is_some()
+        || !service.is_empty()
+        || duration_limit.is_some()
+        || duration_penalty.is_some()
+    {
+        if duration_penalty.is_some() && duration_limit.is_none() {
+            return Err("DURATION_PENALTY without a DISTANCE limit".to_string());
+        }
+        let mut times = vec![service_time.unwrap_or(0.0); dimension];
+        for (node, time) in service {
+            match times.get_mut(node - 1) {
+                Some(slot) => *slot = time,
+                None => return Err(format!("node {} out of 1..={}", node, dimension)),
+            }
+        }
+        Some(Arc::new(RouteDuration {
+            service: times,
+            limit: duration_limit,
+            penalty: duration_penalty,
+        }))
+    } else {
+        None
+    };
+
+    // Explicit instances may still come with coordinates to draw them
+    let coordinates = match (coordinates.is_empty(), display.is_empty()) {
+        (false, _) => Some(nodes(coordinates, dimension)?),
+        (true, false) => Some(nodes(display, dimension)?),
+        (true, true) => None,
+    };
+
+    Ok(TspInstance {
+        name,
+        graph_weights,
+        coordinates: coordinates.map(|coordinates| {
+            coordinates
+                .into_iter()
+                .map(|(x, y)| (x as f32, y as f32))
+                .collect()
+        }),
+        constraints: None,
+        duration,
+        scenarios: None,
+    })
+}
+
+// A time of the header, never negative
+fn time(key: &str, value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(time) if time.is_finite() && time >= 0.0 => Ok(time),
+        _ => Err(format!("invalid {} {}", key.trim(), value)),
+    }
+}
+
+// A "<node> <time>" line
+fn service_of(values: &[f64]) -> Result<(usize, f32), String> {
+    match values {
+        [node, time] if *node >= 1.0 && node.fract() == 0.0 && *time >= 0.0 => {
+            Ok((*node as usize, *time as f32))
+        }
+        _ => Err(format!(
+            "expected a node and its service time, found {:?}",
+            values
+        )),
+    }
+}
+
+// A "<node> <x> <y>" line
+fn node(values: &[f64]) -> Result<(usize, f64, f64), String> {
+    match values {
+        [node, x, y] if *node >= 1.0 && node.fract() == 0.0 => Ok((*node as usize, *x, *y)),
+        _ => Err(format!(
+            "expected a node and two coordinates, found {:?}",
+            values
+        )),
+    }
+}
+
+// Coordinates ordered by node, every one of the `dimension` nodes exactly once
+fn nodes(nodes: Vec<(usize, f64, f64)>, dimension: usize) -> Result<Vec<(f64, f64)>, String> {
+    if nodes.len() != dimension {
+        return Err(format!(
+            "expected {} coordinates, found {}",
+            dimension,
+            nodes.len()
+        ));
+    }
+    let mut coordinates = vec![None; dimension];
+    for (node, x, y) in nodes {
+        match coordinates.get_mut(node - 1) {
+            Some(slot @ None) => *slot = Some((x, y)),
+            Some(Some(_)) => return Err(format!("node {} appears twice", node)),
+            None => return Err(format!("node {} out of 1..={}", node, dimension)),
+        }
+    }
+    Ok(coordinates.into_iter().map(Option::unwrap).collect())
+}
+
+fn pairwise(
+    coordinates: &[(f64, f64)],
+    distance: fn((f64, f64), (f64, f64)) -> f64,
+) -> Vec<Vec<f32>> {
+    coordinates
+        .iter()
+        .map(|from| {
+            coordinates
+                .iter()
+                .map(|to| distance(*from, *to) as f32)
+                .collect()
+        })
+        .collect()
+}
+
+fn euclidean((x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> f64 {
+    ((x1 - x2).powi(2) + (y1 - y2).powi(2)).sqrt().round()
+}
+
+// Distance on the idealized sphere of TSPLIB, coordinates are latitude and
+// longitude in DDD.MM degrees and minutes
+fn geographical(from: (f64, f64), to: (f64, f64)) -> f64 {
+    // TSPLIB truncates pi, the distances must match its published optima
+    #[allow(clippy::approx_constant)]
+    const PI: f64 = 3.141592;
+    const RADIUS: f64 = 6378.388;
+    let radians = |degrees: f64| {
+        let whole = degrees.trunc();
+        PI * (whole + 5.0 * (degrees - whole) / 3.0) / 180.0
+    };
+    if from == to {
+        return 0.0;
+    }
+
+    let (latitude1, longitude1) = (radians(from.0), radians(from.1));
+    let (latitude2, longitude2) = (radians(to.0), radians(to.1));
+    let q1 = (longitude1 - longitude2).cos();
+    let q2 = (latitude1 - latitude2).cos();
+    let q3 = (latitude1 + latitude2).cos();
+    (RADIUS * (0.5 * ((1.0 + q1) * q2 - (1.0 - q1) * q3)).acos() + 1.0).trunc()
+}
+
+// A full matrix row by row, or a triangle of a symmetric one. A column
+// triangle lists the same values as the row triangle on the other side.
+fn matrix(weights: &[f64], dimension: usize, format: &str) -> Result<Vec<Vec<f32>>, String> {
+    let n = dimension;
+    let (cells, expected): (Vec<(usize, usize)>, usize) = match format {
+        "FULL_MATRIX" => (
+            (0..n).flat_map(|i| (0..n).map(move |j| (i, j))).collect(),
+            n * n,
+        ),
+        "UPPER_ROW" | "LOWER_COL" => (
+            (0..n)
+                .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+                .collect(),
+            n * (n - 1) / 2,
+        ),
+        "LOWER_ROW" | "UPPER_COL" => (
+            (0..n).flat_map(|i| (0..i).map(move |j| (i, j))).collect(),
+            n * (n - 1) / 2,
+        ),
+        "UPPER_DIAG_ROW" | "LOWER_DIAG_COL" => (
+            (0..n).flat_map(|i| (i..n).map(move |j| (i, j))).collect(),
+            n * (n + 1) / 2,
+        ),
+        "LOWER_DIAG_ROW" | "UPPER_DIAG_COL" => (
+            (0..n).flat_map(|i| (0..=i).map(move |j| (i, j))).collect(),
+            n * (n + 1) / 2,
+        ),
+        other => return Err(format!("unsupported edge weight format {}", other)),
+    };
+    if weights.len() != expected {
+        return Err(format!(
+            "expected {} edge weights for a {} of {} nodes, found {}",
+            expected,
+            format,
+            n,
+            weights.len()
+        ));
+    }
+
+    let mut graph_weights = vec![vec![0.0; n]; n];
+    for ((i, j), weight) in cells.into_iter().zip(weights) {
+        graph_weights[i][j] = *weight as f32;
+        if format != "FULL_MATRIX" {
+            graph_weights[j][i] = *weight as f32;
+        }
+    }
+    Ok(graph_weights)
+}
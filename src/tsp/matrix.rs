@@ -0,0 +1,56 @@
+use crate::permutation::DistanceMatrix;
+use serde::{Deserialize, Serialize};
+use std::ops::Index;
+
+// The edge weights of an instance in one allocation, row after row, so the
+// fitness walks memory that is already close by instead of a separate row
+// per city. `matrix[from]` is the row of a city as with `Vec<Vec<f32>>`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WeightMatrix {
+    n: usize,
+    weights: Vec<f32>,
+}
+
+impl WeightMatrix {
+    // A row with fewer weights than there are cities is padded with
+    // forbidden edges, `check_configuration` refuses such instances anyway
+    pub fn from_rows(rows: &[Vec<f32>]) -> Self {
+        let n = rows.len();
+        let mut weights = Vec::with_capacity(n * n);
+        for row in rows {
+            weights.extend((0..n).map(|to| row.get(to).copied().unwrap_or(f32::INFINITY)));
+        }
+        WeightMatrix { n, weights }
+    }
+
+    pub fn dist(&self, from: usize, to: usize) -> f32 {
+        self.weights[from * self.n + to]
+    }
+
+    // Number of cities
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+}
+
+impl Index<usize> for WeightMatrix {
+    type Output = [f32];
+
+    fn index(&self, from: usize) -> &[f32] {
+        &self.weights[from * self.n..(from + 1) * self.n]
+    }
+}
+
+impl DistanceMatrix for WeightMatrix {
+    fn nodes(&self) -> usize {
+        self.n
+    }
+
+    fn distance(&self, from: usize, to: usize) -> f32 {
+        self.dist(from, to)
+    }
+}
@@ -0,0 +1,133 @@
+use super::TspInstance;
+use std::path::Path;
+
+// A new weight for the edge between two cities, numbered from 0. Forbidden
+// edges weigh infinity, a tour using one is invalid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EdgeOverride {
+    pub from: usize,
+    pub to: usize,
+    pub cost: f32,
+    // Only from `from` to `to`, otherwise both ways
+    pub directed: bool,
+}
+
+pub fn read(path: &Path) -> Result<Vec<EdgeOverride>, String> {
+    let source = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+    parse(&source)
+}
+
+// One override per line, cities numbered from 1 as in the TSPLIB files:
+// `3 7 1250.5` sets both ways, `3 > 7 900` only from 3 to 7, and a cost of
+// `forbidden` closes the edge. Blank lines and lines starting with # are
+// skipped.
+pub fn parse(source: &str) -> Result<Vec<EdgeOverride>, String> {
+    let mut overrides = Vec::new();
+    for (number, line) in source.lines().map(str::trim).enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let (from, to, cost, directed) = match fields[..] {
+            [from, ">", to, cost] => (from, to, cost, true),
+            [from, to, cost] => (from, to, cost, false),
+            _ => {
+                return Err(format!(
+                    "line {}: expected <city> [>] <city> <cost|forbidden>, found {}",
+                    number + 1,
+                    line
+                ))
+            }
+        };
+        let city = |field: &str| match field.parse::<usize>() {
+            Ok(city) if city >= 1 => Ok(city - 1),
+            _ => Err(format!("line {}: invalid city {}", number + 1, field)),
+        };
+        let cost = match cost {
+            "forbidden" => f32::INFINITY,
+            cost => match cost.parse::<f32>() {
+                Ok(cost) if cost.is_finite() && cost >= 0.0 => cost,
+                _ => return Err(format!("line {}: invalid cost {}", number + 1, cost)),
+            },
+        };
+        overrides.push(EdgeOverride {
+            from: city(from)?,
+            to: city(to)?,
+            cost,
+            directed,
+        });
+    }
+    Ok(overrides)
+}
+
+// Changes the weights of `instance`, refusing overrides out of its cities
+// and closures that leave no tour through every city
+pub fn apply(instance: &mut TspInstance, overrides: &[EdgeOverride]) -> Result<(), String> {
+    let cities = instance.graph_weights.len();
+    for edge in overrides.iter() {
+        if edge.from >= cities || edge.to >= cities || edge.from == edge.to {
+            return Err(format!(
+                "no edge between cities {} and {} of {} cities",
+                edge.from + 1,
+                edge.to + 1,
+                cities
+            ));
+        }
+        instance.graph_weights[edge.from][edge.to] = edge.cost;
+        if !edge.directed {
+            instance.graph_weights[edge.to][edge.from] = edge.cost;
+        }
+    }
+    match infeasibility(&instance.graph_weights) {
+        Some(reason) => Err(format!("no tour is left, {}", reason)),
+        None => Ok(()),
+    }
+}
+
+// Why no tour can go through every city along open edges, None when one may.
+// Only what can be checked quickly: the open edges must connect every city,
+// and a tour has only two ends to reach the cities with a single neighbor.
+pub fn infeasibility(graph_weights: &[Vec<f32>]) -> Option<String> {
+    let cities = graph_weights.len();
+    let neighbors = (0..cities)
+        .map(|city| {
+            (0..cities)
+                .filter(|&other| {
+                    other != city
+                        && (graph_weights[city][other].is_finite()
+                            || graph_weights[other][city].is_finite())
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    if cities > 1 {
+        if let Some(city) = (0..cities).find(|&city| neighbors[city].is_empty()) {
+            return Some(format!("every edge of city {} is forbidden", city + 1));
+        }
+    }
+    let ends = (0..cities)
+        .filter(|&city| neighbors[city].len() == 1)
+        .collect::<Vec<_>>();
+    if ends.len() > 2 {
+        return Some(format!(
+            "cities {} each have a single open edge, a tour has only two ends",
+            ends.iter()
+                .map(|city| (city + 1).to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    let mut reached = vec![false; cities];
+    let mut stack = (0..cities.min(1)).collect::<Vec<_>>();
+    while let Some(city) = stack.pop() {
+        if std::mem::replace(&mut reached[city], true) {
+            continue;
+        }
+        stack.extend(neighbors[city].iter().copied());
+    }
+    (0..cities)
+        .find(|&city| !reached[city])
+        .map(|city| format!("city {} can't be reached from city 1", city + 1))
+}
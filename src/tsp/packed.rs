@@ -0,0 +1,48 @@
+use super::TspSolution;
+use serde::{Deserialize, Serialize};
+
+// A tour as it goes from one rank to another. The cities fit in a u16 for all
+// but the largest instances, a quarter of the bytes bincode spends on the
+// usize of a `TspSolution`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum PackedTour {
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+}
+
+impl PackedTour {
+    pub fn pack(solution: &TspSolution) -> Self {
+        let path = &solution.path;
+        if path.iter().all(|city| *city <= u16::MAX as usize) {
+            PackedTour::Short(path.iter().map(|city| *city as u16).collect())
+        } else {
+            PackedTour::Long(path.iter().map(|city| *city as u32).collect())
+        }
+    }
+
+    pub fn unpack(self) -> TspSolution {
+        let path = match self {
+            PackedTour::Short(path) => path.into_iter().map(usize::from).collect(),
+            PackedTour::Long(path) => path.into_iter().map(|city| city as usize).collect(),
+        };
+        TspSolution { path }
+    }
+}
+
+pub fn pack_all(solutions: &[TspSolution]) -> Vec<PackedTour> {
+    solutions.iter().map(PackedTour::pack).collect()
+}
+
+pub fn pack_evaluated(evaluated: &[(f32, TspSolution)]) -> Vec<(f32, PackedTour)> {
+    evaluated
+        .iter()
+        .map(|(fitness, solution)| (*fitness, PackedTour::pack(solution)))
+        .collect()
+}
+
+pub fn unpack_evaluated(evaluated: Vec<(f32, PackedTour)>) -> Vec<(f32, TspSolution)> {
+    evaluated
+        .into_iter()
+        .map(|(fitness, tour)| (fitness, tour.unpack()))
+        .collect()
+}
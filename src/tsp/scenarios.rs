@@ -0,0 +1,140 @@
+use super::matrix::WeightMatrix;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// How the lengths of a tour in every scenario make its fitness
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ScenarioAggregate {
+    // Weighted by the weight of every scenario
+    Mean,
+    // The worst scenario, whatever the weights
+    Max,
+}
+
+impl ScenarioAggregate {
+    pub const ALL: [ScenarioAggregate; 2] = [ScenarioAggregate::Mean, ScenarioAggregate::Max];
+}
+
+impl fmt::Display for ScenarioAggregate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ScenarioAggregate::Mean => "mean",
+            ScenarioAggregate::Max => "max",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for ScenarioAggregate {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        ScenarioAggregate::ALL
+            .into_iter()
+            .find(|aggregate| aggregate.to_string() == name)
+            .ok_or_else(|| format!("unknown scenario aggregate: {}", name))
+    }
+}
+
+// Other edge weights for the cities of an instance, demand or traffic the
+// tour has to do well under as well. The instance itself is the first
+// scenario, a tour is measured in each and its fitness aggregates them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scenarios {
+    pub matrices: Vec<WeightMatrix>,
+    // Of the instance then of every scenario, they add up to 1
+    weights: Vec<f32>,
+    pub aggregate: ScenarioAggregate,
+}
+
+impl Scenarios {
+    // Every scenario weighs the same without `weights`, which are otherwise
+    // one for the instance and one for every scenario
+    pub fn new(
+        nodes: usize,
+        matrices: Vec<WeightMatrix>,
+        weights: &[f32],
+        aggregate: ScenarioAggregate,
+    ) -> Result<Self, String> {
+        if let Some((scenario, matrix)) = matrices
+            .iter()
+            .enumerate()
+            .find(|(_, matrix)| matrix.len() != nodes)
+        {
+            return Err(format!(
+                "scenario {} has {} cities, the instance has {}",
+                scenario + 1,
+                matrix.len(),
+                nodes
+            ));
+        }
+
+        let count = matrices.len() + 1;
+        let weights = match weights {
+            [] => vec![1.0; count],
+            weights if weights.len() == count => weights.to_vec(),
+            weights => {
+                return Err(format!(
+                    "{} scenario weights for the instance and {} scenarios",
+                    weights.len(),
+                    matrices.len()
+                ))
+            }
+        };
+        if weights
+            .iter()
+            .any(|weight| !weight.is_finite() || *weight < 0.0)
+        {
+            return Err("scenario weights must be non negative numbers".to_string());
+        }
+        let total = weights.iter().sum::<f32>();
+        if total <= 0.0 {
+            return Err("one scenario weight at least must be positive".to_string());
+        }
+
+        Ok(Scenarios {
+            matrices,
+            weights: weights.iter().map(|weight| weight / total).collect(),
+            aggregate,
+        })
+    }
+
+    // `length` of the tour in the instance, aggregated with its length in
+    // every scenario as `measure` gives it
+    pub fn fitness<F>(&self, length: f32, measure: F) -> f32
+    where
+        F: Fn(&WeightMatrix) -> f32,
+    {
+        let lengths = std::iter::once(length).chain(self.matrices.iter().map(measure));
+        match self.aggregate {
+            ScenarioAggregate::Mean => lengths
+                .zip(self.weights.iter())
+                .map(|(length, weight)| length * weight)
+                .fold(0.0, |total, length| total + length),
+            ScenarioAggregate::Max => lengths.fold(f32::NEG_INFINITY, f32::max),
+        }
+    }
+}
+
+impl fmt::Display for Scenarios {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let weights = self
+            .weights
+            .iter()
+            .map(|weight| format!("{:.3}", weight))
+            .collect::<Vec<_>>();
+        let description = match self.aggregate {
+            ScenarioAggregate::Mean => format!(
+                "mean of the instance and {} scenarios weighted {}",
+                self.matrices.len(),
+                weights.join(", ")
+            ),
+            ScenarioAggregate::Max => format!(
+                "worst of the instance and {} scenarios",
+                self.matrices.len()
+            ),
+        };
+        f.pad(&description)
+    }
+}
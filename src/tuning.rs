@@ -0,0 +1,198 @@
+use crate::permutation::{CrossoverOperator, MutationOperator};
+use crate::runner::{PopulationSolver, RunParameters, Solver};
+use crate::tsp::matrix::WeightMatrix;
+use crate::tsp::{TspProblem, TSP};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+use std::sync::Arc;
+
+const MUTATION_RATES: [f32; 4] = [0.05, 0.1, 0.2, 0.4];
+const CROSSOVER_RATES: [f32; 4] = [0.6, 0.8, 0.9, 1.0];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TspConfiguration {
+    pub crossover: CrossoverOperator,
+    pub mutation: MutationOperator,
+    pub mutation_rate: f32,
+    pub crossover_rate: f32,
+}
+
+impl fmt::Display for TspConfiguration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "crossover: {}, mutation: {}, mutation rate: {}, crossover rate: {}",
+            self.crossover, self.mutation, self.mutation_rate, self.crossover_rate
+        )
+    }
+}
+
+// Every combination of operators and rates, in a fixed order
+pub fn tsp_search_space() -> Vec<TspConfiguration> {
+    let mut space = Vec::new();
+    for crossover in CrossoverOperator::ALL {
+        for mutation in MutationOperator::ALL {
+            for mutation_rate in MUTATION_RATES {
+                for crossover_rate in CROSSOVER_RATES {
+                    space.push(TspConfiguration {
+                        crossover,
+                        mutation,
+                        mutation_rate,
+                        crossover_rate,
+                    });
+                }
+            }
+        }
+    }
+    space
+}
+
+pub fn sample_configurations<R: Rng>(count: usize, rng: &mut R) -> Vec<TspConfiguration> {
+    let space = tsp_search_space();
+    space
+        .choose_multiple(rng, count.min(space.len()))
+        .cloned()
+        .collect()
+}
+
+pub fn tsp_solver(
+    graph_weights: &Arc<WeightMatrix>,
+    configuration: &TspConfiguration,
+    population_size: usize,
+    elite: usize,
+    seed: u64,
+) -> Box<dyn Solver> {
+    let problem = Arc::new(
+        TspProblem::new(graph_weights.clone())
+            .with_operators(configuration.crossover, configuration.mutation),
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    let population = (0..population_size)
+        .map(|_| TSP::new_shuffled(&problem, &mut rng))
+        .collect::<Vec<TSP>>();
+
+    let parameters = RunParameters {
+        iterations: 0,
+        population_size,
+        elite,
+        mutation_rate: configuration.mutation_rate,
+        crossover_rate: configuration.crossover_rate,
+    };
+
+    Box::new(PopulationSolver::new(population, parameters).with_seed(rng.gen()))
+}
+
+#[derive(Clone, Debug)]
+pub struct TuningResult<C> {
+    pub configuration: C,
+    pub best: f32,
+    pub generations: usize,
+}
+
+// Starting budget so that the last survivor of `configurations` reaches `max_budget`
+pub fn starting_budget(max_budget: usize, configurations: usize, eta: usize) -> usize {
+    let eta = eta.max(2);
+    let rungs = configurations.max(1).ilog(eta);
+    (max_budget / eta.pow(rungs)).max(1)
+}
+
+// Runs every configuration for `min_budget` generations, keeps the best 1/eta
+// and multiplies the budget by eta until it reaches `max_budget`. Survivors are
+// resumed from where their previous rung stopped instead of being restarted.
+pub fn successive_halving<C, F>(
+    configurations: Vec<C>,
+    make_solver: F,
+    min_budget: usize,
+    max_budget: usize,
+    eta: usize,
+) -> Vec<TuningResult<C>>
+where
+    C: Clone,
+    F: Fn(&C) -> Box<dyn Solver>,
+{
+    let eta = eta.max(2);
+    let mut finished = Vec::new();
+    let mut running = configurations
+        .into_iter()
+        .map(|configuration| {
+            let solver = make_solver(&configuration);
+            (configuration, solver, f32::INFINITY, 0)
+        })
+        .collect::<Vec<(C, Box<dyn Solver>, f32, usize)>>();
+
+    let mut budget = min_budget.max(1);
+    let max_budget = max_budget.max(budget);
+    while !running.is_empty() {
+        for (_, solver, best, generations) in running.iter_mut() {
+            while *generations < budget {
+                *best = best.min(solver.step().best);
+                *generations += 1;
+            }
+        }
+
+        running.sort_by(|a, b| a.2.total_cmp(&b.2));
+        let keep = if budget >= max_budget {
+            0
+        } else {
+            (running.len() / eta).max(1)
+        };
+
+        finished.extend(
+            running
+                .drain(keep..)
+                .map(|(configuration, _, best, generations)| TuningResult {
+                    configuration,
+                    best,
+                    generations,
+                }),
+        );
+        budget = (budget * eta).min(max_budget);
+    }
+
+    finished.sort_by(|a, b| {
+        b.generations
+            .cmp(&a.generations)
+            .then(a.best.total_cmp(&b.best))
+    });
+    finished
+}
+
+// Hyperband: successive halving brackets trading number of configurations
+// against the starting budget, up to `max_budget` generations per configuration
+pub fn hyperband<C, S, F>(
+    max_budget: usize,
+    eta: usize,
+    sample: S,
+    make_solver: F,
+) -> Vec<TuningResult<C>>
+where
+    C: Clone,
+    S: Fn(usize) -> Vec<C>,
+    F: Fn(&C) -> Box<dyn Solver>,
+{
+    let eta = eta.max(2);
+    let mut brackets = 0;
+    while eta.pow(brackets + 1) <= max_budget {
+        brackets += 1;
+    }
+
+    let mut results = Vec::new();
+    for s in (0..=brackets).rev() {
+        let configurations = ((brackets + 1) as f64 / (s + 1) as f64 * eta.pow(s) as f64).ceil();
+        let min_budget = max_budget / eta.pow(s);
+
+        let bracket = successive_halving(
+            sample(configurations as usize),
+            &make_solver,
+            min_budget,
+            max_budget,
+            eta,
+        );
+        results.extend(bracket.into_iter().take(1));
+    }
+
+    results.sort_by(|a, b| a.best.total_cmp(&b.best));
+    results
+}
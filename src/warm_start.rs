@@ -0,0 +1,190 @@
+use crate::tsp::{TspInstance, TspSolution};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
+use std::str::FromStr;
+use std::sync::Arc;
+
+// Most tours of solved instances an instance of a batch starts from
+pub const WARM_START_TOURS: usize = 5;
+
+// A city across instances: the bits of its coordinates, the node numbers of
+// a TSPLIB file only say where it comes in that file
+pub type CityId = (u32, u32);
+
+// Ids of the cities of `instance`, none without coordinates
+pub fn city_ids(instance: &TspInstance) -> Option<Vec<CityId>> {
+    instance.coordinates.as_ref().map(|coordinates| {
+        coordinates
+            .iter()
+            .map(|(x, y)| (x.to_bits(), y.to_bits()))
+            .collect()
+    })
+}
+
+// How much the solutions of one instance tell of another, from 0 for nothing
+// to 1 for the same instance
+pub trait Similarity: Debug + Send + Sync {
+    fn similarity(&self, a: &TspInstance, b: &TspInstance) -> f64;
+}
+
+// Cities both have over cities either has, instances without coordinates
+// are only similar to themselves
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SharedCities;
+
+impl Similarity for SharedCities {
+    fn similarity(&self, a: &TspInstance, b: &TspInstance) -> f64 {
+        if a.checksum() == b.checksum() {
+            return 1.0;
+        }
+        let (Some(a), Some(b)) = (city_ids(a), city_ids(b)) else {
+            return 0.0;
+        };
+        let a = a.into_iter().collect::<HashSet<_>>();
+        let b = b.into_iter().collect::<HashSet<_>>();
+        let union = a.union(&b).count();
+        if union == 0 {
+            return 0.0;
+        }
+        a.intersection(&b).count() as f64 / union as f64
+    }
+}
+
+// Only the same edge weights, whatever the file they come from
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Identical;
+
+impl Similarity for Identical {
+    fn similarity(&self, a: &TspInstance, b: &TspInstance) -> f64 {
+        if a.checksum() == b.checksum() {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+// The similarities that can be chosen by name
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SimilarityKind {
+    SharedCities,
+    Identical,
+}
+
+impl SimilarityKind {
+    pub const ALL: [SimilarityKind; 2] = [SimilarityKind::SharedCities, SimilarityKind::Identical];
+
+    pub fn create(&self) -> Arc<dyn Similarity> {
+        match self {
+            SimilarityKind::SharedCities => Arc::new(SharedCities),
+            SimilarityKind::Identical => Arc::new(Identical),
+        }
+    }
+}
+
+impl fmt::Display for SimilarityKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SimilarityKind::SharedCities => "shared-cities",
+            SimilarityKind::Identical => "identical",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for SimilarityKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        SimilarityKind::ALL
+            .into_iter()
+            .find(|kind| kind.to_string() == name)
+            .ok_or_else(|| format!("unknown similarity: {}", name))
+    }
+}
+
+// `tour` of `from` as a tour of `to`: the cities both have in the order of
+// `tour`, then every city only `to` has inserted where it lengthens the tour
+// the least. None when they share no city.
+pub fn transfer(tour: &TspSolution, from: &TspInstance, to: &TspInstance) -> Option<TspSolution> {
+    if from.checksum() == to.checksum() {
+        return Some(tour.clone());
+    }
+    let (from_ids, to_ids) = (city_ids(from)?, city_ids(to)?);
+    let cities = to_ids
+        .iter()
+        .enumerate()
+        .map(|(city, id)| (*id, city))
+        .collect::<HashMap<_, _>>();
+
+    // Two cities at the same place are one id, each is kept once
+    let mut present = vec![false; to_ids.len()];
+    let mut path = tour
+        .path
+        .iter()
+        .filter_map(|city| cities.get(&from_ids[*city]).copied())
+        .filter(|city| !std::mem::replace(&mut present[*city], true))
+        .collect::<Vec<_>>();
+    if path.is_empty() {
+        return None;
+    }
+
+    let weights = &to.graph_weights;
+    for city in (0..to_ids.len()).filter(|city| !present[*city]) {
+        let first = weights[city][path[0]];
+        let last = weights[path[path.len() - 1]][city];
+        let (position, _) = path
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                let added =
+                    weights[pair[0]][city] + weights[city][pair[1]] - weights[pair[0]][pair[1]];
+                (i + 1, added)
+            })
+            .chain([(0, first), (path.len(), last)])
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        path.insert(position, city);
+    }
+    Some(TspSolution { path })
+}
+
+// The instances of a batch solved so far and their best tours, to start the
+// next ones from those of the most similar
+#[derive(Debug)]
+pub struct WarmStart {
+    similarity: Arc<dyn Similarity>,
+    threshold: f64,
+    solved: Vec<(TspInstance, TspSolution)>,
+}
+
+impl WarmStart {
+    pub fn new(similarity: Arc<dyn Similarity>, threshold: f64) -> Self {
+        WarmStart {
+            similarity,
+            threshold,
+            solved: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, instance: TspInstance, tour: TspSolution) {
+        self.solved.push((instance, tour));
+    }
+
+    // The best tours of up to `WARM_START_TOURS` solved instances at least
+    // `threshold` similar to `instance`, most similar first, as its tours
+    pub fn tours_for(&self, instance: &TspInstance) -> Vec<TspSolution> {
+        let mut similar = self
+            .solved
+            .iter()
+            .map(|(solved, tour)| (self.similarity.similarity(solved, instance), solved, tour))
+            .filter(|(similarity, _, _)| *similarity >= self.threshold)
+            .collect::<Vec<_>>();
+        similar.sort_by(|a, b| b.0.total_cmp(&a.0));
+        similar
+            .into_iter()
+            .filter_map(|(_, solved, tour)| transfer(tour, solved, instance))
+            .take(WARM_START_TOURS)
+            .collect()
+    }
+}